@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::constants::{START_ADDR, XO_RAM_SIZE};
+
+#[derive(Debug)]
+pub struct AssembleError {
+    pub line: usize, // 1 based
+    pub message: String,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Assembles source written with the mnemonics the disassembler prints
+/// (Cowgod's Chip-8 Technical Reference) into a ROM loaded at 0x200.
+///
+/// ```text
+/// ; comments start with a semicolon
+/// start:              ; labels end with a colon and can be used as addresses
+///     LD V0, 0x0A     ; numbers are decimal, 0x hex or 0b binary
+///     LD F, V0
+///     DRW V1, V2, 5
+///     JP start
+/// sprite:
+///     DB 0b11110000, 0x90   ; raw bytes
+///     DW 0x1234             ; raw big endian words
+///     LD I, LONG sprite     ; XO-CHIP's 16-bit address, two words
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    let mut lines = Vec::new();
+
+    for (i, text) in source.lines().enumerate() {
+        let line = parse_line(text).map_err(|message| AssembleError { line: i + 1, message })?;
+
+        lines.push((i + 1, line));
+    }
+
+    // first pass: label addresses
+    let mut labels = HashMap::new();
+    let mut addr = START_ADDR as usize;
+
+    for (line_no, line) in &lines {
+        if let Some(label) = &line.label {
+            if labels.insert(label.to_uppercase(), addr as u16).is_some() {
+                return Err(AssembleError {
+                    line: *line_no,
+                    message: format!("label `{label}` is defined twice"),
+                });
+            }
+        }
+
+        addr += line.size();
+    }
+
+    // second pass: encode
+    let mut rom = Vec::new();
+
+    for (line_no, line) in &lines {
+        let Some((mnemonic, operands)) = &line.instruction else {
+            continue;
+        };
+
+        let error = |message: String| AssembleError {
+            line: *line_no,
+            message,
+        };
+
+        match mnemonic.as_str() {
+            "DB" => {
+                for operand in operands {
+                    let value = number(operand, &labels).map_err(error)?;
+                    rom.push(byte(value).map_err(error)?);
+                }
+            }
+
+            "DW" => {
+                for operand in operands {
+                    let value = number(operand, &labels).map_err(error)?;
+                    rom.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+
+            _ if line.is_long_load() => {
+                let target = operands[1][4..].trim();
+                let value = number(target, &labels).map_err(error)?;
+
+                rom.extend_from_slice(&[0xF0, 0x00]);
+                rom.extend_from_slice(&value.to_be_bytes());
+            }
+
+            _ => {
+                let op = encode(mnemonic, operands, &labels).map_err(error)?;
+                rom.extend_from_slice(&op.to_be_bytes());
+            }
+        }
+    }
+
+    if START_ADDR as usize + rom.len() > XO_RAM_SIZE {
+        return Err(AssembleError {
+            line: lines.len(),
+            message: format!("program is {} bytes, it does not fit in memory", rom.len()),
+        });
+    }
+
+    Ok(rom)
+}
+
+struct Line {
+    label: Option<String>,
+    instruction: Option<(String, Vec<String>)>, // upper case mnemonic and its operands
+}
+
+impl Line {
+    fn size(&self) -> usize {
+        match &self.instruction {
+            None => 0,
+            Some((mnemonic, operands)) if mnemonic == "DB" => operands.len(),
+            Some((mnemonic, operands)) if mnemonic == "DW" => operands.len() * 2,
+            Some(_) if self.is_long_load() => 4,
+            Some(_) => 2,
+        }
+    }
+
+    // `LD I, LONG addr`
+    fn is_long_load(&self) -> bool {
+        match &self.instruction {
+            Some((mnemonic, operands)) if mnemonic == "LD" && operands.len() == 2 => {
+                operands[0].eq_ignore_ascii_case("I")
+                    && operands[1].to_uppercase().starts_with("LONG ")
+            }
+            _ => false,
+        }
+    }
+}
+
+fn parse_line(text: &str) -> Result<Line, String> {
+    let code = text.split(';').next().unwrap_or("").trim();
+
+    let (label, rest) = match code.split_once(':') {
+        Some((label, rest)) => (Some(label.trim().to_string()), rest.trim()),
+        None => (None, code),
+    };
+
+    if let Some(label) = &label {
+        let valid = label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && label.chars().next().is_some_and(|c| !c.is_ascii_digit());
+
+        if !valid {
+            return Err(format!("invalid label `{label}`"));
+        }
+    }
+
+    let instruction = if rest.is_empty() {
+        None
+    } else {
+        let (mnemonic, operands) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+        let operands = operands
+            .split(',')
+            .map(|operand| operand.trim().to_string())
+            .filter(|operand| !operand.is_empty())
+            .collect();
+
+        Some((mnemonic.to_uppercase(), operands))
+    };
+
+    Ok(Line { label, instruction })
+}
+
+fn encode(mnemonic: &str, operands: &[String], labels: &HashMap<String, u16>) -> Result<u16, String> {
+    let ops: Vec<String> = operands.iter().map(|op| op.to_uppercase()).collect();
+    let ops: Vec<&str> = ops.iter().map(String::as_str).collect();
+
+    let reg = |operand: &str| register(operand);
+    let addr = |operand: &str| address(operand, labels);
+    let kk = |operand: &str| number(operand, labels).and_then(byte).map(u16::from);
+
+    // register/register or register/byte forms share the first digit with an offset
+    let x = |operand: &str| reg(operand).map(|x| x << 8);
+    let xy = |a: &str, b: &str| Ok::<u16, String>(x(a)? | (reg(b)? << 4));
+
+    let op = match (mnemonic, ops.as_slice()) {
+        ("NOP", []) => 0x0000,
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("SCD", [n]) => {
+            let n = number(n, labels)?;
+
+            if n > 0xF {
+                return Err(format!("scroll of {n} rows is larger than 15"));
+            }
+
+            0x00C0 | n
+        }
+        ("SCU", [n]) => {
+            let n = number(n, labels)?;
+
+            if n > 0xF {
+                return Err(format!("scroll of {n} rows is larger than 15"));
+            }
+
+            0x00D0 | n
+        }
+        ("SCR", []) => 0x00FB,
+        ("SCL", []) => 0x00FC,
+        ("EXIT", []) => 0x00FD,
+        ("LOW", []) => 0x00FE,
+        ("HIGH", []) => 0x00FF,
+        ("JP", ["V0", target]) => 0xB000 | addr(target)?,
+        ("JP", [target]) => 0x1000 | addr(target)?,
+        ("CALL", [target]) => 0x2000 | addr(target)?,
+        ("SE", [a, b]) if is_register(b) => 0x5000 | xy(a, b)?,
+        ("SE", [a, b]) => 0x3000 | x(a)? | kk(b)?,
+        ("SNE", [a, b]) if is_register(b) => 0x9000 | xy(a, b)?,
+        ("SNE", [a, b]) => 0x4000 | x(a)? | kk(b)?,
+        ("LD", ["I", target]) => 0xA000 | addr(target)?,
+        ("LD", ["[I]", range]) if range.contains('-') => 0x5002 | registers(range)?,
+        ("LD", [range, "[I]"]) if range.contains('-') => 0x5003 | registers(range)?,
+        ("LD", ["AUDIO", "[I]"]) => 0xF002,
+        ("LD", ["PITCH", a]) => 0xF03A | x(a)?,
+        ("LD", ["DT", a]) => 0xF015 | x(a)?,
+        ("LD", ["ST", a]) => 0xF018 | x(a)?,
+        ("LD", ["F", a]) => 0xF029 | x(a)?,
+        ("LD", ["HF", a]) => 0xF030 | x(a)?,
+        ("LD", ["R", a]) => 0xF075 | x(a)?,
+        ("LD", ["B", a]) => 0xF033 | x(a)?,
+        ("LD", ["[I]", a]) => 0xF055 | x(a)?,
+        ("LD", [a, "DT"]) => 0xF007 | x(a)?,
+        ("LD", [a, "K"]) => 0xF00A | x(a)?,
+        ("LD", [a, "[I]"]) => 0xF065 | x(a)?,
+        ("LD", [a, "R"]) => 0xF085 | x(a)?,
+        ("LD", [a, b]) if is_register(b) => 0x8000 | xy(a, b)?,
+        ("LD", [a, b]) => 0x6000 | x(a)? | kk(b)?,
+        ("ADD", ["I", a]) => 0xF01E | x(a)?,
+        ("ADD", [a, b]) if is_register(b) => 0x8004 | xy(a, b)?,
+        ("ADD", [a, b]) => 0x7000 | x(a)? | kk(b)?,
+        ("OR", [a, b]) => 0x8001 | xy(a, b)?,
+        ("AND", [a, b]) => 0x8002 | xy(a, b)?,
+        ("XOR", [a, b]) => 0x8003 | xy(a, b)?,
+        ("SUB", [a, b]) => 0x8005 | xy(a, b)?,
+        ("SHR", [a]) | ("SHR", [a, _]) => 0x8006 | x(a)?,
+        ("SUBN", [a, b]) => 0x8007 | xy(a, b)?,
+        ("SHL", [a]) | ("SHL", [a, _]) => 0x800E | x(a)?,
+        ("RND", [a, b]) => 0xC000 | x(a)? | kk(b)?,
+        ("DRW", [a, b, n]) => {
+            let n = number(n, labels)?;
+
+            if n > 0xF {
+                return Err(format!("sprite height {n} is larger than 15"));
+            }
+
+            0xD000 | xy(a, b)? | n
+        }
+        ("PLANE", [n]) => {
+            let n = number(n, labels)?;
+
+            if n > 3 {
+                return Err(format!("plane mask {n} is larger than 3"));
+            }
+
+            0xF001 | n << 8
+        }
+        ("SKP", [a]) => 0xE09E | x(a)?,
+        ("SKNP", [a]) => 0xE0A1 | x(a)?,
+        ("BRK", []) => 0xFFF0,
+        ("PRINT", []) => 0xFFF1,
+        ("ASSERT", ["VF"]) => 0xFFF2,
+        ("PRINTS", ["[I]"]) => 0xFFF3,
+        _ => return Err(format!("unknown instruction `{mnemonic} {}`", operands.join(", "))),
+    };
+
+    Ok(op)
+}
+
+// `Vx-Vy` as the x and y digits of XO-CHIP's 5XY2/5XY3
+fn registers(range: &str) -> Result<u16, String> {
+    let (x, y) = range.split_once('-').unwrap_or((range, ""));
+
+    Ok(register(x.trim())? << 8 | register(y.trim())? << 4)
+}
+
+fn is_register(operand: &str) -> bool {
+    register(operand).is_ok()
+}
+
+fn register(operand: &str) -> Result<u16, String> {
+    let operand = operand.to_uppercase();
+
+    operand
+        .strip_prefix('V')
+        .filter(|digit| digit.len() == 1)
+        .and_then(|digit| u16::from_str_radix(digit, 16).ok())
+        .ok_or_else(|| format!("expected a register (V0-VF), found `{operand}`"))
+}
+
+fn address(operand: &str, labels: &HashMap<String, u16>) -> Result<u16, String> {
+    let addr = number(operand, labels)?;
+
+    if addr > 0xFFF {
+        return Err(format!("address {addr:#x} is out of range"));
+    }
+
+    Ok(addr)
+}
+
+// numbers or label addresses
+fn number(operand: &str, labels: &HashMap<String, u16>) -> Result<u16, String> {
+    let upper = operand.to_uppercase();
+
+    let parsed = if let Some(hex) = upper.strip_prefix("0X") {
+        u16::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = upper.strip_prefix("0B") {
+        u16::from_str_radix(bin, 2).ok()
+    } else if upper.starts_with(|c: char| c.is_ascii_digit()) {
+        upper.parse().ok()
+    } else {
+        labels.get(&upper).copied()
+    };
+
+    parsed.ok_or_else(|| format!("expected a number or label, found `{operand}`"))
+}
+
+fn byte(value: u16) -> Result<u8, String> {
+    u8::try_from(value).map_err(|_| format!("{value:#x} does not fit in a byte"))
+}
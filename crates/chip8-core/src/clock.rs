@@ -0,0 +1,272 @@
+use crate::constants::{FRAMES_PER_SECOND, TICKS_PER_FRAME};
+
+/// The fastest speed a clock runs at, 60 million instructions a second: more than a frame
+/// has time for anyway, and well before a single frame would take seconds.
+pub const MAX_INSTRUCTIONS_PER_FRAME: f64 = 1_000_000.0;
+
+/// The fastest fast forward, machine frames per 60th of a second.
+pub const MAX_SPEED: f64 = 100.0;
+
+// a budget this close under a whole number has reached it: sums of fractions like 700/60
+// come up a hair short and would drop an instruction now and then
+const BUDGET_EPSILON: f64 = 1e-9;
+
+/// The speeds `step_instructions_per_frame` stays between, Octo's fastest setting at the top.
+pub const MIN_STEPPED_IPF: f64 = 0.25;
+pub const MAX_STEPPED_IPF: f64 = 1000.0;
+
+/// Decides how many instructions run each frame.
+///
+/// Rates don't have to be whole numbers per frame: the fractional part of the budget is
+/// carried over to the next frame, so 10.5 instructions per frame alternates 10 and 11
+/// and "700 instructions per second" averages out to exactly 700.
+///
+/// The speed multiplier is how many frames of the machine run for every 60th of a second,
+/// above 1 while fast forwarding. It is not part of the machine timing: the frontends read it to
+/// run more frames, and their audio to know beeps go by faster.
+///
+/// With realtime timers the delay and sound timers keep counting down at 60 Hz while fast
+/// forwarding: only some of the machine frames tick them, for games pacing their play with
+/// the delay timer, which pure fast forward runs too fast.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    instructions_per_frame: f64,
+    budget: f64, // unspent fraction of an instruction from previous frames
+    speed: f64,
+    frame_budget: f64, // same for machine frames, with fractional speeds
+    realtime_timers: bool,
+    timer_budget: f64, // same for timer ticks, with realtime timers
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::from_ipf(TICKS_PER_FRAME as f64)
+    }
+}
+
+impl Clock {
+    pub fn from_ipf(instructions_per_frame: f64) -> Self {
+        Self {
+            instructions_per_frame: Self::clamp_ipf(instructions_per_frame),
+            budget: 0.0,
+            speed: 1.0,
+            frame_budget: 0.0,
+            realtime_timers: false,
+            timer_budget: 0.0,
+        }
+    }
+
+    pub fn from_hz(instructions_per_second: f64) -> Self {
+        Self::from_ipf(instructions_per_second / FRAMES_PER_SECOND as f64)
+    }
+
+    /// Whether `ipf` is a speed to accept from the user: not negative, not NaN and at most
+    /// MAX_INSTRUCTIONS_PER_FRAME.
+    pub fn is_valid_ipf(ipf: f64) -> bool {
+        (0.0..=MAX_INSTRUCTIONS_PER_FRAME).contains(&ipf)
+    }
+
+    fn clamp_ipf(ipf: f64) -> f64 {
+        if ipf.is_nan() {
+            return 0.0;
+        }
+
+        ipf.clamp(0.0, MAX_INSTRUCTIONS_PER_FRAME)
+    }
+
+    pub fn instructions_per_frame(&self) -> f64 {
+        self.instructions_per_frame
+    }
+
+    /// Changes the speed of a running game, the unspent fraction is kept.
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: f64) {
+        self.instructions_per_frame = Self::clamp_ipf(instructions_per_frame);
+    }
+
+    /// Speeds a running game up (`factor` above 1) or slows it down, within MIN_STEPPED_IPF
+    /// and MAX_STEPPED_IPF. A speed already outside them isn't pulled back in, and a stopped
+    /// game speeds up from MIN_STEPPED_IPF.
+    pub fn step_instructions_per_frame(&mut self, factor: f64) {
+        let ipf = self.instructions_per_frame;
+
+        let stepped = if factor > 1.0 {
+            (ipf.max(MIN_STEPPED_IPF) * factor).min(MAX_STEPPED_IPF.max(ipf))
+        } else {
+            (ipf * factor).max(MIN_STEPPED_IPF.min(ipf))
+        };
+
+        self.set_instructions_per_frame(stepped);
+    }
+
+    /// Unspent fraction carried to the next frame, part of the exact machine timing.
+    pub fn budget(&self) -> f64 {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: f64) {
+        self.budget = budget;
+    }
+
+    /// Number of instructions to run for the next frame.
+    pub fn ticks_for_frame(&mut self) -> u32 {
+        self.budget += self.instructions_per_frame;
+
+        take_whole(&mut self.budget)
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Whether `speed` is a fast forward speed to accept from the user: from 1 to MAX_SPEED.
+    pub fn is_valid_speed(speed: f64) -> bool {
+        (1.0..=MAX_SPEED).contains(&speed)
+    }
+
+    /// Runs `speed` machine frames for every 60th of a second, from one to MAX_SPEED.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = if speed.is_nan() {
+            1.0
+        } else {
+            speed.clamp(1.0, MAX_SPEED)
+        };
+    }
+
+    /// Number of machine frames to run for the next 60th of a second.
+    pub fn frames_for_frame(&mut self) -> u32 {
+        self.frame_budget += self.speed;
+
+        take_whole(&mut self.frame_budget)
+    }
+
+    pub fn realtime_timers(&self) -> bool {
+        self.realtime_timers
+    }
+
+    pub fn set_realtime_timers(&mut self, realtime_timers: bool) {
+        self.realtime_timers = realtime_timers;
+    }
+
+    /// How much faster than 60 Hz the timers and the sound run: the speed, or 1 with
+    /// realtime timers.
+    pub fn timer_speed(&self) -> f64 {
+        if self.realtime_timers {
+            1.0
+        } else {
+            self.speed
+        }
+    }
+
+    /// Whether the next machine frame ticks the timers; without realtime timers or fast
+    /// forward every frame does.
+    pub fn timers_due(&mut self) -> bool {
+        self.timer_budget += self.timer_speed() / self.speed;
+
+        take_whole(&mut self.timer_budget) > 0
+    }
+}
+
+// the whole part of a budget, leaving the fraction to carry over
+fn take_whole(budget: &mut f64) -> u32 {
+    let whole = (*budget + BUDGET_EPSILON).floor();
+
+    *budget = (*budget - whole).max(0.0);
+
+    whole as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stepped(ipf: f64, factor: f64) -> f64 {
+        let mut clock = Clock::from_ipf(ipf);
+        clock.step_instructions_per_frame(factor);
+
+        clock.instructions_per_frame()
+    }
+
+    #[test]
+    fn whole_rates_run_the_same_every_frame() {
+        let mut clock = Clock::default();
+
+        assert!((0..10).all(|_| clock.ticks_for_frame() == TICKS_PER_FRAME as u32));
+    }
+
+    #[test]
+    fn fractions_of_an_instruction_carry_over() {
+        let mut clock = Clock::from_ipf(10.5);
+
+        let ticks: Vec<u32> = (0..4).map(|_| clock.ticks_for_frame()).collect();
+
+        assert_eq!(ticks, [10, 11, 10, 11]);
+        assert_eq!(clock.budget(), 0.0);
+    }
+
+    #[test]
+    fn a_rate_in_hz_averages_out_over_a_second() {
+        let mut clock = Clock::from_hz(700.0);
+
+        let ticks: u32 = (0..FRAMES_PER_SECOND)
+            .map(|_| clock.ticks_for_frame())
+            .sum();
+
+        assert_eq!(ticks, 700);
+    }
+
+    #[test]
+    fn the_budget_survives_a_speed_change() {
+        let mut clock = Clock::from_ipf(0.5);
+
+        assert_eq!(clock.ticks_for_frame(), 0);
+
+        clock.set_instructions_per_frame(1.5);
+
+        assert_eq!(clock.ticks_for_frame(), 2);
+    }
+
+    #[test]
+    fn fractional_fast_forward_carries_machine_frames() {
+        let mut clock = Clock::default();
+        clock.set_speed(1.5);
+
+        let frames: Vec<u32> = (0..4).map(|_| clock.frames_for_frame()).collect();
+
+        assert_eq!(frames, [1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn realtime_timers_tick_once_per_60th_of_a_second() {
+        let mut clock = Clock::default();
+        clock.set_speed(4.0);
+        clock.set_realtime_timers(true);
+
+        let due = (0..8).filter(|_| clock.timers_due()).count();
+
+        assert_eq!(due, 2);
+    }
+
+    #[test]
+    fn steps_by_the_factor() {
+        assert_eq!(stepped(8.0, 1.25), 10.0);
+        assert_eq!(stepped(10.0, 0.8), 8.0);
+    }
+
+    #[test]
+    fn steps_stay_within_the_bounds() {
+        assert_eq!(stepped(900.0, 1.25), MAX_STEPPED_IPF);
+        assert_eq!(stepped(0.3, 0.8), MIN_STEPPED_IPF);
+    }
+
+    #[test]
+    fn speeds_outside_the_bounds_are_kept() {
+        assert_eq!(stepped(5000.0, 1.25), 5000.0);
+        assert_eq!(stepped(0.1, 0.8), 0.1);
+        assert_eq!(stepped(0.0, 0.8), 0.0);
+    }
+
+    #[test]
+    fn a_stopped_game_speeds_up() {
+        assert_eq!(stepped(0.0, 1.25), MIN_STEPPED_IPF * 1.25);
+    }
+}
@@ -0,0 +1,1828 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::constants::{
+    AUDIO_PATTERN_SIZE, BIG_FONTSET, BIG_FONTSET_ADDR, BIG_FONTSET_SIZE, DEFAULT_PITCH, FONTSET,
+    FONTSET_SIZE, FRAMES_PER_SECOND, NUM_KEYS, NUM_PLANES, NUM_REGS, NUM_RPL_FLAGS, RAM_SIZE,
+    STACK_SIZE, START_ADDR, XO_RAM_SIZE,
+};
+use crate::draw_stats::DrawStats;
+use crate::effect::Effect;
+use crate::memory_access::{AccessKind, MemoryAccesses, MemoryEvent};
+use crate::quirks::Quirks;
+use crate::random::RandomSource;
+use crate::reference::Reference;
+use crate::resolution::Resolution;
+use crate::savestate::SaveState;
+use crate::text;
+
+/// What a pre-execution hook wants done with the instruction about to run.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HookAction {
+    Continue,
+    Skip,  // don't run it, PC moves past it
+    Break, // don't run it and request a pause, see `Chip8::take_break_request`
+}
+
+/// Why an instruction couldn't run, see `Chip8::tick`. PC is left on the instruction, so
+/// the machine stays stopped there however often it's ticked again.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Chip8Error {
+    /// An opcode none of the supported instruction sets has.
+    UnknownOpcode(u16),
+    /// CALL with every stack level in use.
+    StackOverflow,
+    /// RET with nothing on the stack.
+    StackUnderflow,
+    /// An instruction reaching past the end of memory from the address, e.g. FX55 with I
+    /// near the end.
+    MemoryOutOfBounds(u16),
+    /// EX9E/EXA1 with a VX past the last key, with the `strict_keys` quirk.
+    InvalidKey(u8),
+    /// A 0000 at the address, past the end of the loaded program, with the `strict_end`
+    /// quirk: PC ran into the empty memory after it.
+    RanOffEnd(u16),
+    /// A ROM of this many bytes reaching past the end of memory from the load address.
+    RomTooLarge(usize),
+}
+
+impl Display for Chip8Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode(op) => write!(f, "unknown opcode {op:04X}"),
+            Chip8Error::StackOverflow => write!(f, "stack overflow, too many nested calls"),
+            Chip8Error::StackUnderflow => write!(f, "stack underflow, return without a call"),
+            Chip8Error::MemoryOutOfBounds(addr) => {
+                write!(f, "memory access past the end of memory from {addr:03X}")
+            }
+            Chip8Error::InvalidKey(key) => {
+                write!(f, "key {key:02X} doesn't exist, keys are 0 to F")
+            }
+            Chip8Error::RanOffEnd(addr) => {
+                write!(f, "ran off the end of the program at {addr:03X}")
+            }
+            Chip8Error::RomTooLarge(size) => {
+                write!(f, "the {size} byte ROM doesn't fit in memory")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
+
+/// Plays the buzzer sound, implemented by the frontends, see `Chip8::set_buzzer`.
+pub trait Buzzer {
+    /// The sound timer runs for `duration` from now.
+    fn beep(&self, duration: Duration);
+
+    /// Like `beep`, for an XO-CHIP game that loaded an audio pattern: its 128 1-bit samples,
+    /// most significant bit first, loop at `pattern_rate(pitch)` samples per second.
+    /// Frontends that can't play samples beep instead.
+    fn play_pattern(&self, duration: Duration, _pattern: &[u8; AUDIO_PATTERN_SIZE], _pitch: u8) {
+        self.beep(duration);
+    }
+}
+
+// frontends keep their output around for diagnostics
+impl<T: Buzzer + ?Sized> Buzzer for Rc<T> {
+    fn beep(&self, duration: Duration) {
+        (**self).beep(duration)
+    }
+
+    fn play_pattern(&self, duration: Duration, pattern: &[u8; AUDIO_PATTERN_SIZE], pitch: u8) {
+        (**self).play_pattern(duration, pattern, pitch)
+    }
+}
+
+/// Samples per second of XO-CHIP's audio pattern at `pitch`, 4000 at the default pitch.
+pub fn pattern_rate(pitch: u8) -> f64 {
+    4000.0 * 2f64.powf((pitch as f64 - DEFAULT_PITCH as f64) / 48.0)
+}
+
+/// Called before every instruction with the machine (PC pointing at the instruction) and
+/// the opcode about to run.
+pub type PreExecHook = Box<dyn FnMut(&Chip8, u16) -> HookAction>;
+
+/// Called after every instruction that ran with the machine (PC past the instruction), its
+/// index within the frame, 0 for the first one after the vertical blank, and what it did.
+/// Lets a frontend show how a frame is built up, e.g. the screen after each DXYN.
+pub type TickHook = Box<dyn FnMut(&Chip8, u32, &Effect)>;
+
+/// Called for every read and write of the addresses it watches.
+pub type MemoryWatch = Box<dyn FnMut(MemoryEvent)>;
+
+pub struct Chip8 {
+    resolution: Resolution,     // size of the display, 64x32 unless changed
+    memory: Vec<u8>,            // RAM, 4KB unless XO-CHIP's 64KB
+    v_reg: [u8; NUM_REGS],      // general purpose registers V0-VF
+    pc: u16,                    // Program Counter
+    i_reg: u16,                 // memory access I Register
+    delay_timer_reg: u8,        // special register for delay timer
+    sound_timer_reg: u8,        // special register for sound timer
+    stack: [u16; STACK_SIZE],   // stack for subroutines calls and returns
+    stack_pointer: u16,         // a var that points to the top of the stack
+    keyboard: [bool; NUM_KEYS], // a 16 key layout keyboard
+
+    // monochrome displays, row major; sprites are 8 pixels wide. Only XO-CHIP draws on the
+    // second plane, `selected_planes` has a bit for each plane drawn on
+    planes: [Vec<bool>; NUM_PLANES],
+    selected_planes: u8,
+
+    // SUPER-CHIP's RPL user flags, they survive a reset like on the HP-48
+    rpl_flags: [u8; NUM_RPL_FLAGS],
+
+    // XO-CHIP's sound, the buzzer plays until a game loads a pattern
+    audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    pitch: u8,
+
+    // state of the random source, kept here so replays can restore it
+    rng_state: u64,
+    random_source: RandomSource,
+
+    // machines running alongside the main one (e.g. A/B comparisons) stay silent
+    sound_enabled: bool,
+
+    // silent until the frontend opens an output device
+    buzzer: Option<Box<dyn Buzzer>>,
+
+    // how the instructions interpreters disagree on behave for the loaded game
+    quirks: Quirks,
+    vblank_wait: bool, // a sprite was drawn with the display_wait quirk, idle until the next frame
+
+    // FFF0-FFF3 are debug instructions for ROM developers, no-ops unless enabled
+    dev_mode: bool,
+    break_requested: bool,
+
+    // counted for the memory heatmap, None while nobody looks
+    memory_accesses: Option<MemoryAccesses>,
+    draw_stats: Option<DrawStats>,
+
+    // live maps, trainers... by id, so tools stop polling the whole memory every frame
+    memory_watches: Vec<(usize, Range<u16>, MemoryWatch)>,
+    next_watch_id: usize,
+
+    // tracing, breakpoints, cheats... installed by the frontend
+    pre_exec_hook: Option<PreExecHook>,
+    resume_pc: Option<u16>, // the hook is not asked again for the instruction it broke on
+    tick_hook: Option<TickHook>,
+    frame_ticks: u32, // instructions run since the vertical blank
+
+    // every instruction is checked against the reference interpreter, debug builds only
+    reference_check: bool,
+
+    // where games are loaded and start, 0x200 except on machines like the ETI-660
+    start_addr: u16,
+    program_end: usize, // past the last byte of the loaded game
+
+    // what the running instruction did that isn't worth comparing afterwards, see
+    // `run_instruction`
+    effect: Effect,
+}
+
+impl Debug for Chip8 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Chip8[program_counter: {}, stack_pointer: {}]",
+            self.pc, self.stack_pointer
+        )
+    }
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chip8 {
+    pub fn new() -> Self {
+        let mut chip8 = Self {
+            pc: START_ADDR,
+            memory: vec![0; RAM_SIZE],
+            planes: [
+                vec![false; Resolution::default().pixels()],
+                vec![false; Resolution::default().pixels()],
+            ],
+            selected_planes: 1,
+            resolution: Resolution::default(),
+            v_reg: [0; NUM_REGS],
+            i_reg: 0,
+            stack_pointer: 0,
+            stack: [0; STACK_SIZE],
+            keyboard: [false; NUM_KEYS],
+            rpl_flags: [0; NUM_RPL_FLAGS],
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            delay_timer_reg: 0,
+            sound_timer_reg: 0,
+            rng_state: RandomSource::default().initial_state(),
+            random_source: RandomSource::default(),
+            sound_enabled: true,
+            buzzer: None,
+            quirks: Quirks::default(),
+            vblank_wait: false,
+            dev_mode: false,
+            break_requested: false,
+            memory_accesses: None,
+            memory_watches: Vec::new(),
+            next_watch_id: 0,
+            draw_stats: None,
+            pre_exec_hook: None,
+            resume_pc: None,
+            tick_hook: None,
+            frame_ticks: 0,
+            reference_check: false,
+            start_addr: START_ADDR,
+            program_end: START_ADDR as usize,
+            effect: Effect::default(),
+        };
+
+        chip8.load_fonts();
+
+        chip8
+    }
+
+    /// A machine with the behaviors of another interpreter, e.g. `Quirks::COSMAC_VIP`.
+    pub fn with_quirks(quirks: Quirks) -> Self {
+        let mut chip8 = Self::new();
+        chip8.set_quirks(quirks);
+        chip8
+    }
+
+    /// A machine whose RND draws the same numbers every run, from `RandomSource::Seeded(seed)`,
+    /// for replays and tests that must come out the same.
+    pub fn with_seed(seed: u64) -> Self {
+        let mut chip8 = Self::new();
+        chip8.set_random_source(RandomSource::Seeded(seed));
+        chip8
+    }
+
+    pub fn reset(&mut self) {
+        self.pc = self.start_addr;
+        self.memory.fill(0);
+        self.planes.iter_mut().for_each(|plane| plane.fill(false));
+        self.selected_planes = 1;
+        self.audio_pattern = None;
+        self.pitch = DEFAULT_PITCH;
+        self.v_reg = [0; NUM_REGS];
+        self.i_reg = 0;
+        self.stack_pointer = 0;
+        self.stack = [0; STACK_SIZE];
+        self.keyboard = [false; NUM_KEYS];
+        self.delay_timer_reg = 0;
+        self.sound_timer_reg = 0;
+        self.break_requested = false;
+        self.resume_pc = None;
+        self.vblank_wait = false;
+        self.frame_ticks = 0;
+        self.load_fonts();
+    }
+
+    fn load_fonts(&mut self) {
+        self.memory[..FONTSET_SIZE].copy_from_slice(&FONTSET);
+        self.memory[BIG_FONTSET_ADDR..BIG_FONTSET_ADDR + BIG_FONTSET_SIZE]
+            .copy_from_slice(&BIG_FONTSET);
+    }
+
+    // the planes drawn on, see XO-CHIP's FN01
+    fn selected_planes(&self) -> impl Iterator<Item = usize> {
+        let selected = self.selected_planes;
+
+        (0..NUM_PLANES).filter(move |plane| selected & (1 << plane) != 0)
+    }
+
+    // moves the picture of the selected planes by dx, dy pixels, what comes in at the edges
+    // is blank
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let Resolution { width, height } = self.resolution;
+        self.effect.screen = true;
+
+        for plane in self.selected_planes().collect::<Vec<_>>() {
+            let previous = self.planes[plane].clone();
+
+            for y in 0..height {
+                for x in 0..width {
+                    let from_x = x.checked_add_signed(-dx).filter(|x| *x < width);
+                    let from_y = y.checked_add_signed(-dy).filter(|y| *y < height);
+
+                    self.planes[plane][x + width * y] = match (from_x, from_y) {
+                        (Some(from_x), Some(from_y)) => previous[from_x + width * from_y],
+                        _ => false,
+                    };
+                }
+            }
+        }
+    }
+
+    /// The display, `resolution().width` pixels per row. Only the first plane, which is all
+    /// there is unless an XO-CHIP game draws on the second, see `get_plane`.
+    pub fn get_screen(&self) -> &[bool] {
+        &self.planes[0]
+    }
+
+    /// XO-CHIP's display plane 0 or 1, laid out like `get_screen`. A pixel's color is 0 to
+    /// 3, a bit per plane set there.
+    pub fn get_plane(&self, plane: usize) -> &[bool] {
+        &self.planes[plane]
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Switches the display to another size, which clears it.
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        self.effect.screen = true;
+        self.planes = [
+            vec![false; resolution.pixels()],
+            vec![false; resolution.pixels()],
+        ];
+    }
+
+    /// Writes `lines` of host text on a cleared screen, one below the other from the top
+    /// left, e.g. to report a crash "in machine".
+    pub fn show_text(&mut self, lines: &[&str]) {
+        self.planes.iter_mut().for_each(|plane| plane.fill(false));
+
+        for (n, line) in lines.iter().enumerate() {
+            let y = 1 + n * (text::GLYPH_HEIGHT + 1);
+
+            text::draw(&mut self.planes[0], self.resolution, 1, y, line);
+        }
+    }
+
+    pub fn get_memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Bytes of memory, RAM_SIZE unless XO-CHIP's were asked for.
+    pub fn memory_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Replaces the memory with `size` empty bytes and the fonts, e.g. XO_RAM_SIZE for
+    /// XO-CHIP games. Games are loaded afterwards.
+    pub fn set_memory_size(&mut self, size: usize) {
+        self.memory = vec![0; size];
+        self.load_fonts();
+
+        if self.memory_accesses.is_some() {
+            self.memory_accesses = Some(MemoryAccesses::new(size));
+        }
+    }
+
+    pub fn write_memory(&mut self, addr: u16, value: u8) {
+        let len = self.memory.len();
+        self.memory[addr as usize % len] = value;
+
+        if let Some(accesses) = &mut self.memory_accesses {
+            accesses.write(addr as usize, 1);
+        }
+
+        self.notify_watches(AccessKind::Write, addr as usize, 1, self.pc);
+    }
+
+    /// Calls `watch` for every read and write of the addresses in `range` by instructions
+    /// or `write_memory`; instruction fetches are not reported. Returns the id to remove it
+    /// with.
+    pub fn add_memory_watch(&mut self, range: Range<u16>, watch: MemoryWatch) -> usize {
+        let id = self.next_watch_id;
+
+        self.next_watch_id += 1;
+        self.memory_watches.push((id, range, watch));
+
+        id
+    }
+
+    pub fn remove_memory_watch(&mut self, id: usize) {
+        self.memory_watches
+            .retain(|(watch_id, _, _)| *watch_id != id);
+    }
+
+    fn notify_watches(&mut self, kind: AccessKind, addr: usize, len: usize, pc: u16) {
+        if self.memory_watches.is_empty() {
+            return;
+        }
+
+        for offset in 0..len {
+            let addr = ((addr + offset) % self.memory.len()) as u16;
+            let value = self.memory[addr as usize];
+
+            for (_, range, watch) in &mut self.memory_watches {
+                if range.contains(&addr) {
+                    watch(MemoryEvent {
+                        kind,
+                        addr,
+                        value,
+                        pc,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Starts or stops counting the accesses to each memory address.
+    pub fn set_memory_tracking(&mut self, enabled: bool) {
+        self.memory_accesses = enabled.then(|| MemoryAccesses::new(self.memory.len()));
+    }
+
+    /// The accesses counted since the last call, None unless tracking is on.
+    pub fn take_memory_accesses(&mut self) -> Option<MemoryAccesses> {
+        let len = self.memory.len();
+
+        self.memory_accesses
+            .as_mut()
+            .map(|accesses| std::mem::replace(accesses, MemoryAccesses::new(len)))
+    }
+
+    /// Starts or stops counting sprite draws, see `DrawStats`.
+    pub fn set_draw_tracking(&mut self, enabled: bool) {
+        self.draw_stats = enabled.then(DrawStats::default);
+    }
+
+    /// The draws counted since tracking started, None unless it is on.
+    pub fn draw_stats(&self) -> Option<&DrawStats> {
+        self.draw_stats.as_ref()
+    }
+
+    /// SUPER-CHIP's RPL user flags, see FX75/FX85.
+    pub fn rpl_flags(&self) -> &[u8] {
+        &self.rpl_flags
+    }
+
+    pub fn get_registers(&self) -> &[u8] {
+        &self.v_reg
+    }
+
+    pub fn get_program_counter(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn get_i_register(&self) -> u16 {
+        self.i_reg
+    }
+
+    pub fn get_delay_timer(&self) -> u8 {
+        self.delay_timer_reg
+    }
+
+    pub fn get_sound_timer(&self) -> u8 {
+        self.sound_timer_reg
+    }
+
+    /// Whether the buzzer sounds, i.e. the sound timer is running.
+    pub fn is_sound_playing(&self) -> bool {
+        self.sound_timer_reg > 0
+    }
+
+    // only the occupied part of the stack, bottom first
+    pub fn get_stack(&self) -> &[u16] {
+        &self.stack[..self.stack_pointer as usize]
+    }
+
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            screen: self.planes[0].clone(),
+            second_plane: self.planes[1].clone(),
+            selected_planes: self.selected_planes,
+            resolution: self.resolution,
+            memory: self.memory.clone(),
+            v_reg: self.v_reg,
+            pc: self.pc,
+            i_reg: self.i_reg,
+            delay_timer_reg: self.delay_timer_reg,
+            sound_timer_reg: self.sound_timer_reg,
+            stack: self.get_stack().to_vec(),
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            rpl_flags: self.rpl_flags,
+        }
+    }
+
+    pub fn load_state(&mut self, state: &SaveState) {
+        self.reset();
+
+        self.set_resolution(state.resolution);
+
+        // copy what fits, states from other emulators are not guaranteed to have our sizes
+        for (plane, saved) in self
+            .planes
+            .iter_mut()
+            .zip([&state.screen, &state.second_plane])
+        {
+            let len = saved.len().min(plane.len());
+            plane[..len].copy_from_slice(&saved[..len]);
+        }
+
+        if state.memory.len() > RAM_SIZE {
+            self.set_memory_size(XO_RAM_SIZE);
+        } else {
+            self.set_memory_size(RAM_SIZE);
+        }
+
+        let memory_len = state.memory.len().min(self.memory.len());
+        self.memory[..memory_len].copy_from_slice(&state.memory[..memory_len]);
+
+        let stack_len = state.stack.len().min(STACK_SIZE);
+        self.stack[..stack_len].copy_from_slice(&state.stack[..stack_len]);
+        self.stack_pointer = stack_len as u16;
+
+        self.v_reg = state.v_reg;
+        self.pc = state.pc;
+        self.i_reg = state.i_reg;
+        self.delay_timer_reg = state.delay_timer_reg;
+        self.sound_timer_reg = state.sound_timer_reg;
+        self.selected_planes = state.selected_planes;
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.rpl_flags = state.rpl_flags;
+    }
+
+    pub fn set_sound_enabled(&mut self, enabled: bool) {
+        self.sound_enabled = enabled;
+    }
+
+    /// Plays the sound through `buzzer`, silent when None.
+    pub fn set_buzzer(&mut self, buzzer: Option<Box<dyn Buzzer>>) {
+        self.buzzer = buzzer;
+    }
+
+    pub fn get_quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// No instructions run until the next frame, see `Quirks::display_wait`.
+    pub fn waiting_for_vblank(&self) -> bool {
+        self.vblank_wait
+    }
+
+    pub fn set_dev_mode(&mut self, enabled: bool) {
+        self.dev_mode = enabled;
+    }
+
+    /// True once after a BRK or failed ASSERT debug instruction, the frontend should pause.
+    pub fn take_break_request(&mut self) -> bool {
+        std::mem::take(&mut self.break_requested)
+    }
+
+    pub fn get_keys(&self) -> &[bool] {
+        &self.keyboard
+    }
+
+    pub fn get_rng_state(&self) -> u64 {
+        self.rng_state
+    }
+
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.rng_state = state;
+    }
+
+    pub fn random_source(&self) -> RandomSource {
+        self.random_source
+    }
+
+    /// Switches RND to `source`, starting it over as on a machine just switched on.
+    pub fn set_random_source(&mut self, source: RandomSource) {
+        self.random_source = source;
+        self.rng_state = source.initial_state();
+    }
+
+    fn next_random_byte(&mut self) -> u8 {
+        self.random_source.next_byte(&mut self.rng_state)
+    }
+
+    pub fn keypress(&mut self, idx: usize, pressed: bool) {
+        self.keyboard[idx] = pressed;
+    }
+
+    pub fn start_addr(&self) -> u16 {
+        self.start_addr
+    }
+
+    /// Where `load` puts the game and the program starts, e.g. 0x600 for ETI-660 programs.
+    /// Moves the program counter there.
+    pub fn set_start_addr(&mut self, addr: u16) {
+        self.start_addr = addr;
+        self.pc = addr;
+    }
+
+    /// Copies the game to the start address. Games too large for 4KB can only be XO-CHIP
+    /// games, the memory grows to its 64KB for them; past that the game isn't loaded and
+    /// `Chip8Error::RomTooLarge` is returned.
+    pub fn load(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        let start = self.start_addr as usize;
+
+        let end = start + data.len();
+
+        if end > XO_RAM_SIZE {
+            return Err(Chip8Error::RomTooLarge(data.len()));
+        }
+
+        if end > self.memory.len() {
+            self.set_memory_size(XO_RAM_SIZE);
+        }
+
+        self.memory[start..end].copy_from_slice(data);
+        self.program_end = end;
+
+        Ok(())
+    }
+
+    fn push(&mut self, val: u16) -> Result<(), Chip8Error> {
+        if self.stack_pointer as usize == STACK_SIZE {
+            return Err(Chip8Error::StackOverflow);
+        }
+
+        self.stack[self.stack_pointer as usize] = val;
+        self.stack_pointer += 1;
+
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<u16, Chip8Error> {
+        if self.stack_pointer == 0 {
+            return Err(Chip8Error::StackUnderflow);
+        }
+
+        self.stack_pointer -= 1;
+
+        Ok(self.stack[self.stack_pointer as usize])
+    }
+
+    // the keypad only decodes the low nibble of a key
+    fn key_index(&self, vx: u8) -> Result<usize, Chip8Error> {
+        if self.quirks.strict_keys && vx as usize >= NUM_KEYS {
+            return Err(Chip8Error::InvalidKey(vx));
+        }
+
+        Ok((vx & 0xF) as usize)
+    }
+
+    // instructions that don't wrap around memory need `len` bytes from I
+    fn check_i_range(&self, len: usize) -> Result<(), Chip8Error> {
+        if self.i_reg as usize + len > self.memory.len() {
+            return Err(Chip8Error::MemoryOutOfBounds(self.i_reg));
+        }
+
+        Ok(())
+    }
+
+    /// Installs `hook`, replacing the previous one.
+    pub fn set_pre_exec_hook(&mut self, hook: PreExecHook) {
+        self.pre_exec_hook = Some(hook);
+    }
+
+    /// Installs `hook`, called after every instruction `tick` runs, replacing the previous one.
+    pub fn set_tick_hook(&mut self, hook: TickHook) {
+        self.tick_hook = Some(hook);
+    }
+
+    pub fn remove_tick_hook(&mut self) {
+        self.tick_hook = None;
+    }
+
+    /// How many instructions ran since the vertical blank, the index the next one gets.
+    pub fn frame_ticks(&self) -> u32 {
+        self.frame_ticks
+    }
+
+    /// Runs every instruction through `Reference` as well and panics, with what differs,
+    /// as soon as the two disagree. Slow, and only available in debug builds.
+    pub fn set_reference_check(&mut self, enabled: bool) {
+        self.reference_check = enabled && cfg!(debug_assertions);
+    }
+
+    /// Runs the instruction at PC and reports what it did, nothing when it didn't run
+    /// (waiting for the vertical blank, skipped by the pre-execution hook...). A game that
+    /// does something no interpreter can run stops with an error instead, see `Chip8Error`.
+    pub fn tick(&mut self) -> Result<Effect, Chip8Error> {
+        // the COSMAC VIP drew sprites during the vertical blank, the rest of the frame is lost
+        if self.vblank_wait {
+            return Ok(Effect::default());
+        }
+
+        match self.run_pre_exec_hook() {
+            HookAction::Continue => (),
+            HookAction::Skip => {
+                self.skip_next();
+                return Ok(Effect::default());
+            }
+            HookAction::Break => {
+                self.resume_pc = Some(self.pc);
+                self.break_requested = true;
+                return Ok(Effect::default());
+            }
+        }
+
+        let reference = self.reference_check.then(|| Reference::of(self));
+
+        // Fetch
+        let pc = self.pc;
+        let op = self.get_operation_code();
+
+        // Decode & Execute
+        let effect = match self.run_instruction(op) {
+            Ok(effect) => effect,
+            Err(err) => {
+                self.pc = pc;
+                return Err(err);
+            }
+        };
+
+        if let Some(mut reference) = reference {
+            reference.step(&self.keyboard, self.quirks);
+
+            if let Some(difference) = reference.difference(&Reference::of(self)) {
+                panic!(
+                    "{op:04X} at {:03X} disagrees with the reference interpreter: {difference}",
+                    self.pc.wrapping_sub(2)
+                );
+            }
+        }
+
+        let index = self.frame_ticks;
+
+        self.frame_ticks += 1;
+
+        // taken out while it runs, it borrows the whole machine
+        if let Some(mut hook) = self.tick_hook.take() {
+            hook(self, index, &effect);
+            self.tick_hook = Some(hook);
+        }
+
+        Ok(effect)
+    }
+
+    /// Runs `op` as if it were the instruction at PC, whatever memory holds there, and
+    /// reports what it did, for test vectors and fuzzers that feed opcodes one at a
+    /// time. PC moves on as after any instruction, so a stream of opcodes runs like a
+    /// program at PC; jumps and skips only move PC. F000 still takes its address from the
+    /// memory after PC, and skipping over it still looks there. The pre-execution hook,
+    /// the reference check and the wait for the vertical blank are left out. A failing
+    /// opcode leaves PC where it was, see `tick`.
+    pub fn execute_op(&mut self, op: u16) -> Result<Effect, Chip8Error> {
+        let pc = self.pc;
+
+        self.pc = self.pc.wrapping_add(2);
+
+        self.run_instruction(op).inspect_err(|_| self.pc = pc)
+    }
+
+    // executes `op` with PC already past it. The registers, timers and such are compared
+    // with their values before, which is cheaper than recording every write to them
+    fn run_instruction(&mut self, op: u16) -> Result<Effect, Chip8Error> {
+        let (v_reg, i_reg, pc, stack_pointer) =
+            (self.v_reg, self.i_reg, self.pc, self.stack_pointer);
+        let (delay_timer, sound_timer) = (self.delay_timer_reg, self.sound_timer_reg);
+        let (resolution, selected_planes) = (self.resolution, self.selected_planes);
+        let (audio_pattern, pitch) = (self.audio_pattern, self.pitch);
+
+        self.effect = Effect::default();
+        self.execute(op)?;
+
+        let mut effect = self.effect;
+
+        effect.registers = (0..NUM_REGS)
+            .filter(|reg| v_reg[*reg] != self.v_reg[*reg])
+            .fold(0, |registers, reg| registers | 1 << reg);
+        effect.i_register = (i_reg != self.i_reg).then_some(self.i_reg);
+
+        // F000 is followed by its address
+        let next = pc.wrapping_add(if op == 0xF000 { 2 } else { 0 });
+        effect.jumped =
+            self.pc != next && !effect.skipped && !effect.waits_for_key && !effect.halted;
+
+        effect.pushed =
+            (self.stack_pointer > stack_pointer).then(|| self.stack[stack_pointer as usize]);
+        effect.popped =
+            (self.stack_pointer < stack_pointer).then(|| self.stack[self.stack_pointer as usize]);
+        effect.delay_timer = (delay_timer != self.delay_timer_reg).then_some(self.delay_timer_reg);
+        effect.sound_timer = (sound_timer != self.sound_timer_reg).then_some(self.sound_timer_reg);
+        effect.sound_started = sound_timer == 0 && self.sound_timer_reg > 0;
+        effect.resolution = (resolution != self.resolution).then_some(self.resolution);
+        effect.selected_planes =
+            (selected_planes != self.selected_planes).then_some(self.selected_planes);
+        effect.audio = audio_pattern != self.audio_pattern || pitch != self.pitch;
+
+        Ok(effect)
+    }
+
+    fn run_pre_exec_hook(&mut self) -> HookAction {
+        // continuing after a break runs the instruction it broke on
+        if self.resume_pc.take() == Some(self.pc) {
+            return HookAction::Continue;
+        }
+
+        // taken out while it runs, it borrows the whole machine
+        let Some(mut hook) = self.pre_exec_hook.take() else {
+            return HookAction::Continue;
+        };
+
+        let action = hook(self, self.peek_operation_code());
+
+        self.pre_exec_hook = Some(hook);
+
+        action
+    }
+
+    fn get_operation_code(&mut self) -> u16 {
+        let op = self.peek_operation_code();
+
+        if let Some(accesses) = &mut self.memory_accesses {
+            accesses.execute(self.pc as usize);
+        }
+
+        //increment pc by 2 bytes to factor in program counter
+        self.pc = self.pc.wrapping_add(2);
+
+        op
+    }
+
+    fn peek_operation_code(&self) -> u16 {
+        let len = self.memory.len();
+
+        let higher_byte = self.memory[self.pc as usize % len] as u16;
+        let lower_byte = self.memory[(self.pc as usize + 1) % len] as u16;
+
+        // << is a left shift by 8 bits, filling the remaining digits with 0s
+        // | is a bitwise or operation that performs boolean OR on each bit of integer arguments
+        // we are effectively combining the values as Big Endian
+        (higher_byte << 8) | lower_byte
+    }
+
+    // moves PC past the instruction it points at, XO-CHIP's long LD I is two words
+    fn skip_next(&mut self) {
+        self.effect.skipped = true;
+        let len = if self.peek_operation_code() == 0xF000 {
+            4
+        } else {
+            2
+        };
+
+        self.pc = self.pc.wrapping_add(len);
+    }
+
+    // Vx to Vy for XO-CHIP's 5XY2/5XY3, backwards when x is larger
+    fn register_range(x: usize, y: usize) -> Vec<usize> {
+        if x <= y {
+            (x..=y).collect()
+        } else {
+            (y..=x).rev().collect()
+        }
+    }
+
+    fn execute(&mut self, op: u16) -> Result<(), Chip8Error> {
+        let digit1 = (op & 0xF000) >> 12;
+        let digit2 = (op & 0x0F00) >> 8;
+        let digit3 = (op & 0x00F0) >> 4;
+        let digit4 = op & 0x000F;
+
+        match (digit1, digit2, digit3, digit4) {
+            // 0000 - No Operation, or the empty memory after the program
+            (0, 0, 0, 0) => {
+                let addr = self.pc.wrapping_sub(2);
+
+                if self.quirks.strict_end && addr as usize >= self.program_end {
+                    return Err(Chip8Error::RanOffEnd(addr));
+                }
+            }
+
+            // 00E0 - Clear display (the selected planes on XO-CHIP)
+            (0, 0, 0xE, 0) => {
+                for plane in self.selected_planes().collect::<Vec<_>>() {
+                    self.planes[plane].fill(false);
+                }
+
+                self.effect.screen = true;
+            }
+
+            // 00Cn - SCD nibble (SUPER-CHIP: scroll the display down n rows.)
+            (0, 0, 0xC, _) => {
+                self.scroll(0, digit4 as isize);
+            }
+
+            // 00Dn - SCU nibble (XO-CHIP: scroll the display up n rows.)
+            (0, 0, 0xD, _) => {
+                self.scroll(0, -(digit4 as isize));
+            }
+
+            // 00FB - SCR (SUPER-CHIP: scroll the display right 4 pixels.)
+            (0, 0, 0xF, 0xB) => {
+                self.scroll(4, 0);
+            }
+
+            // 00FC - SCL (SUPER-CHIP: scroll the display left 4 pixels.)
+            (0, 0, 0xF, 0xC) => {
+                self.scroll(-4, 0);
+            }
+
+            // 00FD - EXIT (SUPER-CHIP: stop the interpreter, the machine stays on this instruction.)
+            (0, 0, 0xF, 0xD) => {
+                self.pc = self.pc.wrapping_sub(2);
+                self.effect.halted = true;
+            }
+
+            // 00FE - LOW (SUPER-CHIP: switch to the 64x32 display, which clears it.)
+            (0, 0, 0xF, 0xE) => {
+                self.set_resolution(Resolution::LORES);
+            }
+
+            // 00FF - HIGH (SUPER-CHIP: switch to the 128x64 display, which clears it.)
+            (0, 0, 0xF, 0xF) => {
+                self.set_resolution(Resolution::SCHIP);
+            }
+
+            // 00EE - RET (Return from a subroutine.)
+            (0, 0, 0xE, 0xE) => {
+                let ret_addr = self.pop()?;
+                self.pc = ret_addr;
+            }
+
+            // 1nnn - JP addr (Jump to location nnn.)
+            (1, _, _, _) => {
+                let nnn = op & 0xFFF;
+                self.pc = nnn;
+            }
+
+            // 2nnn - CALL addr (Call subroutine at nnn.)
+            (2, _, _, _) => {
+                let nnn = op & 0xFFF;
+                self.push(self.pc)?;
+                self.pc = nnn;
+            }
+
+            // 3xkk - SE Vx, (byte Skip next instruction if Vx = kk.)
+            (3, _, _, _) => {
+                let x = digit2 as usize;
+                let nn = (op & 0xFF) as u8;
+
+                if self.v_reg[x] == nn {
+                    self.skip_next();
+                }
+            }
+
+            // 4xkk - SNE Vx, byte (Skip next instruction if Vx != kk.)
+            (4, _, _, _) => {
+                let x = digit2 as usize;
+                let nn = (op & 0xFF) as u8;
+                if self.v_reg[x] != nn {
+                    self.skip_next();
+                }
+            }
+
+            // 5xy0 - SE Vx, Vy (Skip next instruction if Vx = Vy.)
+            (5, _, _, 0) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                if self.v_reg[x] == self.v_reg[y] {
+                    self.skip_next();
+                }
+            }
+
+            // 5xy2 - LD [I], Vx-Vy (XO-CHIP: store Vx through Vy in memory starting at location I, I is unchanged.)
+            (5, _, _, 2) => {
+                let regs = Self::register_range(digit2 as usize, digit3 as usize);
+                let len = self.memory.len();
+
+                for (offset, reg) in regs.iter().enumerate() {
+                    self.memory[(self.i_reg as usize + offset) % len] = self.v_reg[*reg];
+                }
+
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.write(self.i_reg as usize, regs.len());
+                }
+
+                self.effect.memory = Some((self.i_reg, regs.len()));
+
+                self.notify_watches(
+                    AccessKind::Write,
+                    self.i_reg as usize,
+                    regs.len(),
+                    self.pc.wrapping_sub(2),
+                );
+            }
+
+            // 5xy3 - LD Vx-Vy, [I] (XO-CHIP: read Vx through Vy from memory starting at location I, I is unchanged.)
+            (5, _, _, 3) => {
+                let regs = Self::register_range(digit2 as usize, digit3 as usize);
+                let len = self.memory.len();
+
+                for (offset, reg) in regs.iter().enumerate() {
+                    self.v_reg[*reg] = self.memory[(self.i_reg as usize + offset) % len];
+                }
+
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.read(self.i_reg as usize, regs.len());
+                }
+
+                self.notify_watches(
+                    AccessKind::Read,
+                    self.i_reg as usize,
+                    regs.len(),
+                    self.pc.wrapping_sub(2),
+                );
+            }
+
+            // 6xkk - LD Vx, byte (Set Vx = kk.)
+            (6, _, _, _) => {
+                let x = digit2 as usize;
+                let nn = (op & 0xFF) as u8;
+
+                self.v_reg[x] = nn
+            }
+
+            // 7xkk - ADD Vx, byte (Set Vx = Vx + kk.)
+            (7, _, _, _) => {
+                let x = digit2 as usize;
+                let nn = (op & 0xFF) as u8;
+
+                self.v_reg[x] = self.v_reg[x].wrapping_add(nn);
+            }
+
+            // 8xy0 - LD Vx, Vy (Set Vx = Vy.)
+            (8, _, _, 0) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                self.v_reg[x] = self.v_reg[y];
+            }
+
+            // 8xy1 - OR Vx, Vy (Set Vx = Vx OR Vy.)
+            (8, _, _, 1) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                // bitwise OR
+                self.v_reg[x] |= self.v_reg[y];
+
+                if self.quirks.vf_reset {
+                    self.v_reg[0xF] = 0;
+                }
+            }
+
+            // 8xy2 - AND Vx, Vy (Set Vx = Vx AND Vy.)
+            (8, _, _, 2) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                // bitwise AND
+                self.v_reg[x] &= self.v_reg[y];
+
+                if self.quirks.vf_reset {
+                    self.v_reg[0xF] = 0;
+                }
+            }
+
+            // 8xy3 - XOR Vx, Vy (Set Vx = Vx XOR Vy.)
+            (8, _, _, 3) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                // bitwise XOR
+                self.v_reg[x] ^= self.v_reg[y];
+
+                if self.quirks.vf_reset {
+                    self.v_reg[0xF] = 0;
+                }
+            }
+
+            // 8xy4 - ADD Vx, Vy (Set Vx = Vx + Vy, set VF = carry.)
+            (8, _, _, 4) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                let (new_vx, carry) = self.v_reg[x].overflowing_add(self.v_reg[y]);
+                let new_vf = if carry { 1 } else { 0 };
+
+                self.v_reg[x] = new_vx;
+                self.v_reg[0xF] = new_vf;
+            }
+
+            // 8xy5 - SUB Vx, Vy (Set Vx = Vx - Vy, set VF = NOT borrow.)
+            (8, _, _, 5) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                let (new_vx, borrow) = self.v_reg[x].overflowing_sub(self.v_reg[y]);
+                let new_vf = if borrow { 0 } else { 1 };
+
+                self.v_reg[x] = new_vx;
+                self.v_reg[0xF] = new_vf;
+            }
+
+            // 8xy6 - SHR Vx {, Vy} (Set Vx = Vx SHR 1.)
+            (8, _, _, 6) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                if self.quirks.vy_shift {
+                    self.v_reg[x] = self.v_reg[y];
+                }
+
+                // get the least significant bit
+                let lsb = self.v_reg[x] & 1;
+
+                // right shift by 1 (equivalent to dividing by 2)
+                self.v_reg[x] >>= 1;
+
+                self.v_reg[0xF] = lsb;
+            }
+
+            // 8xy7 - SUBN Vx, Vy (Set Vx = Vy - Vx, set VF = NOT borrow.)
+            (8, _, _, 7) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                let (new_vx, borrow) = self.v_reg[y].overflowing_sub(self.v_reg[x]);
+                let new_vf = if borrow { 0 } else { 1 };
+
+                self.v_reg[x] = new_vx;
+                self.v_reg[0xF] = new_vf;
+            }
+
+            // 8xyE - SHL Vx {, Vy} (Set Vx = Vx SHL 1.)
+            (8, _, _, 0xE) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                if self.quirks.vy_shift {
+                    self.v_reg[x] = self.v_reg[y];
+                }
+
+                // get the most significant bit
+                let msb = (self.v_reg[x] >> 7) & 1;
+
+                // left shift by 1 (equivalent to multiplying by 2)
+                self.v_reg[x] <<= 1;
+
+                self.v_reg[0xF] = msb;
+            }
+
+            // 9xy0 - SNE Vx, Vy (Skip next instruction if Vx != Vy.)
+            (9, _, _, 0) => {
+                let x = digit2 as usize;
+                let y = digit3 as usize;
+
+                if self.v_reg[x] != self.v_reg[y] {
+                    self.skip_next();
+                }
+            }
+
+            // Annn - LD I, addr (Set I = nnn.)
+            (0xA, _, _, _) => {
+                let nnn = op & 0xFFF;
+
+                self.i_reg = nnn;
+            }
+
+            // Bnnn - JP V0, addr (Jump to location nnn + V0.)
+            (0xB, _, _, _) => {
+                let nnn = op & 0xFFF;
+
+                // BXNN - JP VX, addr on SUPER-CHIP
+                let offset = if self.quirks.jump_vx {
+                    self.v_reg[digit2 as usize]
+                } else {
+                    self.v_reg[0]
+                };
+
+                self.pc = (offset as u16) + nnn;
+            }
+
+            // Cxkk - RND Vx, byte (Set Vx = random byte AND kk.)
+            (0xC, _, _, _) => {
+                let x = digit2 as usize;
+                let kk = (op & 0xFF) as u8;
+
+                let number = self.next_random_byte();
+
+                self.v_reg[x] = number & kk;
+            }
+
+            // Dxyn - DRW Vx, Vy, nibble (Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.)
+            // Dxy0 draws a 16x16 sprite of 32 bytes instead, two per row (SUPER-CHIP).
+            // XO-CHIP draws on each selected plane, with the sprite of the next one right after.
+            (0xD, _, _, _) => {
+                // Get the (x, y) coords for our sprite
+                let x_coord = self.v_reg[digit2 as usize] as u16;
+                let y_coord = self.v_reg[digit3 as usize] as u16;
+
+                // The last digit determines how many rows high our sprite is
+                let (num_of_rows_in_sprite, sprite_width) = match digit4 {
+                    0 => (16, 16),
+                    n => (n, 8),
+                };
+                let bytes_per_row = sprite_width as u16 / 8;
+
+                // Keep track if any pixels were flipped
+                let mut flipped = false;
+
+                let Resolution { width, height } = self.resolution;
+
+                let planes: Vec<usize> = self.selected_planes().collect();
+                let sprite_len = (num_of_rows_in_sprite * bytes_per_row) as usize;
+                let len = sprite_len * planes.len();
+
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.read(self.i_reg as usize, len);
+                }
+
+                self.notify_watches(
+                    AccessKind::Read,
+                    self.i_reg as usize,
+                    len,
+                    self.pc.wrapping_sub(2),
+                );
+
+                // PC already points past the instruction
+                if let Some(stats) = &mut self.draw_stats {
+                    stats.draw(self.pc.wrapping_sub(2), num_of_rows_in_sprite);
+                }
+
+                let memory_len = self.memory.len();
+
+                for (n, plane) in planes.into_iter().enumerate() {
+                    let sprite = self.i_reg as usize + n * sprite_len;
+
+                    // Iterate over each row of our sprite
+                    for y_line in 0..num_of_rows_in_sprite {
+                        // Determine which memory address our row's data is stored, the
+                        // leftmost pixel ends up in the top bit
+                        let addr = sprite + (y_line * bytes_per_row) as usize;
+                        let pixels = match bytes_per_row {
+                            2 => u16::from_be_bytes([
+                                self.memory[addr % memory_len],
+                                self.memory[(addr + 1) % memory_len],
+                            ]),
+                            _ => (self.memory[addr % memory_len] as u16) << 8,
+                        };
+
+                        // Iterate over each column in our row (8 or 16 pixels wide)
+                        for x_line in 0..sprite_width {
+                            // Use a mask to fetch current pixel's bit. Only flip if a 1
+                            if (pixels & (0x8000 >> x_line)) != 0 {
+                                // the sprite starts on screen, its parts past the edges are
+                                // cut or wrap around depending on the quirks
+                                let x = (x_coord as usize % width) + x_line as usize;
+                                let y = (y_coord as usize % height) + y_line as usize;
+
+                                if self.quirks.clip && (x >= width || y >= height) {
+                                    continue;
+                                }
+
+                                let x = x % width;
+                                let y = y % height;
+
+                                // Get our pixel's index in the 1D screen array
+                                let idx = x + width * y;
+
+                                if let Some(stats) = &mut self.draw_stats {
+                                    stats.pixel(idx);
+                                }
+
+                                // Check if we're about to flip the pixel and set
+                                flipped |= self.planes[plane][idx];
+
+                                self.planes[plane][idx] ^= true;
+                                self.effect.screen = true;
+                            }
+                        }
+                    }
+                }
+
+                // Populate VF register
+                if flipped {
+                    self.v_reg[0xF] = 1;
+                } else {
+                    self.v_reg[0xF] = 0;
+                }
+
+                self.vblank_wait = self.quirks.display_wait;
+                self.effect.waits_for_vblank = self.vblank_wait;
+            }
+
+            // Ex9E - SKP Vx (Skip next instruction if key with the value of Vx is pressed.)
+            (0xE, _, 9, 0xE) => {
+                let x = digit2 as usize;
+
+                let vx = self.v_reg[x];
+
+                let key = self.keyboard[self.key_index(vx)?];
+
+                if key {
+                    self.skip_next();
+                }
+            }
+
+            // ExA1 - SKNP Vx (Skip next instruction if key with the value of Vx is not pressed.)
+            (0xE, _, 0xA, 1) => {
+                let x = digit2 as usize;
+
+                let vx = self.v_reg[x];
+
+                let key = self.keyboard[self.key_index(vx)?];
+
+                if !key {
+                    self.skip_next();
+                }
+            }
+
+            // F000 nnnn - LD I, LONG addr (XO-CHIP: set I = the 16-bit address in the next two bytes.)
+            (0xF, 0, 0, 0) => {
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.execute(self.pc as usize);
+                }
+
+                self.i_reg = self.peek_operation_code();
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            // Fn01 - PLANE n (XO-CHIP: select the planes drawn on, scrolled and cleared, a bit each.)
+            (0xF, _, 0, 1) => {
+                self.selected_planes = digit2 as u8 & 0b11;
+            }
+
+            // F002 - LD AUDIO, [I] (XO-CHIP: load the 16 byte audio pattern starting at location I.)
+            (0xF, 0, 0, 2) => {
+                let len = self.memory.len();
+                let mut pattern = [0; AUDIO_PATTERN_SIZE];
+
+                for (offset, byte) in pattern.iter_mut().enumerate() {
+                    *byte = self.memory[(self.i_reg as usize + offset) % len];
+                }
+
+                self.audio_pattern = Some(pattern);
+
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.read(self.i_reg as usize, AUDIO_PATTERN_SIZE);
+                }
+
+                self.notify_watches(
+                    AccessKind::Read,
+                    self.i_reg as usize,
+                    AUDIO_PATTERN_SIZE,
+                    self.pc.wrapping_sub(2),
+                );
+            }
+
+            // Fx3A - LD PITCH, Vx (XO-CHIP: set the audio pattern's playback pitch = Vx.)
+            (0xF, _, 3, 0xA) => {
+                self.pitch = self.v_reg[digit2 as usize];
+            }
+
+            // Fx07 - LD Vx, DT (Set Vx = delay timer value.)
+            (0xF, _, 0, 7) => {
+                let x = digit2 as usize;
+
+                self.v_reg[x] = self.delay_timer_reg
+            }
+
+            // Fx0A - LD Vx, K (Wait for a key press, store the value of the key in Vx.)
+            (0xF, _, 0, 0xA) => {
+                let x = digit2 as usize;
+
+                let mut is_key_pressed = false;
+
+                // check if a key is pressed
+                for i in 0..self.keyboard.len() {
+                    if self.keyboard[i] {
+                        self.v_reg[x] = i as u8;
+
+                        is_key_pressed = true;
+
+                        break;
+                    }
+                }
+
+                // if no key is pressed redo operation
+                if !is_key_pressed {
+                    self.pc = self.pc.wrapping_sub(2);
+                    self.effect.waits_for_key = true;
+                }
+            }
+
+            // Fx15 - LD DT, Vx (Set delay timer = Vx.)
+            (0xF, _, 1, 5) => {
+                let x = digit2 as usize;
+
+                self.delay_timer_reg = self.v_reg[x];
+            }
+
+            // Fx18 - LD ST, Vx (Set sound timer = Vx.)
+            (0xF, _, 1, 8) => {
+                let x = digit2 as usize;
+
+                self.sound_timer_reg = self.v_reg[x];
+            }
+
+            // Fx1E - ADD I, Vx (Set I = I + Vx.)
+            (0xF, _, 1, 0xE) => {
+                let x = digit2 as usize;
+
+                self.i_reg = self.i_reg.wrapping_add(self.v_reg[x] as u16);
+            }
+
+            // Fx29 - LD F, Vx (Set I = location of sprite for digit Vx.)
+            (0xF, _, 2, 9) => {
+                let x = digit2 as usize;
+
+                self.i_reg = (self.v_reg[x] as u16) * 5;
+            }
+
+            // Fx30 - LD HF, Vx (SUPER-CHIP: set I = location of the 8x10 sprite for digit Vx.)
+            (0xF, _, 3, 0) => {
+                let x = digit2 as usize;
+
+                self.i_reg = (BIG_FONTSET_ADDR + (self.v_reg[x] as usize & 0xF) * 10) as u16;
+            }
+
+            // Fx33 - LD B, Vx (Store BCD representation of Vx in memory locations I, I+1, and I+2.)
+            (0xF, _, 3, 3) => {
+                let x = digit2 as usize;
+                let vx = self.v_reg[x] as f32;
+
+                // Fetch the hundreds digit by dividing by 100 and tossing the decimal
+                let hundreds = (vx / 100.0).floor() as u8;
+
+                // Fetch the tens digit by dividing by 10, tossing the ones digit and the decimal
+                let tens = ((vx / 10.0) % 10.0).floor() as u8;
+
+                // Fetch the ones digit by tossing the hundreds and the tens
+                let ones = (vx % 10.0) as u8;
+
+                self.check_i_range(3)?;
+
+                self.memory[self.i_reg as usize] = hundreds;
+                self.memory[self.i_reg as usize + 1] = tens;
+                self.memory[self.i_reg as usize + 2] = ones;
+
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.write(self.i_reg as usize, 3);
+                }
+
+                self.effect.memory = Some((self.i_reg, 3));
+
+                self.notify_watches(
+                    AccessKind::Write,
+                    self.i_reg as usize,
+                    3,
+                    self.pc.wrapping_sub(2),
+                );
+            }
+
+            // Fx55 - LD [I], Vx (Store registers V0 through Vx in memory starting at location I.)
+            (0xF, _, 5, 5) => {
+                let x = digit2 as usize;
+
+                self.check_i_range(x + 1)?;
+
+                for i in 0..=x {
+                    self.memory[(self.i_reg as usize) + i] = self.v_reg[i]
+                }
+
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.write(self.i_reg as usize, x + 1);
+                }
+
+                self.effect.memory = Some((self.i_reg, x + 1));
+
+                self.notify_watches(
+                    AccessKind::Write,
+                    self.i_reg as usize,
+                    x + 1,
+                    self.pc.wrapping_sub(2),
+                );
+
+                if self.quirks.memory_increment {
+                    self.i_reg = self.i_reg.wrapping_add(x as u16 + 1);
+                }
+            }
+
+            // Fx65 - LD Vx, [I] (Read registers V0 through Vx from memory starting at location I.)
+            (0xF, _, 6, 5) => {
+                let x = digit2 as usize;
+
+                self.check_i_range(x + 1)?;
+
+                for i in 0..=x {
+                    self.v_reg[i] = self.memory[(self.i_reg as usize) + i];
+                }
+
+                if let Some(accesses) = &mut self.memory_accesses {
+                    accesses.read(self.i_reg as usize, x + 1);
+                }
+
+                self.notify_watches(
+                    AccessKind::Read,
+                    self.i_reg as usize,
+                    x + 1,
+                    self.pc.wrapping_sub(2),
+                );
+
+                if self.quirks.memory_increment {
+                    self.i_reg = self.i_reg.wrapping_add(x as u16 + 1);
+                }
+            }
+
+            // Fx75 - LD R, Vx (SUPER-CHIP: store V0 through Vx in the RPL flags, x <= 7.)
+            (0xF, _, 7, 5) => {
+                let x = (digit2 as usize).min(NUM_RPL_FLAGS - 1);
+
+                self.rpl_flags[..=x].copy_from_slice(&self.v_reg[..=x]);
+            }
+
+            // Fx85 - LD Vx, R (SUPER-CHIP: read V0 through Vx from the RPL flags, x <= 7.)
+            (0xF, _, 8, 5) => {
+                let x = (digit2 as usize).min(NUM_RPL_FLAGS - 1);
+
+                self.v_reg[..=x].copy_from_slice(&self.rpl_flags[..=x]);
+            }
+
+            // FFF0-FFF3 - developer debug instructions, see `execute_debug_op`
+            (0xF, 0xF, 0xF, 0..=3) => {
+                if self.dev_mode {
+                    self.execute_debug_op(op);
+                }
+            }
+
+            (_, _, _, _) => return Err(Chip8Error::UnknownOpcode(op)),
+        }
+
+        Ok(())
+    }
+
+    // Not part of any CHIP-8 variant, only interpreted in dev mode so homebrew authors can
+    // instrument their ROMs. PC already points past the instruction.
+    fn execute_debug_op(&mut self, op: u16) {
+        let addr = self.pc.wrapping_sub(2);
+
+        match op {
+            // FFF0 - BRK (Pause the emulator.)
+            0xFFF0 => {
+                println!("[{addr:03X}] BRK");
+                self.break_requested = true;
+            }
+
+            // FFF1 - PRINT (Print the registers.)
+            0xFFF1 => {
+                let registers: Vec<String> = self
+                    .v_reg
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| format!("V{i:X}={v:02X}"))
+                    .collect();
+
+                println!(
+                    "[{addr:03X}] PRINT I={:03X} DT={:02X} ST={:02X} {}",
+                    self.i_reg,
+                    self.delay_timer_reg,
+                    self.sound_timer_reg,
+                    registers.join(" ")
+                );
+            }
+
+            // FFF2 - ASSERT VF (Pause the emulator if VF is 0.)
+            0xFFF2 => {
+                if self.v_reg[0xF] == 0 {
+                    println!("[{addr:03X}] ASSERT failed: VF is 0");
+                    self.break_requested = true;
+                }
+            }
+
+            // FFF3 - PRINTS [I] (Print the zero terminated ASCII string at I.)
+            _ => {
+                let text: String = self
+                    .memory
+                    .get(self.i_reg as usize..)
+                    .unwrap_or_default()
+                    .iter()
+                    .take_while(|byte| **byte != 0)
+                    .map(|byte| *byte as char)
+                    .collect();
+
+                println!("[{addr:03X}] {text}");
+            }
+        }
+    }
+
+    /// Runs a frame of `instructions` instructions, stopping at one that fails, and then
+    /// counts the timers down, what a frontend does 60 times a second. Reports what the
+    /// instructions did together, see `Effect::merge`.
+    pub fn run_frame(&mut self, instructions: u32) -> Result<Effect, Chip8Error> {
+        let mut effect = Effect::default();
+        let ran = (0..instructions).try_for_each(|_| self.tick().map(|tick| effect.merge(tick)));
+
+        self.tick_timers();
+
+        ran.map(|_| effect)
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.end_frame();
+
+        if self.delay_timer_reg > 0 {
+            self.delay_timer_reg -= 1;
+        }
+
+        if self.sound_timer_reg > 0 {
+            // the whole remaining time is queued, the output plays it even if it only gets to
+            // it after the timer ran out
+            if let Some(buzzer) = self.buzzer.as_ref().filter(|_| self.sound_enabled) {
+                let duration =
+                    Duration::from_secs_f64(self.sound_timer_reg as f64 / FRAMES_PER_SECOND as f64);
+
+                match &self.audio_pattern {
+                    Some(pattern) => buzzer.play_pattern(duration, pattern, self.pitch),
+                    None => buzzer.beep(duration),
+                }
+            }
+
+            self.sound_timer_reg -= 1;
+        }
+    }
+
+    /// The vertical blank of a frame that leaves the timers alone, e.g. one of the frames
+    /// fast forwarded with realtime timers: sprites waiting for it get drawn.
+    pub fn end_frame(&mut self) {
+        self.random_source.frame(&mut self.rng_state);
+        self.vblank_wait = false;
+        self.frame_ticks = 0;
+
+        if let Some(stats) = &mut self.draw_stats {
+            stats.end_frame();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // runs `op` with V0 = 1F and key F down, returning its result and the machine after it
+    fn run_with_key_1f(op: u16, strict_keys: bool) -> (Result<Effect, Chip8Error>, Chip8) {
+        let mut chip8 = Chip8::new();
+        let [high, low] = op.to_be_bytes();
+
+        chip8.set_quirks(Quirks {
+            strict_keys,
+            ..Quirks::default()
+        });
+        chip8.load(&[0x60, 0x1F, high, low]).unwrap();
+        chip8.keypress(0xF, true);
+
+        chip8.tick().unwrap();
+        let result = chip8.tick();
+
+        (result, chip8)
+    }
+
+    #[test]
+    fn keys_past_f_are_masked_to_their_low_nibble() {
+        // key F is down, so SKP skips and SKNP doesn't
+        let (result, chip8) = run_with_key_1f(0xE09E, false);
+        assert!(result.is_ok());
+        assert_eq!(chip8.get_program_counter(), START_ADDR + 6);
+
+        let (result, chip8) = run_with_key_1f(0xE0A1, false);
+        assert!(result.is_ok());
+        assert_eq!(chip8.get_program_counter(), START_ADDR + 4);
+    }
+
+    #[test]
+    fn strict_keys_rejects_keys_past_f() {
+        for op in [0xE09E, 0xE0A1] {
+            let (result, _) = run_with_key_1f(op, true);
+
+            assert_eq!(result, Err(Chip8Error::InvalidKey(0x1F)));
+        }
+    }
+
+    // runs the first instruction of `program` loaded at `addr`, near the end of XO-CHIP's 64KB
+    fn run_at(addr: u16, program: &[u8]) -> Chip8 {
+        let mut chip8 = Chip8::new();
+
+        chip8.set_start_addr(addr);
+        chip8.load(program).unwrap();
+        chip8.tick().unwrap();
+
+        chip8
+    }
+
+    #[test]
+    fn pc_wraps_around_the_end_of_memory() {
+        // SE V0, 0 skips past the end back to the start
+        assert_eq!(run_at(0xFFFE, &[0x30, 0x00]).get_program_counter(), 0x0002);
+
+        // EXIT stays on itself
+        assert_eq!(run_at(0xFFFE, &[0x00, 0xFD]).get_program_counter(), 0xFFFE);
+
+        // the long LD I's operand is the last word
+        let chip8 = run_at(0xFFFC, &[0xF0, 0x00, 0x12, 0x34]);
+        assert_eq!(chip8.get_i_register(), 0x1234);
+        assert_eq!(chip8.get_program_counter(), 0x0000);
+    }
+
+    #[test]
+    fn wait_for_key_overwrites_vx_with_either_quirk() {
+        // FX0A writes VX instead of reading it, so a VX past F is no invalid key
+        for strict_keys in [false, true] {
+            let (result, chip8) = run_with_key_1f(0xF00A, strict_keys);
+
+            assert!(result.is_ok());
+            assert_eq!(chip8.get_registers()[0], 0xF);
+            assert_eq!(chip8.get_program_counter(), START_ADDR + 4);
+        }
+    }
+
+    // a machine with a jump to itself at PC, which execute_op must not run
+    fn with_a_loop_at_pc() -> Chip8 {
+        let mut chip8 = Chip8::new();
+
+        chip8.load(&[0x12, 0x00, 0x12, 0x34]).unwrap();
+
+        chip8
+    }
+
+    #[test]
+    fn execute_op_runs_the_given_opcode_instead_of_memory() {
+        let mut chip8 = with_a_loop_at_pc();
+
+        let effect = chip8.execute_op(0x6A42).unwrap();
+
+        assert_eq!(chip8.get_registers()[0xA], 0x42);
+        assert_eq!(chip8.get_program_counter(), START_ADDR + 2);
+        assert_eq!(
+            effect,
+            Effect {
+                registers: 1 << 0xA,
+                ..Effect::default()
+            }
+        );
+    }
+
+    #[test]
+    fn execute_op_reports_jumps_skips_and_the_stack() {
+        let mut chip8 = with_a_loop_at_pc();
+
+        let effect = chip8.execute_op(0x2300).unwrap();
+        assert!(effect.jumped);
+        assert_eq!(effect.pushed, Some(START_ADDR + 2));
+        assert_eq!(chip8.get_program_counter(), 0x300);
+
+        // V0 is 0, so SE V0, 0 skips
+        let effect = chip8.execute_op(0x3000).unwrap();
+        assert!(effect.skipped && !effect.jumped);
+        assert_eq!(chip8.get_program_counter(), 0x304);
+
+        let effect = chip8.execute_op(0x00EE).unwrap();
+        assert!(effect.jumped);
+        assert_eq!(effect.popped, Some(START_ADDR + 2));
+        assert_eq!(chip8.get_program_counter(), START_ADDR + 2);
+    }
+
+    #[test]
+    fn execute_op_reports_timers_and_sound() {
+        let mut chip8 = with_a_loop_at_pc();
+
+        chip8.execute_op(0x6005).unwrap();
+
+        let effect = chip8.execute_op(0xF015).unwrap();
+        assert_eq!(effect.delay_timer, Some(5));
+
+        let effect = chip8.execute_op(0xF018).unwrap();
+        assert_eq!(effect.sound_timer, Some(5));
+        assert!(effect.sound_started);
+    }
+
+    #[test]
+    fn execute_op_reads_the_long_address_after_pc() {
+        let mut chip8 = with_a_loop_at_pc();
+
+        let effect = chip8.execute_op(0xF000).unwrap();
+
+        assert_eq!(effect.i_register, Some(0x1234));
+        assert!(!effect.jumped);
+        assert_eq!(chip8.get_program_counter(), START_ADDR + 4);
+    }
+
+    #[test]
+    fn execute_op_leaves_pc_on_a_failing_opcode() {
+        let mut chip8 = with_a_loop_at_pc();
+
+        assert_eq!(chip8.execute_op(0x00EE), Err(Chip8Error::StackUnderflow));
+        assert_eq!(
+            chip8.execute_op(0x5001),
+            Err(Chip8Error::UnknownOpcode(0x5001))
+        );
+        assert_eq!(chip8.get_program_counter(), START_ADDR);
+    }
+}
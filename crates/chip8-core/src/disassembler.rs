@@ -0,0 +1,179 @@
+use crate::core::Chip8;
+
+/// Converts an opcode into its mnemonic, e.g. `0x6312` -> `LD V3, 0x12`.
+/// Mnemonics follow Cowgod's Chip-8 Technical Reference, unknown opcodes are shown as raw data.
+pub fn disassemble(op: u16) -> String {
+    let digit1 = (op & 0xF000) >> 12;
+    let x = (op & 0x0F00) >> 8;
+    let y = (op & 0x00F0) >> 4;
+    let n = op & 0x000F;
+
+    let nnn = op & 0xFFF;
+    let kk = op & 0xFF;
+
+    match (digit1, x, y, n) {
+        (0, 0, 0, 0) => "NOP".to_string(),
+        (0, 0, 0xE, 0) => "CLS".to_string(),
+        (0, 0, 0xE, 0xE) => "RET".to_string(),
+        (0, 0, 0xC, _) => format!("SCD {n}"),
+        (0, 0, 0xD, _) => format!("SCU {n}"),
+        (0, 0, 0xF, 0xB) => "SCR".to_string(),
+        (0, 0, 0xF, 0xC) => "SCL".to_string(),
+        (0, 0, 0xF, 0xD) => "EXIT".to_string(),
+        (0, 0, 0xF, 0xE) => "LOW".to_string(),
+        (0, 0, 0xF, 0xF) => "HIGH".to_string(),
+        (1, _, _, _) => format!("JP {nnn:#05x}"),
+        (2, _, _, _) => format!("CALL {nnn:#05x}"),
+        (3, _, _, _) => format!("SE V{x:X}, {kk:#04x}"),
+        (4, _, _, _) => format!("SNE V{x:X}, {kk:#04x}"),
+        (5, _, _, 0) => format!("SE V{x:X}, V{y:X}"),
+        (5, _, _, 2) => format!("LD [I], V{x:X}-V{y:X}"),
+        (5, _, _, 3) => format!("LD V{x:X}-V{y:X}, [I]"),
+        (6, _, _, _) => format!("LD V{x:X}, {kk:#04x}"),
+        (7, _, _, _) => format!("ADD V{x:X}, {kk:#04x}"),
+        (8, _, _, 0) => format!("LD V{x:X}, V{y:X}"),
+        (8, _, _, 1) => format!("OR V{x:X}, V{y:X}"),
+        (8, _, _, 2) => format!("AND V{x:X}, V{y:X}"),
+        (8, _, _, 3) => format!("XOR V{x:X}, V{y:X}"),
+        (8, _, _, 4) => format!("ADD V{x:X}, V{y:X}"),
+        (8, _, _, 5) => format!("SUB V{x:X}, V{y:X}"),
+        (8, _, _, 6) => format!("SHR V{x:X}"),
+        (8, _, _, 7) => format!("SUBN V{x:X}, V{y:X}"),
+        (8, _, _, 0xE) => format!("SHL V{x:X}"),
+        (9, _, _, 0) => format!("SNE V{x:X}, V{y:X}"),
+        (0xA, _, _, _) => format!("LD I, {nnn:#05x}"),
+        (0xB, _, _, _) => format!("JP V0, {nnn:#05x}"),
+        (0xC, _, _, _) => format!("RND V{x:X}, {kk:#04x}"),
+        (0xD, _, _, _) => format!("DRW V{x:X}, V{y:X}, {n}"),
+        (0xE, _, 9, 0xE) => format!("SKP V{x:X}"),
+        (0xE, _, 0xA, 1) => format!("SKNP V{x:X}"),
+        (0xF, 0, 0, 0) => "LD I, LONG".to_string(),
+        (0xF, _, 0, 1) => format!("PLANE {x}"),
+        (0xF, 0, 0, 2) => "LD AUDIO, [I]".to_string(),
+        (0xF, _, 3, 0xA) => format!("LD PITCH, V{x:X}"),
+        (0xF, _, 0, 7) => format!("LD V{x:X}, DT"),
+        (0xF, _, 0, 0xA) => format!("LD V{x:X}, K"),
+        (0xF, _, 1, 5) => format!("LD DT, V{x:X}"),
+        (0xF, _, 1, 8) => format!("LD ST, V{x:X}"),
+        (0xF, _, 1, 0xE) => format!("ADD I, V{x:X}"),
+        (0xF, _, 2, 9) => format!("LD F, V{x:X}"),
+        (0xF, _, 3, 0) => format!("LD HF, V{x:X}"),
+        (0xF, _, 3, 3) => format!("LD B, V{x:X}"),
+        (0xF, _, 5, 5) => format!("LD [I], V{x:X}"),
+        (0xF, _, 6, 5) => format!("LD V{x:X}, [I]"),
+        (0xF, _, 7, 5) => format!("LD R, V{x:X}"),
+        (0xF, _, 8, 5) => format!("LD V{x:X}, R"),
+        (0xF, 0xF, 0xF, 0) => "BRK".to_string(),
+        (0xF, 0xF, 0xF, 1) => "PRINT".to_string(),
+        (0xF, 0xF, 0xF, 2) => "ASSERT VF".to_string(),
+        (0xF, 0xF, 0xF, 3) => "PRINTS [I]".to_string(),
+        (_, _, _, _) => format!("DW {op:#06x}"),
+    }
+}
+
+/// Describes the effect of an opcode in words, e.g. `0x6312` -> `set V3 to 0x12`.
+/// Used where the mnemonic alone would be too terse, like the learning mode.
+pub fn describe(op: u16) -> String {
+    let digit1 = (op & 0xF000) >> 12;
+    let x = (op & 0x0F00) >> 8;
+    let y = (op & 0x00F0) >> 4;
+    let n = op & 0x000F;
+
+    let nnn = op & 0xFFF;
+    let kk = op & 0xFF;
+
+    match (digit1, x, y, n) {
+        (0, 0, 0, 0) => "do nothing".to_string(),
+        (0, 0, 0xE, 0) => "clear the screen".to_string(),
+        (0, 0, 0xE, 0xE) => "return from the subroutine".to_string(),
+        (0, 0, 0xC, _) => format!("scroll the screen down {n} rows"),
+        (0, 0, 0xD, _) => format!("scroll the screen up {n} rows"),
+        (0, 0, 0xF, 0xB) => "scroll the screen right 4 pixels".to_string(),
+        (0, 0, 0xF, 0xC) => "scroll the screen left 4 pixels".to_string(),
+        (0, 0, 0xF, 0xD) => "stop the program".to_string(),
+        (0, 0, 0xF, 0xE) => "switch to the 64x32 screen".to_string(),
+        (0, 0, 0xF, 0xF) => "switch to the 128x64 screen".to_string(),
+        (1, _, _, _) => format!("jump to {nnn:#05x}"),
+        (2, _, _, _) => format!("call the subroutine at {nnn:#05x}"),
+        (3, _, _, _) => format!("skip the next instruction if V{x:X} is {kk:#04x}"),
+        (4, _, _, _) => format!("skip the next instruction if V{x:X} is not {kk:#04x}"),
+        (5, _, _, 0) => format!("skip the next instruction if V{x:X} is V{y:X}"),
+        (5, _, _, 2) => format!("store V{x:X} to V{y:X} at I"),
+        (5, _, _, 3) => format!("load V{x:X} to V{y:X} from I"),
+        (6, _, _, _) => format!("set V{x:X} to {kk:#04x}"),
+        (7, _, _, _) => format!("add {kk:#04x} to V{x:X}, no carry"),
+        (8, _, _, 0) => format!("set V{x:X} to V{y:X}"),
+        (8, _, _, 1) => format!("set V{x:X} to V{x:X} or V{y:X}"),
+        (8, _, _, 2) => format!("set V{x:X} to V{x:X} and V{y:X}"),
+        (8, _, _, 3) => format!("set V{x:X} to V{x:X} xor V{y:X}"),
+        (8, _, _, 4) => format!("add V{y:X} to V{x:X}, VF is the carry"),
+        (8, _, _, 5) => format!("subtract V{y:X} from V{x:X}, VF is 1 without borrow"),
+        (8, _, _, 6) => format!("shift V{x:X} right, VF is the bit shifted out"),
+        (8, _, _, 7) => format!("set V{x:X} to V{y:X} minus V{x:X}, VF is 1 without borrow"),
+        (8, _, _, 0xE) => format!("shift V{x:X} left, VF is the bit shifted out"),
+        (9, _, _, 0) => format!("skip the next instruction if V{x:X} is not V{y:X}"),
+        (0xA, _, _, _) => format!("set I to {nnn:#05x}"),
+        (0xB, _, _, _) => format!("jump to {nnn:#05x} plus V0"),
+        (0xC, _, _, _) => format!("set V{x:X} to a random number and {kk:#04x}"),
+        (0xD, _, _, 0) => format!("draw the 16x16 sprite at I at V{x:X}, V{y:X}"),
+        (0xD, _, _, _) => format!("draw {n} rows of the sprite at I at V{x:X}, V{y:X}"),
+        (0xE, _, 9, 0xE) => format!("skip the next instruction if key V{x:X} is down"),
+        (0xE, _, 0xA, 1) => format!("skip the next instruction if key V{x:X} is up"),
+        (0xF, 0, 0, 0) => "set I to the address in the next two bytes".to_string(),
+        (0xF, _, 0, 1) => format!("draw on the planes in {x:#04b}"),
+        (0xF, 0, 0, 2) => "load the audio pattern at I".to_string(),
+        (0xF, _, 3, 0xA) => format!("set the audio pitch to V{x:X}"),
+        (0xF, _, 0, 7) => format!("set V{x:X} to the delay timer"),
+        (0xF, _, 0, 0xA) => format!("wait for a key press and put it in V{x:X}"),
+        (0xF, _, 1, 5) => format!("set the delay timer to V{x:X}"),
+        (0xF, _, 1, 8) => format!("set the sound timer to V{x:X}"),
+        (0xF, _, 1, 0xE) => format!("add V{x:X} to I"),
+        (0xF, _, 2, 9) => format!("point I at the font digit in V{x:X}"),
+        (0xF, _, 3, 0) => format!("point I at the big font digit in V{x:X}"),
+        (0xF, _, 3, 3) => format!("store the decimal digits of V{x:X} at I"),
+        (0xF, _, 5, 5) => format!("store V0 to V{x:X} at I"),
+        (0xF, _, 6, 5) => format!("load V0 to V{x:X} from I"),
+        (0xF, _, 7, 5) => format!("store V0 to V{x:X} in the RPL flags"),
+        (0xF, _, 8, 5) => format!("load V0 to V{x:X} from the RPL flags"),
+        (0xF, 0xF, 0xF, 0) => "break into the debugger".to_string(),
+        (0xF, 0xF, 0xF, 1) => "print the registers".to_string(),
+        (0xF, 0xF, 0xF, 2) => "break if VF is 0".to_string(),
+        (0xF, 0xF, 0xF, 3) => "print the text at I".to_string(),
+        (_, _, _, _) => "not an instruction, just data".to_string(),
+    }
+}
+
+/// Reads the big endian opcode at `addr`, wrapping around the end of memory.
+pub fn opcode_at(memory: &[u8], addr: u16) -> u16 {
+    let addr = addr as usize;
+
+    let higher_byte = memory[addr % memory.len()] as u16;
+    let lower_byte = memory[(addr + 1) % memory.len()] as u16;
+
+    (higher_byte << 8) | lower_byte
+}
+
+/// Every instruction of `rom` loaded at `base`, one `ADDR: OPCODE  MNEMONIC` line each.
+/// Instructions are assumed to start on even offsets, data mixed in is shown as `DW`.
+pub fn listing(rom: &[u8], base: u16) -> Vec<String> {
+    rom.chunks(2)
+        .enumerate()
+        .map(|(idx, bytes)| {
+            let op = u16::from_be_bytes([bytes[0], bytes.get(1).copied().unwrap_or(0)]);
+            let addr = base as usize + idx * 2;
+
+            line(addr, op)
+        })
+        .collect()
+}
+
+/// The instruction the machine runs next, as a `listing` line.
+pub fn at_pc(chip8: &Chip8) -> String {
+    let pc = chip8.get_program_counter();
+
+    line(pc as usize, opcode_at(chip8.get_memory(), pc))
+}
+
+fn line(addr: usize, op: u16) -> String {
+    format!("{addr:03X}: {op:04X}  {}", disassemble(op))
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+/// Sprite drawing counted by the core while draw tracking is on, see
+/// `Chip8::set_draw_tracking`: how many DXYN run per frame, how tall their sprites are and
+/// how many pixels are flipped again within the same frame (overdraw), which costs time
+/// and usually shows up as flicker.
+#[derive(Clone, Default, Debug)]
+pub struct DrawStats {
+    pub frames: u32,
+    pub draws: u64,                        // DXYN executed
+    pub rows: u64,                         // sprite rows drawn, the N of each DXYN
+    pub pixels: u64,                       // screen pixels flipped, clipped ones don't count
+    pub overdrawn: u64,                    // flips of a pixel already flipped in the same frame
+    pub busiest_frame: u32,                // most DXYN in a single frame
+    pub draws_by_addr: BTreeMap<u16, u64>, // DXYN executed per instruction address
+    frame_draws: u32,
+    flipped: Vec<bool>, // pixels flipped this frame
+}
+
+impl DrawStats {
+    /// A DXYN at `addr` drawing a sprite `rows` tall.
+    pub fn draw(&mut self, addr: u16, rows: u16) {
+        self.draws += 1;
+        self.rows += rows as u64;
+        self.frame_draws += 1;
+
+        *self.draws_by_addr.entry(addr).or_default() += 1;
+    }
+
+    /// The screen pixel `idx` was flipped by a sprite.
+    pub fn pixel(&mut self, idx: usize) {
+        if idx >= self.flipped.len() {
+            self.flipped.resize(idx + 1, false);
+        }
+
+        self.pixels += 1;
+        self.overdrawn += self.flipped[idx] as u64;
+        self.flipped[idx] = true;
+    }
+
+    /// The 60Hz frame is over, overdraw is counted again from the next one.
+    pub fn end_frame(&mut self) {
+        self.frames += 1;
+        self.busiest_frame = self.busiest_frame.max(self.frame_draws);
+        self.frame_draws = 0;
+        self.flipped.fill(false);
+    }
+
+    pub fn draws_per_frame(&self) -> f64 {
+        self.draws as f64 / self.frames.max(1) as f64
+    }
+
+    /// Average N of the DXYN executed.
+    pub fn average_height(&self) -> f64 {
+        self.rows as f64 / self.draws.max(1) as f64
+    }
+
+    /// Share of the flipped pixels that had been flipped already that frame, 0 to 1.
+    pub fn overdraw(&self) -> f64 {
+        self.overdrawn as f64 / self.pixels.max(1) as f64
+    }
+}
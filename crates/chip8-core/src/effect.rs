@@ -0,0 +1,64 @@
+use crate::resolution::Resolution;
+
+/// What an instruction did besides moving on, returned by `Chip8::tick` and
+/// `Chip8::execute_op`, so frontends and tools can react to it (redraw only when the screen
+/// changed, log skips...) without comparing the machine's state themselves. Registers, I
+/// and the timers are reported when their value changed; memory and the screen when they
+/// were written, even with what they already held.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Effect {
+    pub registers: u16,               // a bit per V register changed, V0 the lowest
+    pub i_register: Option<u16>,      // the new value
+    pub memory: Option<(u16, usize)>, // bytes written, the first address and how many
+    pub jumped: bool,                 // a jump, call or return moved PC
+    pub skipped: bool,                // a skip instruction skipped the next one
+    pub pushed: Option<u16>,          // return address of a call
+    pub popped: Option<u16>,          // return address of a return
+    pub delay_timer: Option<u8>,
+    pub sound_timer: Option<u8>,
+    pub sound_started: bool, // the sound timer was set from 0, the buzzer sounds
+    pub screen: bool,        // pixels drawn, cleared or scrolled, or the resolution changed
+    pub resolution: Option<Resolution>,
+    pub selected_planes: Option<u8>,
+    pub audio: bool,            // XO-CHIP's audio pattern or pitch changed
+    pub waits_for_key: bool,    // FX0A with no key down, PC stays on it
+    pub waits_for_vblank: bool, // a sprite drawn with the display_wait quirk
+    pub halted: bool,           // SUPER-CHIP's EXIT, PC stays on it
+}
+
+impl Effect {
+    /// Adds what a later instruction did, e.g. to sum up a frame: flags add up, the later
+    /// values win and written memory grows to span both.
+    pub fn merge(&mut self, later: Effect) {
+        self.registers |= later.registers;
+        self.i_register = later.i_register.or(self.i_register);
+        self.memory = match (self.memory, later.memory) {
+            (Some((addr, len)), Some((later_addr, later_len))) => {
+                let start = addr.min(later_addr);
+                let end = (addr as usize + len).max(later_addr as usize + later_len);
+
+                Some((start, end - start as usize))
+            }
+            (memory, later_memory) => later_memory.or(memory),
+        };
+        self.jumped |= later.jumped;
+        self.skipped |= later.skipped;
+        self.pushed = later.pushed.or(self.pushed);
+        self.popped = later.popped.or(self.popped);
+        self.delay_timer = later.delay_timer.or(self.delay_timer);
+        self.sound_timer = later.sound_timer.or(self.sound_timer);
+        self.sound_started |= later.sound_started;
+        self.screen |= later.screen;
+        self.resolution = later.resolution.or(self.resolution);
+        self.selected_planes = later.selected_planes.or(self.selected_planes);
+        self.audio |= later.audio;
+        self.waits_for_key |= later.waits_for_key;
+        self.waits_for_vblank |= later.waits_for_vblank;
+        self.halted |= later.halted;
+    }
+
+    /// Whether register `reg` (0 to F) changed.
+    pub fn register(&self, reg: usize) -> bool {
+        self.registers & (1 << reg) != 0
+    }
+}
@@ -0,0 +1,51 @@
+use crate::constants::FRAMES_PER_SECOND;
+
+const FRAME_MS: f64 = 1000.0 / FRAMES_PER_SECOND as f64;
+
+// frames run at once to catch up after the frontend stalled or was throttled, the rest is
+// dropped
+const MAX_CATCH_UP_FRAMES: u32 = 6;
+
+/// Turns the times a frontend draws at into whole 60Hz machine frames, so the instructions
+/// and the timers keep their pace whatever the display's refresh rate.
+///
+/// Displays refresh at 60, 120 or 144Hz and frontends skip frames under load, so the time
+/// between calls is accumulated and spent one machine frame at a time: a 120Hz display runs
+/// a frame every other call, a late call runs several. Timestamps are in ms from any fixed
+/// point, e.g. a browser's animation frame timestamps or the time since a game started.
+#[derive(Default)]
+pub struct FrameLoop {
+    last: Option<f64>, // timestamp of the previous call, ms
+    accumulator: f64,  // time not yet spent on frames, ms
+}
+
+impl FrameLoop {
+    /// Machine frames due at `now`.
+    pub fn frames_due(&mut self, now: f64) -> u32 {
+        match self.last {
+            Some(last) => self.accumulator += (now - last).max(0.0),
+            // half a frame in, so calls jittering around a 60Hz display's refresh run a
+            // frame each instead of alternating none and two
+            None => self.accumulator = FRAME_MS / 2.0,
+        }
+
+        self.last = Some(now);
+
+        let frames = (self.accumulator / FRAME_MS) as u32;
+
+        if frames > MAX_CATCH_UP_FRAMES {
+            self.accumulator = FRAME_MS / 2.0;
+            return MAX_CATCH_UP_FRAMES;
+        }
+
+        self.accumulator -= frames as f64 * FRAME_MS;
+
+        frames
+    }
+
+    /// Forgets the time since the last call, e.g. while a browser tab was hidden.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.accumulator = 0.0;
+    }
+}
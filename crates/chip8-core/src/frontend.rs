@@ -0,0 +1,91 @@
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::clock::Clock;
+use crate::constants::{FRAMES_PER_SECOND, NUM_KEYS};
+use crate::core::{Buzzer, Chip8, Chip8Error};
+
+/// Shows the machine's screen, implemented by frontends.
+pub trait Renderer {
+    /// Draws the screen of `chip8`, see `Chip8::get_screen`; called once per frame.
+    fn draw(&mut self, chip8: &Chip8);
+}
+
+/// What the player did since the last frame, see `InputSource`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    /// Keypad buttons held down, indexed by button.
+    Keys([bool; NUM_KEYS]),
+    /// The player is done, the game loop ends.
+    Quit,
+}
+
+/// Reads the player's keypad, implemented by frontends.
+pub trait InputSource {
+    /// Called once per frame, before its instructions run.
+    fn poll(&mut self) -> Input;
+}
+
+/// Plays the machine's sound, implemented by frontends. The game loop makes it the
+/// machine's `Buzzer` and keeps it in step with the clock.
+pub trait AudioSink: Buzzer {
+    /// The timers run `speed` times as fast as usual, e.g. while fast forwarding, see
+    /// `Clock::timer_speed`; called once per frame.
+    fn set_speed(&self, _speed: f64) {}
+}
+
+/// Polls `input` and holds the machine's keys down to match, false once the player quit.
+/// The start of every frame of `run`, for frontends keeping a loop of their own.
+pub fn read_input(chip8: &mut Chip8, input: &mut dyn InputSource) -> bool {
+    match input.poll() {
+        Input::Quit => false,
+        Input::Keys(keys) => {
+            for (idx, pressed) in keys.into_iter().enumerate() {
+                chip8.keypress(idx, pressed);
+            }
+
+            true
+        }
+    }
+}
+
+/// The game loop of a simple frontend: reads the input, runs a frame and draws it, 60
+/// times a second, until the player quits or the game crashes. Sound goes through `audio`,
+/// silent when None.
+///
+/// Frontends with their own event loop (a browser's animation frames) or features needing
+/// it (pausing, overlays, movies) drive the machine with `Chip8::run_frame` themselves,
+/// still reading the keypad with `read_input`.
+pub fn run(
+    chip8: &mut Chip8,
+    clock: &mut Clock,
+    renderer: &mut dyn Renderer,
+    input: &mut dyn InputSource,
+    audio: Option<Rc<dyn AudioSink>>,
+) -> Result<(), Chip8Error> {
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut next_frame = Instant::now();
+
+    if let Some(audio) = &audio {
+        chip8.set_buzzer(Some(Box::new(audio.clone())));
+    }
+
+    loop {
+        if !read_input(chip8, input) {
+            return Ok(());
+        }
+
+        if let Some(audio) = &audio {
+            audio.set_speed(clock.timer_speed());
+        }
+
+        chip8.run_frame(clock.ticks_for_frame())?;
+
+        renderer.draw(chip8);
+
+        // a frame running late starts the next one right away rather than catching up
+        next_frame = (next_frame + frame_time).max(Instant::now());
+        thread::sleep(next_frame.saturating_duration_since(Instant::now()));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::constants::NUM_KEYS;
+
+// buttons of the W3C "standard" gamepad layout, which browsers and SDL both follow
+const BUTTON_COUNT: usize = 17;
+
+/// Which keypad button each gamepad button presses, shared by every frontend that reads
+/// controllers so a mapping made in one works in the others.
+///
+/// Buttons are numbered as in the standard gamepad layout (0 bottom face button, 12-15 the
+/// d-pad up, down, left and right...). The text form has a `button=key` line per mapped
+/// button, the key as a hex digit, and `#` comments:
+///
+/// ```text
+/// 12=2
+/// 13=8
+/// 0=5
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GamepadMap {
+    keys: [Option<u8>; BUTTON_COUNT], // indexed by button
+}
+
+impl Default for GamepadMap {
+    // the d-pad on the 2/4/6/8 cross most games move with, 5 on the main face button
+    fn default() -> Self {
+        let mut keys = [None; BUTTON_COUNT];
+
+        for (button, key) in [
+            (12, 0x2),
+            (13, 0x8),
+            (14, 0x4),
+            (15, 0x6),
+            (0, 0x5),
+            (1, 0xA),
+        ] {
+            keys[button] = Some(key);
+        }
+
+        Self { keys }
+    }
+}
+
+impl GamepadMap {
+    /// The keypad button pressed by gamepad button `button`, if any.
+    pub fn key(&self, button: usize) -> Option<usize> {
+        self.keys.get(button).copied().flatten().map(usize::from)
+    }
+
+    /// Makes gamepad button `button` press keypad button `key`, or nothing. Buttons past the
+    /// standard layout are ignored.
+    pub fn set(&mut self, button: usize, key: Option<usize>) {
+        if let Some(slot) = self.keys.get_mut(button) {
+            *slot = key.filter(|key| *key < NUM_KEYS).map(|key| key as u8);
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut keys = [None; BUTTON_COUNT];
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (button, key) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Expected button=key, got {line}"))?;
+
+            let button = button
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|button| *button < BUTTON_COUNT)
+                .ok_or_else(|| format!("Unknown gamepad button {}", button.trim()))?;
+
+            let key = u8::from_str_radix(key.trim(), 16)
+                .ok()
+                .filter(|key| (*key as usize) < NUM_KEYS)
+                .ok_or_else(|| format!("Unknown keypad key {}", key.trim()))?;
+
+            keys[button] = Some(key);
+        }
+
+        Ok(Self { keys })
+    }
+
+    /// The text form read by `parse`.
+    pub fn to_text(&self) -> String {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter_map(|(button, key)| key.map(|key| format!("{button}={key:X}\n")))
+            .collect()
+    }
+}
@@ -0,0 +1,35 @@
+//! The CHIP-8 machine, with no window, sound or file system: load a ROM into a `Chip8`,
+//! press its keys and run it a frame at a time with `Chip8::run_frame`, or an instruction
+//! at a time with `Chip8::tick` and `Chip8::tick_timers`, which reports what the instruction
+//! did as an `Effect`. `Chip8::execute_op` runs opcodes given directly instead of from memory.
+//! The screen, memory, registers, stack and timers can be read back at any point, and the
+//! whole state saved and restored with `SaveState`. A frontend implementing `Renderer`,
+//! `InputSource` and `AudioSink` can leave the game loop to `frontend::run`.
+
+pub mod assembler;
+pub mod clock;
+pub mod constants;
+pub mod core;
+pub mod disassembler;
+pub mod draw_stats;
+pub mod effect;
+pub mod frame_loop;
+pub mod frontend;
+pub mod gamepad_map;
+pub mod machine;
+pub mod memory_access;
+pub mod quirks;
+pub mod random;
+pub mod reference;
+pub mod resolution;
+pub mod savestate;
+pub mod storage;
+pub mod text;
+
+pub use crate::core::{Buzzer, Chip8, Chip8Error};
+pub use crate::effect::Effect;
+pub use crate::frontend::{AudioSink, Input, InputSource, Renderer};
+pub use crate::quirks::Quirks;
+pub use crate::random::RandomSource;
+pub use crate::resolution::Resolution;
+pub use crate::savestate::SaveState;
@@ -0,0 +1,84 @@
+use crate::constants::{RAM_SIZE, START_ADDR, XO_RAM_SIZE};
+use crate::resolution::Resolution;
+
+// names used on the command line and in the game library
+const NAMES: [(Machine, &str); 3] = [
+    (Machine::CosmacVip, "vip"),
+    (Machine::Eti660, "eti-660"),
+    (Machine::XoChip, "xo-chip"),
+];
+
+/// A computer CHIP-8 ran on. Programs written for one expect its load address and keypad
+/// layout; the COSMAC VIP is what almost every game targets.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Machine {
+    #[default]
+    CosmacVip,
+    /// The ETI-660 learner's microcomputer, whose programs start at 0x600 and draw on a
+    /// 64x48 screen.
+    Eti660,
+    /// Octo's XO-CHIP, a VIP with 64KB of memory for modern homebrew games. Games larger
+    /// than 4KB get its memory on any machine.
+    XoChip,
+}
+
+impl Machine {
+    pub fn parse(name: &str) -> Option<Self> {
+        NAMES
+            .iter()
+            .find(|(_, known)| known.eq_ignore_ascii_case(name))
+            .map(|(machine, _)| *machine)
+    }
+
+    pub fn name(self) -> &'static str {
+        let (_, name) = NAMES.iter().find(|(machine, _)| *machine == self).unwrap();
+
+        name
+    }
+
+    /// Where programs are loaded and start.
+    pub fn start_addr(self) -> u16 {
+        match self {
+            Machine::CosmacVip | Machine::XoChip => START_ADDR,
+            Machine::Eti660 => 0x600,
+        }
+    }
+
+    pub fn resolution(self) -> Resolution {
+        match self {
+            Machine::CosmacVip | Machine::XoChip => Resolution::LORES,
+            Machine::Eti660 => Resolution::ETI_660,
+        }
+    }
+
+    pub fn memory_size(self) -> usize {
+        match self {
+            Machine::CosmacVip | Machine::Eti660 => RAM_SIZE,
+            Machine::XoChip => XO_RAM_SIZE,
+        }
+    }
+
+    /// The keypad buttons row by row from the top left, as printed on the machine's keypad.
+    pub const fn keypad_layout(self) -> [usize; 16] {
+        match self {
+            Machine::CosmacVip | Machine::XoChip => [
+                0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+            ],
+            Machine::Eti660 => [
+                0x3, 0x7, 0xB, 0xF, 0x2, 0x6, 0xA, 0xE, 0x1, 0x5, 0x9, 0xD, 0x0, 0x4, 0x8, 0xC,
+            ],
+        }
+    }
+
+    /// The button of this machine's keypad at the place `vip_button` has on the COSMAC VIP
+    /// keypad, so keys bound to the VIP layout press the button under the same finger.
+    pub fn keypad_button(self, vip_button: usize) -> usize {
+        let place = Machine::CosmacVip
+            .keypad_layout()
+            .iter()
+            .position(|button| *button == vip_button)
+            .unwrap_or(vip_button);
+
+        self.keypad_layout()[place]
+    }
+}
@@ -0,0 +1,63 @@
+use crate::constants::RAM_SIZE;
+
+/// How often each memory address was read, written and executed, counted by the core
+/// while memory tracking is on, see `Chip8::set_memory_tracking`.
+#[derive(Clone, Debug)]
+pub struct MemoryAccesses {
+    pub reads: Vec<u32>,    // sprite data, FX65 and the like
+    pub writes: Vec<u32>,   // FX33, FX55 and changes from outside (ROM editor, cheats)
+    pub executes: Vec<u32>, // instruction fetches, counted for both bytes
+}
+
+impl Default for MemoryAccesses {
+    fn default() -> Self {
+        Self::new(RAM_SIZE)
+    }
+}
+
+impl MemoryAccesses {
+    /// Nothing counted yet for a memory of `size` bytes.
+    pub fn new(size: usize) -> Self {
+        Self {
+            reads: vec![0; size],
+            writes: vec![0; size],
+            executes: vec![0; size],
+        }
+    }
+
+    pub fn read(&mut self, addr: usize, len: usize) {
+        Self::count(&mut self.reads, addr, len);
+    }
+
+    pub fn write(&mut self, addr: usize, len: usize) {
+        Self::count(&mut self.writes, addr, len);
+    }
+
+    pub fn execute(&mut self, addr: usize) {
+        Self::count(&mut self.executes, addr, 2);
+    }
+
+    fn count(counts: &mut [u32], addr: usize, len: usize) {
+        for offset in 0..len {
+            let len = counts.len();
+            let count = &mut counts[(addr + offset) % len];
+            *count = count.saturating_add(1);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// One byte of a watched range read or written, see `Chip8::add_memory_watch`. `pc` is
+/// the instruction that did it, or where the machine stands for writes from outside.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MemoryEvent {
+    pub kind: AccessKind,
+    pub addr: u16,
+    pub value: u8,
+    pub pc: u16,
+}
@@ -0,0 +1,150 @@
+/// Behaviors that differ between CHIP-8 interpreters, which games were written against
+/// without knowing. The default is what this emulator always did; each flag switches one
+/// instruction to the other common behavior.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Quirks {
+    /// 8XY6/8XYE shift VY into VX (COSMAC VIP) instead of shifting VX itself.
+    pub vy_shift: bool,
+    /// FX55/FX65 leave I past the last register (COSMAC VIP).
+    pub memory_increment: bool,
+    /// BXNN jumps to XNN + VX (SUPER-CHIP) instead of NNN + V0.
+    pub jump_vx: bool,
+    /// 8XY1/8XY2/8XY3 clear VF (COSMAC VIP).
+    pub vf_reset: bool,
+    /// Sprites are cut at the screen edges instead of wrapping around.
+    pub clip: bool,
+    /// DXYN waits for the next frame before the game goes on (COSMAC VIP), which limits
+    /// a game to a sprite per frame.
+    pub display_wait: bool,
+    /// EX9E/EXA1 with VX above F stop the game with an error instead of checking the key
+    /// of its low nibble, as the COSMAC VIP's keypad latch does. For finding bugs in a game.
+    pub strict_keys: bool,
+    /// A 0000 past the end of the loaded game stops it with an error instead of running as
+    /// a no-op, so a game running off its end doesn't slide through empty memory forever.
+    pub strict_end: bool,
+}
+
+// names used on the command line and in the game library, in field order
+const NAMES: [&str; 8] = [
+    "vy-shift",
+    "memory-increment",
+    "jump-vx",
+    "vf-reset",
+    "clip",
+    "display-wait",
+    "strict-keys",
+    "strict-end",
+];
+
+// the flags quirk detection tries, the first ones
+const DETECTED: usize = 5;
+
+// interpreters whose whole set of behaviors can be picked by name
+const PRESETS: [(&str, Quirks); 3] = [
+    ("vip", Quirks::COSMAC_VIP),
+    ("chip-48", Quirks::CHIP_48),
+    ("schip", Quirks::SCHIP),
+];
+
+impl Quirks {
+    /// The original interpreter of the COSMAC VIP.
+    pub const COSMAC_VIP: Self = Self {
+        vy_shift: true,
+        memory_increment: true,
+        jump_vx: false,
+        vf_reset: true,
+        clip: true,
+        display_wait: true,
+        strict_keys: false,
+        strict_end: false,
+    };
+
+    /// CHIP-48 on the HP-48 calculators.
+    pub const CHIP_48: Self = Self {
+        vy_shift: false,
+        memory_increment: false,
+        jump_vx: true,
+        vf_reset: false,
+        clip: true,
+        display_wait: false,
+        strict_keys: false,
+        strict_end: false,
+    };
+
+    /// SUPER-CHIP 1.1, which kept the behaviors of CHIP-48 it was built on.
+    pub const SCHIP: Self = Self::CHIP_48;
+
+    fn flags(&self) -> [bool; 8] {
+        [
+            self.vy_shift,
+            self.memory_increment,
+            self.jump_vx,
+            self.vf_reset,
+            self.clip,
+            self.display_wait,
+            self.strict_keys,
+            self.strict_end,
+        ]
+    }
+
+    fn from_flags(flags: [bool; 8]) -> Self {
+        Self {
+            vy_shift: flags[0],
+            memory_increment: flags[1],
+            jump_vx: flags[2],
+            vf_reset: flags[3],
+            clip: flags[4],
+            display_wait: flags[5],
+            strict_keys: flags[6],
+            strict_end: flags[7],
+        }
+    }
+
+    /// Every combination of the flags, the default one first. `display_wait` and the
+    /// strict flags stay off, they change how fast a game runs or how it fails rather than
+    /// what it does.
+    pub fn combinations() -> Vec<Self> {
+        (0..1u32 << DETECTED)
+            .map(|bits| Self::from_flags(std::array::from_fn(|i| bits & (1 << i) != 0)))
+            .collect()
+    }
+
+    /// Comma separated names of the flags set, e.g. `vy-shift,clip`, or `none`. The name
+    /// of an interpreter (`vip`, `chip-48` or `schip`) sets all of its flags.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut flags = [false; 8];
+
+        for name in text
+            .split(',')
+            .map(str::trim)
+            .filter(|name| *name != "none")
+        {
+            match PRESETS.iter().find(|(preset, _)| *preset == name) {
+                Some((_, quirks)) => {
+                    for (flag, set) in flags.iter_mut().zip(quirks.flags()) {
+                        *flag |= set;
+                    }
+                }
+                None => flags[NAMES.iter().position(|known| *known == name)?] = true,
+            }
+        }
+
+        Some(Self::from_flags(flags))
+    }
+
+    /// The names of the flags set, see `parse`.
+    pub fn names(&self) -> String {
+        let names: Vec<&str> = NAMES
+            .iter()
+            .zip(self.flags())
+            .filter(|(_, set)| *set)
+            .map(|(name, _)| *name)
+            .collect();
+
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(",")
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use rand::{thread_rng, Rng};
+
+// PCG32 (XSH RR) constants, see pcg-random.org
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const PCG_INCREMENT: u64 = 1442695040888963407;
+
+// stands in for the page of interpreter code the VIP's generator walked, see `Vip`
+const VIP_TABLE: [u8; 256] = vip_table();
+
+/// Where RND (CXKK) gets its numbers. Each source keeps its whole state in the machine's
+/// RNG state, so save states, movies and sessions replay the same numbers whichever it is.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum RandomSource {
+    /// xorshift64 seeded by the operating system, different numbers every run.
+    #[default]
+    Os,
+    /// PCG32 seeded with the number, the same numbers every run.
+    Seeded(u64),
+    /// Modeled on the COSMAC VIP interpreter: a byte pointer stepped by every RND and every
+    /// 60Hz frame walks a page of code, and the byte found is added to the previous number.
+    /// The numbers are poor, repeat and depend on when the game asks for them, which some
+    /// games were tuned for. The VIP walked its interpreter's code, which this emulator
+    /// doesn't have; a fixed table of arbitrary bytes stands in for it, so the numbers
+    /// behave like the VIP's without being the same ones.
+    Vip,
+}
+
+impl RandomSource {
+    /// `os`, `vip` or `seed:N`, as `name` writes them.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "os" => Some(RandomSource::Os),
+            "vip" => Some(RandomSource::Vip),
+            name => name
+                .strip_prefix("seed:")
+                .and_then(|seed| seed.parse().ok())
+                .map(RandomSource::Seeded),
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            RandomSource::Os => "os".to_string(),
+            RandomSource::Seeded(seed) => format!("seed:{seed}"),
+            RandomSource::Vip => "vip".to_string(),
+        }
+    }
+
+    /// The RNG state of a machine that was just switched on.
+    pub fn initial_state(self) -> u64 {
+        match self {
+            RandomSource::Os => thread_rng().gen::<u64>() | 1, // xorshift must never be 0
+            RandomSource::Seeded(seed) => {
+                let mut state = 0;
+
+                Self::pcg_step(&mut state);
+                state = state.wrapping_add(seed);
+                Self::pcg_step(&mut state);
+
+                state
+            }
+            RandomSource::Vip => 0,
+        }
+    }
+
+    pub fn next_byte(self, state: &mut u64) -> u8 {
+        match self {
+            RandomSource::Os => {
+                *state = (*state).max(1);
+
+                *state ^= *state << 13;
+                *state ^= *state >> 7;
+                *state ^= *state << 17;
+
+                (*state >> 56) as u8
+            }
+            RandomSource::Seeded(_) => {
+                let old = Self::pcg_step(state);
+
+                let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+                let output = xorshifted.rotate_right((old >> 59) as u32);
+
+                (output >> 24) as u8
+            }
+            RandomSource::Vip => {
+                Self::vip_step(state);
+
+                // the pointer in the low byte, the last number in the next one
+                let byte = VIP_TABLE[(*state & 0xFF) as usize];
+                let number = ((*state >> 8) as u8).wrapping_add(byte);
+
+                *state = (*state & !0xFF00) | (number as u64) << 8;
+
+                number
+            }
+        }
+    }
+
+    /// A 60Hz frame went by, only the VIP-style generator cares.
+    pub fn frame(self, state: &mut u64) {
+        if self == RandomSource::Vip {
+            Self::vip_step(state);
+        }
+    }
+
+    // only the low byte counts the frames, the bytes above are left alone
+    fn vip_step(state: &mut u64) {
+        *state = (*state & !0xFF) | ((*state as u8).wrapping_add(1) as u64);
+    }
+
+    // advances the state, returning the one before
+    fn pcg_step(state: &mut u64) -> u64 {
+        let old = *state;
+
+        *state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(PCG_INCREMENT);
+
+        old
+    }
+}
+
+// any bytes with no pattern do, these come from a 32-bit LCG
+const fn vip_table() -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut seed: u32 = 0x1802;
+    let mut idx = 0;
+
+    while idx < table.len() {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        table[idx] = (seed >> 24) as u8;
+        idx += 1;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vip_frames_count_in_the_low_byte() {
+        let mut state = 0x1234_56FE;
+
+        RandomSource::Vip.frame(&mut state);
+        assert_eq!(state, 0x1234_56FF);
+
+        RandomSource::Vip.frame(&mut state);
+        assert_eq!(state, 0x1234_5600);
+    }
+
+    #[test]
+    fn vip_frames_wrap_at_the_largest_state() {
+        // reachable from save states and movies
+        let mut state = u64::MAX;
+
+        RandomSource::Vip.frame(&mut state);
+        assert_eq!(state, 0xFFFF_FFFF_FFFF_FF00);
+    }
+}
@@ -0,0 +1,450 @@
+use crate::constants::{AUDIO_PATTERN_SIZE, BIG_FONTSET_ADDR, NUM_REGS, NUM_RPL_FLAGS};
+use crate::core::Chip8;
+use crate::quirks::Quirks;
+use crate::random::RandomSource;
+use crate::resolution::Resolution;
+
+/// A second interpreter of the instruction set, written to be obviously right rather than
+/// fast: one instruction at a time on a plain copy of the machine, each decoded from its
+/// nibbles and spelled out as the spec describes it.
+///
+/// With `Chip8::set_reference_check` (debug builds only) every instruction the machine runs
+/// is also run here from the state before it, and the two results must be identical, which
+/// catches changes to the interpreter that break an instruction as soon as a game uses it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Reference {
+    planes: [Vec<bool>; 2],
+    selected_planes: u8,
+    resolution: Resolution,
+    memory: Vec<u8>,
+    v: [u8; NUM_REGS],
+    i: u16,
+    pc: u16,
+    stack: Vec<u16>, // bottom first
+    delay_timer: u8,
+    sound_timer: u8,
+    rpl_flags: [u8; NUM_RPL_FLAGS],
+    rng_state: u64,
+    random_source: RandomSource,
+    audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    pitch: u8,
+}
+
+impl Reference {
+    /// A copy of the state of `chip8`.
+    pub fn of(chip8: &Chip8) -> Self {
+        let state = chip8.save_state();
+
+        Self {
+            planes: [state.screen, state.second_plane],
+            selected_planes: state.selected_planes,
+            resolution: state.resolution,
+            memory: state.memory,
+            v: state.v_reg,
+            i: state.i_reg,
+            pc: state.pc,
+            stack: state.stack,
+            delay_timer: state.delay_timer_reg,
+            sound_timer: state.sound_timer_reg,
+            rpl_flags: chip8.rpl_flags().try_into().unwrap(),
+            rng_state: chip8.get_rng_state(),
+            random_source: chip8.random_source(),
+            audio_pattern: state.audio_pattern,
+            pitch: state.pitch,
+        }
+    }
+
+    /// Runs the instruction at PC with `keys` held.
+    pub fn step(&mut self, keys: &[bool], quirks: Quirks) {
+        let op = self.word_at(self.pc);
+
+        let kind = op >> 12;
+        let x = ((op >> 8) & 0xF) as usize;
+        let y = ((op >> 4) & 0xF) as usize;
+        let n = op & 0xF;
+        let kk = (op & 0xFF) as u8;
+        let nnn = op & 0xFFF;
+
+        self.pc = self.pc.wrapping_add(2);
+
+        match (kind, x, y, n) {
+            (0x0, 0, 0x0, 0x0) => (),
+            (0x0, 0, 0xE, 0x0) => {
+                for plane in self.selected() {
+                    self.planes[plane].fill(false);
+                }
+            }
+            (0x0, 0, 0xE, 0xE) => self.pc = self.stack.pop().expect("return without a call"),
+            (0x0, 0, 0xC, _) => self.scroll(0, n as usize, Direction::Down),
+            (0x0, 0, 0xD, _) => self.scroll(0, n as usize, Direction::Up),
+            (0x0, 0, 0xF, 0xB) => self.scroll(4, 0, Direction::Right),
+            (0x0, 0, 0xF, 0xC) => self.scroll(4, 0, Direction::Left),
+            (0x0, 0, 0xF, 0xD) => self.pc = self.pc.wrapping_sub(2), // exit, stay here
+            (0x0, 0, 0xF, 0xE) => self.switch_resolution(Resolution::LORES),
+            (0x0, 0, 0xF, 0xF) => self.switch_resolution(Resolution::SCHIP),
+            (0x1, _, _, _) => self.pc = nnn,
+            (0x2, _, _, _) => {
+                self.stack.push(self.pc);
+                self.pc = nnn;
+            }
+            (0x3, _, _, _) => self.skip_if(self.v[x] == kk),
+            (0x4, _, _, _) => self.skip_if(self.v[x] != kk),
+            (0x5, _, _, 0x0) => self.skip_if(self.v[x] == self.v[y]),
+            (0x5, _, _, 0x2) => {
+                for (offset, reg) in Self::registers(x, y).into_iter().enumerate() {
+                    let addr = (self.i as usize + offset) % self.memory.len();
+                    self.memory[addr] = self.v[reg];
+                }
+            }
+            (0x5, _, _, 0x3) => {
+                for (offset, reg) in Self::registers(x, y).into_iter().enumerate() {
+                    self.v[reg] = self.memory[(self.i as usize + offset) % self.memory.len()];
+                }
+            }
+            (0x6, _, _, _) => self.v[x] = kk,
+            (0x7, _, _, _) => self.v[x] = self.v[x].wrapping_add(kk),
+            (0x8, _, _, 0x0) => self.v[x] = self.v[y],
+            (0x8, _, _, 0x1..=0x3) => {
+                self.v[x] = match n {
+                    0x1 => self.v[x] | self.v[y],
+                    0x2 => self.v[x] & self.v[y],
+                    _ => self.v[x] ^ self.v[y],
+                };
+
+                if quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
+            }
+            // the flag is written last, it wins when X is F
+            (0x8, _, _, 0x4) => {
+                let sum = self.v[x] as u16 + self.v[y] as u16;
+                self.v[x] = sum as u8;
+                self.v[0xF] = (sum > 0xFF) as u8;
+            }
+            (0x8, _, _, 0x5) => {
+                let no_borrow = self.v[x] >= self.v[y];
+                self.v[x] = self.v[x].wrapping_sub(self.v[y]);
+                self.v[0xF] = no_borrow as u8;
+            }
+            (0x8, _, _, 0x7) => {
+                let no_borrow = self.v[y] >= self.v[x];
+                self.v[x] = self.v[y].wrapping_sub(self.v[x]);
+                self.v[0xF] = no_borrow as u8;
+            }
+            (0x8, _, _, 0x6) => {
+                let value = if quirks.vy_shift {
+                    self.v[y]
+                } else {
+                    self.v[x]
+                };
+                self.v[x] = value / 2;
+                self.v[0xF] = value % 2;
+            }
+            (0x8, _, _, 0xE) => {
+                let value = if quirks.vy_shift {
+                    self.v[y]
+                } else {
+                    self.v[x]
+                };
+                self.v[x] = value.wrapping_mul(2);
+                self.v[0xF] = value / 0x80;
+            }
+            (0x9, _, _, 0x0) => self.skip_if(self.v[x] != self.v[y]),
+            (0xA, _, _, _) => self.i = nnn,
+            (0xB, _, _, _) => {
+                let offset = if quirks.jump_vx { self.v[x] } else { self.v[0] };
+                self.pc = nnn + offset as u16;
+            }
+            (0xC, _, _, _) => self.v[x] = self.random_byte() & kk,
+            (0xD, _, _, _) => self.draw(self.v[x], self.v[y], n, quirks.clip),
+            (0xE, _, 0x9, 0xE) => self.skip_if(keys[(self.v[x] & 0xF) as usize]),
+            (0xE, _, 0xA, 0x1) => self.skip_if(!keys[(self.v[x] & 0xF) as usize]),
+            (0xF, 0xF, 0xF, 0x0..=0x3) => (), // debug instructions, they leave the state alone
+            (0xF, 0, 0x0, 0x0) => {
+                self.i = self.word_at(self.pc);
+                self.pc = self.pc.wrapping_add(2);
+            }
+            (0xF, _, 0x0, 0x1) => self.selected_planes = x as u8 % 4,
+            (0xF, 0, 0x0, 0x2) => {
+                let mut pattern = [0; AUDIO_PATTERN_SIZE];
+                for (offset, byte) in pattern.iter_mut().enumerate() {
+                    *byte = self.memory[(self.i as usize + offset) % self.memory.len()];
+                }
+                self.audio_pattern = Some(pattern);
+            }
+            (0xF, _, 0x3, 0xA) => self.pitch = self.v[x],
+            (0xF, _, 0x0, 0x7) => self.v[x] = self.delay_timer,
+            (0xF, _, 0x0, 0xA) => match keys.iter().position(|down| *down) {
+                Some(key) => self.v[x] = key as u8,
+                None => self.pc = self.pc.wrapping_sub(2), // wait here
+            },
+            (0xF, _, 0x1, 0x5) => self.delay_timer = self.v[x],
+            (0xF, _, 0x1, 0x8) => self.sound_timer = self.v[x],
+            (0xF, _, 0x1, 0xE) => self.i = self.i.wrapping_add(self.v[x] as u16),
+            (0xF, _, 0x2, 0x9) => self.i = self.v[x] as u16 * 5,
+            (0xF, _, 0x3, 0x0) => {
+                self.i = (BIG_FONTSET_ADDR + (self.v[x] % 16) as usize * 10) as u16
+            }
+            (0xF, _, 0x3, 0x3) => {
+                let i = self.i as usize;
+                self.memory[i] = self.v[x] / 100;
+                self.memory[i + 1] = self.v[x] / 10 % 10;
+                self.memory[i + 2] = self.v[x] % 10;
+            }
+            (0xF, _, 0x5, 0x5) => {
+                for reg in 0..=x {
+                    self.memory[self.i as usize + reg] = self.v[reg];
+                }
+
+                if quirks.memory_increment {
+                    self.i += x as u16 + 1;
+                }
+            }
+            (0xF, _, 0x6, 0x5) => {
+                for reg in 0..=x {
+                    self.v[reg] = self.memory[self.i as usize + reg];
+                }
+
+                if quirks.memory_increment {
+                    self.i += x as u16 + 1;
+                }
+            }
+            (0xF, _, 0x7, 0x5) => {
+                for reg in 0..=x.min(7) {
+                    self.rpl_flags[reg] = self.v[reg];
+                }
+            }
+            (0xF, _, 0x8, 0x5) => {
+                for reg in 0..=x.min(7) {
+                    self.v[reg] = self.rpl_flags[reg];
+                }
+            }
+            _ => panic!("Unknown instruction {op:04X}"),
+        }
+    }
+
+    /// What differs from `other`, field by field, or None when they are the same.
+    pub fn difference(&self, other: &Reference) -> Option<String> {
+        let mut differences = Vec::new();
+
+        if self.pc != other.pc {
+            differences.push(format!("PC {:03X} != {:03X}", self.pc, other.pc));
+        }
+
+        if self.i != other.i {
+            differences.push(format!("I {:03X} != {:03X}", self.i, other.i));
+        }
+
+        for (reg, (a, b)) in self.v.iter().zip(other.v).enumerate() {
+            if *a != b {
+                differences.push(format!("V{reg:X} {a:02X} != {b:02X}"));
+            }
+        }
+
+        if self.delay_timer != other.delay_timer || self.sound_timer != other.sound_timer {
+            differences.push("timers".to_string());
+        }
+
+        if self.rpl_flags != other.rpl_flags {
+            differences.push(format!(
+                "RPL flags {:X?} != {:X?}",
+                self.rpl_flags, other.rpl_flags
+            ));
+        }
+
+        if self.selected_planes != other.selected_planes {
+            differences.push(format!(
+                "planes {} != {}",
+                self.selected_planes, other.selected_planes
+            ));
+        }
+
+        if self.audio_pattern != other.audio_pattern || self.pitch != other.pitch {
+            differences.push("audio pattern".to_string());
+        }
+
+        if self.resolution != other.resolution {
+            differences.push(format!(
+                "resolution {} != {}",
+                self.resolution.name(),
+                other.resolution.name()
+            ));
+        }
+
+        if self.stack != other.stack {
+            differences.push(format!("stack {:X?} != {:X?}", self.stack, other.stack));
+        }
+
+        if let Some(addr) = (0..self.memory.len().min(other.memory.len()))
+            .find(|a| self.memory[*a] != other.memory[*a])
+        {
+            differences.push(format!("memory from {addr:03X}"));
+        }
+
+        if self.memory.len() != other.memory.len() {
+            differences.push(format!(
+                "memory size {} != {}",
+                self.memory.len(),
+                other.memory.len()
+            ));
+        }
+
+        for (plane, (a, b)) in self.planes.iter().zip(&other.planes).enumerate() {
+            if a != b {
+                differences.push(format!("plane {plane}"));
+            }
+        }
+
+        if self.rng_state != other.rng_state {
+            differences.push("random generator".to_string());
+        }
+
+        (!differences.is_empty()).then(|| differences.join(", "))
+    }
+
+    fn word_at(&self, addr: u16) -> u16 {
+        let len = self.memory.len();
+
+        u16::from_be_bytes([
+            self.memory[addr as usize % len],
+            self.memory[(addr as usize + 1) % len],
+        ])
+    }
+
+    // the long LD I of XO-CHIP is skipped as a whole
+    fn skip_if(&mut self, condition: bool) {
+        if condition {
+            let len = if self.word_at(self.pc) == 0xF000 {
+                4
+            } else {
+                2
+            };
+
+            self.pc = self.pc.wrapping_add(len);
+        }
+    }
+
+    // the planes XO-CHIP's FN01 selected, plane 0 alone for everything else
+    fn selected(&self) -> Vec<usize> {
+        (0..self.planes.len())
+            .filter(|plane| self.selected_planes & (1 << plane) != 0)
+            .collect()
+    }
+
+    // Vx to Vy, in that order even when x is larger
+    fn registers(x: usize, y: usize) -> Vec<usize> {
+        if x <= y {
+            (x..=y).collect()
+        } else {
+            (y..=x).rev().collect()
+        }
+    }
+
+    // the same source as the machine, a game's random numbers are part of its state
+    fn random_byte(&mut self) -> u8 {
+        self.random_source.next_byte(&mut self.rng_state)
+    }
+
+    fn switch_resolution(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        self.planes = [
+            vec![false; resolution.pixels()],
+            vec![false; resolution.pixels()],
+        ];
+    }
+
+    // whole rows or columns of the selected planes move over, the ones left behind are blank
+    fn scroll(&mut self, columns: usize, rows: usize, direction: Direction) {
+        for plane in self.selected() {
+            self.scroll_plane(plane, columns, rows, &direction);
+        }
+    }
+
+    fn scroll_plane(&mut self, plane: usize, columns: usize, rows: usize, direction: &Direction) {
+        let Resolution { width, height } = self.resolution;
+        let mut lines: Vec<Vec<bool>> = self.planes[plane]
+            .chunks(width)
+            .map(<[bool]>::to_vec)
+            .collect();
+
+        match direction {
+            Direction::Down => {
+                lines.rotate_right(rows.min(height));
+                lines[..rows.min(height)]
+                    .iter_mut()
+                    .for_each(|line| line.fill(false));
+            }
+            Direction::Up => {
+                lines.rotate_left(rows.min(height));
+                lines[height - rows.min(height)..]
+                    .iter_mut()
+                    .for_each(|line| line.fill(false));
+            }
+            Direction::Right => {
+                for line in &mut lines {
+                    line.rotate_right(columns);
+                    line[..columns].fill(false);
+                }
+            }
+            Direction::Left => {
+                for line in &mut lines {
+                    line.rotate_left(columns);
+                    line[width - columns..].fill(false);
+                }
+            }
+        }
+
+        self.planes[plane] = lines.concat();
+    }
+
+    // the sprite starts on screen, the pixels past the edges wrap around or are clipped;
+    // a height of 0 is a 16x16 sprite of two bytes per row. Each selected plane gets its
+    // own sprite, one after the other in memory.
+    fn draw(&mut self, x: u8, y: u8, rows: u16, clip: bool) {
+        let Resolution { width, height } = self.resolution;
+        let left = x as usize % width;
+        let top = y as usize % height;
+        let mut collision = false;
+
+        let (rows, columns) = if rows == 0 {
+            (16, 16)
+        } else {
+            (rows as usize, 8)
+        };
+
+        for (n, plane) in self.selected().into_iter().enumerate() {
+            let sprite = self.i as usize + n * rows * columns / 8;
+
+            for row in 0..rows {
+                for column in 0..columns {
+                    let addr = sprite + row * columns / 8 + column / 8;
+                    let byte = self.memory[addr % self.memory.len()];
+
+                    if byte & (0x80 >> (column % 8)) == 0 {
+                        continue;
+                    }
+
+                    let (px, py) = (left + column, top + row);
+
+                    if clip && (px >= width || py >= height) {
+                        continue;
+                    }
+
+                    let pixel = &mut self.planes[plane][px % width + py % height * width];
+
+                    collision |= *pixel;
+                    *pixel = !*pixel;
+                }
+            }
+        }
+
+        self.v[0xF] = collision as u8;
+    }
+}
+
+enum Direction {
+    Down,
+    Up,
+    Right,
+    Left,
+}
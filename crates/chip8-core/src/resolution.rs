@@ -0,0 +1,55 @@
+/// Size of the framebuffer, in pixels. The machine's screen is `width * height` pixels row
+/// major, whichever of the sizes used by the CHIP-8 variants it was set to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Resolution {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Self::LORES
+    }
+}
+
+impl Resolution {
+    /// The original COSMAC VIP screen, what almost every game uses.
+    pub const LORES: Resolution = Resolution::new(64, 32);
+    /// The ETI-660's taller screen.
+    pub const ETI_660: Resolution = Resolution::new(64, 48);
+    /// Two-page HIRES CHIP-8.
+    pub const HIRES: Resolution = Resolution::new(64, 64);
+    /// SUPER-CHIP and XO-CHIP high resolution.
+    pub const SCHIP: Resolution = Resolution::new(128, 64);
+    /// Mega-Chip.
+    pub const MEGA_CHIP: Resolution = Resolution::new(256, 192);
+
+    pub const ALL: [Resolution; 5] = [
+        Self::LORES,
+        Self::ETI_660,
+        Self::HIRES,
+        Self::SCHIP,
+        Self::MEGA_CHIP,
+    ];
+
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    pub fn pixels(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// One of the supported sizes written as `WIDTHxHEIGHT`, e.g. `128x64`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (width, height) = text.split_once(['x', 'X'])?;
+        let resolution = Self::new(width.trim().parse().ok()?, height.trim().parse().ok()?);
+
+        Self::ALL.contains(&resolution).then_some(resolution)
+    }
+
+    /// The size as `parse` reads it.
+    pub fn name(&self) -> String {
+        format!("{}x{}", self.width, self.height)
+    }
+}
@@ -0,0 +1,624 @@
+use std::fmt::{Display, Formatter};
+
+use serde_json::{json, Value};
+
+use crate::constants::{
+    AUDIO_PATTERN_SIZE, DEFAULT_PITCH, NUM_REGS, NUM_RPL_FLAGS, RAM_SIZE, STACK_SIZE, XO_RAM_SIZE,
+};
+use crate::resolution::Resolution;
+
+// "C8ST" followed by the format version; version 1 states predate resolutions and are 64x32,
+// version 2 states predate XO-CHIP and have 4KB of memory and a single plane, version 3
+// states predate the RPL flags
+const MAGIC: &[u8; 4] = b"C8ST";
+const VERSION: u8 = 4;
+
+// Octo always keeps two 128x64 planes around, lo-res games only use the top left 64x32
+const OCTO_HIRES_WIDTH: usize = 128;
+
+#[derive(Debug)]
+pub enum SaveStateError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnsupportedResolution(usize, usize),
+    UnsupportedMemorySize(usize),
+    Truncated,
+    InvalidJson(String),
+    MissingField(&'static str),
+}
+
+impl Display for SaveStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveStateError::BadMagic => write!(f, "not a chip8-emulator save state"),
+            SaveStateError::UnsupportedVersion(v) => write!(f, "unsupported save state version {v}"),
+            SaveStateError::UnsupportedResolution(width, height) => {
+                write!(f, "unsupported screen resolution {width}x{height}")
+            }
+            SaveStateError::UnsupportedMemorySize(size) => {
+                write!(f, "unsupported memory size of {size} bytes")
+            }
+            SaveStateError::Truncated => write!(f, "save state is truncated"),
+            SaveStateError::InvalidJson(err) => write!(f, "invalid JSON: {err}"),
+            SaveStateError::MissingField(field) => write!(f, "missing field `{field}`"),
+        }
+    }
+}
+
+/// A full snapshot of the machine, see `Chip8::save_state` and `Chip8::load_state`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SaveState {
+    pub screen: Vec<bool>,       // the first plane
+    pub second_plane: Vec<bool>, // XO-CHIP's, blank for other games
+    pub selected_planes: u8,
+    pub resolution: Resolution,
+    pub memory: Vec<u8>,
+    pub v_reg: [u8; NUM_REGS],
+    pub pc: u16,
+    pub i_reg: u16,
+    pub delay_timer_reg: u8,
+    pub sound_timer_reg: u8,
+    pub stack: Vec<u16>, // bottom first, only the occupied part
+    pub audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    pub pitch: u8,
+    pub rpl_flags: [u8; NUM_RPL_FLAGS],
+}
+
+impl SaveState {
+    /// Native binary format: magic, version, registers, stack, the memory size and memory,
+    /// the screen resolution, both bit packed planes, XO-CHIP's plane selection and audio and
+    /// SUPER-CHIP's RPL flags.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(RAM_SIZE + 512);
+
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+
+        data.extend_from_slice(&self.pc.to_be_bytes());
+        data.extend_from_slice(&self.i_reg.to_be_bytes());
+        data.push(self.delay_timer_reg);
+        data.push(self.sound_timer_reg);
+        data.extend_from_slice(&self.v_reg);
+
+        data.push(self.stack.len() as u8);
+        for addr in &self.stack {
+            data.extend_from_slice(&addr.to_be_bytes());
+        }
+
+        data.extend_from_slice(&(self.memory.len() as u32).to_be_bytes());
+        data.extend_from_slice(&self.memory);
+
+        data.extend_from_slice(&(self.resolution.width as u16).to_be_bytes());
+        data.extend_from_slice(&(self.resolution.height as u16).to_be_bytes());
+
+        // 8 pixels per byte, most significant bit first like sprites
+        for plane in [&self.screen, &self.second_plane] {
+            for chunk in plane.chunks(8) {
+                let byte = chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, on)| byte | ((*on as u8) << (7 - i)));
+
+                data.push(byte);
+            }
+        }
+
+        data.push(self.selected_planes);
+        data.push(self.pitch);
+        data.push(self.audio_pattern.is_some() as u8);
+        data.extend_from_slice(&self.audio_pattern.unwrap_or_default());
+        data.extend_from_slice(&self.rpl_flags);
+
+        data
+    }
+
+    // bit packed pixels, see `to_bytes`
+    fn read_plane(
+        reader: &mut ByteReader,
+        resolution: Resolution,
+    ) -> Result<Vec<bool>, SaveStateError> {
+        Ok(reader
+            .take(resolution.pixels() / 8)?
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| byte & (0b1000_0000 >> i) != 0))
+            .collect())
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SaveStateError> {
+        let mut reader = ByteReader { data, pos: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SaveStateError::BadMagic);
+        }
+
+        let version = reader.u8()?;
+
+        if !(1..=VERSION).contains(&version) {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        let pc = reader.u16()?;
+        let i_reg = reader.u16()?;
+        let delay_timer_reg = reader.u8()?;
+        let sound_timer_reg = reader.u8()?;
+
+        let mut v_reg = [0; NUM_REGS];
+        v_reg.copy_from_slice(reader.take(NUM_REGS)?);
+
+        let stack_len = (reader.u8()? as usize).min(STACK_SIZE);
+        let mut stack = Vec::with_capacity(stack_len);
+        for _ in 0..stack_len {
+            stack.push(reader.u16()?);
+        }
+
+        let memory_size = if version < 3 {
+            RAM_SIZE
+        } else {
+            match reader.u32()? as usize {
+                size @ (RAM_SIZE | XO_RAM_SIZE) => size,
+                size => return Err(SaveStateError::UnsupportedMemorySize(size)),
+            }
+        };
+
+        let memory = reader.take(memory_size)?.to_vec();
+
+        let resolution = if version == 1 {
+            Resolution::LORES
+        } else {
+            let (width, height) = (reader.u16()? as usize, reader.u16()? as usize);
+            let resolution = Resolution::new(width, height);
+
+            if !Resolution::ALL.contains(&resolution) {
+                return Err(SaveStateError::UnsupportedResolution(width, height));
+            }
+
+            resolution
+        };
+
+        let screen = Self::read_plane(&mut reader, resolution)?;
+
+        if version < 3 {
+            return Ok(Self {
+                screen,
+                second_plane: vec![false; resolution.pixels()],
+                selected_planes: 1,
+                resolution,
+                memory,
+                v_reg,
+                pc,
+                i_reg,
+                delay_timer_reg,
+                sound_timer_reg,
+                stack,
+                audio_pattern: None,
+                pitch: DEFAULT_PITCH,
+                rpl_flags: [0; NUM_RPL_FLAGS],
+            });
+        }
+
+        let second_plane = Self::read_plane(&mut reader, resolution)?;
+        let selected_planes = reader.u8()?;
+        let pitch = reader.u8()?;
+        let has_pattern = reader.u8()? != 0;
+        let pattern: [u8; AUDIO_PATTERN_SIZE] =
+            reader.take(AUDIO_PATTERN_SIZE)?.try_into().unwrap();
+
+        let mut rpl_flags = [0; NUM_RPL_FLAGS];
+        if version >= 4 {
+            rpl_flags.copy_from_slice(reader.take(NUM_RPL_FLAGS)?);
+        }
+
+        Ok(Self {
+            screen,
+            second_plane,
+            selected_planes,
+            resolution,
+            memory,
+            v_reg,
+            pc,
+            i_reg,
+            delay_timer_reg,
+            sound_timer_reg,
+            stack,
+            audio_pattern: has_pattern.then_some(pattern),
+            pitch,
+            rpl_flags,
+        })
+    }
+
+    /// Imports the JSON serialization of an Octo emulator (`pc`, `i`, `v`, `r`, `dt`, `st`, `m`,
+    /// the `p` display planes, the selected `plane`, the audio `pattern` and the RPL `flags`),
+    /// in 128x64 when Octo was in hi-res and with 64KB of memory when it uses more than 4KB.
+    /// What doesn't fit this machine (memory past 64KB, a deeper stack, more than 8 flags...)
+    /// is dropped and reported in the returned warnings.
+    pub fn from_octo_json(text: &str) -> Result<(Self, Vec<String>), SaveStateError> {
+        let octo: Value =
+            serde_json::from_str(text).map_err(|err| SaveStateError::InvalidJson(err.to_string()))?;
+
+        let mut warnings = Vec::new();
+
+        let number = |field: &'static str| -> Result<u64, SaveStateError> {
+            octo[field].as_u64().ok_or(SaveStateError::MissingField(field))
+        };
+
+        let numbers = |field: &'static str| -> Result<Vec<u64>, SaveStateError> {
+            let values = octo[field].as_array().ok_or(SaveStateError::MissingField(field))?;
+
+            Ok(values.iter().map(|v| v.as_u64().unwrap_or(0)).collect())
+        };
+
+        let pc = number("pc")?;
+        let i_reg = number("i")?;
+
+        let octo_memory = numbers("m")?;
+
+        let memory_size = if octo_memory.iter().skip(RAM_SIZE).any(|byte| *byte != 0) {
+            XO_RAM_SIZE
+        } else {
+            RAM_SIZE
+        };
+
+        if pc >= memory_size as u64 || i_reg >= memory_size as u64 {
+            warnings.push(format!(
+                "pc {pc:#x} / i {i_reg:#x} point past {}KB of memory and were wrapped",
+                memory_size / 1024
+            ));
+        }
+
+        let mut v_reg = [0; NUM_REGS];
+        for (reg, value) in v_reg.iter_mut().zip(numbers("v")?) {
+            *reg = value as u8;
+        }
+
+        let mut stack: Vec<u16> = numbers("r")?.iter().map(|addr| *addr as u16).collect();
+        if stack.len() > STACK_SIZE {
+            warnings.push(format!(
+                "return stack is {} deep, only the {STACK_SIZE} oldest entries were kept",
+                stack.len()
+            ));
+            stack.truncate(STACK_SIZE);
+        }
+
+        let mut memory = vec![0; memory_size];
+        for (byte, value) in memory.iter_mut().zip(&octo_memory) {
+            *byte = *value as u8;
+        }
+
+        if octo_memory.iter().skip(XO_RAM_SIZE).any(|byte| *byte != 0) {
+            warnings.push("memory past 64KB is not supported and was dropped".to_string());
+        }
+
+        let resolution = if octo["hires"].as_bool() == Some(true) {
+            Resolution::SCHIP
+        } else {
+            Resolution::LORES
+        };
+
+        let screen = Self::octo_plane(&octo, 0, resolution).unwrap_or_else(|| {
+            warnings.push("display plane 0 is missing, the screen was cleared".to_string());
+            vec![false; resolution.pixels()]
+        });
+
+        let second_plane = Self::octo_plane(&octo, 1, resolution)
+            .unwrap_or_else(|| vec![false; resolution.pixels()]);
+
+        // older Octo states have no flags
+        let octo_flags = numbers("flags").unwrap_or_default();
+
+        let mut rpl_flags = [0; NUM_RPL_FLAGS];
+        for (flag, value) in rpl_flags.iter_mut().zip(&octo_flags) {
+            *flag = *value as u8;
+        }
+
+        if octo_flags.iter().skip(NUM_RPL_FLAGS).any(|flag| *flag != 0) {
+            warnings.push(format!(
+                "RPL flags past the first {NUM_RPL_FLAGS} are not supported and were dropped"
+            ));
+        }
+
+        let audio_pattern = match octo["pattern"].as_array() {
+            Some(pattern) if pattern.len() == AUDIO_PATTERN_SIZE => {
+                let mut bytes = [0; AUDIO_PATTERN_SIZE];
+                for (byte, value) in bytes.iter_mut().zip(pattern) {
+                    *byte = value.as_u64().unwrap_or(0) as u8;
+                }
+
+                Some(bytes)
+            }
+            Some(pattern) if !pattern.is_empty() => {
+                warnings.push(format!(
+                    "audio pattern is {} bytes instead of {AUDIO_PATTERN_SIZE} and was dropped",
+                    pattern.len()
+                ));
+                None
+            }
+            _ => None,
+        };
+
+        let state = Self {
+            screen,
+            second_plane,
+            selected_planes: number("plane").unwrap_or(1) as u8 & 0b11,
+            resolution,
+            memory,
+            v_reg,
+            pc: (pc as usize % memory_size) as u16,
+            i_reg: (i_reg as usize % memory_size) as u16,
+            delay_timer_reg: number("dt").unwrap_or(0) as u8,
+            sound_timer_reg: number("st").unwrap_or(0) as u8,
+            stack,
+            audio_pattern,
+            pitch: DEFAULT_PITCH,
+            rpl_flags,
+        };
+
+        Ok((state, warnings))
+    }
+
+    /// Exports the state in the shape `from_octo_json` reads. Screens larger than Octo's
+    /// 128x64 are cut to their top left.
+    pub fn to_octo_json(&self) -> String {
+        let planes: Vec<Vec<u8>> = [&self.screen, &self.second_plane]
+            .iter()
+            .map(|screen| {
+                let mut plane = vec![0; OCTO_HIRES_WIDTH * 64];
+
+                for (i, pixel) in screen.iter().enumerate() {
+                    let (x, y) = (i % self.resolution.width, i / self.resolution.width);
+
+                    if x < OCTO_HIRES_WIDTH && y < 64 {
+                        plane[x + y * OCTO_HIRES_WIDTH] = *pixel as u8;
+                    }
+                }
+
+                plane
+            })
+            .collect();
+
+        let octo = json!({
+            "pc": self.pc,
+            "i": self.i_reg,
+            "v": self.v_reg,
+            "r": self.stack,
+            "dt": self.delay_timer_reg,
+            "st": self.sound_timer_reg,
+            "m": self.memory,
+            "p": planes,
+            "hires": self.resolution == Resolution::SCHIP,
+            "plane": self.selected_planes,
+            "flags": self.rpl_flags,
+            "pattern": self.audio_pattern.map_or(Vec::new(), |pattern| pattern.to_vec()),
+        });
+
+        octo.to_string()
+    }
+
+    // Octo planes are either 64x32 or 128x64 row major buffers
+    fn octo_plane(octo: &Value, plane: usize, resolution: Resolution) -> Option<Vec<bool>> {
+        let pixels = octo["p"][plane].as_array()?;
+
+        let width = if pixels.len() >= OCTO_HIRES_WIDTH * 64 {
+            OCTO_HIRES_WIDTH
+        } else {
+            Resolution::LORES.width
+        };
+
+        let screen = (0..resolution.pixels())
+            .map(|i| {
+                let idx = (i % resolution.width) + (i / resolution.width) * width;
+
+                pixels.get(idx).and_then(Value::as_u64).unwrap_or(0) != 0
+            })
+            .collect();
+
+        Some(screen)
+    }
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(SaveStateError::Truncated)?;
+
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SaveStateError> {
+        let bytes = self.take(2)?;
+
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SaveStateError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a state with something different in every field
+    fn sample(resolution: Resolution, memory_size: usize) -> SaveState {
+        SaveState {
+            screen: (0..resolution.pixels()).map(|i| i % 3 == 0).collect(),
+            second_plane: (0..resolution.pixels()).map(|i| i % 5 == 0).collect(),
+            selected_planes: 3,
+            resolution,
+            memory: (0..memory_size).map(|i| (i * 7) as u8).collect(),
+            v_reg: std::array::from_fn(|reg| reg as u8 * 11),
+            pc: 0x2A4,
+            i_reg: 0x3F0,
+            delay_timer_reg: 7,
+            sound_timer_reg: 9,
+            stack: vec![0x202, 0x310],
+            audio_pattern: Some([0xA5; AUDIO_PATTERN_SIZE]),
+            pitch: 100,
+            rpl_flags: [1, 2, 3, 4, 5, 6, 7, 8],
+        }
+    }
+
+    // the state as an older version wrote it, which had no room for what came later
+    fn old_bytes(state: &SaveState, version: u8) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+
+        data.push(version);
+        data.extend_from_slice(&state.pc.to_be_bytes());
+        data.extend_from_slice(&state.i_reg.to_be_bytes());
+        data.push(state.delay_timer_reg);
+        data.push(state.sound_timer_reg);
+        data.extend_from_slice(&state.v_reg);
+
+        data.push(state.stack.len() as u8);
+        for addr in &state.stack {
+            data.extend_from_slice(&addr.to_be_bytes());
+        }
+
+        data.extend_from_slice(&state.memory);
+
+        if version == 2 {
+            data.extend_from_slice(&(state.resolution.width as u16).to_be_bytes());
+            data.extend_from_slice(&(state.resolution.height as u16).to_be_bytes());
+        }
+
+        for chunk in state.screen.chunks(8) {
+            data.push(chunk.iter().fold(0, |byte, on| byte << 1 | *on as u8));
+        }
+
+        data
+    }
+
+    #[test]
+    fn the_current_version_round_trips() {
+        for resolution in Resolution::ALL {
+            for memory_size in [RAM_SIZE, XO_RAM_SIZE] {
+                let state = sample(resolution, memory_size);
+
+                assert_eq!(SaveState::from_bytes(&state.to_bytes()).unwrap(), state);
+            }
+        }
+    }
+
+    #[test]
+    fn version_3_states_load_without_rpl_flags() {
+        let state = sample(Resolution::SCHIP, XO_RAM_SIZE);
+        let mut data = state.to_bytes();
+
+        data[MAGIC.len()] = 3;
+        data.truncate(data.len() - NUM_RPL_FLAGS);
+
+        let loaded = SaveState::from_bytes(&data).unwrap();
+
+        assert_eq!(
+            loaded,
+            SaveState {
+                rpl_flags: [0; NUM_RPL_FLAGS],
+                ..state
+            }
+        );
+    }
+
+    #[test]
+    fn version_2_states_load_as_a_single_plane() {
+        let state = sample(Resolution::SCHIP, RAM_SIZE);
+
+        let loaded = SaveState::from_bytes(&old_bytes(&state, 2)).unwrap();
+
+        assert_eq!(
+            loaded,
+            SaveState {
+                second_plane: vec![false; Resolution::SCHIP.pixels()],
+                selected_planes: 1,
+                audio_pattern: None,
+                pitch: DEFAULT_PITCH,
+                rpl_flags: [0; NUM_RPL_FLAGS],
+                ..state
+            }
+        );
+    }
+
+    #[test]
+    fn version_1_states_load_in_lo_res() {
+        let state = sample(Resolution::LORES, RAM_SIZE);
+
+        let loaded = SaveState::from_bytes(&old_bytes(&state, 1)).unwrap();
+
+        assert_eq!(loaded.resolution, Resolution::LORES);
+        assert_eq!(loaded.screen, state.screen);
+        assert_eq!(loaded.memory, state.memory);
+        assert_eq!(loaded.stack, state.stack);
+        assert_eq!(loaded.selected_planes, 1);
+    }
+
+    #[test]
+    fn bad_headers_and_short_states_are_rejected() {
+        let data = sample(Resolution::LORES, RAM_SIZE).to_bytes();
+
+        let mut future = data.clone();
+        future[MAGIC.len()] = VERSION + 1;
+
+        assert!(matches!(
+            SaveState::from_bytes(b"OCTO\x04"),
+            Err(SaveStateError::BadMagic)
+        ));
+        assert!(matches!(
+            SaveState::from_bytes(&future),
+            Err(SaveStateError::UnsupportedVersion(5))
+        ));
+        assert!(matches!(
+            SaveState::from_bytes(&data[..data.len() - 1]),
+            Err(SaveStateError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn octo_json_round_trips() {
+        // Octo has no pitch, imports get the default
+        for (resolution, memory_size) in [
+            (Resolution::LORES, RAM_SIZE),
+            (Resolution::SCHIP, RAM_SIZE),
+            (Resolution::SCHIP, XO_RAM_SIZE),
+        ] {
+            let state = SaveState {
+                pitch: DEFAULT_PITCH,
+                ..sample(resolution, memory_size)
+            };
+
+            let (loaded, warnings) = SaveState::from_octo_json(&state.to_octo_json()).unwrap();
+
+            assert!(warnings.is_empty(), "{warnings:?}");
+            assert_eq!(loaded, state);
+        }
+    }
+
+    #[test]
+    fn octo_json_without_flags_or_pattern_loads_with_none() {
+        let state = SaveState {
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            rpl_flags: [0; NUM_RPL_FLAGS],
+            ..sample(Resolution::LORES, RAM_SIZE)
+        };
+
+        let mut octo: Value = serde_json::from_str(&state.to_octo_json()).unwrap();
+        octo.as_object_mut().unwrap().remove("flags");
+
+        let (loaded, warnings) = SaveState::from_octo_json(&octo.to_string()).unwrap();
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(loaded, state);
+    }
+}
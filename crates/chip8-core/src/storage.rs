@@ -0,0 +1,13 @@
+/// Where a frontend keeps what must outlive it: settings, save states, recent games...
+///
+/// Values are bytes under `/` separated keys (`config/gamepad_map`, `state/PONG`), so the
+/// same layout fits files on the desktop and an IndexedDB object store in the browser.
+/// Reads and writes are synchronous: asynchronous backends load everything up front and
+/// write in the background.
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    fn set(&mut self, key: &str, value: &[u8]) -> Result<(), String>;
+
+    fn remove(&mut self, key: &str) -> Result<(), String>;
+}
@@ -0,0 +1,103 @@
+use crate::constants::FONTSET;
+use crate::resolution::Resolution;
+
+// glyphs are sprites like the fontset: 5 rows, 4 pixels in the high nibble of each
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 5;
+
+// one blank column between characters
+pub const ADVANCE: usize = GLYPH_WIDTH + 1;
+
+// the letters and signs the fontset lacks, O is rounder than the fontset's 0
+const EXTENDED_GLYPHS: [(char, [u8; GLYPH_HEIGHT]); 40] = [
+    ('G', [0xF0, 0x80, 0xB0, 0x90, 0xF0]),
+    ('H', [0x90, 0x90, 0xF0, 0x90, 0x90]),
+    ('I', [0xE0, 0x40, 0x40, 0x40, 0xE0]),
+    ('J', [0x70, 0x20, 0x20, 0xA0, 0xE0]),
+    ('K', [0x90, 0xA0, 0xC0, 0xA0, 0x90]),
+    ('L', [0x80, 0x80, 0x80, 0x80, 0xF0]),
+    ('M', [0x90, 0xF0, 0xF0, 0x90, 0x90]),
+    ('N', [0x90, 0xD0, 0xB0, 0x90, 0x90]),
+    ('O', [0x60, 0x90, 0x90, 0x90, 0x60]),
+    ('P', [0xE0, 0x90, 0xE0, 0x80, 0x80]),
+    ('Q', [0x60, 0x90, 0x90, 0xB0, 0x70]),
+    ('R', [0xE0, 0x90, 0xE0, 0xA0, 0x90]),
+    ('S', [0x70, 0x80, 0x60, 0x10, 0xE0]),
+    ('T', [0xF0, 0x40, 0x40, 0x40, 0x40]),
+    ('U', [0x90, 0x90, 0x90, 0x90, 0xF0]),
+    ('V', [0x90, 0x90, 0x90, 0xA0, 0x40]),
+    ('W', [0x90, 0x90, 0xF0, 0xF0, 0x90]),
+    ('X', [0x90, 0x90, 0x60, 0x90, 0x90]),
+    ('Y', [0xA0, 0xA0, 0x40, 0x40, 0x40]),
+    ('Z', [0xF0, 0x10, 0x60, 0x80, 0xF0]),
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x40]),
+    (',', [0x00, 0x00, 0x00, 0x40, 0x80]),
+    (':', [0x00, 0x40, 0x00, 0x40, 0x00]),
+    ('-', [0x00, 0x00, 0xF0, 0x00, 0x00]),
+    ('+', [0x00, 0x40, 0xE0, 0x40, 0x00]),
+    ('=', [0x00, 0xF0, 0x00, 0xF0, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0xF0]),
+    ('!', [0x40, 0x40, 0x40, 0x00, 0x40]),
+    ('?', [0xE0, 0x10, 0x60, 0x00, 0x40]),
+    ('\'', [0x40, 0x40, 0x00, 0x00, 0x00]),
+    ('/', [0x10, 0x20, 0x20, 0x40, 0x80]),
+    ('<', [0x20, 0x40, 0x80, 0x40, 0x20]),
+    ('>', [0x80, 0x40, 0x20, 0x40, 0x80]),
+    ('(', [0x40, 0x80, 0x80, 0x80, 0x40]),
+    (')', [0x40, 0x20, 0x20, 0x20, 0x40]),
+    ('[', [0xC0, 0x80, 0x80, 0x80, 0xC0]),
+    (']', [0x60, 0x20, 0x20, 0x20, 0x60]),
+    ('*', [0x00, 0xA0, 0x40, 0xA0, 0x00]),
+    ('#', [0x50, 0xF0, 0x50, 0xF0, 0x50]),
+];
+
+/// The sprite of `c`: the machine's own fontset for hex digits, the extended glyphs for
+/// the rest. Lowercase letters are shown in uppercase, characters without a glyph as `?`.
+pub fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    let c = c.to_ascii_uppercase();
+
+    if let Some(digit) = c.to_digit(16) {
+        let start = digit as usize * GLYPH_HEIGHT;
+        let mut rows = [0; GLYPH_HEIGHT];
+        rows.copy_from_slice(&FONTSET[start..start + GLYPH_HEIGHT]);
+
+        return rows;
+    }
+
+    let find = |c| EXTENDED_GLYPHS.iter().find(|(glyph, _)| *glyph == c);
+
+    find(c)
+        .or_else(|| find('?'))
+        .map_or([0; GLYPH_HEIGHT], |(_, rows)| *rows)
+}
+
+/// The glyphs of `text` one after the other, GLYPH_HEIGHT bytes each, ready to be drawn
+/// with `DRW Vx, Vy, 5` from a ROM.
+pub fn sprites(text: &str) -> Vec<u8> {
+    text.chars().flat_map(glyph).collect()
+}
+
+/// Pixels `text` spans horizontally, without the gap after the last character.
+pub fn width(text: &str) -> usize {
+    (text.chars().count() * ADVANCE).saturating_sub(1)
+}
+
+/// Lights the pixels of `text` on a framebuffer of `resolution`, with the top left of the
+/// first character at `x`, `y`. Pixels off the screen are dropped rather than wrapped, and
+/// nothing is cleared, so the text can go over a picture.
+pub fn draw(screen: &mut [bool], resolution: Resolution, x: usize, y: usize, text: &str) {
+    for (n, c) in text.chars().enumerate() {
+        let left = x + n * ADVANCE;
+
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                let (px, py) = (left + col, y + row);
+
+                if px < resolution.width && py < resolution.height && bits & (0x80 >> col) != 0 {
+                    screen[py * resolution.width + px] = true;
+                }
+            }
+        }
+    }
+}
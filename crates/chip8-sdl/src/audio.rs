@@ -0,0 +1,548 @@
+use std::cell::Cell;
+use std::fs;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chip8_core::constants::AUDIO_PATTERN_SIZE;
+use chip8_core::core::{pattern_rate, Buzzer};
+use chip8_core::frontend::AudioSink;
+use rodio::cpal::traits::{HostTrait, StreamTrait};
+use rodio::cpal::{
+    BufferSize, Device, FromSample, OutputCallbackInfo, SampleFormat, SizedSample, Stream,
+    StreamConfig, StreamError, SupportedBufferSize,
+};
+use rodio::source::UniformSourceIterator;
+use rodio::{Decoder, DeviceTrait, Source};
+
+// the sound is cut to this length and a beep plays at least this long
+const BEEP_DURATION: Duration = Duration::from_millis(200);
+/// The volume of the beep unless set, from 0 to 1.
+pub const DEFAULT_VOLUME: f32 = 0.20;
+
+const DEFAULT_TONE_FREQUENCY: f32 = 440.0;
+
+// how often the audio thread tries to reopen a lost output device
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Names of the output devices the audio backend can play on.
+pub fn output_device_names() -> Vec<String> {
+    rodio::cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// The output device called `name`, or the default device when no name is given.
+/// Falls back to the default device when the named one is missing (e.g. unplugged mid-session).
+fn output_device(name: Option<&str>) -> Option<Device> {
+    let host = rodio::cpal::default_host();
+
+    if let Some(name) = name {
+        let device = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|device| device.name().is_ok_and(|n| n == name)));
+
+        match device {
+            Some(device) => return Some(device),
+            None => println!("Audio device {name} not found, using the default"),
+        }
+    }
+
+    host.default_output_device()
+}
+
+/// What the buzzer plays.
+#[derive(Clone, PartialEq, Debug)]
+pub enum BeepSound {
+    /// A square wave at this frequency, in Hz.
+    Tone(f32),
+    /// The start of a sound file (WAV, MP3, FLAC or Vorbis), looped for longer beeps.
+    File(String),
+}
+
+impl Default for BeepSound {
+    fn default() -> Self {
+        BeepSound::Tone(DEFAULT_TONE_FREQUENCY)
+    }
+}
+
+/// What the buzzer does while fast forwarding.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FastForwardAudio {
+    /// Silent until the game runs at normal speed again.
+    #[default]
+    Mute,
+    /// Beeps last as long as they do in the sped up game, at their usual pitch, instead of
+    /// the full length queued back to back for each of them.
+    Pitch,
+}
+
+impl FastForwardAudio {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "mute" => Some(FastForwardAudio::Mute),
+            "pitch" => Some(FastForwardAudio::Pitch),
+            _ => None,
+        }
+    }
+}
+
+/// What the emulator tells the audio thread.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AudioCommand {
+    /// Plays for `duration`, extending a beep still playing. With an XO-CHIP audio pattern
+    /// and its pitch, the pattern plays instead of the sound.
+    BeepOn {
+        duration: Duration,
+        pattern: Option<([u8; AUDIO_PATTERN_SIZE], u8)>,
+    },
+    /// Silences the beep playing.
+    BeepOff,
+    /// From 0 (silent) to 1.
+    SetVolume(f32),
+}
+
+// shared between the audio thread and the audio callback, the emulator reads the latency
+#[derive(Default)]
+struct BeeperState {
+    pending: AtomicU64,      // frames of sound still to play
+    restart: AtomicBool,     // a new beep starts, play the sound from its start
+    latency_us: AtomicU64,   // between a callback and the playback of its data, 0 until measured
+    pattern_on: AtomicBool,  // play the pattern below instead of the sound
+    pattern: [AtomicU64; 2], // XO-CHIP audio pattern, most significant bit first
+    pattern_step: AtomicU64, // pattern samples per output frame, as f64 bits
+    volume: AtomicU32,       // the samples are scaled by it, as f32 bits
+}
+
+impl BeeperState {
+    fn apply(&self, command: AudioCommand, sample_rate: u32) {
+        match command {
+            AudioCommand::BeepOn { duration, pattern } => {
+                if let Some((pattern, pitch)) = pattern {
+                    for (half, bytes) in self.pattern.iter().zip(pattern.chunks(8)) {
+                        half.store(
+                            u64::from_be_bytes(bytes.try_into().unwrap()),
+                            Ordering::Relaxed,
+                        );
+                    }
+
+                    let step = pattern_rate(pitch) / sample_rate as f64;
+
+                    self.pattern_step.store(step.to_bits(), Ordering::Relaxed);
+                }
+
+                self.pattern_on.store(pattern.is_some(), Ordering::Relaxed);
+
+                let frames = (duration.as_secs_f64() * sample_rate as f64) as u64;
+
+                // extends what's left of the beep playing
+                if self.pending.fetch_max(frames, Ordering::Relaxed) == 0 {
+                    self.restart.store(true, Ordering::Relaxed);
+                }
+            }
+            AudioCommand::BeepOff => self.pending.store(0, Ordering::Relaxed),
+            AudioCommand::SetVolume(volume) => self
+                .volume
+                .store(volume.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed),
+        }
+    }
+}
+
+/// The buzzer: plays the beep tone or sound on its own output stream, whose buffer size can be
+/// chosen, while the sound timer runs.
+///
+/// The stream belongs to an audio thread the beeper sends commands to over a channel, so
+/// the emulation never waits on the audio device. When the device goes away, e.g. a USB
+/// headset is unplugged, the thread reopens it, or the default device, as soon as it can.
+///
+/// Beeps are queued as a number of frames the audio callback plays out whenever it runs,
+/// instead of a flag it samples, so a burst of a frame or two is never swallowed by a
+/// large buffer; it only starts as late as the output latency, which is measured from the
+/// callback timestamps.
+pub struct Beeper {
+    commands: Sender<AudioCommand>, // the audio thread ends when dropped
+    state: Arc<BeeperState>,
+    buffer_frames: Option<u32>, // None for the device's default
+    speed: Cell<f64>,           // the clock's speed multiplier, see `AudioSink::set_speed`
+    fast_forward: FastForwardAudio,
+}
+
+impl Beeper {
+    /// Starts the audio thread on the output device called `name` (the default device when
+    /// missing) with a buffer of `buffer_frames` frames, or the device's default buffer size,
+    /// to play `sound`. Fails when the device can't be opened at first.
+    pub fn open(
+        name: Option<&str>,
+        buffer_frames: Option<u32>,
+        sound: &BeepSound,
+    ) -> Result<Self, String> {
+        let state = Arc::new(BeeperState::default());
+
+        state
+            .volume
+            .store(DEFAULT_VOLUME.to_bits(), Ordering::Relaxed);
+
+        let (commands, received) = mpsc::channel();
+        let (opened_sender, opened) = mpsc::sync_channel(1);
+
+        let output = OutputOptions {
+            name: name.map(str::to_string),
+            buffer_frames,
+            sound: sound.clone(),
+        };
+        let thread_state = state.clone();
+
+        thread::Builder::new()
+            .name("audio".to_string())
+            .spawn(move || audio_thread(output, thread_state, received, opened_sender))
+            .map_err(|err| err.to_string())?;
+
+        let buffer_frames = opened
+            .recv()
+            .map_err(|_| "the audio thread stopped".to_string())??;
+
+        Ok(Self {
+            commands,
+            state,
+            buffer_frames,
+            speed: Cell::new(1.0),
+            fast_forward: FastForwardAudio::default(),
+        })
+    }
+
+    // the audio thread only stops once the beeper is dropped, nothing is lost
+    fn send(&self, command: AudioCommand) {
+        let _ = self.commands.send(command);
+    }
+
+    /// Plays the sound for `duration`, at least BEEP_DURATION. A beep still playing is
+    /// extended rather than restarted.
+    ///
+    /// `duration` is in game time: while fast forwarding the beep is dropped or shortened
+    /// as many times as the game is sped up, depending on the FastForwardAudio choice.
+    pub fn beep(&self, duration: Duration) {
+        if let Some(duration) = self.real_duration(duration.max(BEEP_DURATION)) {
+            self.send(AudioCommand::BeepOn {
+                duration,
+                pattern: None,
+            });
+        }
+    }
+
+    /// Plays an XO-CHIP audio pattern for `duration`, see `Buzzer::play_pattern`. Unlike
+    /// the sound, a pattern plays only as long as the sound timer runs.
+    pub fn play_pattern(&self, duration: Duration, pattern: &[u8; AUDIO_PATTERN_SIZE], pitch: u8) {
+        if let Some(duration) = self.real_duration(duration) {
+            self.send(AudioCommand::BeepOn {
+                duration,
+                pattern: Some((*pattern, pitch)),
+            });
+        }
+    }
+
+    /// Silences the beep playing.
+    pub fn stop(&self) {
+        self.send(AudioCommand::BeepOff);
+    }
+
+    /// Sets the volume of the beep from 0 (silent) to 1, DEFAULT_VOLUME until set.
+    pub fn set_volume(&self, volume: f32) {
+        self.send(AudioCommand::SetVolume(volume));
+    }
+
+    // how long `duration` of game time plays, None while fast forwarding muted
+    fn real_duration(&self, duration: Duration) -> Option<Duration> {
+        let speed = self.speed.get();
+
+        match self.fast_forward {
+            _ if speed <= 1.0 => Some(duration),
+            FastForwardAudio::Mute => None,
+            // the sound itself is played at its own rate, only cut shorter
+            FastForwardAudio::Pitch => Some(duration.div_f64(speed)),
+        }
+    }
+
+    /// Time between the audio being generated and it reaching the device, once measured.
+    pub fn latency(&self) -> Option<Duration> {
+        match self.state.latency_us.load(Ordering::Relaxed) {
+            0 => None,
+            us => Some(Duration::from_micros(us)),
+        }
+    }
+
+    pub fn set_fast_forward_audio(&mut self, fast_forward: FastForwardAudio) {
+        self.fast_forward = fast_forward;
+    }
+
+    /// Frames per audio buffer, `None` when the device picked it.
+    pub fn buffer_frames(&self) -> Option<u32> {
+        self.buffer_frames
+    }
+}
+
+impl Buzzer for Beeper {
+    fn beep(&self, duration: Duration) {
+        Beeper::beep(self, duration);
+    }
+
+    fn play_pattern(&self, duration: Duration, pattern: &[u8; AUDIO_PATTERN_SIZE], pitch: u8) {
+        Beeper::play_pattern(self, duration, pattern, pitch);
+    }
+}
+
+// the clock's speed multiplier, above 1 while the game is fast forwarded
+impl AudioSink for Beeper {
+    fn set_speed(&self, speed: f64) {
+        let was_fast = self.speed.replace(speed) > 1.0;
+
+        if speed > 1.0 && !was_fast && self.fast_forward == FastForwardAudio::Mute {
+            self.stop();
+        }
+    }
+}
+
+// what the audio thread opens, again after the device was lost
+struct OutputOptions {
+    name: Option<String>,
+    buffer_frames: Option<u32>,
+    sound: BeepSound,
+}
+
+// owns the output stream: applies the beeper's commands and reopens the device when it's
+// lost, until the beeper is dropped. Sends back the buffer size once the device is opened.
+fn audio_thread(
+    options: OutputOptions,
+    state: Arc<BeeperState>,
+    commands: Receiver<AudioCommand>,
+    opened: SyncSender<Result<Option<u32>, String>>,
+) {
+    let open = || {
+        Output::open(
+            options.name.as_deref(),
+            options.buffer_frames,
+            &options.sound,
+            state.clone(),
+        )
+    };
+
+    let mut output = match open() {
+        Ok(output) => {
+            let _ = opened.send(Ok(output.buffer_frames));
+            Some(output)
+        }
+        Err(err) => {
+            let _ = opened.send(Err(err));
+            return;
+        }
+    };
+
+    let mut sample_rate = output.as_ref().map_or(0, |output| output.sample_rate);
+    let mut last_attempt = Instant::now();
+
+    loop {
+        match commands.recv_timeout(RECONNECT_INTERVAL) {
+            Ok(command) => state.apply(command, sample_rate),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if output
+            .as_ref()
+            .is_some_and(|output| output.lost.load(Ordering::Relaxed))
+        {
+            println!("Audio device lost, reconnecting");
+            output = None;
+        }
+
+        if output.is_none() && last_attempt.elapsed() >= RECONNECT_INTERVAL {
+            last_attempt = Instant::now();
+
+            if let Ok(reopened) = open() {
+                println!("Audio device reconnected");
+
+                sample_rate = reopened.sample_rate;
+                output = Some(reopened);
+            }
+        }
+    }
+}
+
+// an open output stream, on the audio thread
+struct Output {
+    _stream: Stream, // the sound stops when dropped
+    sample_rate: u32,
+    buffer_frames: Option<u32>,
+    lost: Arc<AtomicBool>, // the device went away, set by the stream
+}
+
+impl Output {
+    fn open(
+        name: Option<&str>,
+        buffer_frames: Option<u32>,
+        sound: &BeepSound,
+        state: Arc<BeeperState>,
+    ) -> Result<Self, String> {
+        let device = output_device(name).ok_or("no audio device")?;
+        let supported = device
+            .default_output_config()
+            .map_err(|err| err.to_string())?;
+
+        let buffer_frames = buffer_frames.map(|frames| match supported.buffer_size() {
+            SupportedBufferSize::Range { min, max } if frames < *min || frames > *max => {
+                let clamped = frames.clamp(*min, *max);
+                println!("Audio buffers are {min} to {max} frames on this device, using {clamped}");
+                clamped
+            }
+            _ => frames,
+        });
+
+        let config = StreamConfig {
+            buffer_size: buffer_frames.map_or(BufferSize::Default, BufferSize::Fixed),
+            ..supported.config()
+        };
+
+        let sound = match sound {
+            BeepSound::Tone(frequency) => {
+                Self::square_wave(*frequency, config.channels, config.sample_rate.0)
+            }
+            BeepSound::File(path) => Self::load_sound(path, config.channels, config.sample_rate.0)?,
+        };
+        let sound = Arc::new(sound);
+        let lost = Arc::new(AtomicBool::new(false));
+
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => Self::build_stream::<f32>(&device, &config, sound, state, &lost),
+            SampleFormat::I16 => Self::build_stream::<i16>(&device, &config, sound, state, &lost),
+            SampleFormat::U16 => Self::build_stream::<u16>(&device, &config, sound, state, &lost),
+            SampleFormat::I32 => Self::build_stream::<i32>(&device, &config, sound, state, &lost),
+            format => return Err(format!("unsupported sample format {format}")),
+        }?;
+
+        stream.play().map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            _stream: stream,
+            sample_rate: config.sample_rate.0,
+            buffer_frames,
+            lost,
+        })
+    }
+
+    // a single period, the callback loops it for as long as the beep lasts
+    fn square_wave(frequency: f32, channels: u16, sample_rate: u32) -> Vec<f32> {
+        let period = (sample_rate as f32 / frequency).round().max(2.0) as usize;
+
+        (0..period)
+            .flat_map(|frame| {
+                let value = if frame < period / 2 { 1.0 } else { -1.0 };
+
+                std::iter::repeat_n(value, channels as usize)
+            })
+            .collect()
+    }
+
+    // the beep sound, converted to the output's format
+    fn load_sound(path: &str, channels: u16, sample_rate: u32) -> Result<Vec<f32>, String> {
+        let data = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+
+        let source = Decoder::new(Cursor::new(data))
+            .map_err(|err| format!("{path}: {err}"))?
+            .take_duration(BEEP_DURATION)
+            .convert_samples::<f32>();
+
+        Ok(UniformSourceIterator::<_, f32>::new(source, channels, sample_rate).collect())
+    }
+
+    fn build_stream<T: SizedSample + FromSample<f32>>(
+        device: &Device,
+        config: &StreamConfig,
+        sound: Arc<Vec<f32>>,
+        state: Arc<BeeperState>,
+        lost: &Arc<AtomicBool>,
+    ) -> Result<Stream, String> {
+        let channels = config.channels as usize;
+        let lost = lost.clone();
+        let mut position = 0;
+        let mut phase = 0.0; // in pattern samples
+
+        let callback = move |data: &mut [T], info: &OutputCallbackInfo| {
+            let timestamp = info.timestamp();
+
+            if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+                state
+                    .latency_us
+                    .store(latency.as_micros() as u64, Ordering::Relaxed);
+            }
+
+            if state.restart.swap(false, Ordering::Relaxed) {
+                position = 0;
+                phase = 0.0;
+            }
+
+            let mut pending = state.pending.load(Ordering::Relaxed);
+
+            let pattern_on = state.pattern_on.load(Ordering::Relaxed);
+            let pattern = state
+                .pattern
+                .each_ref()
+                .map(|half| half.load(Ordering::Relaxed));
+            let pattern_step = f64::from_bits(state.pattern_step.load(Ordering::Relaxed));
+            let volume = f32::from_bits(state.volume.load(Ordering::Relaxed));
+
+            for frame in data.chunks_mut(channels) {
+                let bit = phase as usize % (AUDIO_PATTERN_SIZE * 8);
+                let pattern_value = if pattern[bit / 64] & (1 << (63 - bit % 64)) != 0 {
+                    1.0
+                } else {
+                    -1.0
+                };
+
+                for sample in frame {
+                    let value = if pending == 0 {
+                        0.0
+                    } else if pattern_on {
+                        pattern_value
+                    } else if !sound.is_empty() {
+                        let value = sound[position % sound.len()];
+                        position += 1;
+                        value
+                    } else {
+                        0.0
+                    };
+
+                    *sample = T::from_sample(value * volume);
+                }
+
+                if pending > 0 && pattern_on {
+                    phase = (phase + pattern_step) % (AUDIO_PATTERN_SIZE * 8) as f64;
+                }
+
+                pending = pending.saturating_sub(1);
+            }
+
+            // beeps queued meanwhile extend the one playing, see `beep`
+            let _ = state
+                .pending
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |now| {
+                    Some(now.saturating_sub(data.len() as u64 / channels as u64))
+                });
+        };
+
+        device
+            .build_output_stream(
+                config,
+                callback,
+                move |err| match err {
+                    StreamError::DeviceNotAvailable => lost.store(true, Ordering::Relaxed),
+                    err => println!("Audio output error: {err}"),
+                },
+                None,
+            )
+            .map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,9 @@
+use sdl2::pixels::Color;
+
+// share of the usable desktop area the window may cover at most
+pub const MAX_DESKTOP_SHARE: f32 = 0.9;
+
+pub const BLACK_COLOR: Color = Color::RGB(0, 0, 0);
+pub const WHITE_COLOR: Color = Color::RGB(255, 255, 255);
+pub const GRAY_COLOR: Color = Color::RGB(85, 85, 85);
+pub const LIGHT_GRAY_COLOR: Color = Color::RGB(170, 170, 170);
@@ -0,0 +1,72 @@
+use chip8_core::assembler;
+use chip8_core::core::Chip8;
+use chip8_core::text;
+
+// the menu program itself, assembled at startup with the names table appended
+const SOURCE: &str = include_str!("bios.c8s");
+
+// a name shows its first 12 characters, 60 pixels and 60 bytes of sprites; 48 names fit in
+// memory after the program, the host still shows the others
+const NAME_CHARS: usize = 12;
+const MAX_NAMES: usize = 48;
+
+// peripheral ports, below the program area and above the font
+const PORT_GAME_COUNT: u16 = 0x1F0;
+const PORT_SELECTED: u16 = 0x1F1;
+const PORT_CONFIRMED: u16 = 0x1F2;
+const PORT_NAME_COUNT: u16 = 0x1F3;
+
+/// The boot menu: a small CHIP-8 program (`bios.c8s`) running on the emulated machine, that
+/// shows a splash and lets the player pick a game with the keypad. It talks to the host
+/// through a few bytes of memory: the host writes the number of games, the program
+/// publishes the selection and sets a flag once the player confirmed it. The game names
+/// are generated into the ROM as text sprites, so the program draws them itself.
+pub struct Bios;
+
+impl Bios {
+    /// Loads the menu for the games called `names` into a freshly reset machine.
+    pub fn boot(chip8: &mut Chip8, names: &[&str]) {
+        let rom =
+            assembler::assemble(&Self::source(names)).expect("The built-in menu does not assemble");
+
+        chip8.reset();
+        chip8.load(&rom).expect("The built-in menu fits in memory");
+        chip8.write_memory(PORT_GAME_COUNT, names.len().min(u8::MAX as usize) as u8);
+        chip8.write_memory(PORT_SELECTED, 0);
+        chip8.write_memory(PORT_CONFIRMED, 0);
+        chip8.write_memory(PORT_NAME_COUNT, names.len().min(MAX_NAMES) as u8);
+    }
+
+    // the program followed by the `names` table, one line of NAME_CHARS glyphs per game
+    fn source(names: &[&str]) -> String {
+        let mut source = format!("{SOURCE}\nnames:\n");
+
+        for name in names.iter().take(MAX_NAMES) {
+            let shown: String = name
+                .chars()
+                .chain(std::iter::repeat(' '))
+                .take(NAME_CHARS)
+                .collect();
+            let bytes: Vec<String> = text::sprites(&shown)
+                .iter()
+                .map(|b| format!("{b:#04x}"))
+                .collect();
+
+            source.push_str(&format!("    DB {}\n", bytes.join(", ")));
+        }
+
+        source
+    }
+
+    /// The game currently highlighted in the menu.
+    pub fn selected(chip8: &Chip8) -> usize {
+        chip8.get_memory()[PORT_SELECTED as usize] as usize
+    }
+
+    /// The chosen game, once the player confirmed it.
+    pub fn chosen(chip8: &Chip8) -> Option<usize> {
+        let memory = chip8.get_memory();
+
+        (memory[PORT_CONFIRMED as usize] != 0).then_some(memory[PORT_SELECTED as usize] as usize)
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use chip8_core::core::{Chip8, Chip8Error};
+use chip8_core::resolution::Resolution;
+
+// exported heatmap pixels are blown up to be readable
+const HEATMAP_SCALE: usize = 8;
+
+/// A/B comparison: runs a second machine ("B") in lockstep with the one being played ("A"),
+/// fed the same key presses and instruction budget, and counts per pixel how many frames the
+/// two screens differed. The result is exported as a heatmap PNG plus a short text report.
+///
+/// The random number generator is not shared, ROMs using `RND` will differ on their own.
+pub struct Comparison {
+    chip8: Chip8,
+    rom_path: String,
+    differences: Vec<u32>,
+    frames: u32,
+    differing_frames: u32,
+}
+
+impl Comparison {
+    /// Runs `rom` set up like the game `played` is running: same quirks, load address,
+    /// resolution, memory size and random source.
+    pub fn new(rom_path: &str, rom: &[u8], played: &Chip8) -> Result<Self, Chip8Error> {
+        let mut chip8 = Chip8::new();
+
+        chip8.set_resolution(played.resolution());
+        chip8.set_memory_size(played.memory_size());
+        chip8.set_random_source(played.random_source());
+        chip8.set_start_addr(played.start_addr());
+        chip8.load(rom)?;
+        chip8.set_quirks(played.get_quirks());
+        chip8.set_sound_enabled(false);
+
+        Ok(Self {
+            chip8,
+            rom_path: rom_path.to_string(),
+            differences: vec![0; played.resolution().pixels()],
+            frames: 0,
+            differing_frames: 0,
+        })
+    }
+
+    pub fn keypress(&mut self, idx: usize, pressed: bool) {
+        self.chip8.keypress(idx, pressed);
+    }
+
+    /// Runs B for a frame and compares its screen against A's.
+    pub fn run_frame(&mut self, ticks: u32, screen_a: &[bool]) {
+        // a crashed B stays on the instruction, showing its last screen
+        let _ = self.chip8.run_frame(ticks);
+
+        let mut differs = false;
+
+        for ((count, a), b) in self
+            .differences
+            .iter_mut()
+            .zip(screen_a)
+            .zip(self.chip8.get_screen())
+        {
+            if a != b {
+                *count += 1;
+                differs = true;
+            }
+        }
+
+        self.frames += 1;
+        self.differing_frames += differs as u32;
+    }
+
+    /// Writes `<prefix>.heatmap.png` and `<prefix>.report.txt`.
+    pub fn export(&self, prefix: &str) -> std::io::Result<()> {
+        let report = format!(
+            "B rom: {}\nframes compared: {}\nframes with differences: {}\npixels that ever differed: {}\nmost differing pixel: {} frames\n",
+            self.rom_path,
+            self.frames,
+            self.differing_frames,
+            self.differences.iter().filter(|count| **count > 0).count(),
+            self.differences.iter().max().unwrap_or(&0),
+        );
+
+        fs::write(format!("{prefix}.report.txt"), report)?;
+
+        self.write_heatmap(Path::new(&format!("{prefix}.heatmap.png")))
+    }
+
+    // black where the screens always agreed, dark red to yellow the more often a pixel differed
+    fn write_heatmap(&self, path: &Path) -> std::io::Result<()> {
+        let Resolution { width, height } = self.chip8.resolution();
+        let (columns, width, height) = (width, width * HEATMAP_SCALE, height * HEATMAP_SCALE);
+
+        let max = (*self.differences.iter().max().unwrap_or(&0)).max(1) as f32;
+
+        let mut image = vec![0u8; width * height * 3];
+
+        for (i, count) in self.differences.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+
+            let heat = *count as f32 / max;
+            let color = [
+                (96.0 + 159.0 * heat.min(0.5) * 2.0) as u8,
+                (255.0 * (heat - 0.5).max(0.0) * 2.0) as u8,
+                0,
+            ];
+
+            let x = (i % columns) * HEATMAP_SCALE;
+            let y = (i / columns) * HEATMAP_SCALE;
+
+            for row in y..y + HEATMAP_SCALE {
+                for col in x..x + HEATMAP_SCALE {
+                    let idx = (row * width + col) * 3;
+
+                    image[idx..idx + 3].copy_from_slice(&color);
+                }
+            }
+        }
+
+        let file = BufWriter::new(fs::File::create(path)?);
+
+        let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&image))
+            .map_err(std::io::Error::other)
+    }
+}
@@ -0,0 +1,201 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use chip8_core::clock::Clock;
+use chip8_core::quirks::Quirks;
+use chip8_tools::file_io;
+use toml_edit::{Document, Item};
+
+use crate::audio::DEFAULT_VOLUME;
+use crate::managers::key_map::KeyMap;
+use crate::managers::palette::{self, Palette};
+
+// inside the config directory
+const CONFIG_FILE: &str = "config.toml";
+
+const DEFAULT_ROM_DIR: &str = "./c8games";
+
+// preferred window scale, lowered when the window would not fit the desktop
+const DEFAULT_SCALE: u32 = 30;
+
+/// The emulator's settings from `config.toml` in the config directory, e.g.
+///
+/// ```toml
+/// ipf = 15
+/// scale = 20
+/// volume = 0.5
+/// rom_dir = "~/games/chip8"
+/// quirks = "vy-shift,clip"
+/// palette = "amber"
+/// check_updates = true
+///
+/// [colors]
+/// foreground = "33FF66"
+///
+/// [keys]
+/// A = "Z"
+/// pause = "P"
+/// ```
+///
+/// Every setting is optional and a missing file leaves them all at their defaults. Command
+/// line flags override the file, as do the palette and key bindings saved from the emulator.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Config {
+    pub instructions_per_frame: Option<f64>, // None for the clock's default
+    pub scale: u32,
+    pub palette: Palette,
+    pub key_map: Option<KeyMap>, // None without a [keys] table
+    pub quirks: Option<Quirks>,  // for every game, None to use the library's
+    pub volume: f32,
+    pub rom_dir: String,
+    pub check_updates: bool, // ask for a newer release at startup
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            instructions_per_frame: None,
+            scale: DEFAULT_SCALE,
+            palette: Palette::default(),
+            key_map: None,
+            quirks: None,
+            volume: DEFAULT_VOLUME,
+            rom_dir: DEFAULT_ROM_DIR.to_string(),
+            check_updates: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        file_io::config_dir().join(CONFIG_FILE)
+    }
+
+    /// The settings of the config file, read the first time they are needed.
+    pub fn get() -> &'static Config {
+        static CONFIG: OnceLock<Config> = OnceLock::new();
+
+        CONFIG.get_or_init(|| Self::load(&Self::path()))
+    }
+
+    /// The settings in the file at `path`, the defaults when there is none. Invalid
+    /// settings are reported and keep their default.
+    pub fn load(path: &Path) -> Self {
+        let mut config = Self::default();
+
+        let Ok(text) = fs::read_to_string(path) else {
+            return config;
+        };
+
+        let document: Document = match text.parse() {
+            Ok(document) => document,
+            Err(err) => {
+                println!("Ignoring config file {}: {err}", path.display());
+                return config;
+            }
+        };
+
+        let invalid = |name: &str| println!("Ignoring invalid {name} in config file");
+
+        if let Some(item) = document.get("ipf") {
+            match number(item).filter(|ipf| ipf.is_finite() && *ipf >= 0.0) {
+                Some(ipf) => config.instructions_per_frame = Some(ipf),
+                None => invalid("ipf"),
+            }
+        }
+
+        if let Some(item) = document.get("scale") {
+            match item.as_integer().filter(|scale| *scale >= 1) {
+                Some(scale) => config.scale = scale as u32,
+                None => invalid("scale"),
+            }
+        }
+
+        if let Some(item) = document.get("volume") {
+            match number(item).filter(|volume| (0.0..=1.0).contains(volume)) {
+                Some(volume) => config.volume = volume as f32,
+                None => invalid("volume"),
+            }
+        }
+
+        if let Some(item) = document.get("rom_dir") {
+            match item.as_str() {
+                Some(dir) => config.rom_dir = expand_home(dir),
+                None => invalid("rom_dir"),
+            }
+        }
+
+        if let Some(item) = document.get("quirks") {
+            match item.as_str().and_then(Quirks::parse) {
+                Some(quirks) => config.quirks = Some(quirks),
+                None => invalid("quirks"),
+            }
+        }
+
+        if let Some(item) = document.get("check_updates") {
+            match item.as_bool() {
+                Some(check) => config.check_updates = check,
+                None => invalid("check_updates"),
+            }
+        }
+
+        // a preset the [colors] table can then change
+        if let Some(item) = document.get("palette") {
+            match item.as_str().and_then(Palette::preset) {
+                Some(palette) => config.palette = palette,
+                None => invalid("palette"),
+            }
+        }
+
+        if let Some(colors) = document.get("colors").and_then(Item::as_table) {
+            for (name, item) in colors.iter() {
+                let idx = Palette::NAMES.iter().position(|known| *known == name);
+
+                match (idx, item.as_str().and_then(palette::parse_hex)) {
+                    (Some(idx), Some(color)) => *config.palette.color_mut(idx) = color,
+                    _ => invalid(&format!("color {name}")),
+                }
+            }
+        }
+
+        if let Some(keys) = document.get("keys").and_then(Item::as_table) {
+            let mut key_map = KeyMap::default();
+
+            for (name, item) in keys.iter() {
+                let key = match item.as_bool() {
+                    Some(physical) => physical.to_string(),
+                    None => item.as_str().unwrap_or_default().to_string(),
+                };
+
+                if !key_map.bind(name, &key) {
+                    invalid(&format!("key {name}"));
+                }
+            }
+
+            config.key_map = Some(key_map);
+        }
+
+        config
+    }
+
+    /// The clock games start with unless a session or the command line sets the speed.
+    pub fn clock(&self) -> Clock {
+        self.instructions_per_frame
+            .map_or_else(Clock::default, Clock::from_ipf)
+    }
+}
+
+// integers are fine where fractions are allowed, `ipf = 15`
+fn number(item: &Item) -> Option<f64> {
+    item.as_float()
+        .or_else(|| item.as_integer().map(|number| number as f64))
+}
+
+// `~/games` is in the home directory
+fn expand_home(dir: &str) -> String {
+    match (dir.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => dir.to_string(),
+    }
+}
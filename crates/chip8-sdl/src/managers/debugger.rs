@@ -0,0 +1,209 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use chip8_core::core::{Chip8, HookAction, PreExecHook};
+use chip8_core::disassembler;
+use chip8_core::effect::Effect;
+
+/// Instructions shown before and after PC while paused.
+pub const TRACE_LINES: usize = 5;
+
+/// The built-in debugger, on top of the same pre-execution hook library users get: records
+/// the last executed addresses and breaks at the breakpoints, which can be set on the
+/// command line (`--break`) and toggled at PC while paused. The desktop pauses, steps and
+/// resumes the machine; the debugger shows where it is.
+pub struct Debugger {
+    trace: Rc<RefCell<VecDeque<u16>>>, // oldest first, shared with the hook
+    breakpoints: Rc<RefCell<Vec<u16>>>,
+}
+
+impl Debugger {
+    pub fn new(breakpoints: Vec<u16>) -> Self {
+        Self {
+            trace: Rc::new(RefCell::new(VecDeque::with_capacity(TRACE_LINES))),
+            breakpoints: Rc::new(RefCell::new(breakpoints)),
+        }
+    }
+
+    /// The hook to install with `Chip8::set_pre_exec_hook`.
+    pub fn hook(&self) -> PreExecHook {
+        let trace = self.trace.clone();
+        let breakpoints = self.breakpoints.clone();
+
+        Box::new(move |chip8, _op| {
+            let pc = chip8.get_program_counter();
+
+            if breakpoints.borrow().contains(&pc) {
+                println!("Breakpoint at {pc:03X}");
+                Self::print_state(chip8);
+
+                return HookAction::Break;
+            }
+
+            let mut trace = trace.borrow_mut();
+
+            if trace.len() == TRACE_LINES {
+                trace.pop_front();
+            }
+
+            trace.push_back(pc);
+
+            HookAction::Continue
+        })
+    }
+
+    /// Forgets the executed addresses, e.g. when another game is loaded.
+    pub fn clear_trace(&self) {
+        self.trace.borrow_mut().clear();
+    }
+
+    /// Sets a breakpoint at `addr` or removes the one there, returns whether it is set now.
+    pub fn toggle_breakpoint(&self, addr: u16) -> bool {
+        let mut breakpoints = self.breakpoints.borrow_mut();
+
+        match breakpoints
+            .iter()
+            .position(|breakpoint| *breakpoint == addr)
+        {
+            Some(idx) => {
+                breakpoints.remove(idx);
+                false
+            }
+            None => {
+                breakpoints.push(addr);
+                true
+            }
+        }
+    }
+
+    /// Parses `--break 2A4,0x300`, addresses in hex.
+    pub fn parse_breakpoints(list: &str) -> Vec<u16> {
+        list.split(',')
+            .filter_map(|addr| {
+                let addr = addr.trim();
+                let digits = addr.trim_start_matches("0x").trim_start_matches("0X");
+
+                match u16::from_str_radix(digits, 16) {
+                    Ok(addr) => Some(addr),
+                    Err(_) => {
+                        println!("Ignoring invalid breakpoint {addr}");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// The last executed instructions, the one at PC (marked `>`) and the ones following
+    /// it; breakpoints are marked `*`.
+    pub fn hud_lines(&self, chip8: &Chip8) -> Vec<String> {
+        let memory = chip8.get_memory();
+        let pc = chip8.get_program_counter();
+        let breakpoints = self.breakpoints.borrow();
+
+        let line = |marker: char, addr: u16| {
+            let op = disassembler::opcode_at(memory, addr);
+            let breakpoint = if breakpoints.contains(&addr) {
+                '*'
+            } else {
+                ' '
+            };
+
+            format!(
+                "{marker}{breakpoint}{addr:03X}: {}",
+                disassembler::disassemble(op)
+            )
+        };
+
+        let mut lines: Vec<String> = self
+            .trace
+            .borrow()
+            .iter()
+            .map(|addr| line(' ', *addr))
+            .collect();
+
+        lines.push(line('>', pc));
+
+        for n in 1..=TRACE_LINES as u16 {
+            lines.push(line(' ', pc.wrapping_add(n * 2)));
+        }
+
+        lines
+    }
+
+    /// What a stepped instruction did besides changing registers, e.g. `skipped the next
+    /// instruction`, empty when it did nothing else.
+    pub fn effect_text(effect: &Effect) -> String {
+        let mut parts = Vec::new();
+
+        if effect.skipped {
+            parts.push("skipped the next instruction".to_string());
+        }
+
+        if let Some(addr) = effect.pushed {
+            parts.push(format!("called, returns to {addr:03X}"));
+        } else if let Some(addr) = effect.popped {
+            parts.push(format!("returned to {addr:03X}"));
+        } else if effect.jumped {
+            parts.push("jumped".to_string());
+        }
+
+        if let Some((addr, len)) = effect.memory {
+            parts.push(format!("wrote {len} bytes at {addr:03X}"));
+        }
+
+        if effect.screen {
+            parts.push("drew".to_string());
+        }
+
+        if effect.sound_started {
+            parts.push("started the buzzer".to_string());
+        }
+
+        if effect.waits_for_key {
+            parts.push("waits for a key".to_string());
+        }
+
+        if effect.waits_for_vblank {
+            parts.push("waits for the next frame".to_string());
+        }
+
+        if effect.halted {
+            parts.push("halted".to_string());
+        }
+
+        parts.join(", ")
+    }
+
+    /// Prints the instruction at PC, the registers, timers and stack, e.g. after every step.
+    pub fn print_state(chip8: &Chip8) {
+        println!("{}", disassembler::at_pc(chip8));
+        println!("{}", Self::registers_text(chip8));
+    }
+
+    /// The registers, timers and stack as text, e.g. to paste into a bug report.
+    pub fn registers_text(chip8: &Chip8) -> String {
+        let registers: Vec<String> = chip8
+            .get_registers()
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| format!("V{idx:X}={value:02X}"))
+            .collect();
+        let stack: Vec<String> = chip8
+            .get_stack()
+            .iter()
+            .map(|addr| format!("{addr:03X}"))
+            .collect();
+
+        format!(
+            "PC={:03X} I={:03X} DT={:02X} ST={:02X} {}\nStack: [{}]",
+            chip8.get_program_counter(),
+            chip8.get_i_register(),
+            chip8.get_delay_timer(),
+            chip8.get_sound_timer(),
+            registers.join(" "),
+            stack.join(", ")
+        )
+    }
+}
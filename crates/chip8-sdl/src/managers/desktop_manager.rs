@@ -0,0 +1,2725 @@
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+use std::fs;
+
+use chip8_core::clock::{Clock, MAX_SPEED};
+use chip8_core::constants::{FRAMES_PER_SECOND, NUM_KEYS, START_ADDR, XO_RAM_SIZE};
+use chip8_core::core::{Chip8, Chip8Error};
+use chip8_core::effect::Effect;
+use chip8_core::frame_loop::FrameLoop;
+use chip8_core::frontend::{self, AudioSink, Renderer};
+use chip8_core::gamepad_map::GamepadMap;
+use chip8_core::machine::Machine;
+use chip8_core::quirks::Quirks;
+use chip8_core::random::RandomSource;
+use chip8_core::resolution::Resolution;
+use chip8_core::savestate::SaveState;
+use chip8_tools::cartridge::Cartridge;
+use chip8_tools::cli::Cli;
+use chip8_tools::file_io;
+use chip8_tools::hot_patch;
+use chip8_tools::quirk_detector;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, TextureCreator, WindowCanvas};
+use sdl2::video::{FullscreenType, WindowContext, WindowPos};
+use sdl2::{Sdl, VideoSubsystem};
+
+use crate::audio::{BeepSound, Beeper, FastForwardAudio};
+use crate::constants::{BLACK_COLOR, MAX_DESKTOP_SHARE, WHITE_COLOR};
+use crate::managers::bios::Bios;
+use crate::managers::comparison::Comparison;
+use crate::managers::config::Config;
+use crate::managers::debugger::{Debugger, TRACE_LINES};
+use crate::managers::frame_history::FrameHistory;
+use crate::managers::gamepad::{self, GamepadSetup, Gamepads};
+use crate::managers::hud;
+use crate::managers::input::{KeyPriority, KeypadInput, SdlInput};
+use crate::managers::input_test::InputTest;
+use crate::managers::key_layout::{KeyLayout, LayoutMenu};
+use crate::managers::key_map::{Action, KeyMap};
+use crate::managers::key_setup::KeySetup;
+use crate::managers::led_stream::LedStream;
+use crate::managers::memory_heatmap::MemoryHeatmap;
+use crate::managers::movie::Movie;
+use crate::managers::opcode_quiz::OpcodeQuiz;
+use crate::managers::orientation::Orientation;
+use crate::managers::palette::Palette;
+use crate::managers::palette_editor::PaletteEditor;
+use crate::managers::play_stats::PlayStats;
+use crate::managers::post_effects::{self, EffectsScreen, Frame, Pipeline};
+use crate::managers::rewind::Rewind;
+use crate::managers::rom_browser::{RomBrowser, RomEntry};
+use crate::managers::rom_editor::RomEditor;
+use crate::managers::rom_library::RomLibrary;
+use crate::managers::screenshots;
+use crate::managers::session::{Session, WindowGeometry};
+use crate::managers::signature::Signer;
+use crate::managers::skin::Skin;
+use crate::managers::sound_events::SoundEvents;
+use crate::managers::spectator_server::SpectatorServer;
+use crate::managers::sprite_editor::{SpriteAction, SpriteEditor, MAX_HEIGHT};
+use crate::managers::update_check::{Release, UpdateCheck};
+use crate::managers::verifier::Verifier;
+use crate::managers::view_preset::{ViewMenu, ViewPreset};
+
+// remembered `--audio-device`, `--audio-buffer` and `--fast-forward-audio` choices, inside
+// the config directory
+const AUDIO_DEVICE_FILE: &str = "audio_device";
+const AUDIO_BUFFER_FILE: &str = "audio_buffer";
+const FAST_FORWARD_AUDIO_FILE: &str = "fast_forward_audio";
+
+// frames of the game per frame shown while the fast forward key is held
+const DEFAULT_FAST_FORWARD_SPEED: f64 = 4.0;
+
+// how far back the rewind key goes unless `--rewind-seconds` says otherwise
+const DEFAULT_REWIND_SECONDS: u32 = 10;
+
+// the speed up and slow down keys change the instructions per frame by this factor, see
+// `Clock::step_instructions_per_frame`
+const SPEED_STEP: f64 = 1.25;
+
+// how often `--watch` looks at the game file
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+const HUD_SCALE: u32 = 4;
+const HUD_BACKGROUND_COLOR: Color = Color::RGBA(0, 0, 0, 200);
+const HUD_PAST_COLOR: Color = Color::RGB(120, 120, 120);
+const HUD_NEXT_COLOR: Color = Color::RGB(180, 180, 180);
+
+// `--visual-sound` border, shown at least this long so a game's shortest beeps don't go unseen
+const SOUND_CUE_COLOR: Color = Color::RGB(255, 200, 0);
+const SOUND_CUE_MIN_TIME: Duration = Duration::from_millis(150);
+const SOUND_CUE_WIDTH: u32 = 6;
+
+// a newer release is announced over the game this long, with the first lines of its notes
+const UPDATE_NOTICE_TIME: Duration = Duration::from_secs(15);
+const UPDATE_NOTICE_LINES: usize = 3;
+
+// longer frames were spent somewhere else, e.g. in the ROM editor prompt
+const MAX_PLAY_FRAME_TIME: Duration = Duration::from_secs(1);
+
+// time the machine frames of a window frame may take before the rest are dropped, checked
+// every BUDGET_CHECK_INTERVAL instructions and reported at most every OVERRUN_REPORT_INTERVAL
+const FRAME_TIME_BUDGET: Duration = Duration::from_millis(1000 / FRAMES_PER_SECOND as u64);
+const BUDGET_CHECK_INTERVAL: u32 = 256;
+const OVERRUN_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// time between the full machine states stored in recorded movies
+const DEFAULT_KEYFRAME_SECONDS: u32 = 5;
+
+// two seconds of frames
+const DEFAULT_FLIPBOOK_FRAMES: usize = 120;
+
+// Ctrl+E writes the play stats here
+const STATS_EXPORT_FILE: &str = "chip8-stats.json";
+
+pub struct DesktopGameManager {
+    sdl_context: Sdl,
+    canvas: WindowCanvas,
+    spectator_server: Option<SpectatorServer>,
+    hud_lines: Vec<String>, // drawn over the bottom of the screen when not empty
+    grab_keyboard: bool,    // grab the keyboard exclusively while the game runs
+    view: ViewPreset,       // how large the screen is drawn
+    visual_sound: bool,     // flash the window border while the buzzer sounds
+    key_map: KeyMap,
+    palette: Palette,
+    orientation: Orientation,
+    memory_heatmap: Option<MemoryHeatmap>, // F4, drawn over the right of the screen
+    sprite_editor: Option<SpriteEditor>,   // F12, drawn over the left of the screen
+    signer: Signer,                        // signs and checks states and movies
+    windowed_geometry: Option<WindowGeometry>, // where the window goes back to when leaving fullscreen
+    machine: Machine,                          // of the game being played
+    resolution: Resolution,                    // of the screen the window was sized for
+    skin: Option<Skin<'static>>,               // frame drawn around the screen, `--skin`
+    effects: Option<EffectsScreen>,            // display effects, `--effects`
+    led_stream: Option<LedStream>,             // mirrors the screen on an LED matrix, `--led`
+    sound_events: Option<SoundEvents>,         // buzzer starts and stops, `--sound-events`
+    sound_cue_until: Option<Instant>,          // end of the `--visual-sound` border flash
+    stats: PlayStats,                          // the Ctrl+I dashboard
+    update_check: Option<UpdateCheck>,         // `check_updates` or `--check-updates`
+    update_notice: Option<(Release, Instant)>, // a newer release, shown until then
+}
+
+impl Renderer for DesktopGameManager {
+    fn draw(&mut self, chip8: &Chip8) {
+        self.draw_screen(chip8);
+    }
+}
+
+impl Default for DesktopGameManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DesktopGameManager {
+    pub fn new() -> Self {
+        let sdl = Self::create_sql();
+        let orientation = Self::orientation();
+        let view = Self::view_preset();
+        let canvas = Self::create_canvas(&sdl, orientation, view);
+        let skin = Self::load_skin(&canvas);
+        let effects = Self::load_effects(&canvas);
+
+        let mut manager = Self {
+            canvas,
+            sdl_context: sdl,
+            spectator_server: Self::create_spectator_server(),
+            led_stream: Self::create_led_stream(),
+            sound_events: Self::create_sound_events(),
+            sound_cue_until: None,
+            stats: PlayStats::load(),
+            hud_lines: Vec::new(),
+            grab_keyboard: Self::has_switch("--grab-keyboard"),
+            view,
+            visual_sound: Self::has_switch("--visual-sound"),
+            key_map: KeyMap::load().unwrap_or_default(),
+            palette: Self::load_palette(),
+            orientation,
+            memory_heatmap: None,
+            sprite_editor: None,
+            signer: Signer::load(),
+            windowed_geometry: None,
+            machine: Machine::default(),
+            resolution: Resolution::default(),
+            skin,
+            effects,
+            update_check: None,
+            update_notice: None,
+        };
+
+        if manager.skin.is_some() {
+            manager.fit_window_to_display(0);
+        }
+
+        manager
+    }
+
+    fn load_skin(canvas: &WindowCanvas) -> Option<Skin<'static>> {
+        let path = Self::arg_value("--skin")?;
+
+        // the skin's textures live as long as the window, which is the whole run
+        let texture_creator: &'static TextureCreator<WindowContext> =
+            Box::leak(Box::new(canvas.texture_creator()));
+
+        match Skin::load(texture_creator, Path::new(&path)) {
+            Ok(skin) => Some(skin),
+            Err(err) => {
+                println!("Could not load skin {path}: {err}");
+                None
+            }
+        }
+    }
+
+    // value following `flag` on the command line, e.g. `--spectate 0.0.0.0:8765`
+    fn arg_value(flag: &str) -> Option<String> {
+        Cli::get().value(flag).map(str::to_string)
+    }
+
+    // flags without a value, e.g. `--grab-keyboard`
+    fn has_switch(switch: &str) -> bool {
+        Cli::get().switch(switch)
+    }
+
+    // command line arguments that are neither flags nor flag values
+    fn positional_args() -> Vec<String> {
+        Cli::get().positional.clone()
+    }
+
+    // path of the chosen rom or cartridge and the game to start with inside it
+    fn choose_game(&mut self, session: Option<&Session>) -> Option<(String, usize)> {
+        let args = Self::positional_args();
+
+        if args.len() == 1 {
+            return Some((args[0].to_owned(), 0));
+        }
+
+        if let Some(session) = session {
+            return Some((session.game_path.clone(), session.sub_game));
+        }
+
+        let mut browser = RomBrowser::scan(&Config::get().rom_dir);
+
+        if Self::has_switch("--browser") {
+            return browser.run(&self.sdl_context, &mut self.canvas);
+        }
+
+        self.run_bios(browser.entries())
+    }
+
+    // the boot menu runs on an emulated machine of its own, the host shows the full names
+    fn run_bios(&mut self, entries: &[RomEntry]) -> Option<(String, usize)> {
+        if entries.is_empty() {
+            println!("No games found");
+            return None;
+        }
+
+        let mut chip8 = Chip8::new();
+        let mut clock = Clock::default();
+
+        let names: Vec<&str> = entries.iter().map(|entry| entry.title.as_str()).collect();
+        Bios::boot(&mut chip8, &names);
+
+        let mut event_pump = self.sdl_context.event_pump().unwrap();
+
+        loop {
+            for evt in event_pump.poll_iter() {
+                match evt {
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => return None,
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F1),
+                        ..
+                    } => InputTest::new(self.key_map.clone()).run(&self.sdl_context, &mut self.canvas),
+
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        scancode,
+                        ..
+                    } => {
+                        if let Some(k) = self.key2btn(key, scancode) {
+                            chip8.keypress(k, true);
+                        }
+                    }
+
+                    Event::KeyUp {
+                        keycode: Some(key),
+                        scancode,
+                        ..
+                    } => {
+                        if let Some(k) = self.key2btn(key, scancode) {
+                            chip8.keypress(k, false);
+                        }
+                    }
+
+                    _ => (),
+                }
+            }
+
+            for _ in 0..clock.ticks_for_frame() {
+                chip8.tick().expect("the boot menu crashed");
+            }
+
+            chip8.tick_timers();
+
+            if let Some(entry) = Bios::chosen(&chip8).and_then(|idx| entries.get(idx)) {
+                self.hud_lines.clear();
+
+                return Some((entry.path.clone(), entry.sub_game));
+            }
+
+            let selected = entries
+                .get(Bios::selected(&chip8))
+                .map_or("", |entry| entry.title.as_str());
+
+            self.hud_lines = vec![
+                selected.to_string(),
+                format!(
+                    "{} {} SELECT   {} START   F1 INPUT TEST",
+                    self.key_map.keypad_key(0x2).name(),
+                    self.key_map.keypad_key(0x8).name(),
+                    self.key_map.keypad_key(0x5).name()
+                ),
+            ];
+
+            self.draw_screen(&chip8);
+        }
+    }
+
+    // the actions keep their keys, the layout is saved with the key bindings
+    fn set_key_layout(&mut self, layout: KeyLayout) {
+        self.key_map.set_layout(layout);
+
+        match self.key_map.save() {
+            Ok(_) => println!("Key layout: {}", layout.name()),
+            Err(err) => println!("Could not save key bindings: {err}"),
+        }
+    }
+
+    // skipping the setup saves the default keys, so it is not shown again
+    fn setup_keys(&mut self) {
+        let mut key_setup = KeySetup::new();
+        key_setup.set_physical(self.key_map.physical());
+
+        if let Some(key_map) = key_setup.run(&self.sdl_context, &mut self.canvas) {
+            self.key_map = key_map;
+        }
+
+        match self.key_map.save() {
+            Ok(_) => println!("Saved key bindings, run with --setup-keys to change them"),
+            Err(err) => println!("Could not save key bindings: {err}"),
+        }
+    }
+
+    // `--palette theme.txt` uses and saves a theme file instead of the remembered palette,
+    // `--palette green` starts from a built-in one unless there is a file of that name
+    fn palette_path() -> PathBuf {
+        Self::arg_value("--palette")
+            .filter(|name| Palette::preset(name).is_none() || Path::new(name).exists())
+            .map(PathBuf::from)
+            .unwrap_or_else(Palette::config_path)
+    }
+
+    fn load_palette() -> Palette {
+        let preset = Self::arg_value("--palette")
+            .filter(|name| !Path::new(name).exists())
+            .and_then(|name| Palette::preset(&name));
+
+        preset
+            .or_else(|| Palette::load(&Self::palette_path()))
+            .unwrap_or(Config::get().palette)
+    }
+
+    fn save_palette(&self) {
+        let path = Self::palette_path();
+
+        match self.palette.save(&path) {
+            Ok(_) => println!("Saved palette to {}", path.display()),
+            Err(err) => println!("Could not save palette to {}: {err}", path.display()),
+        }
+    }
+
+    fn read_game_rom(&self, path: &String) -> Cartridge {
+        println!("Loading {path}...");
+
+        Cartridge::open(path).expect("Failed to load ROM")
+    }
+
+    fn modified_time(path: &str) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+
+    // `--pack games.c8pak ROM...` bundles roms into a multi-game cartridge
+    fn pack_cartridge(&self, out_path: &str) {
+        let games = Self::positional_args()
+            .iter()
+            .map(|path| match file_io::read_file(path) {
+                Ok(rom) => Ok((file_io::display_name(path), rom)),
+                Err(err) => Err(format!("{path}: {err}")),
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .and_then(Cartridge::new);
+
+        let result = games.and_then(|cartridge| {
+            file_io::write_file(out_path, &cartridge.to_bytes())
+                .map(|_| cartridge.len())
+                .map_err(|err| format!("{out_path}: {err}"))
+        });
+
+        match result {
+            Ok(count) => println!("Packed {count} games into {out_path}"),
+            Err(err) => println!("Could not pack cartridge: {err}"),
+        }
+    }
+
+    /// Looks for a release newer than `version`, the running binary's, in the background when
+    /// `check_updates` or `--check-updates` asks for it.
+    pub fn check_for_updates(&mut self, version: &str) {
+        if Config::get().check_updates || Self::has_switch("--check-updates") {
+            self.update_check = Some(UpdateCheck::start(version));
+        }
+    }
+
+    pub fn start_game_loop(&mut self, mut chip8: Chip8) {
+        if let Some(out_path) = Self::arg_value("--pack") {
+            self.pack_cartridge(&out_path);
+            return;
+        }
+
+        // first run, walk the user through binding their keys
+        let first_run = KeyMap::load().is_none();
+
+        // `--key-mapping physical` binds the keypad by key position, `layout` by key label
+        if let Some(mapping) = Self::arg_value("--key-mapping") {
+            match mapping.as_str() {
+                "physical" | "layout" => {
+                    self.key_map.set_physical(mapping == "physical");
+
+                    if let Err(err) = self.key_map.save() {
+                        println!("Could not save key bindings: {err}");
+                    }
+                }
+                _ => println!("Unknown key mapping {mapping}, expected physical or layout"),
+            }
+        }
+
+        if let Some(name) = Self::arg_value("--key-layout") {
+            match KeyLayout::parse(&name) {
+                Some(layout) => self.set_key_layout(layout),
+                None => println!("Unknown key layout {name}, see --help"),
+            }
+        }
+
+        if first_run || Self::has_switch("--setup-keys") {
+            self.setup_keys();
+        }
+
+        // `--restore-session` brings back the window, game, speed and palette of the last run
+        let session = Self::has_switch("--restore-session")
+            .then(Session::load)
+            .flatten();
+
+        if let Some(session) = &session {
+            self.restore_window(session.window);
+            self.palette = session.palette;
+        }
+
+        // get game from args or let the user choose one in the browser
+        let Some((game_path, mut sub_game)) = self.choose_game(session.as_ref()) else {
+            return;
+        };
+
+        // read the game data from the file, plain roms are single game cartridges
+        let mut cartridge = self.read_game_rom(&game_path);
+
+        if let Some(name) = Self::arg_value("--game") {
+            match cartridge.names().position(|game| game.eq_ignore_ascii_case(&name)) {
+                Some(idx) => sub_game = idx,
+                None => println!("{game_path} has no game named {name}"),
+            }
+        }
+
+        // load the game into the chip memory
+        self.load_rom(&mut chip8, cartridge.rom(sub_game));
+        Self::apply_quirks(&mut chip8, cartridge.rom(sub_game));
+        self.stats
+            .game_started(&RomLibrary::key(cartridge.rom(sub_game)));
+
+        // kept here too for the diagnostics overlay
+        let audio_device = Self::audio_device();
+        let opened = if Self::has_switch("--mute") {
+            Err("muted".to_string())
+        } else {
+            Beeper::open(
+                audio_device.as_deref(),
+                Self::audio_buffer_frames(),
+                &Self::beep_sound(),
+            )
+        };
+
+        let beeper = match opened {
+            Ok(mut beeper) => {
+                beeper.set_fast_forward_audio(Self::fast_forward_audio());
+                beeper.set_volume(Self::volume());
+                Some(Rc::new(beeper))
+            }
+            Err(_) if Self::has_switch("--mute") => None,
+            Err(err) => {
+                println!("Could not open audio output: {err}");
+                None
+            }
+        };
+
+        if let Some(beeper) = &beeper {
+            chip8.set_buzzer(Some(Box::new(beeper.clone())));
+        }
+
+        // interpret the FFF0-FFF3 debug instructions
+        chip8.set_dev_mode(Self::has_switch("--dev"));
+
+        // `--reference-check` cross-checks every instruction against the reference interpreter
+        if Self::has_switch("--reference-check") {
+            if cfg!(debug_assertions) {
+                chip8.set_reference_check(true);
+            } else {
+                println!("The reference check is only available in debug builds");
+            }
+        }
+
+        // machine state of the other games in the cartridge, kept while switching between them
+        let mut sub_game_states: Vec<Option<SaveState>> = vec![None; cartridge.len()];
+
+        // patches the rom in memory while the game is paused
+        let mut rom_editor = RomEditor::new(cartridge.rom(sub_game).len());
+
+        // resume from a save state, possibly made by another emulator
+        if let Some(state_path) = Self::arg_value("--import-state") {
+            self.import_state(&mut chip8, &state_path);
+        } else if let Some(session) = session
+            .as_ref()
+            .filter(|session| session.game_path == game_path && !Self::has_switch("--no-resume"))
+        {
+            if session.resume(&mut chip8, cartridge.rom(sub_game)) {
+                println!("Resumed the last session");
+            }
+        }
+
+        // play time of the current game, added to the game library when switching or quitting
+        let mut played = Duration::ZERO;
+        let mut last_frame = Instant::now();
+
+        // the game runs at 60Hz whatever the display refreshes at, vsync only paces drawing
+        let mut frame_loop = FrameLoop::default();
+        let started = Instant::now();
+
+        // quick save slot next to the rom
+        let mut quick_save_path = Self::quick_save_path(&game_path, &cartridge, sub_game);
+
+        // shown to spectators next to the screen
+        let mut osd_text = format!("Playing {}", cartridge.name(sub_game));
+
+        // recent frames for the flipbook export
+        let mut frame_history = FrameHistory::new(Self::flipbook_frames());
+        let flipbook_dir = format!("{game_path}.flipbook");
+
+        // `--compare-rom patched.ch8` runs a second rom in lockstep and maps where the screens differ
+        let mut comparison = Self::arg_value("--compare-rom").and_then(|path| {
+            let rom = file_io::read_file(&path).expect("Failed to read comparison ROM");
+
+            Comparison::new(&path, &rom, &chip8)
+                .map_err(|err| println!("Not comparing with {path}: {err}"))
+                .ok()
+        });
+
+        // `--watch` reloads the game whenever its file changes, e.g. while writing it
+        let watch = Self::has_switch("--watch");
+        // and with `--hot-patch` patches the running game instead where it can
+        let hot_patch = Self::has_switch("--hot-patch");
+        let mut last_modified = Self::modified_time(&game_path);
+        let mut last_watch_check = Instant::now();
+
+        // instructions per frame, possibly fractional
+        let mut clock = Self::create_clock(session.as_ref().map_or_else(
+            || Config::get().clock(),
+            |session| Clock::from_ipf(session.instructions_per_frame),
+        ));
+
+        // `--realtime-timers` keeps the timers and the sound at 60 Hz while fast forwarding
+        clock.set_realtime_timers(Self::has_switch("--realtime-timers"));
+
+        // `--record movie.c8m` saves the keypad input of every frame to replay it later
+        let mut recording = Self::arg_value("--record").map(|path| (path, Movie::new(Self::keyframe_interval())));
+
+        // `--play movie.c8m` replays one instead of reading the keypad, from `--seek FRAME` on
+        let mut playback = self.load_movie(&mut chip8, &mut clock);
+
+        // `--verify` runs a second machine in lockstep and checks they never diverge
+        let mut verifier = Self::has_switch("--verify").then(|| Verifier::new(&chip8));
+
+        // the window's events, the keypad's held buttons and `--max-keys 2` and
+        // `--key-priority oldest` deciding what happens to chords
+        let mut input = SdlInput::new(
+            self.sdl_context.event_pump().unwrap(),
+            Self::create_keypad_input(),
+        );
+
+        // whether the screen may have changed since the last frame, only instructions drawing
+        // on it or events (loading a state...) do
+        let mut screen_dirty = true;
+
+        // controllers press keypad keys through the game's mapping, Ctrl+G remaps them
+        let mut gamepads = Gamepads::open(&self.sdl_context);
+        let mut gamepad_map = Self::gamepad_map(cartridge.rom(sub_game));
+        let mut gamepad_setup: Option<GamepadSetup> = None;
+
+        // P pauses the game, N then steps one instruction at a time
+        let mut paused = false;
+
+        // `--learn` asks what each instruction does before running it
+        let mut quiz = Self::has_switch("--learn").then(OpcodeQuiz::new);
+
+        // F3 edits the palette over the running game, the keys go to the editor meanwhile
+        let mut palette_editor: Option<PaletteEditor> = None;
+        let mut view_menu: Option<ViewMenu> = None;
+        let mut layout_menu: Option<LayoutMenu> = None;
+
+        // the fast forward key runs this many frames of the game per frame shown
+        let fast_forward_speed = Self::fast_forward_speed();
+
+        // held, the rewind key goes back through the last seconds of the game
+        let mut rewind = Rewind::new(Self::rewind_seconds());
+        let mut rewinding = false;
+
+        // F10 shows the audio latency and other numbers useful when something feels off
+        let mut diagnostics = false;
+
+        // Ctrl+I shows the play stats of this session and all of them
+        let mut play_stats = false;
+
+        // frames whose instructions took longer than FRAME_TIME_BUDGET to run
+        let mut overruns: u32 = 0;
+        let mut last_overrun_report: Option<Instant> = None;
+
+        // shows the last executed instructions while paused and stops at breakpoints
+        let debugger = Debugger::new(Self::breakpoints());
+        chip8.set_pre_exec_hook(debugger.hook());
+
+        self.set_keyboard_grab(true);
+
+        //setup game loop
+        loop {
+            // wall clock time, so a faster clock doesn't count more, and neither paused time
+            // nor long stalls (the ROM editor) count at all
+            let frame_time = last_frame.elapsed();
+            last_frame = Instant::now();
+
+            if !paused && frame_time < MAX_PLAY_FRAME_TIME {
+                played += frame_time;
+            }
+
+            // 60Hz frames since the last frame shown, kept up while paused so resuming
+            // doesn't catch up
+            let frames_due = frame_loop.frames_due(started.elapsed().as_secs_f64() * 1000.0);
+
+            if watch && last_watch_check.elapsed() >= WATCH_INTERVAL {
+                last_watch_check = Instant::now();
+
+                let modified = Self::modified_time(&game_path);
+
+                if modified != last_modified {
+                    last_modified = modified;
+
+                    match Cartridge::open(&game_path) {
+                        Ok(reloaded) => {
+                            // the time so far was spent on the previous version
+                            self.record_play(cartridge.rom(sub_game), &mut played);
+
+                            // e.g. a translator's text fix goes on from where the game is
+                            let patched = hot_patch
+                                && reloaded.len() == cartridge.len()
+                                && Self::hot_patch(
+                                    &mut chip8,
+                                    cartridge.rom(sub_game),
+                                    reloaded.rom(sub_game),
+                                );
+
+                            cartridge = reloaded;
+                            sub_game = sub_game.min(cartridge.len() - 1);
+                            sub_game_states = vec![None; cartridge.len()];
+
+                            if !patched {
+                                chip8.reset();
+                                self.load_rom(&mut chip8, cartridge.rom(sub_game));
+                                Self::apply_quirks(&mut chip8, cartridge.rom(sub_game));
+
+                                debugger.clear_trace();
+                                rewind.clear();
+
+                                println!("Reloaded {game_path}");
+                            }
+
+                            rom_editor = RomEditor::new(cartridge.rom(sub_game).len());
+                            gamepad_map = Self::gamepad_map(cartridge.rom(sub_game));
+                            screen_dirty = true;
+                            Self::machine_changed(&chip8, &mut recording, &mut verifier);
+                        }
+
+                        // keep playing the last good version
+                        Err(err) => println!("Could not reload: {err}"),
+                    }
+                }
+            }
+
+            for evt in input.events() {
+                // loaded states, edits, seeking... may all change the screen
+                screen_dirty = true;
+
+                if let Some((button, pressed)) = gamepads.event(&evt) {
+                    match gamepad_setup.as_mut() {
+                        Some(setup) if pressed => {
+                            if let Some(key) = setup.button_down() {
+                                gamepad_map.set(button, Some(key));
+                                gamepad::save_map(cartridge.rom(sub_game), gamepad_map);
+
+                                println!("Gamepad button {button} presses {key:X}");
+                            }
+                        }
+                        Some(_) => (),
+                        // a movie plays the keypad meanwhile
+                        None if playback.is_some() => (),
+                        None => {
+                            if let Some(k) = gamepad_map.key(button) {
+                                input.press(k, pressed);
+                            }
+                        }
+                    }
+
+                    continue;
+                }
+
+                match evt {
+                    Event::Window {
+                        win_event: WindowEvent::DisplayChanged(display_index),
+                        ..
+                    } => {
+                        self.fit_window_to_display(display_index);
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F11),
+                        ..
+                    } => {
+                        self.toggle_fullscreen();
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F4),
+                        ..
+                    } => {
+                        self.memory_heatmap = match self.memory_heatmap {
+                            Some(_) => None,
+                            None => Some(MemoryHeatmap::new()),
+                        };
+
+                        chip8.set_memory_tracking(self.memory_heatmap.is_some());
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F6),
+                        ..
+                    } => {
+                        self.set_orientation(self.orientation.rotated());
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F7),
+                        ..
+                    } => {
+                        self.set_orientation(self.orientation.toggled_mirror());
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F10),
+                        ..
+                    } => {
+                        diagnostics = !diagnostics;
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::I),
+                        keymod,
+                        ..
+                    } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                        play_stats = !play_stats;
+                    }
+
+                    // kept with the game's other screenshots, see the ROM browser's details
+                    Event::KeyDown {
+                        keycode: Some(Keycode::S),
+                        keymod,
+                        ..
+                    } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                        let key = RomLibrary::key(cartridge.rom(sub_game));
+
+                        match screenshots::take(&key, &chip8, &self.palette) {
+                            Ok(path) => println!("Saved screenshot {}", path.display()),
+                            Err(err) => println!("Could not save the screenshot: {err}"),
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::E),
+                        keymod,
+                        ..
+                    } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                        match self.stats.export(STATS_EXPORT_FILE) {
+                            Ok(_) => println!("Saved the play stats to {STATS_EXPORT_FILE}"),
+                            Err(err) => println!("Could not save the play stats: {err}"),
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        keymod,
+                        ..
+                    } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+                        && Self::view_shortcut(key, self.view).is_some() =>
+                    {
+                        if let Some(view) = Self::view_shortcut(key, self.view) {
+                            self.set_view(view);
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::V),
+                        keymod,
+                        ..
+                    } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                        view_menu = match view_menu {
+                            Some(_) => None,
+                            None => Some(ViewMenu::new(self.view)),
+                        };
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if view_menu.is_some() => {
+                        if let Some(view) = view_menu.as_mut().and_then(|menu| menu.key_down(key)) {
+                            self.set_view(view);
+                            view_menu = None;
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::K),
+                        keymod,
+                        ..
+                    } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                        layout_menu = match layout_menu {
+                            Some(_) => None,
+                            None => Some(LayoutMenu::new(self.key_map.layout())),
+                        };
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if layout_menu.is_some() => {
+                        if let Some(layout) =
+                            layout_menu.as_mut().and_then(|menu| menu.key_down(key))
+                        {
+                            self.set_key_layout(layout);
+                            layout_menu = None;
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::G),
+                        keymod,
+                        ..
+                    } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                        gamepad_setup = match gamepad_setup {
+                            Some(_) => None,
+                            None => Some(GamepadSetup::default()),
+                        };
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        scancode,
+                        ..
+                    } if gamepad_setup.is_some() => {
+                        if let (Some(setup), Some(k)) =
+                            (gamepad_setup.as_mut(), self.key2btn(key, scancode))
+                        {
+                            setup.key_down(k);
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F3),
+                        ..
+                    } => {
+                        palette_editor = match palette_editor {
+                            Some(_) => None,
+                            None => Some(PaletteEditor::new()),
+                        };
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if palette_editor.is_some() => {
+                        if let Some(editor) = &mut palette_editor {
+                            if editor.key_down(key, &mut self.palette) {
+                                self.save_palette();
+                            }
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F12),
+                        ..
+                    } => {
+                        self.sprite_editor = match self.sprite_editor {
+                            Some(_) => None,
+                            None => Some(SpriteEditor::new()),
+                        };
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if self.sprite_editor.is_some() => {
+                        let action = self
+                            .sprite_editor
+                            .as_mut()
+                            .and_then(|editor| editor.key_down(key));
+
+                        if let Some(action) = action {
+                            self.sprite_action(&mut chip8, action);
+
+                            if action == SpriteAction::Write {
+                                Self::machine_changed(&chip8, &mut recording, &mut verifier);
+                            }
+                        }
+                    }
+
+                    // the sprite editor draws with the left button and erases with the right,
+                    // dragging included
+                    Event::MouseButtonDown {
+                        x, y, mouse_btn, ..
+                    } if self.sprite_editor.is_some()
+                        && matches!(mouse_btn, MouseButton::Left | MouseButton::Right) =>
+                    {
+                        self.paint_sprite(x, y, mouse_btn == MouseButton::Left);
+                    }
+
+                    Event::MouseMotion {
+                        x, y, mousestate, ..
+                    } if self.sprite_editor.is_some()
+                        && (mousestate.left() || mousestate.right()) =>
+                    {
+                        self.paint_sprite(x, y, mousestate.left());
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if self.key_map.action(key) == Some(Action::Pause) => {
+                        paused = !paused;
+
+                        // give OS shortcuts back while paused
+                        self.set_keyboard_grab(!paused);
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if paused && self.key_map.action(key) == Some(Action::Step) => {
+                        // a game waiting for the display has its frame end first
+                        if chip8.waiting_for_vblank() {
+                            chip8.tick_timers();
+                        }
+
+                        let effect = Self::tick_or_crash(&mut chip8).unwrap_or_default();
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+
+                        let effect_text = Debugger::effect_text(&effect);
+
+                        if !effect_text.is_empty() {
+                            println!("{effect_text}");
+                        }
+
+                        Debugger::print_state(&chip8);
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if paused && self.key_map.action(key) == Some(Action::Breakpoint) => {
+                        let pc = chip8.get_program_counter();
+
+                        if debugger.toggle_breakpoint(pc) {
+                            println!("Breakpoint set at {pc:03X}");
+                        } else {
+                            println!("Breakpoint removed at {pc:03X}");
+                        }
+                    }
+
+                    // the listing and the registers, for bug reports and chat
+                    Event::KeyDown {
+                        keycode: Some(Keycode::C),
+                        keymod,
+                        ..
+                    } if paused && keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                        let mut text = debugger.hud_lines(&chip8).join("\n");
+                        text += &format!("\n{}", Debugger::registers_text(&chip8));
+
+                        let clipboard = self.canvas.window().subsystem().clipboard();
+
+                        match clipboard.set_clipboard_text(&text) {
+                            Ok(_) => println!("Copied the listing and registers"),
+                            Err(err) => println!("Could not copy the listing: {err}"),
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key @ (Keycode::Up | Keycode::Down)),
+                        ..
+                    } if quiz.is_some() => {
+                        if let Some(quiz) = &mut quiz {
+                            quiz.select(if key == Keycode::Up { -1 } else { 1 });
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return),
+                        ..
+                    } if !paused && quiz.as_mut().is_some_and(|quiz| quiz.confirm()) => {
+                        paused = Self::tick_or_crash(&mut chip8).is_none();
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+                    }
+
+                    // seek a second or a frame back and forth, or back to the start
+                    Event::KeyDown {
+                        keycode:
+                            Some(
+                                key @ (Keycode::Left
+                                | Keycode::Right
+                                | Keycode::Comma
+                                | Keycode::Period
+                                | Keycode::Home),
+                            ),
+                        ..
+                    } if playback.is_some() => {
+                        if let Some((movie, frame)) = &mut playback {
+                            let second = FRAMES_PER_SECOND;
+
+                            let target = match key {
+                                Keycode::Left => frame.saturating_sub(second),
+                                Keycode::Right => *frame + second,
+                                Keycode::Comma => frame.saturating_sub(1),
+                                Keycode::Period => *frame + 1,
+                                _ => 0,
+                            };
+
+                            *frame = movie.seek(target, &mut chip8, &mut clock);
+                            debugger.clear_trace();
+
+                            Self::machine_changed(&chip8, &mut recording, &mut verifier);
+
+                            println!("Frame {frame}/{}", movie.len());
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F2),
+                        ..
+                    } => {
+                        // the editor prompt lives in the terminal, let the user switch to it
+                        self.set_keyboard_grab(false);
+
+                        let clipboard = self.canvas.window().subsystem().clipboard();
+
+                        rom_editor.run_console(&mut chip8, &clipboard);
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+
+                        self.set_keyboard_grab(!paused);
+                    }
+
+                    // held, `--fast-forward-speed 8` picks how fast
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if self.key_map.action(key) == Some(Action::FastForward) => {
+                        clock.set_speed(fast_forward_speed);
+                    }
+
+                    Event::KeyUp {
+                        keycode: Some(key), ..
+                    } if self.key_map.action(key) == Some(Action::FastForward) => {
+                        clock.set_speed(1.0);
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if self.key_map.action(key) == Some(Action::Rewind) => rewinding = true,
+
+                    Event::KeyUp {
+                        keycode: Some(key), ..
+                    } if self.key_map.action(key) == Some(Action::Rewind) => rewinding = false,
+
+                    // a movie plays at the speed it was recorded at
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if playback.is_none()
+                        && matches!(
+                            self.key_map.action(key),
+                            Some(Action::SpeedUp | Action::SlowDown)
+                        ) =>
+                    {
+                        let step = match self.key_map.action(key) {
+                            Some(Action::SpeedUp) => SPEED_STEP,
+                            _ => 1.0 / SPEED_STEP,
+                        };
+
+                        clock.step_instructions_per_frame(step);
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+
+                        println!(
+                            "Speed: {:.2} instructions per frame ({:.0} Hz)",
+                            clock.instructions_per_frame(),
+                            clock.instructions_per_frame() * FRAMES_PER_SECOND as f64
+                        );
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if self.key_map.action(key) == Some(Action::QuickSave) => {
+                        self.export_state(&chip8, &quick_save_path);
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key), ..
+                    } if self.key_map.action(key) == Some(Action::QuickLoad) => {
+                        self.import_state(&mut chip8, &quick_save_path);
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+                    }
+
+                    // switch between the games of a cartridge
+                    Event::KeyDown {
+                        keycode: Some(key @ (Keycode::PageUp | Keycode::PageDown)),
+                        ..
+                    } if cartridge.len() > 1 => {
+                        sub_game_states[sub_game] = Some(chip8.save_state());
+
+                        self.record_play(cartridge.rom(sub_game), &mut played);
+
+                        sub_game = if key == Keycode::PageDown {
+                            (sub_game + 1) % cartridge.len()
+                        } else {
+                            (sub_game + cartridge.len() - 1) % cartridge.len()
+                        };
+
+                        match &sub_game_states[sub_game] {
+                            Some(state) => chip8.load_state(state),
+                            None => {
+                                chip8.reset();
+                                self.load_rom(&mut chip8, cartridge.rom(sub_game));
+                            }
+                        }
+
+                        Self::apply_quirks(&mut chip8, cartridge.rom(sub_game));
+                        self.stats
+                            .game_started(&RomLibrary::key(cartridge.rom(sub_game)));
+
+                        gamepad_map = Self::gamepad_map(cartridge.rom(sub_game));
+                        rom_editor = RomEditor::new(cartridge.rom(sub_game).len());
+                        quick_save_path = Self::quick_save_path(&game_path, &cartridge, sub_game);
+                        osd_text = format!("Playing {}", cartridge.name(sub_game));
+                        debugger.clear_trace();
+                        rewind.clear();
+
+                        println!("Switched to {}", cartridge.name(sub_game));
+
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F8),
+                        ..
+                    } => match frame_history.export(Path::new(&flipbook_dir)) {
+                        Ok(frames) => println!("Exported {frames} frames to {flipbook_dir}"),
+                        Err(err) => println!("Could not export frames to {flipbook_dir}: {err}"),
+                    },
+
+                    //track when key is pressed
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        scancode: Some(scancode),
+                        ..
+                    } => {
+                        //only satisfied if value on right matches on
+                        //left
+                        if let Some(k) = self
+                            .key2btn(key, Some(scancode))
+                            .filter(|_| playback.is_none())
+                        {
+                            input.key_down(k, scancode);
+                        }
+                    }
+
+                    //track when key released
+                    Event::KeyUp {
+                        keycode: Some(key),
+                        scancode,
+                        ..
+                    } => {
+                        if let Some(k) = self.key2btn(key, scancode).filter(|_| playback.is_none())
+                        {
+                            input.key_up(k);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+
+            // the keypad as the events left it, a movie presses its own keys frame by frame
+            if !frontend::read_input(&mut chip8, &mut input) {
+                break;
+            }
+
+            if let Some(comparison) = &mut comparison {
+                for (k, pressed) in chip8.get_keys().iter().enumerate() {
+                    comparison.keypress(k, *pressed);
+                }
+            }
+
+            // stop at the end of the movie, seeking back continues it
+            if !paused && playback.as_ref().is_some_and(|(movie, frame)| *frame >= movie.len()) {
+                println!("End of movie");
+
+                paused = true;
+                self.set_keyboard_grab(false);
+            }
+
+            if paused {
+                self.hud_lines = debugger.hud_lines(&chip8);
+            } else if let Some(quiz) = &mut quiz {
+                // instructions only run once answered, timers keep their 60Hz pace
+                quiz.ask(&chip8);
+
+                for _ in 0..frames_due {
+                    chip8.tick_timers();
+                }
+
+                self.hud_lines = quiz.hud_lines();
+            } else if rewinding && playback.is_none() {
+                // a movie plays back its frames in order, it seeks instead
+                for _ in 0..frames_due {
+                    if rewind.step_back(&mut chip8) {
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+                        screen_dirty = true;
+                    }
+                }
+
+                self.hud_lines.clear();
+            } else {
+                if let Some(beeper) = &beeper {
+                    beeper.set_speed(clock.timer_speed());
+                }
+
+                // several frames of the game per 60Hz frame while fast forwarding
+                let machine_frames: u32 = (0..frames_due).map(|_| clock.frames_for_frame()).sum();
+
+                // the budget covers all of them, fast forward and catching up included
+                let frames_start = Instant::now();
+                let mut over_budget = false;
+
+                for _ in 0..machine_frames {
+                    let movie_ended = playback
+                        .as_ref()
+                        .is_some_and(|(movie, frame)| *frame >= movie.len());
+
+                    if paused || movie_ended || over_budget {
+                        break;
+                    }
+
+                    if let Some((_, movie)) = &mut recording {
+                        movie.record_frame(&chip8, &clock);
+                    }
+
+                    if let Some((movie, frame)) = &mut playback {
+                        if movie.prepare_frame(*frame, &mut chip8, &mut clock) {
+                            Self::machine_changed(&chip8, &mut None, &mut verifier);
+                        }
+
+                        *frame += 1;
+                    }
+
+                    let ticks = clock.ticks_for_frame();
+                    let mut executed = 0;
+                    let mut broke = false;
+
+                    while executed < ticks {
+                        let Some(effect) = Self::tick_or_crash(&mut chip8) else {
+                            paused = true;
+                            broke = true;
+                            self.set_keyboard_grab(false);
+                            break;
+                        };
+
+                        screen_dirty |= effect.screen;
+
+                        executed += 1;
+
+                        // a breakpoint, BRK or a failed ASSERT in a dev mode rom
+                        if chip8.take_break_request() {
+                            paused = true;
+                            broke = true;
+                            self.set_keyboard_grab(false);
+                            break;
+                        }
+
+                        // a huge speed or a pathological rom must not freeze the window, the
+                        // rest of the frame's instructions and the machine frames left are
+                        // dropped
+                        if (executed == ticks || executed.is_multiple_of(BUDGET_CHECK_INTERVAL))
+                            && frames_start.elapsed() > FRAME_TIME_BUDGET
+                        {
+                            overruns += 1;
+                            broke = true;
+                            over_budget = true;
+
+                            let report_due = last_overrun_report
+                                .is_none_or(|at| at.elapsed() >= OVERRUN_REPORT_INTERVAL);
+
+                            if report_due {
+                                last_overrun_report = Some(Instant::now());
+                                println!(
+                                    "Frame over its {} ms budget, ran {executed} of {ticks} \
+                                     instructions ({overruns} frames cut short so far)",
+                                    FRAME_TIME_BUDGET.as_millis()
+                                );
+                            }
+
+                            break;
+                        }
+                    }
+
+                    // fast forwarded with realtime timers, some frames only end the vblank
+                    if clock.timers_due() {
+                        chip8.tick_timers();
+                    } else {
+                        chip8.end_frame();
+                        broke = true;
+                    }
+
+                    rewind.record(&chip8);
+
+                    self.stats.frame_ran(executed, chip8.is_sound_playing());
+
+                    if let Some(comparison) = &mut comparison {
+                        comparison.run_frame(executed, chip8.get_screen());
+                    }
+
+                    // the frame was cut short or left the timers alone, the other machines
+                    // can't follow
+                    if broke {
+                        Self::machine_changed(&chip8, &mut recording, &mut verifier);
+                    } else if let Some(verifier) = &mut verifier {
+                        verifier.run_frame(ticks, &chip8);
+                    }
+                }
+                self.hud_lines.clear();
+            }
+
+            if let Some(menu) = &view_menu {
+                self.hud_lines = menu.hud_lines(self.view);
+            } else if let Some(menu) = &layout_menu {
+                self.hud_lines = menu.hud_lines(self.key_map.layout());
+            } else if let Some(setup) = &gamepad_setup {
+                self.hud_lines = setup.hud_lines(gamepads.connected());
+            } else if let Some(editor) = &palette_editor {
+                self.hud_lines = editor.hud_lines(&self.palette);
+            } else if diagnostics {
+                self.hud_lines = Self::diagnostics_hud_lines(beeper.as_deref(), &clock, overruns);
+            } else if play_stats {
+                self.hud_lines = self.stats.hud_lines();
+            }
+
+            if let Some(heatmap) = &mut self.memory_heatmap {
+                if let Some(accesses) = chip8.take_memory_accesses() {
+                    heatmap.update(&accesses);
+                }
+            }
+
+            self.draw(&chip8);
+
+            frame_history.push(chip8.get_screen(), chip8.resolution());
+
+            if let Some(spectator_server) = &self.spectator_server {
+                spectator_server.publish(&chip8, &osd_text);
+            }
+
+            if let Some(led_stream) = &mut self.led_stream {
+                led_stream.publish(&chip8, screen_dirty);
+            }
+
+            screen_dirty = false;
+
+            if let Some(sound_events) = &mut self.sound_events {
+                sound_events.update(chip8.is_sound_playing() && !paused);
+            }
+        }
+
+        if let Some(sound_events) = &mut self.sound_events {
+            sound_events.update(false);
+        }
+
+        self.set_keyboard_grab(false);
+
+        self.record_play(cartridge.rom(sub_game), &mut played);
+
+        Session::new(
+            self.window_geometry(),
+            &game_path,
+            sub_game,
+            clock.instructions_per_frame(),
+            self.palette,
+        )
+        .save(&chip8, cartridge.rom(sub_game));
+
+        if let Some(comparison) = &comparison {
+            match comparison.export(&game_path) {
+                Ok(_) => println!("Wrote comparison heatmap and report next to {game_path}"),
+                Err(err) => println!("Could not write comparison report: {err}"),
+            }
+        }
+
+        if let Some((path, movie)) = &mut recording {
+            movie.finish(&chip8, cartridge.rom(sub_game));
+
+            match file_io::write_file(path.as_str(), &self.signer.sign(movie.to_bytes())) {
+                Ok(_) => println!("Saved {} frames to {path}", movie.len()),
+                Err(err) => println!("Could not save movie {path}: {err}"),
+            }
+        }
+
+        if let Some(state_path) = Self::arg_value("--export-state") {
+            self.export_state(&chip8, &state_path);
+        }
+
+        self.stats.save();
+    }
+
+    // no-op unless `--grab-keyboard` was given
+    fn set_keyboard_grab(&mut self, grabbed: bool) {
+        if self.grab_keyboard {
+            self.canvas.window_mut().set_keyboard_grab(grabbed);
+        }
+    }
+
+    // `--break 2A4,0x300`, addresses in hex
+    fn breakpoints() -> Vec<u16> {
+        Self::arg_value("--break")
+            .map(|list| Debugger::parse_breakpoints(&list))
+            .unwrap_or_default()
+    }
+
+    // `--hot-patch`, false when the game has to start over instead
+    fn hot_patch(chip8: &mut Chip8, old: &[u8], new: &[u8]) -> bool {
+        match hot_patch::apply(chip8, old, new) {
+            Ok(patched) => {
+                println!("Hot patched {patched} bytes");
+                true
+            }
+            Err(err) => {
+                println!("Could not hot patch, reloading: {err}");
+                false
+            }
+        }
+    }
+
+    // runs one instruction; when the machine crashes (e.g. on an opcode no interpreter
+    // knows) it shows where on its own screen instead of taking the emulator down
+    fn tick_or_crash(chip8: &mut Chip8) -> Option<Effect> {
+        let err = match chip8.tick() {
+            Ok(effect) => return Some(effect),
+            Err(err) => err,
+        };
+
+        let pc = chip8.get_program_counter();
+        println!("The game crashed at {pc:03X}: {err}");
+
+        let memory = chip8.get_memory();
+        let op = u16::from_be_bytes([
+            memory[pc as usize % memory.len()],
+            memory[(pc as usize + 1) % memory.len()],
+        ]);
+
+        // the 0000 tells nothing, the empty memory after the game does
+        let detail = match err {
+            Chip8Error::RanOffEnd(_) => "RAN OFF END".to_string(),
+            _ => format!("OP {op:04X}"),
+        };
+
+        chip8.show_text(&["CRASHED", &format!("PC {pc:03X}"), &detail]);
+
+        None
+    }
+
+    fn diagnostics_hud_lines(beeper: Option<&Beeper>, clock: &Clock, overruns: u32) -> Vec<String> {
+        let audio = match beeper {
+            Some(beeper) => {
+                let latency = beeper.latency().map_or("-".to_string(), |latency| {
+                    format!("{} MS", latency.as_millis())
+                });
+
+                let buffer = beeper
+                    .buffer_frames()
+                    .map_or("DEVICE DEFAULT".to_string(), |frames| {
+                        format!("{frames} FRAMES")
+                    });
+
+                vec![
+                    format!("AUDIO LATENCY {latency}"),
+                    format!("AUDIO BUFFER {buffer}"),
+                ]
+            }
+
+            None => vec!["AUDIO OFF".to_string()],
+        };
+
+        let mut lines = vec![
+            format!("SPEED {:.2} IPF", clock.instructions_per_frame()),
+            format!("FRAMES CUT SHORT {overruns}"),
+        ];
+        lines.extend(audio);
+
+        lines
+    }
+
+    // adds the play time to the game's statistics and resets it
+    // `--machine eti-660` runs the game as on another machine than the COSMAC VIP: its load
+    // address, keypad layout and screen. `--load-addr 600` only moves the load address. Both
+    // are remembered for the game in the library, like `--random vip` (or `os`, `seed:N`),
+    // where RND gets its numbers. `--resolution 128x64` overrides the screen size of the
+    // machine for this run.
+    fn load_rom(&mut self, chip8: &mut Chip8, rom: &[u8]) {
+        let key = RomLibrary::key(rom);
+        let mut library = RomLibrary::load();
+
+        if let Some(name) = Self::arg_value("--machine") {
+            match Machine::parse(&name) {
+                Some(machine) => library.set_machine(&key, machine),
+                None => println!("Unknown machine {name}, expected vip or eti-660"),
+            }
+        }
+
+        if let Some(addr) = Self::arg_value("--load-addr") {
+            match u16::from_str_radix(addr.trim_start_matches("0x"), 16) {
+                Ok(addr) => library.set_load_addr(&key, addr),
+                Err(_) => println!("Ignoring invalid load address {addr}"),
+            }
+        }
+
+        if let Some(name) = Self::arg_value("--random") {
+            match RandomSource::parse(&name) {
+                Some(random) => library.set_random(&key, random),
+                None => println!("Unknown random source {name}, expected os, vip or seed:N"),
+            }
+        }
+
+        if ["--machine", "--load-addr", "--random"]
+            .iter()
+            .any(|flag| Self::arg_value(flag).is_some())
+        {
+            library.save();
+        }
+
+        let record = library.get(&key);
+        self.machine = record.machine;
+
+        let addr = match record.load_addr.unwrap_or(record.machine.start_addr()) {
+            addr if addr as usize + rom.len() <= XO_RAM_SIZE => addr,
+            addr => {
+                println!("The game doesn't fit in memory at {addr:03X}, loading it at 200");
+                START_ADDR
+            }
+        };
+
+        let resolution = match Self::arg_value("--resolution") {
+            Some(size) => Resolution::parse(&size).unwrap_or_else(|| {
+                println!("Unsupported resolution {size}, see the README");
+                record.machine.resolution()
+            }),
+            None => record.machine.resolution(),
+        };
+
+        chip8.set_resolution(resolution);
+        chip8.set_memory_size(record.machine.memory_size());
+        chip8.set_random_source(record.random);
+        chip8.set_start_addr(addr);
+
+        if let Err(err) = chip8.load(rom) {
+            println!("Could not load the game: {err}");
+        }
+    }
+
+    // `--quirks vy-shift,clip` (or `none`) picks the quirks, otherwise the ones in the game
+    // library are used, detected and remembered there the first time a game is played
+    fn apply_quirks(chip8: &mut Chip8, rom: &[u8]) {
+        if let Some(names) = Self::arg_value("--quirks") {
+            match Quirks::parse(&names) {
+                Some(quirks) => return chip8.set_quirks(quirks),
+                None => println!("Unknown quirks {names}, see the README"),
+            }
+        }
+
+        if let Some(quirks) = Config::get().quirks {
+            return chip8.set_quirks(quirks);
+        }
+
+        let key = RomLibrary::key(rom);
+        let mut library = RomLibrary::load();
+
+        let quirks = match library.get(&key).quirks {
+            Some(quirks) => quirks,
+            None => {
+                let scores = quirk_detector::detect(rom, Clock::default().instructions_per_frame());
+                let quirks = scores[0].quirks;
+
+                println!("Unknown game, detected quirks: {}", quirks.names());
+
+                library.set_quirks(&key, quirks);
+                library.save();
+
+                quirks
+            }
+        };
+
+        chip8.set_quirks(quirks);
+    }
+
+    // `--gamepad-map 12=1,13=4` remembers a mapping for the game, e.g. Pong's paddle on the
+    // d-pad's up and down, otherwise the game's or every game's saved one is used
+    fn gamepad_map(rom: &[u8]) -> GamepadMap {
+        if let Some(text) = Self::arg_value("--gamepad-map") {
+            match GamepadMap::parse(&text.replace(',', "\n")) {
+                Ok(map) => gamepad::save_map(rom, map),
+                Err(err) => println!("Ignoring --gamepad-map: {err}"),
+            }
+        }
+
+        gamepad::load_map(rom)
+    }
+
+    fn record_play(&mut self, rom: &[u8], played: &mut Duration) {
+        let mut library = RomLibrary::load();
+
+        library.record_play(&RomLibrary::key(rom), *played);
+        library.save();
+
+        self.stats.add_play_time(*played);
+
+        *played = Duration::ZERO;
+    }
+
+    // cartridges get one quick save slot per game
+    fn quick_save_path(game_path: &str, cartridge: &Cartridge, sub_game: usize) -> String {
+        if cartridge.len() > 1 {
+            format!("{game_path}.{}.state", cartridge.name(sub_game))
+        } else {
+            format!("{game_path}.state")
+        }
+    }
+
+    // `.json` (or `.json.gz`) files are Octo states, anything else uses our own format
+    fn is_octo_state(path: &str) -> bool {
+        file_io::inner_extension(path).is_some_and(|ext| ext == "json")
+    }
+
+    fn import_state(&self, chip8: &mut Chip8, path: &str) {
+        let file = match file_io::read_file(path) {
+            Ok(data) => data,
+            Err(err) => {
+                println!("Could not read save state {path}: {err}");
+                return;
+            }
+        };
+
+        // Octo states are written unsigned, see `export_state`, and load unchecked like Octo's
+        let verified = if Self::is_octo_state(path) {
+            Ok(file.as_slice())
+        } else {
+            self.signer.verify(&file)
+        };
+
+        let data = match verified {
+            Ok(data) => data,
+            Err(err) => {
+                println!("Could not load save state {path}: {err}");
+                return;
+            }
+        };
+
+        let imported = if Self::is_octo_state(path) {
+            SaveState::from_octo_json(&String::from_utf8_lossy(data))
+        } else {
+            SaveState::from_bytes(data).map(|state| (state, Vec::new()))
+        };
+
+        match imported {
+            Ok((state, warnings)) => {
+                for warning in warnings {
+                    println!("Warning: {warning}");
+                }
+
+                chip8.load_state(&state);
+
+                println!("Loaded save state {path}");
+            }
+
+            Err(err) => println!("Could not load save state {path}: {err}"),
+        }
+    }
+
+    fn export_state(&mut self, chip8: &Chip8, path: &str) {
+        let state = chip8.save_state();
+
+        // Octo's format has no room for a signature, `.gz` paths are gzipped by `write_file`
+        let data = if Self::is_octo_state(path) {
+            state.to_octo_json().into_bytes()
+        } else if file_io::is_gzip_path(path) {
+            self.signer.sign(state.to_bytes())
+        } else {
+            file_io::pack_state(self.signer.sign(state.to_bytes()))
+        };
+
+        match file_io::write_file(path, &data) {
+            Ok(_) => {
+                println!("Saved state to {path}");
+                self.stats.state_saved();
+            }
+            Err(err) => println!("Could not save state to {path}: {err}"),
+        }
+    }
+
+    // value of `flag`, remembered in `file` of the config directory for the next runs
+    fn remembered_arg_value(flag: &str, file: &str) -> Option<String> {
+        let remembered = file_io::config_dir().join(file);
+
+        match Self::arg_value(flag) {
+            Some(value) => {
+                let saved = fs::create_dir_all(file_io::config_dir())
+                    .and_then(|_| fs::write(&remembered, &value));
+
+                if let Err(err) = saved {
+                    println!("Could not remember {flag}: {err}");
+                }
+
+                Some(value)
+            }
+
+            None => fs::read_to_string(remembered)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty()),
+        }
+    }
+
+    // `--audio-device NAME` picks the output device
+    fn audio_device() -> Option<String> {
+        Self::remembered_arg_value("--audio-device", AUDIO_DEVICE_FILE)
+    }
+
+    // `--audio-buffer 512`, frames per audio buffer, `--audio-buffer default` lets the device pick
+    fn audio_buffer_frames() -> Option<u32> {
+        let value = Self::remembered_arg_value("--audio-buffer", AUDIO_BUFFER_FILE)?;
+
+        match value.parse() {
+            Ok(frames) if frames > 0 => Some(frames),
+            _ if value == "default" => None,
+            _ => {
+                println!("Ignoring invalid audio buffer size: {value}");
+                None
+            }
+        }
+    }
+
+    // `--beep-sound beep.wav` plays a sound file, otherwise a square wave at
+    // `--beep-frequency 440` Hz sounds
+    fn beep_sound() -> BeepSound {
+        if let Some(path) = Self::arg_value("--beep-sound") {
+            return BeepSound::File(path);
+        }
+
+        let Some(frequency) = Self::arg_value("--beep-frequency") else {
+            return BeepSound::default();
+        };
+
+        match frequency.parse::<f32>() {
+            Ok(frequency) if frequency > 0.0 => BeepSound::Tone(frequency),
+            _ => {
+                println!("Ignoring invalid beep frequency {frequency}");
+                BeepSound::default()
+            }
+        }
+    }
+
+    // `--fast-forward-audio pitch` shortens the beeps while fast forwarding, `mute` (the
+    // default) silences them
+    fn fast_forward_audio() -> FastForwardAudio {
+        let value = Self::remembered_arg_value("--fast-forward-audio", FAST_FORWARD_AUDIO_FILE)
+            .unwrap_or_default();
+
+        match FastForwardAudio::parse(&value) {
+            Some(fast_forward) => fast_forward,
+            _ if value.is_empty() => FastForwardAudio::default(),
+            None => {
+                println!("Ignoring unknown fast forward audio {value}, expected mute or pitch");
+                FastForwardAudio::default()
+            }
+        }
+    }
+
+    // `--volume 0.5`, from 0 to 1, or the config file's
+    fn volume() -> f32 {
+        let Some(value) = Self::arg_value("--volume") else {
+            return Config::get().volume;
+        };
+
+        match value.parse::<f32>() {
+            Ok(volume) if (0.0..=1.0).contains(&volume) => volume,
+            _ => {
+                println!("Ignoring invalid volume {value}, expected 0 to 1");
+                Config::get().volume
+            }
+        }
+    }
+
+    // `--fast-forward-speed 8`, possibly fractional
+    fn fast_forward_speed() -> f64 {
+        match Self::arg_value("--fast-forward-speed").map(|speed| speed.parse::<f64>()) {
+            Some(Ok(speed)) if Clock::is_valid_speed(speed) => speed,
+            Some(_) => {
+                println!("Ignoring invalid fast forward speed, it must be 1 to {MAX_SPEED}");
+                DEFAULT_FAST_FORWARD_SPEED
+            }
+            None => DEFAULT_FAST_FORWARD_SPEED,
+        }
+    }
+
+    // `--rewind-seconds 30`, how far back the rewind key goes, 0 turns it off
+    fn rewind_seconds() -> u32 {
+        match Self::arg_value("--rewind-seconds").map(|seconds| seconds.parse::<u32>()) {
+            Some(Ok(seconds)) => seconds,
+            Some(_) => {
+                println!("Ignoring invalid rewind seconds, expected a whole number");
+                DEFAULT_REWIND_SECONDS
+            }
+            None => DEFAULT_REWIND_SECONDS,
+        }
+    }
+
+    // `--keyframe-seconds 5`, how often recorded movies store the full machine state
+    fn keyframe_interval() -> u32 {
+        let seconds = Self::arg_value("--keyframe-seconds")
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or(DEFAULT_KEYFRAME_SECONDS);
+
+        seconds * FRAMES_PER_SECOND
+    }
+
+    // the movie and the frame playback is at
+    fn load_movie(&self, chip8: &mut Chip8, clock: &mut Clock) -> Option<(Movie, u32)> {
+        let path = Self::arg_value("--play")?;
+
+        let movie = file_io::read_file(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|data| {
+                let data = self.signer.verify(&data).map_err(|err| err.to_string())?;
+                Movie::from_bytes(data)
+            });
+
+        match movie {
+            Ok(movie) => {
+                let start = Self::arg_value("--seek")
+                    .and_then(|frame| frame.parse().ok())
+                    .unwrap_or(0);
+
+                let frame = movie.seek(start, chip8, clock);
+
+                println!("Playing {path}, {} frames", movie.len());
+
+                Some((movie, frame))
+            }
+
+            Err(err) => {
+                println!("Could not play movie {path}: {err}");
+                None
+            }
+        }
+    }
+
+    // The machine changed outside of the keypad: the movie needs a keyframe to stay in sync
+    // and the determinism check a fresh copy.
+    fn machine_changed(
+        chip8: &Chip8,
+        recording: &mut Option<(String, Movie)>,
+        verifier: &mut Option<Verifier>,
+    ) {
+        if let Some((_, movie)) = recording {
+            movie.resync();
+        }
+
+        if let Some(verifier) = verifier {
+            verifier.sync(chip8);
+        }
+    }
+
+    // `--flipbook-frames 120`, how many frames F8 exports
+    fn flipbook_frames() -> usize {
+        Self::arg_value("--flipbook-frames")
+            .and_then(|frames| frames.parse().ok())
+            .unwrap_or(DEFAULT_FLIPBOOK_FRAMES)
+    }
+
+    // `--ipf 10.5` or `--speed 10` (instructions per frame), or `--hz 700` (instructions per
+    // second)
+    fn create_clock(default: Clock) -> Clock {
+        // `per_frame` turns the value into instructions per frame
+        let parse = |flag: &str, per_frame: f64| {
+            let value = Self::arg_value(flag)?;
+
+            match value.parse::<f64>() {
+                Ok(rate) if Clock::is_valid_ipf(rate / per_frame) => Some(rate),
+                _ => {
+                    println!("Ignoring invalid {flag} value: {value}");
+                    None
+                }
+            }
+        };
+
+        if let Some(ipf) = parse("--ipf", 1.0).or_else(|| parse("--speed", 1.0)) {
+            Clock::from_ipf(ipf)
+        } else if let Some(hz) = parse("--hz", FRAMES_PER_SECOND as f64) {
+            Clock::from_hz(hz)
+        } else {
+            default
+        }
+    }
+
+    fn create_keypad_input() -> KeypadInput {
+        let max_keys = Self::arg_value("--max-keys")
+            .and_then(|max| max.parse().ok())
+            .unwrap_or(NUM_KEYS);
+
+        let priority = match Self::arg_value("--key-priority") {
+            Some(name) => KeyPriority::parse(&name).unwrap_or_else(|| {
+                println!("Ignoring unknown key priority {name}, use newest or oldest");
+                KeyPriority::Newest
+            }),
+            None => KeyPriority::Newest,
+        };
+
+        KeypadInput::new(max_keys, priority)
+    }
+
+    fn create_spectator_server() -> Option<SpectatorServer> {
+        let addr = Self::arg_value("--spectate")?;
+
+        match SpectatorServer::start(&addr) {
+            Ok(server) => Some(server),
+            Err(err) => {
+                println!("Could not start spectator server on {addr}: {err}");
+                None
+            }
+        }
+    }
+
+    // `--effects effects.toml`, after `--phosphor 0.6` fading pixels out
+    fn load_effects(canvas: &WindowCanvas) -> Option<EffectsScreen> {
+        let mut pipeline = Pipeline::default();
+
+        if let Some(value) = Self::arg_value("--phosphor") {
+            match value.parse::<f32>() {
+                Ok(decay) if (0.0..=1.0).contains(&decay) => {
+                    pipeline.push(post_effects::phosphor(decay));
+                }
+                _ => println!("Ignoring invalid phosphor decay {value}, expected 0 to 1"),
+            }
+        }
+
+        if let Some(path) = Self::arg_value("--effects") {
+            match Pipeline::load(Path::new(&path)) {
+                Ok(effects) => pipeline.append(effects),
+                Err(err) => println!("Could not load effects {path}: {err}"),
+            }
+        }
+
+        (!pipeline.is_empty()).then(|| EffectsScreen::new(pipeline, canvas))
+    }
+
+    fn create_led_stream() -> Option<LedStream> {
+        let target = Self::arg_value("--led")?;
+
+        match LedStream::open(&target) {
+            Ok(stream) => Some(stream),
+            Err(err) => {
+                println!("Could not open LED output {target}: {err}");
+                None
+            }
+        }
+    }
+
+    fn create_sound_events() -> Option<SoundEvents> {
+        let target = Self::arg_value("--sound-events")?;
+        let channel = Self::arg_value("--sound-events-channel");
+        let note = Self::arg_value("--sound-events-note");
+
+        match SoundEvents::open(&target, channel.as_deref(), note.as_deref()) {
+            Ok(events) => Some(events),
+            Err(err) => {
+                println!("Could not open sound events output: {err}");
+                None
+            }
+        }
+    }
+
+    fn create_sql() -> Sdl {
+        // Setup SDL
+        sdl2::init().unwrap()
+    }
+
+    fn create_canvas(sdl: &Sdl, orientation: Orientation, view: ViewPreset) -> WindowCanvas {
+        let video_subsystem = sdl.video().unwrap();
+
+        // the window opens on the first display
+        let (width, height) = Self::fit_window_size(
+            &video_subsystem,
+            0,
+            orientation,
+            Resolution::default(),
+            view,
+        );
+
+        //create screen according to size and position in center of monitor
+        let window = video_subsystem
+            .window("Chip-8 Emulator", width, height)
+            .position_centered()
+            .opengl()
+            .resizable()
+            .allow_highdpi()
+            .build()
+            .expect("Could not create video window");
+
+        let mut canvas = window
+            .into_canvas()
+            .present_vsync()
+            .build()
+            .expect("Could not create canvas");
+
+        canvas.set_draw_color(BLACK_COLOR);
+
+        canvas.clear();
+        canvas.present();
+
+        canvas
+    }
+
+    // The window size at the largest scale up to the preferred one (`--scale N`, the 1x to 8x
+    // view or the config file's) at which the window fits the usable area of the display, e.g.
+    // 19 on a 1366x768 laptop instead of 30.
+    fn fit_window_size(
+        video_subsystem: &VideoSubsystem,
+        display_index: i32,
+        orientation: Orientation,
+        resolution: Resolution,
+        view: ViewPreset,
+    ) -> (u32, u32) {
+        let scale = Self::arg_value("--scale").and_then(|scale| scale.parse().ok());
+
+        // the 1x to 8x views are sized for the 64x32 screen, whatever the game's resolution
+        let (preferred, resolution) = match (scale, view) {
+            (None, ViewPreset::Scale(factor)) => (factor, Resolution::LORES),
+            (scale, _) => (scale.unwrap_or(Config::get().scale), resolution),
+        };
+        let preferred = preferred.max(1);
+
+        let (width, height) = orientation.display_size(resolution);
+        let size = |scale: u32| (width as u32 * scale, height as u32 * scale);
+
+        let Ok(bounds) = video_subsystem.display_usable_bounds(display_index) else {
+            return size(preferred);
+        };
+
+        let fit_width = bounds.width() as f32 * MAX_DESKTOP_SHARE / width as f32;
+        let fit_height = bounds.height() as f32 * MAX_DESKTOP_SHARE / height as f32;
+
+        let scale = preferred.min(fit_width.min(fit_height) as u32).max(1);
+
+        if let Ok((dpi, _, _)) = video_subsystem.display_dpi(display_index) {
+            println!(
+                "Display {display_index}: {}x{} usable at {dpi:.0} DPI, scale {scale}",
+                bounds.width(),
+                bounds.height()
+            );
+        }
+
+        size(scale)
+    }
+
+    // the window moved to another monitor or the screen changed size, pick a scale that suits it
+    fn fit_window_to_display(&mut self, display_index: i32) {
+        let video_subsystem = self.canvas.window().subsystem().clone();
+
+        if let Some(skin) = &self.skin {
+            let (width, height) = Self::fit_skin(&video_subsystem, display_index, skin.size());
+            let window = self.canvas.window_mut();
+
+            if window.set_size(width, height).is_ok() {
+                window.set_position(WindowPos::Centered, WindowPos::Centered);
+            }
+
+            return;
+        }
+
+        let (width, height) = Self::fit_window_size(
+            &video_subsystem,
+            display_index,
+            self.orientation,
+            self.resolution,
+            self.view,
+        );
+
+        let window = self.canvas.window_mut();
+
+        if window.set_size(width, height).is_ok() {
+            window.set_position(WindowPos::Centered, WindowPos::Centered);
+        }
+    }
+
+    // skins open at the size of their background image, smaller when it would not fit
+    fn fit_skin(
+        video_subsystem: &VideoSubsystem,
+        display_index: i32,
+        (width, height): (u32, u32),
+    ) -> (u32, u32) {
+        let Ok(bounds) = video_subsystem.display_usable_bounds(display_index) else {
+            return (width, height);
+        };
+
+        let fit_width = bounds.width() as f32 * MAX_DESKTOP_SHARE / width as f32;
+        let fit_height = bounds.height() as f32 * MAX_DESKTOP_SHARE / height as f32;
+        let scale = fit_width.min(fit_height).min(1.0);
+
+        (
+            ((width as f32 * scale) as u32).max(1),
+            ((height as f32 * scale) as u32).max(1),
+        )
+    }
+
+    // the windowed geometry, also while fullscreen
+    fn window_geometry(&self) -> WindowGeometry {
+        let window = self.canvas.window();
+        let fullscreen = window.fullscreen_state() != FullscreenType::Off;
+
+        if let Some(windowed) = self.windowed_geometry.filter(|_| fullscreen) {
+            return WindowGeometry {
+                fullscreen,
+                ..windowed
+            };
+        }
+
+        let (x, y) = window.position();
+        let (width, height) = window.size();
+
+        WindowGeometry {
+            x,
+            y,
+            width,
+            height,
+            fullscreen,
+        }
+    }
+
+    fn restore_window(&mut self, geometry: WindowGeometry) {
+        let window = self.canvas.window_mut();
+
+        if let Err(err) = window.set_size(geometry.width, geometry.height) {
+            println!("Could not restore the window size: {err}");
+        }
+
+        window.set_position(
+            WindowPos::Positioned(geometry.x),
+            WindowPos::Positioned(geometry.y),
+        );
+
+        if geometry.fullscreen {
+            self.toggle_fullscreen();
+        }
+    }
+
+    // F11, fullscreen at the desktop resolution
+    fn toggle_fullscreen(&mut self) {
+        let fullscreen = self.canvas.window().fullscreen_state() != FullscreenType::Off;
+
+        let result = if fullscreen {
+            self.canvas.window_mut().set_fullscreen(FullscreenType::Off)
+        } else {
+            self.windowed_geometry = Some(self.window_geometry());
+            self.canvas
+                .window_mut()
+                .set_fullscreen(FullscreenType::Desktop)
+        };
+
+        if let Err(err) = result {
+            println!("Could not toggle fullscreen: {err}");
+        }
+    }
+
+    // `--rotate 90|180|270` and `--mirror` override the orientation last set with F6 and F7
+    fn orientation() -> Orientation {
+        let saved = Orientation::load();
+
+        let rotation = match Self::arg_value("--rotate").map(|degrees| degrees.parse::<u16>()) {
+            Some(Ok(degrees)) if degrees % 90 == 0 => degrees,
+            Some(_) => {
+                println!("--rotate takes 0, 90, 180 or 270 degrees");
+                saved.rotation()
+            }
+            None => saved.rotation(),
+        };
+
+        Orientation::new(rotation, saved.mirrored() || Self::has_switch("--mirror"))
+    }
+
+    // F6 rotates, F7 mirrors; the window turns with the screen unless it is fullscreen
+    // `--view 4x`, `pixel-perfect` (the default), `fit` or `fill`; `--fractional-scale` is `fit`
+    fn view_preset() -> ViewPreset {
+        if let Some(name) = Self::arg_value("--view") {
+            match ViewPreset::parse(&name) {
+                Some(view) => return view,
+                None => println!("Ignoring unknown view {name}, e.g. 4x, fit or fill"),
+            }
+        }
+
+        if Self::has_switch("--fractional-scale") {
+            ViewPreset::Fit
+        } else {
+            ViewPreset::default()
+        }
+    }
+
+    // Ctrl+1 to Ctrl+8 pick the 1x to 8x views, Ctrl+Plus/Minus the next or previous one
+    fn view_shortcut(key: Keycode, view: ViewPreset) -> Option<ViewPreset> {
+        match key {
+            Keycode::Num1 => Some(ViewPreset::Scale(1)),
+            Keycode::Num2 => Some(ViewPreset::Scale(2)),
+            Keycode::Num3 => Some(ViewPreset::Scale(3)),
+            Keycode::Num4 => Some(ViewPreset::Scale(4)),
+            Keycode::Num5 => Some(ViewPreset::Scale(5)),
+            Keycode::Num6 => Some(ViewPreset::Scale(6)),
+            Keycode::Num7 => Some(ViewPreset::Scale(7)),
+            Keycode::Num8 => Some(ViewPreset::Scale(8)),
+            Keycode::Equals | Keycode::Plus | Keycode::KpPlus => Some(view.step(true)),
+            Keycode::Minus | Keycode::KpMinus => Some(view.step(false)),
+            _ => None,
+        }
+    }
+
+    // the 1x to 8x views resize the window to match, unless it's fullscreen or skinned
+    fn set_view(&mut self, view: ViewPreset) {
+        self.view = view;
+
+        println!("View: {}", view.name());
+
+        let window = self.canvas.window();
+
+        if matches!(view, ViewPreset::Scale(_))
+            && self.skin.is_none()
+            && window.fullscreen_state() == FullscreenType::Off
+        {
+            if let Ok(display_index) = window.display_index() {
+                self.fit_window_to_display(display_index);
+            }
+        }
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        let turned = orientation.is_portrait() != self.orientation.is_portrait();
+        self.orientation = orientation;
+
+        if let Err(err) = orientation.save() {
+            println!("Could not save the screen orientation: {err}");
+        }
+
+        println!(
+            "Screen rotated {} degrees{}",
+            orientation.rotation(),
+            if orientation.mirrored() {
+                ", mirrored"
+            } else {
+                ""
+            }
+        );
+
+        let window = self.canvas.window_mut();
+
+        if turned && window.fullscreen_state() == FullscreenType::Off {
+            let (width, height) = window.size();
+
+            if let Err(err) = window.set_size(height, width) {
+                println!("Could not resize the window: {err}");
+            }
+        }
+    }
+
+    // drawable pixels per window coordinate, above 1 on high DPI displays
+    fn pixel_ratio(&self) -> u32 {
+        let (window_width, _) = self.canvas.window().size();
+        let (output_width, _) = self.canvas.output_size().unwrap_or((window_width, 0));
+
+        (output_width / window_width.max(1)).max(1)
+    }
+
+    // Scale across and down and top left corner of the screen inside the drawable area, which
+    // follows window resizes and is larger than the window on high DPI displays, or inside the
+    // skin's screen, as the view preset has it. The screen is centered, the bars around it
+    // stay black.
+    fn screen_layout(&self) -> ((f32, f32), i32, i32) {
+        let output = self.canvas.output_size().unwrap();
+        let area = match &self.skin {
+            Some(skin) => skin.screen_area(output),
+            None => Rect::new(0, 0, output.0, output.1),
+        };
+        let (width, height) = self.orientation.display_size(self.resolution);
+
+        let scale = self.view.scale(
+            (width, height),
+            self.resolution,
+            (area.width(), area.height()),
+            self.pixel_ratio(),
+        );
+
+        let left = area.x() as f32 + (area.width() as f32 - width as f32 * scale.0) / 2.0;
+        let top = area.y() as f32 + (area.height() as f32 - height as f32 * scale.1) / 2.0;
+
+        (scale, left as i32, top as i32)
+    }
+
+    pub fn draw_screen(&mut self, chip8: &Chip8) {
+        // Clear canvas with the background color
+        self.canvas.set_draw_color(self.palette.background);
+        self.canvas.clear();
+
+        // self.canvas.fill_rect(None).unwrap();
+
+        // the game or a loaded state changed the screen size, the window follows unless a
+        // skin sets its size
+        if chip8.resolution() != self.resolution {
+            self.resolution = chip8.resolution();
+
+            if self.skin.is_none() {
+                let display_index = self.canvas.window().display_index().unwrap_or(0);
+                self.fit_window_to_display(display_index);
+            }
+        }
+
+        if let Some(skin) = &self.skin {
+            let (key_map, machine) = (&self.key_map, self.machine);
+
+            // labels name the key that presses the button on this machine's keypad
+            skin.draw(&mut self.canvas, |button| {
+                (0..NUM_KEYS)
+                    .find(|vip_button| machine.keypad_button(*vip_button) == button)
+                    .map_or(String::new(), |vip_button| {
+                        key_map.keypad_key(vip_button).name()
+                    })
+            });
+
+            // the screen is not see-through, whatever the image has there
+            let area = skin.screen_area(self.canvas.output_size().unwrap());
+
+            self.canvas.set_draw_color(self.palette.background);
+            self.canvas.fill_rect(area).unwrap();
+        }
+
+        if self.effects.is_some() {
+            self.draw_effects_screen(chip8);
+        } else {
+            self.draw_pixels(chip8);
+        }
+
+        if self.memory_heatmap.is_some() {
+            self.draw_memory_heatmap(chip8);
+        }
+
+        let (cell, left, top) = self.sprite_editor_layout();
+        let text_scale = HUD_SCALE * self.pixel_ratio();
+
+        if let Some(editor) = &self.sprite_editor {
+            editor.draw(&mut self.canvas, left, top, cell, text_scale);
+        }
+
+        if self.visual_sound {
+            self.draw_sound_cue(chip8);
+        }
+
+        if !self.hud_lines.is_empty() {
+            self.draw_hud();
+        }
+
+        if self.canvas.window().keyboard_grab() {
+            self.draw_grab_indicator();
+        }
+
+        self.draw_update_notice();
+
+        self.canvas.present();
+    }
+
+    // a rectangle per lit pixel
+    fn draw_pixels(&mut self, chip8: &Chip8) {
+        let (plane0, plane1) = (chip8.get_plane(0), chip8.get_plane(1));
+
+        let ((scale_x, scale_y), left, top) = self.screen_layout();
+
+        // pixel edges are rounded separately so fractional scales leave no gaps between pixels
+        let edge = |offset: i32, i: u32, scale: f32| offset + (i as f32 * scale).round() as i32;
+
+        // a pass per color, a pixel's color has a bit for each plane it is set on
+        for color in 1..=3 {
+            self.canvas.set_draw_color(self.palette.color(color));
+
+            for (i, (pixel0, pixel1)) in plane0.iter().zip(plane1).enumerate() {
+                if usize::from(*pixel0) | usize::from(*pixel1) << 1 != color {
+                    continue;
+                }
+
+                // Convert our 1D array's index into a 2D (x,y) position, turned for the display
+                let (column, row) = (i % self.resolution.width, i / self.resolution.width);
+                let (x, y) = self.orientation.map(column, row, self.resolution);
+                let (x, y) = (x as u32, y as u32);
+
+                // Draw a rectangle at (x,y), scaled up by our scale value
+                let (x1, y1) = (edge(left, x, scale_x), edge(top, y, scale_y));
+                let (x2, y2) = (edge(left, x + 1, scale_x), edge(top, y + 1, scale_y));
+
+                let rect = Rect::new(x1, y1, (x2 - x1) as u32, (y2 - y1) as u32);
+
+                self.canvas.fill_rect(rect).unwrap();
+            }
+        }
+    }
+
+    // the screen as an image, put through the `--effects` pipeline
+    fn draw_effects_screen(&mut self, chip8: &Chip8) {
+        let (plane0, plane1) = (chip8.get_plane(0), chip8.get_plane(1));
+        let (width, height) = self.orientation.display_size(self.resolution);
+
+        let mut frame = Frame::new(width, height, self.palette.background);
+
+        for (i, (pixel0, pixel1)) in plane0.iter().zip(plane1).enumerate() {
+            let color = usize::from(*pixel0) | usize::from(*pixel1) << 1;
+
+            if color != 0 {
+                let (column, row) = (i % self.resolution.width, i / self.resolution.width);
+                let (x, y) = self.orientation.map(column, row, self.resolution);
+
+                frame.pixels[y * width + x] = self.palette.color(color);
+            }
+        }
+
+        let ((scale_x, scale_y), left, top) = self.screen_layout();
+        let area = Rect::new(
+            left,
+            top,
+            (width as f32 * scale_x).round() as u32,
+            (height as f32 * scale_y).round() as u32,
+        );
+
+        if let Some(effects) = &mut self.effects {
+            effects.draw(&mut self.canvas, frame, area);
+        }
+    }
+
+    // strip at the bottom of the window listing the instructions around PC
+    fn draw_hud(&mut self) {
+        let hud_scale = HUD_SCALE * self.pixel_ratio();
+        let (output_width, output_height) = self.canvas.output_size().unwrap();
+
+        let line_height = (hud::GLYPH_HEIGHT + 1) * hud_scale;
+        let height = self.hud_lines.len() as u32 * line_height + 2 * hud_scale;
+
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(HUD_BACKGROUND_COLOR);
+        self.canvas
+            .fill_rect(Rect::new(
+                0,
+                output_height as i32 - height as i32,
+                output_width,
+                height,
+            ))
+            .unwrap();
+
+        let current = TRACE_LINES.min(self.hud_lines.len() - 1);
+
+        for (i, line) in self.hud_lines.iter().enumerate() {
+            let y = output_height as i32 - height as i32 + (hud_scale + i as u32 * line_height) as i32;
+
+            // PC is highlighted, the instructions around it are dimmed
+            let color = if line.starts_with('>') {
+                WHITE_COLOR
+            } else if i < current {
+                HUD_PAST_COLOR
+            } else {
+                HUD_NEXT_COLOR
+            };
+
+            self.canvas.set_draw_color(color);
+            hud::draw_text(&mut self.canvas, line, 2 * hud_scale as i32, y, hud_scale);
+        }
+    }
+
+    // top right corner, as large as fits in half the window
+    fn draw_memory_heatmap(&mut self, chip8: &Chip8) {
+        let Some(heatmap) = &self.memory_heatmap else {
+            return;
+        };
+
+        let hud_scale = HUD_SCALE * self.pixel_ratio();
+        let (output_width, output_height) = self.canvas.output_size().unwrap();
+
+        let cell = MemoryHeatmap::fit_cell(
+            output_width / 2,
+            output_height.saturating_sub(2 * hud_scale),
+            hud_scale,
+        );
+        let (width, _) = MemoryHeatmap::size(cell, hud_scale);
+
+        heatmap.draw(
+            &mut self.canvas,
+            output_width as i32 - (width + hud_scale) as i32,
+            hud_scale as i32,
+            cell,
+            hud_scale,
+            chip8.get_memory(),
+        );
+    }
+
+    // cell size and top left corner of the sprite editor, over the left of the screen
+    fn sprite_editor_layout(&self) -> (u32, i32, i32) {
+        let hud_scale = HUD_SCALE * self.pixel_ratio();
+        let (_, output_height) = self.canvas.output_size().unwrap();
+
+        let cell = (output_height / 2 / MAX_HEIGHT as u32).max(hud_scale);
+
+        (cell, hud_scale as i32, hud_scale as i32)
+    }
+
+    // sets the sprite editor's pixel under the mouse at (`x`, `y`) in window coordinates
+    fn paint_sprite(&mut self, x: i32, y: i32, on: bool) {
+        let ratio = self.pixel_ratio() as i32;
+        let (cell, left, top) = self.sprite_editor_layout();
+
+        if let Some(editor) = &mut self.sprite_editor {
+            if let Some((column, row)) = editor.pixel_at(x * ratio, y * ratio, left, top, cell) {
+                editor.set_pixel(column, row, on);
+            }
+        }
+    }
+
+    fn sprite_action(&mut self, chip8: &mut Chip8, action: SpriteAction) {
+        let Some(editor) = &mut self.sprite_editor else {
+            return;
+        };
+
+        let addr = chip8.get_i_register();
+
+        match action {
+            SpriteAction::Load => {
+                let memory = chip8.get_memory();
+                let bytes: Vec<u8> = (0..MAX_HEIGHT)
+                    .map(|n| memory[(addr as usize + n) % memory.len()])
+                    .collect();
+
+                editor.load(&bytes);
+            }
+
+            SpriteAction::Write => {
+                for (n, byte) in editor.bytes().iter().enumerate() {
+                    chip8.write_memory(addr.wrapping_add(n as u16), *byte);
+                }
+
+                println!("Wrote the sprite to {addr:03X}");
+            }
+
+            SpriteAction::Copy => {
+                let line = editor.db_line();
+                let clipboard = self.canvas.window().subsystem().clipboard();
+
+                match clipboard.set_clipboard_text(&line) {
+                    Ok(_) => println!("Copied {line}"),
+                    Err(err) => println!("Could not copy the sprite: {err}"),
+                }
+            }
+        }
+    }
+
+    // tells the user why OS shortcuts don't work right now
+    fn draw_grab_indicator(&mut self) {
+        let hud_scale = HUD_SCALE * self.pixel_ratio();
+        let (output_width, _) = self.canvas.output_size().unwrap();
+
+        let text = "KEYBOARD GRABBED";
+        let x = output_width as i32 - (hud::text_width(text, hud_scale) + hud_scale) as i32;
+
+        self.canvas.set_draw_color(HUD_PAST_COLOR);
+        hud::draw_text(&mut self.canvas, text, x, hud_scale as i32, hud_scale);
+    }
+
+    // top left corner, for a while after the update check found a newer release
+    fn draw_update_notice(&mut self) {
+        if let Some(release) = self
+            .update_check
+            .as_ref()
+            .and_then(UpdateCheck::newer_release)
+        {
+            println!("Version {} is out: {}", release.version, release.url);
+
+            if !release.notes.is_empty() {
+                println!("{}", release.notes);
+            }
+
+            self.update_notice = Some((release, Instant::now() + UPDATE_NOTICE_TIME));
+        }
+
+        let Some((release, until)) = &self.update_notice else {
+            return;
+        };
+
+        if Instant::now() >= *until {
+            self.update_notice = None;
+            return;
+        }
+
+        // the notes are markdown, their headings and bullets are left out
+        let notes = release
+            .notes
+            .lines()
+            .map(|line| line.trim_start_matches(['#', '-', '*', ' ']).trim())
+            .filter(|line| !line.is_empty())
+            .take(UPDATE_NOTICE_LINES);
+
+        let lines: Vec<String> = [format!("VERSION {} IS OUT", release.version)]
+            .into_iter()
+            .chain(notes.map(str::to_string))
+            .collect();
+
+        let hud_scale = HUD_SCALE * self.pixel_ratio();
+        let line_height = (hud::GLYPH_HEIGHT + 1) * hud_scale;
+
+        let width = lines
+            .iter()
+            .map(|line| hud::text_width(line, hud_scale))
+            .max()
+            .unwrap_or(0)
+            + 3 * hud_scale;
+        let height = lines.len() as u32 * line_height + 2 * hud_scale;
+
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(HUD_BACKGROUND_COLOR);
+        self.canvas
+            .fill_rect(Rect::new(0, 0, width, height))
+            .unwrap();
+
+        for (i, line) in lines.iter().enumerate() {
+            let y = (hud_scale + i as u32 * line_height) as i32;
+
+            // the version stands out from the notes
+            let color = if i == 0 { WHITE_COLOR } else { HUD_NEXT_COLOR };
+
+            self.canvas.set_draw_color(color);
+            hud::draw_text(&mut self.canvas, line, 2 * hud_scale as i32, y, hud_scale);
+        }
+    }
+
+    // a border around the window for players who can't hear the buzzer
+    fn draw_sound_cue(&mut self, chip8: &Chip8) {
+        let now = Instant::now();
+
+        if chip8.is_sound_playing() {
+            self.sound_cue_until = Some(now + SOUND_CUE_MIN_TIME);
+        }
+
+        if self.sound_cue_until.is_none_or(|until| now >= until) {
+            return;
+        }
+
+        let width = SOUND_CUE_WIDTH * self.pixel_ratio();
+        let (output_width, output_height) = self.canvas.output_size().unwrap();
+
+        let edges = [
+            Rect::new(0, 0, output_width, width),
+            Rect::new(0, (output_height - width) as i32, output_width, width),
+            Rect::new(0, 0, width, output_height),
+            Rect::new((output_width - width) as i32, 0, width, output_height),
+        ];
+
+        self.canvas.set_draw_color(SOUND_CUE_COLOR);
+        self.canvas.fill_rects(&edges).unwrap();
+    }
+
+    // keys are bound to the COSMAC VIP keypad, other machines get the button at that place
+    fn key2btn(&self, key: Keycode, scancode: Option<Scancode>) -> Option<usize> {
+        self.key_map
+            .button(key, scancode)
+            .map(|button| self.machine.keypad_button(button))
+    }
+}
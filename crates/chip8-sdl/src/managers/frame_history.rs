@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use chip8_core::resolution::Resolution;
+
+// exported pixels are blown up so single frames are readable in an image viewer
+const EXPORT_SCALE: usize = 4;
+
+// frames per row in the sprite sheet montage
+const MONTAGE_COLUMNS: usize = 10;
+
+// gap between frames in the montage, in exported pixels
+const MONTAGE_GAP: usize = 2;
+
+const ON: u8 = 0xFF;
+const OFF: u8 = 0x00;
+const GAP: u8 = 0x60;
+
+/// Keeps the last N framebuffers around so they can be exported as a flipbook to analyze
+/// flickering and draw order issues frame by frame. Frames from before the game changed
+/// resolution are dropped, the flipbook is all one size.
+pub struct FrameHistory {
+    frames: VecDeque<Vec<bool>>,
+    resolution: Resolution,
+    capacity: usize,
+}
+
+impl FrameHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            resolution: Resolution::default(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, screen: &[bool], resolution: Resolution) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if resolution != self.resolution {
+            self.frames.clear();
+            self.resolution = resolution;
+        }
+
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(screen.to_vec());
+    }
+
+    /// Writes `frame_0000.png`, `frame_0001.png`, ... (oldest first) and a `montage.png`
+    /// sprite sheet of all of them into `dir`. Returns the number of exported frames.
+    pub fn export(&self, dir: &Path) -> std::io::Result<usize> {
+        fs::create_dir_all(dir)?;
+
+        let width = self.resolution.width * EXPORT_SCALE;
+        let height = self.resolution.height * EXPORT_SCALE;
+
+        for (n, frame) in self.frames.iter().enumerate() {
+            let mut image = vec![OFF; width * height];
+
+            self.blit(frame, &mut image, width, 0, 0);
+
+            Self::write_png(&dir.join(format!("frame_{n:04}.png")), &image, width, height)?;
+        }
+
+        if !self.frames.is_empty() {
+            let columns = self.frames.len().min(MONTAGE_COLUMNS);
+            let rows = self.frames.len().div_ceil(MONTAGE_COLUMNS);
+
+            let montage_width = columns * (width + MONTAGE_GAP) - MONTAGE_GAP;
+            let montage_height = rows * (height + MONTAGE_GAP) - MONTAGE_GAP;
+
+            let mut montage = vec![GAP; montage_width * montage_height];
+
+            for (n, frame) in self.frames.iter().enumerate() {
+                let left = (n % MONTAGE_COLUMNS) * (width + MONTAGE_GAP);
+                let top = (n / MONTAGE_COLUMNS) * (height + MONTAGE_GAP);
+
+                self.blit(frame, &mut montage, montage_width, left, top);
+            }
+
+            Self::write_png(&dir.join("montage.png"), &montage, montage_width, montage_height)?;
+        }
+
+        Ok(self.frames.len())
+    }
+
+    // draw a scaled up frame into a grayscale image at (left, top)
+    fn blit(&self, frame: &[bool], image: &mut [u8], image_width: usize, left: usize, top: usize) {
+        let columns = self.resolution.width;
+
+        for (i, pixel) in frame.iter().enumerate() {
+            let x = left + (i % columns) * EXPORT_SCALE;
+            let y = top + (i / columns) * EXPORT_SCALE;
+
+            let color = if *pixel { ON } else { OFF };
+
+            for row in y..y + EXPORT_SCALE {
+                let start = row * image_width + x;
+
+                image[start..start + EXPORT_SCALE].fill(color);
+            }
+        }
+    }
+
+    fn write_png(path: &Path, image: &[u8], width: usize, height: usize) -> std::io::Result<()> {
+        let file = BufWriter::new(fs::File::create(path)?);
+
+        let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(image))
+            .map_err(std::io::Error::other)
+    }
+}
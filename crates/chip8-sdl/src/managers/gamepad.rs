@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::gamepad_map::GamepadMap;
+use chip8_tools::file_io;
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::{GameControllerSubsystem, Sdl};
+
+use crate::managers::rom_library::RomLibrary;
+
+// inside the config directory, in the text form the browser frontend stores too
+const GAMEPAD_MAP_FILE: &str = "gamepad_map";
+
+// how far a trigger is pulled before it counts as pressed, out of 32767
+const TRIGGER_THRESHOLD: i16 = 16384;
+
+// standard layout numbers of the triggers, which SDL reports as axes
+const LEFT_TRIGGER: usize = 6;
+const RIGHT_TRIGGER: usize = 7;
+
+/// The game controllers plugged in, opened as they come and closed as they go, with their
+/// buttons numbered as in the standard gamepad layout of `GamepadMap`.
+pub struct Gamepads {
+    subsystem: Option<GameControllerSubsystem>, // None when SDL has no controller support
+    controllers: Vec<GameController>,           // they have to stay open to send events
+    triggers: [bool; 2],                        // left and right pulled past the threshold
+}
+
+impl Gamepads {
+    pub fn open(sdl: &Sdl) -> Self {
+        Self {
+            subsystem: sdl.game_controller().ok(),
+            controllers: Vec::new(),
+            triggers: [false; 2],
+        }
+    }
+
+    pub fn connected(&self) -> usize {
+        self.controllers.len()
+    }
+
+    /// Handles hot plugging, returns the standard layout button pressed or released, if any.
+    pub fn event(&mut self, evt: &Event) -> Option<(usize, bool)> {
+        match *evt {
+            Event::ControllerDeviceAdded { which, .. } => {
+                let subsystem = self.subsystem.as_ref()?;
+
+                match subsystem.open(which) {
+                    Ok(controller) => {
+                        println!("Controller connected: {}", controller.name());
+                        self.controllers.push(controller);
+                    }
+                    Err(err) => println!("Could not open controller {which}: {err}"),
+                }
+
+                None
+            }
+
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(idx) = self
+                    .controllers
+                    .iter()
+                    .position(|controller| controller.instance_id() == which)
+                {
+                    let controller = self.controllers.remove(idx);
+                    println!("Controller disconnected: {}", controller.name());
+                }
+
+                None
+            }
+
+            Event::ControllerButtonDown { button, .. } => Some((standard_button(button)?, true)),
+            Event::ControllerButtonUp { button, .. } => Some((standard_button(button)?, false)),
+
+            Event::ControllerAxisMotion { axis, value, .. } => {
+                let (idx, button) = match axis {
+                    Axis::TriggerLeft => (0, LEFT_TRIGGER),
+                    Axis::TriggerRight => (1, RIGHT_TRIGGER),
+                    _ => return None,
+                };
+
+                let pulled = value >= TRIGGER_THRESHOLD;
+
+                if pulled == self.triggers[idx] {
+                    return None;
+                }
+
+                self.triggers[idx] = pulled;
+
+                Some((button, pulled))
+            }
+
+            _ => None,
+        }
+    }
+}
+
+// SDL's buttons in the standard gamepad layout, where the browsers number them the same
+fn standard_button(button: Button) -> Option<usize> {
+    let idx = match button {
+        Button::A => 0,
+        Button::B => 1,
+        Button::X => 2,
+        Button::Y => 3,
+        Button::LeftShoulder => 4,
+        Button::RightShoulder => 5,
+        Button::Back => 8,
+        Button::Start => 9,
+        Button::LeftStick => 10,
+        Button::RightStick => 11,
+        Button::DPadUp => 12,
+        Button::DPadDown => 13,
+        Button::DPadLeft => 14,
+        Button::DPadRight => 15,
+        Button::Guide => 16,
+        _ => return None,
+    };
+
+    Some(idx)
+}
+
+fn map_path() -> PathBuf {
+    file_io::config_dir().join(GAMEPAD_MAP_FILE)
+}
+
+/// The mapping `rom` plays with: its own from the game library, else the one of every game,
+/// else the default.
+pub fn load_map(rom: &[u8]) -> GamepadMap {
+    if let Some(map) = RomLibrary::load().get(&RomLibrary::key(rom)).gamepad_map {
+        return map;
+    }
+
+    let Ok(text) = fs::read_to_string(map_path()) else {
+        return GamepadMap::default();
+    };
+
+    GamepadMap::parse(&text).unwrap_or_else(|err| {
+        println!(
+            "Ignoring invalid gamepad mapping {}: {err}",
+            map_path().display()
+        );
+        GamepadMap::default()
+    })
+}
+
+/// Remembers `map` for `rom` in the game library.
+pub fn save_map(rom: &[u8], map: GamepadMap) {
+    let mut library = RomLibrary::load();
+
+    library.set_gamepad_map(&RomLibrary::key(rom), map);
+    library.save();
+}
+
+/// Gamepad mapping over the running game (Ctrl+G): press a keypad key, then the gamepad
+/// button that should press it.
+#[derive(Default)]
+pub struct GamepadSetup {
+    key: Option<usize>, // the keypad key waiting for a button
+}
+
+impl GamepadSetup {
+    pub fn key_down(&mut self, key: usize) {
+        self.key = Some(key);
+    }
+
+    /// The keypad key a gamepad button was just pressed for, if one was picked.
+    pub fn button_down(&mut self) -> Option<usize> {
+        self.key.take()
+    }
+
+    pub fn hud_lines(&self, connected: usize) -> Vec<String> {
+        let prompt = match self.key {
+            _ if connected == 0 => "CONNECT A CONTROLLER".to_string(),
+            Some(key) => format!("PRESS A BUTTON FOR {key:X}"),
+            None => "PRESS A KEYPAD KEY".to_string(),
+        };
+
+        vec![
+            "GAMEPAD MAPPING".to_string(),
+            prompt,
+            "CTRL+G DONE".to_string(),
+        ]
+    }
+}
@@ -0,0 +1,310 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use chip8_core::clock::Clock;
+use chip8_core::constants::NUM_KEYS;
+use chip8_core::core::Chip8;
+use chip8_core::disassembler;
+use chip8_core::quirks::Quirks;
+use chip8_tools::cartridge::Cartridge;
+use chip8_tools::cli::Cli;
+use chip8_tools::quirk_detector;
+use serde_json::{json, Value};
+
+const DEFAULT_FRAMES: u32 = 600;
+
+// frames a `--press` holds its key down, long enough for games polling once a frame
+const PRESS_FRAMES: u32 = 5;
+
+// screenshots are blown up like flipbook frames, single pixels are hard to look at
+const PNG_SCALE: usize = 4;
+
+// gray levels of a pixel set on no plane, the first, the second and both
+const PNG_LEVELS: [u8; 4] = [0x00, 0xFF, 0x55, 0xAA];
+
+/// When a headless run stops, besides running out of frames.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Until {
+    Frames,
+    /// The game jumps to itself, the way test roms end, or exits (SUPER-CHIP's EXIT).
+    Halt,
+    /// PC reaches the address.
+    Pc(u16),
+}
+
+/// Why a headless run stopped.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Stop {
+    Frames,
+    Halt,
+    Pc,
+    Crash(String),
+}
+
+impl Stop {
+    fn name(&self) -> &'static str {
+        match self {
+            Stop::Frames => "frames",
+            Stop::Halt => "halt",
+            Stop::Pc => "pc",
+            Stop::Crash(_) => "crash",
+        }
+    }
+}
+
+/// Runs a game without a window or sound (`--frontend headless`), e.g. a test rom suite in
+/// CI, and dumps the final screen and machine state:
+///
+/// - `--frames N` runs at most N frames, 600 (10 seconds) unless given
+/// - `--until halt` stops at the first jump to itself, `--until 2A4` when PC reaches 2A4
+/// - `--press 1@30,A@90` presses keypad buttons at frames, for menus
+/// - `--screen FILE` writes the screen as a PNG (`.png`) or text, `-` prints it
+/// - `--state FILE` writes PC, I, the registers, timers and stack as JSON, `-` prints them
+///
+/// The screen is printed when neither is given. Quirks are detected unless set with
+/// `--quirks`, the speed is `--ipf` and random numbers always start from the same seed, so
+/// runs are reproducible. A crash, or not halting with `--until halt` or `--until ADDR`,
+/// fails the command once everything was written.
+pub struct HeadlessGameManager {
+    frames: u32,
+    until: Until,
+    presses: Vec<(u32, usize)>, // frame and keypad button
+}
+
+impl HeadlessGameManager {
+    pub fn new(frames: u32, until: Until, presses: Vec<(u32, usize)>) -> Self {
+        Self {
+            frames,
+            until,
+            presses,
+        }
+    }
+
+    /// The command line entry point.
+    pub fn play() -> Result<(), String> {
+        let cli = Cli::get();
+
+        let path = cli
+            .positional
+            .first()
+            .ok_or("No ROM given: chip8-emulator --frontend headless ROM")?;
+
+        let frames = match cli.value("--frames") {
+            Some(frames) => frames
+                .parse()
+                .map_err(|_| format!("Bad number of frames {frames}"))?,
+            None => DEFAULT_FRAMES,
+        };
+        let until = match cli.value("--until") {
+            None => Until::Frames,
+            Some("halt") => Until::Halt,
+            Some(addr) => u16::from_str_radix(addr.trim_start_matches("0x"), 16)
+                .map(Until::Pc)
+                .map_err(|_| format!("Bad --until {addr}, halt or an address in hex"))?,
+        };
+        let presses = match cli.value("--press") {
+            Some(list) => Self::parse_presses(list)?,
+            None => Vec::new(),
+        };
+
+        let cartridge = Cartridge::open(path)?;
+        let rom = cartridge.rom(0);
+
+        // the desktop's checks, see `Clock::is_valid_ipf`
+        let mut clock = match cli.value("--ipf") {
+            Some(ipf) => Clock::from_ipf(
+                ipf.parse()
+                    .ok()
+                    .filter(|ipf| Clock::is_valid_ipf(*ipf))
+                    .ok_or(format!("Bad instructions per frame {ipf}"))?,
+            ),
+            None => Clock::default(),
+        };
+
+        let quirks = match cli.value("--quirks") {
+            Some(names) => Quirks::parse(names).ok_or(format!("Unknown quirks {names}"))?,
+            None => quirk_detector::detect(rom, clock.instructions_per_frame())[0].quirks,
+        };
+
+        let mut chip8 = Chip8::with_quirks(quirks);
+
+        chip8.load(rom).map_err(|err| format!("{path}: {err}"))?;
+        chip8.set_sound_enabled(false);
+        chip8.set_rng_state(1);
+
+        let manager = Self::new(frames, until, presses);
+        let (frames, stop) = manager.run(&mut chip8, &mut clock);
+
+        let screen = cli.value("--screen");
+        let state = cli.value("--state");
+
+        if screen.is_none() && state.is_none() {
+            print!("{}", Self::screen_text(&chip8));
+        }
+
+        if let Some(path) = screen {
+            Self::write_screen(&chip8, path)?;
+        }
+
+        if let Some(path) = state {
+            let json = serde_json::to_string_pretty(&Self::state_json(&chip8, frames, &stop))
+                .unwrap()
+                + "\n";
+
+            write_output(path, json.as_bytes())?;
+        }
+
+        match stop {
+            Stop::Crash(err) => Err(format!(
+                "The game crashed after {frames} frames: {err}\n{}",
+                disassembler::at_pc(&chip8)
+            )),
+            Stop::Frames if until != Until::Frames => Err(format!(
+                "Stopped after {frames} frames without reaching --until"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parses `--press 1@30,A@90`, keypad buttons in hex at frames.
+    pub fn parse_presses(list: &str) -> Result<Vec<(u32, usize)>, String> {
+        list.split(',')
+            .map(|press| {
+                let bad = || format!("Bad key press {press}, e.g. 1@30");
+                let (button, frame) = press.trim().split_once('@').ok_or_else(bad)?;
+
+                let button = usize::from_str_radix(button, 16)
+                    .ok()
+                    .filter(|button| *button < NUM_KEYS)
+                    .ok_or_else(bad)?;
+                let frame = frame.parse().map_err(|_| bad())?;
+
+                Ok((frame, button))
+            })
+            .collect()
+    }
+
+    /// Runs `chip8` until the run stops, returns the frames run and why it stopped.
+    pub fn run(&self, chip8: &mut Chip8, clock: &mut Clock) -> (u32, Stop) {
+        for frame in 0..self.frames {
+            for button in 0..NUM_KEYS {
+                let held = self.presses.iter().any(|(at, pressed)| {
+                    *pressed == button && (*at..*at + PRESS_FRAMES).contains(&frame)
+                });
+
+                chip8.keypress(button, held);
+            }
+
+            // ticked one at a time, a frame may pass the address it stops at
+            for _ in 0..clock.ticks_for_frame() {
+                let pc = chip8.get_program_counter();
+
+                match self.until {
+                    Until::Halt
+                        if disassembler::opcode_at(chip8.get_memory(), pc) == 0x1000 | pc =>
+                    {
+                        return (frame, Stop::Halt);
+                    }
+                    Until::Pc(addr) if pc == addr => return (frame, Stop::Pc),
+                    _ => (),
+                }
+
+                match chip8.tick() {
+                    Err(err) => return (frame, Stop::Crash(err.to_string())),
+                    // SUPER-CHIP's EXIT halts too
+                    Ok(effect) if effect.halted && self.until == Until::Halt => {
+                        return (frame, Stop::Halt);
+                    }
+                    Ok(_) => (),
+                }
+            }
+
+            chip8.tick_timers();
+        }
+
+        (self.frames, Stop::Frames)
+    }
+
+    /// The screen as text, a line per row with `#` for a lit pixel and `.` for a dark one.
+    pub fn screen_text(chip8: &Chip8) -> String {
+        let (plane0, plane1) = (chip8.get_plane(0), chip8.get_plane(1));
+        let width = chip8.resolution().width;
+
+        let mut text = String::new();
+
+        for (i, (pixel0, pixel1)) in plane0.iter().zip(plane1).enumerate() {
+            text.push(if *pixel0 || *pixel1 { '#' } else { '.' });
+
+            if i % width == width - 1 {
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    /// The machine's state for scripts: why the run stopped, PC, I, the registers (`v`),
+    /// timers and stack, as numbers.
+    pub fn state_json(chip8: &Chip8, frames: u32, stop: &Stop) -> Value {
+        json!({
+            "frames": frames,
+            "stopped": stop.name(),
+            "error": match stop {
+                Stop::Crash(err) => Some(err.as_str()),
+                _ => None,
+            },
+            "pc": chip8.get_program_counter(),
+            "i": chip8.get_i_register(),
+            "v": chip8.get_registers(),
+            "delay_timer": chip8.get_delay_timer(),
+            "sound_timer": chip8.get_sound_timer(),
+            "stack": chip8.get_stack(),
+        })
+    }
+
+    fn write_screen(chip8: &Chip8, path: &str) -> Result<(), String> {
+        if !path.to_ascii_lowercase().ends_with(".png") {
+            return write_output(path, Self::screen_text(chip8).as_bytes());
+        }
+
+        let resolution = chip8.resolution();
+        let (width, height) = (resolution.width * PNG_SCALE, resolution.height * PNG_SCALE);
+        let (plane0, plane1) = (chip8.get_plane(0), chip8.get_plane(1));
+
+        let mut image = vec![0; width * height];
+
+        for (y, row) in image.chunks_mut(width).enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let i = (y / PNG_SCALE) * resolution.width + x / PNG_SCALE;
+
+                *pixel = PNG_LEVELS[usize::from(plane0[i]) | usize::from(plane1[i]) << 1];
+            }
+        }
+
+        let file = fs::File::create(path).map_err(|err| format!("{path}: {err}"))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&image))
+            .map_err(|err| format!("{path}: {err}"))
+    }
+}
+
+// `data` into the file at `path`, or printed for `-`
+fn write_output(path: &str, data: &[u8]) -> Result<(), String> {
+    if path == "-" {
+        print!("{}", String::from_utf8_lossy(data));
+        return Ok(());
+    }
+
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    }
+
+    fs::write(path, data).map_err(|err| format!("{path}: {err}"))
+}
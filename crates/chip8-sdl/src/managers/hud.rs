@@ -0,0 +1,96 @@
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+// glyphs are 3x5 pixels, drawn with a one pixel gap
+pub const GLYPH_WIDTH: u32 = 3;
+pub const GLYPH_HEIGHT: u32 = 5;
+
+// each row is 3 bits, most significant bit on the left
+const GLYPHS: [(char, [u8; 5]); 45] = [
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('[', [0b110, 0b100, 0b100, 0b100, 0b110]),
+    (']', [0b011, 0b001, 0b001, 0b001, 0b011]),
+    ('>', [0b100, 0b010, 0b001, 0b010, 0b100]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+];
+
+// shown for characters the font does not have
+const UNKNOWN_GLYPH: [u8; 5] = [0b111, 0b001, 0b010, 0b000, 0b010];
+
+/// Width in window pixels of `text` drawn at `scale`.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    text.chars().count() as u32 * (GLYPH_WIDTH + 1) * scale
+}
+
+/// Draws `text` with its top left corner at (x, y) using the canvas' current draw color.
+/// The font only has upper case letters, lower case ones are drawn as upper case.
+pub fn draw_text(canvas: &mut WindowCanvas, text: &str, x: i32, y: i32, scale: u32) {
+    for (i, c) in text.chars().enumerate() {
+        let c = c.to_ascii_uppercase();
+
+        let rows = GLYPHS
+            .iter()
+            .find(|(glyph, _)| *glyph == c)
+            .map(|(_, rows)| rows)
+            .unwrap_or(&UNKNOWN_GLYPH);
+
+        let left = x + (i as u32 * (GLYPH_WIDTH + 1) * scale) as i32;
+
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+
+                let rect = Rect::new(
+                    left + (col * scale) as i32,
+                    y + (row as u32 * scale) as i32,
+                    scale,
+                    scale,
+                );
+
+                canvas.fill_rect(rect).unwrap();
+            }
+        }
+    }
+}
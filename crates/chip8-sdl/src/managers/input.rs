@@ -0,0 +1,257 @@
+use chip8_core::constants::NUM_KEYS;
+use chip8_core::frontend::{Input, InputSource};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::EventPump;
+
+// physical keyboard rows as most keyboards wire their key matrix, by scancode so the
+// layout doesn't matter
+const MATRIX_ROWS: [&[Scancode]; 4] = [
+    &[
+        Scancode::Num1,
+        Scancode::Num2,
+        Scancode::Num3,
+        Scancode::Num4,
+        Scancode::Num5,
+        Scancode::Num6,
+        Scancode::Num7,
+        Scancode::Num8,
+        Scancode::Num9,
+        Scancode::Num0,
+    ],
+    &[
+        Scancode::Q,
+        Scancode::W,
+        Scancode::E,
+        Scancode::R,
+        Scancode::T,
+        Scancode::Y,
+        Scancode::U,
+        Scancode::I,
+        Scancode::O,
+        Scancode::P,
+    ],
+    &[
+        Scancode::A,
+        Scancode::S,
+        Scancode::D,
+        Scancode::F,
+        Scancode::G,
+        Scancode::H,
+        Scancode::J,
+        Scancode::K,
+        Scancode::L,
+    ],
+    &[
+        Scancode::Z,
+        Scancode::X,
+        Scancode::C,
+        Scancode::V,
+        Scancode::B,
+        Scancode::N,
+        Scancode::M,
+    ],
+];
+
+/// Which keys stay down when more keypad keys are pressed than allowed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyPriority {
+    Newest, // a new press releases the key held the longest
+    Oldest, // a new press is ignored until a held key is released
+}
+
+impl KeyPriority {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "newest" => Some(KeyPriority::Newest),
+            "oldest" => Some(KeyPriority::Oldest),
+            _ => None,
+        }
+    }
+}
+
+/// Keyboard input for the keypad: limits how many keys are down at once and notices chords
+/// the keyboard probably can't report. Cheap keyboards share wires between keys, so three
+/// keys on the corners of a rectangle of their key matrix make the fourth corner look
+/// pressed (ghosting) or block one of them (jamming), which breaks two-player games.
+pub struct KeypadInput {
+    held: Vec<(usize, Scancode)>, // keypad buttons down, oldest first
+    max_keys: usize,
+    priority: KeyPriority,
+    ghost_chord: Option<[Scancode; 3]>, // last reported, so a chord is reported once
+}
+
+impl KeypadInput {
+    pub fn new(max_keys: usize, priority: KeyPriority) -> Self {
+        Self {
+            held: Vec::new(),
+            max_keys: max_keys.clamp(1, NUM_KEYS),
+            priority,
+            ghost_chord: None,
+        }
+    }
+
+    /// A key bound to `button` went down, returns the keypad changes to apply.
+    pub fn key_down(&mut self, button: usize, scancode: Scancode) -> Vec<(usize, bool)> {
+        // held keys repeat
+        if self.held.iter().any(|(held, _)| *held == button) {
+            return Vec::new();
+        }
+
+        let mut changes = Vec::new();
+
+        if self.held.len() >= self.max_keys {
+            match self.priority {
+                KeyPriority::Newest => {
+                    let (oldest, _) = self.held.remove(0);
+                    changes.push((oldest, false));
+                }
+                KeyPriority::Oldest => return changes,
+            }
+        }
+
+        self.held.push((button, scancode));
+        changes.push((button, true));
+
+        changes
+    }
+
+    /// A key bound to `button` went up, returns the keypad change to apply, none when the
+    /// press was dropped.
+    pub fn key_up(&mut self, button: usize) -> Option<(usize, bool)> {
+        let idx = self.held.iter().position(|(held, _)| *held == button)?;
+
+        self.held.remove(idx);
+
+        if self.ghost_chord.is_some_and(|chord| !self.is_held(&chord)) {
+            self.ghost_chord = None;
+        }
+
+        Some((button, false))
+    }
+
+    fn is_held(&self, chord: &[Scancode]) -> bool {
+        chord
+            .iter()
+            .all(|key| self.held.iter().any(|(_, held)| held == key))
+    }
+
+    fn matrix_position(scancode: Scancode) -> Option<(usize, usize)> {
+        MATRIX_ROWS.iter().enumerate().find_map(|(row, keys)| {
+            keys.iter()
+                .position(|key| *key == scancode)
+                .map(|col| (row, col))
+        })
+    }
+
+    /// A warning the first time the held keys contain three corners of a rectangle.
+    pub fn take_ghosting_warning(&mut self) -> Option<String> {
+        let keys: Vec<_> = self
+            .held
+            .iter()
+            .filter_map(|(_, key)| Self::matrix_position(*key).map(|pos| (*key, pos)))
+            .collect();
+
+        // the corner sharing a row with one key and a column with another
+        for (corner, (row, col)) in &keys {
+            for (across, (across_row, across_col)) in &keys {
+                for (down, (down_row, down_col)) in &keys {
+                    if across_row == row && across_col != col && down_col == col && down_row != row
+                    {
+                        let chord = [*corner, *across, *down];
+
+                        if self.ghost_chord.is_some_and(|reported| reported == chord) {
+                            return None;
+                        }
+
+                        self.ghost_chord = Some(chord);
+
+                        return Some(format!(
+                            "{}, {} and {} are held together, most keyboards can't report that \
+                             chord reliably (ghosting); consider binding one of them elsewhere",
+                            corner.name(),
+                            across.name(),
+                            down.name()
+                        ));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// The desktop's keypad as an `InputSource`. The game loop takes the window's events with
+/// `events`, hands those pressing keypad buttons back through `key_down`, `key_up` and
+/// `press` and handles the rest as hotkeys; `poll` then reports the buttons held. Closing
+/// the window or Escape quits.
+pub struct SdlInput {
+    event_pump: EventPump,
+    keypad: KeypadInput,
+    keys: [bool; NUM_KEYS],
+    quit: bool,
+}
+
+impl SdlInput {
+    pub fn new(event_pump: EventPump, keypad: KeypadInput) -> Self {
+        Self {
+            event_pump,
+            keypad,
+            keys: [false; NUM_KEYS],
+            quit: false,
+        }
+    }
+
+    /// The events since the last frame, but for quitting, which `poll` reports.
+    pub fn events(&mut self) -> Vec<Event> {
+        let (quit, events): (Vec<Event>, Vec<Event>) =
+            self.event_pump.poll_iter().partition(|evt| {
+                matches!(
+                    evt,
+                    Event::Quit { .. }
+                        | Event::KeyDown {
+                            keycode: Some(Keycode::Escape),
+                            ..
+                        }
+                )
+            });
+
+        self.quit |= !quit.is_empty();
+
+        events
+    }
+
+    /// A keyboard key bound to `button` went down, see `KeypadInput::key_down`.
+    pub fn key_down(&mut self, button: usize, scancode: Scancode) {
+        for (button, pressed) in self.keypad.key_down(button, scancode) {
+            self.keys[button] = pressed;
+        }
+
+        if let Some(warning) = self.keypad.take_ghosting_warning() {
+            println!("{warning}");
+        }
+    }
+
+    /// A keyboard key bound to `button` went up.
+    pub fn key_up(&mut self, button: usize) {
+        if let Some((button, pressed)) = self.keypad.key_up(button) {
+            self.keys[button] = pressed;
+        }
+    }
+
+    /// A gamepad button mapped to `button` went down or up, chords aren't limited.
+    pub fn press(&mut self, button: usize, pressed: bool) {
+        self.keys[button] = pressed;
+    }
+}
+
+impl InputSource for SdlInput {
+    fn poll(&mut self) -> Input {
+        if self.quit {
+            Input::Quit
+        } else {
+            Input::Keys(self.keys)
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+
+use chip8_core::constants::NUM_KEYS;
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::pixels::Color;
+use sdl2::render::WindowCanvas;
+use sdl2::Sdl;
+
+use crate::constants::{BLACK_COLOR, WHITE_COLOR};
+use crate::managers::hud;
+use crate::managers::key_map::KeyMap;
+
+const TITLE_SCALE: u32 = 8;
+const TEXT_SCALE: u32 = 4;
+const MARGIN: u32 = 40;
+
+const DIM_COLOR: Color = Color::RGB(120, 120, 120);
+
+// most recent key events listed
+const MAX_EVENTS: usize = 16;
+
+struct KeyEvent {
+    down: bool,
+    repeat: bool,
+    scancode: Option<Scancode>,
+    keycode: Option<Keycode>,
+    button: Option<usize>,
+    since_previous: u32, // ms since the previous key event, by SDL's timestamps
+    held: Option<u32>,   // ms the key was down, on release
+}
+
+/// Input diagnostics (F1 in the menus): lists the raw key events SDL delivers, with their
+/// scancode (the physical key), keycode (what the layout makes of it), the keypad button
+/// it is bound to and their timing, to debug keys that don't work on some layouts.
+pub struct InputTest {
+    key_map: KeyMap,
+    events: VecDeque<KeyEvent>,
+    last_timestamp: Option<u32>,
+    down_since: Vec<(Scancode, u32)>, // keys down and when they went down
+}
+
+impl InputTest {
+    pub fn new(key_map: KeyMap) -> Self {
+        Self {
+            key_map,
+            events: VecDeque::with_capacity(MAX_EVENTS),
+            last_timestamp: None,
+            down_since: Vec::new(),
+        }
+    }
+
+    /// Runs until Escape or the window is closed.
+    pub fn run(&mut self, sdl: &Sdl, canvas: &mut WindowCanvas) {
+        let mut event_pump = sdl.event_pump().unwrap();
+
+        loop {
+            for evt in event_pump.poll_iter() {
+                match evt {
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => return,
+
+                    Event::KeyDown {
+                        timestamp,
+                        keycode,
+                        scancode,
+                        repeat,
+                        ..
+                    } => self.record(timestamp, true, repeat, scancode, keycode),
+
+                    Event::KeyUp {
+                        timestamp,
+                        keycode,
+                        scancode,
+                        ..
+                    } => self.record(timestamp, false, false, scancode, keycode),
+
+                    _ => (),
+                }
+            }
+
+            self.draw(canvas);
+        }
+    }
+
+    fn record(
+        &mut self,
+        timestamp: u32,
+        down: bool,
+        repeat: bool,
+        scancode: Option<Scancode>,
+        keycode: Option<Keycode>,
+    ) {
+        let since_previous = timestamp.saturating_sub(self.last_timestamp.unwrap_or(timestamp));
+        self.last_timestamp = Some(timestamp);
+
+        let mut held = None;
+
+        if let Some(scancode) = scancode {
+            let idx = self.down_since.iter().position(|(key, _)| *key == scancode);
+
+            match (down, idx) {
+                (true, None) => self.down_since.push((scancode, timestamp)),
+                (false, Some(idx)) => {
+                    held = Some(timestamp.saturating_sub(self.down_since.remove(idx).1));
+                }
+                _ => (),
+            }
+        }
+
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+
+        self.events.push_back(KeyEvent {
+            down,
+            repeat,
+            scancode,
+            keycode,
+            button: keycode.and_then(|key| self.key_map.button(key, scancode)),
+            since_previous,
+            held,
+        });
+    }
+
+    fn describe(event: &KeyEvent) -> String {
+        let scancode = event.scancode.map_or("-".to_string(), |scancode| {
+            format!("{} {}", scancode as i32, scancode.name())
+        });
+
+        let keycode = event.keycode.map_or("-".to_string(), |key| key.name());
+
+        let button = event
+            .button
+            .map_or("-".to_string(), |button| format!("{button:X}"));
+
+        let state = match (event.down, event.repeat) {
+            (true, true) => "REPEAT",
+            (true, false) => "DOWN",
+            (false, _) => "UP",
+        };
+
+        let held = event
+            .held
+            .map_or(String::new(), |held| format!("  HELD {held} MS"));
+
+        format!(
+            "{state:<6} SCANCODE {scancode:<12} KEY {keycode:<10} KEYPAD {button}  AFTER {} MS{held}",
+            event.since_previous
+        )
+    }
+
+    fn draw(&self, canvas: &mut WindowCanvas) {
+        let (_, window_height) = canvas.output_size().unwrap();
+
+        canvas.set_draw_color(BLACK_COLOR);
+        canvas.clear();
+
+        canvas.set_draw_color(WHITE_COLOR);
+        hud::draw_text(
+            canvas,
+            "INPUT TEST",
+            MARGIN as i32,
+            MARGIN as i32,
+            TITLE_SCALE,
+        );
+
+        let line_height = (hud::GLYPH_HEIGHT + 2) * TEXT_SCALE;
+        let mut y = MARGIN * 2 + hud::GLYPH_HEIGHT * TITLE_SCALE;
+
+        // keypad buttons whose keys are down right now
+        let held: Vec<String> = (0..NUM_KEYS)
+            .filter(|button| {
+                self.down_since.iter().any(|(scancode, _)| {
+                    Keycode::from_scancode(*scancode)
+                        .is_some_and(|key| self.key_map.button(key, Some(*scancode)) == Some(*button))
+                })
+            })
+            .map(|button| format!("{button:X}"))
+            .collect();
+
+        hud::draw_text(
+            canvas,
+            &format!("KEYPAD DOWN: {}", held.join(" ")),
+            MARGIN as i32,
+            y as i32,
+            TEXT_SCALE,
+        );
+
+        y += line_height * 2;
+
+        if self.events.is_empty() {
+            canvas.set_draw_color(DIM_COLOR);
+            hud::draw_text(canvas, "PRESS ANY KEY", MARGIN as i32, y as i32, TEXT_SCALE);
+        }
+
+        // newest first, older events fade out
+        for (i, event) in self.events.iter().rev().enumerate() {
+            canvas.set_draw_color(if i == 0 { WHITE_COLOR } else { DIM_COLOR });
+            hud::draw_text(
+                canvas,
+                &Self::describe(event),
+                MARGIN as i32,
+                (y + i as u32 * line_height) as i32,
+                TEXT_SCALE,
+            );
+        }
+
+        canvas.set_draw_color(DIM_COLOR);
+        hud::draw_text(
+            canvas,
+            "ESC: BACK",
+            MARGIN as i32,
+            (window_height - MARGIN - hud::GLYPH_HEIGHT * TEXT_SCALE) as i32,
+            TEXT_SCALE,
+        );
+
+        canvas.present();
+    }
+}
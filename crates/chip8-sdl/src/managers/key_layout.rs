@@ -0,0 +1,185 @@
+use sdl2::keyboard::Keycode;
+
+// keypad button at each spot of the 4x4 grid, row by row
+const GRID: [usize; 16] = [
+    0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+];
+
+// button 0 on 0 to button F on F
+const HEX_KEYS: [Keycode; 16] = [
+    Keycode::Num0,
+    Keycode::Num1,
+    Keycode::Num2,
+    Keycode::Num3,
+    Keycode::Num4,
+    Keycode::Num5,
+    Keycode::Num6,
+    Keycode::Num7,
+    Keycode::Num8,
+    Keycode::Num9,
+    Keycode::A,
+    Keycode::B,
+    Keycode::C,
+    Keycode::D,
+    Keycode::E,
+    Keycode::F,
+];
+
+/// Ready-made keypad bindings, picked with `--key-layout`, `layout = "..."` in the
+/// `[keys]` table of the config file or the key layout menu (Ctrl+K).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyLayout {
+    /// The 4x4 grid on `1234`/`QWER`/`ASDF`/`ZXCV`, the default.
+    Qwerty,
+    /// The same keys of an AZERTY keyboard, `1234`/`AZER`/`QSDF`/`WXCV`.
+    Azerty,
+    /// The same keys of a Dvorak keyboard, `1234`/`',.P`/`AOEU`/`;QJK`.
+    Dvorak,
+    /// Each button on the key labelled with its hex digit, `0`-`9` and `A`-`F`.
+    Hex,
+}
+
+impl KeyLayout {
+    pub const ALL: [KeyLayout; 4] = [
+        KeyLayout::Qwerty,
+        KeyLayout::Azerty,
+        KeyLayout::Dvorak,
+        KeyLayout::Hex,
+    ];
+
+    /// `qwerty`, `azerty`, `dvorak` or `hex`.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyLayout::Qwerty => "qwerty",
+            KeyLayout::Azerty => "azerty",
+            KeyLayout::Dvorak => "dvorak",
+            KeyLayout::Hex => "hex",
+        }
+    }
+
+    /// The key of each keypad button, indexed by button.
+    pub fn keypad(self) -> [Keycode; 16] {
+        let grid = match self {
+            KeyLayout::Qwerty => [
+                Keycode::Num1,
+                Keycode::Num2,
+                Keycode::Num3,
+                Keycode::Num4,
+                Keycode::Q,
+                Keycode::W,
+                Keycode::E,
+                Keycode::R,
+                Keycode::A,
+                Keycode::S,
+                Keycode::D,
+                Keycode::F,
+                Keycode::Z,
+                Keycode::X,
+                Keycode::C,
+                Keycode::V,
+            ],
+            // SDL reports the number row as numbers on AZERTY too
+            KeyLayout::Azerty => [
+                Keycode::Num1,
+                Keycode::Num2,
+                Keycode::Num3,
+                Keycode::Num4,
+                Keycode::A,
+                Keycode::Z,
+                Keycode::E,
+                Keycode::R,
+                Keycode::Q,
+                Keycode::S,
+                Keycode::D,
+                Keycode::F,
+                Keycode::W,
+                Keycode::X,
+                Keycode::C,
+                Keycode::V,
+            ],
+            KeyLayout::Dvorak => [
+                Keycode::Num1,
+                Keycode::Num2,
+                Keycode::Num3,
+                Keycode::Num4,
+                Keycode::Quote,
+                Keycode::Comma,
+                Keycode::Period,
+                Keycode::P,
+                Keycode::A,
+                Keycode::O,
+                Keycode::E,
+                Keycode::U,
+                Keycode::Semicolon,
+                Keycode::Q,
+                Keycode::J,
+                Keycode::K,
+            ],
+            KeyLayout::Hex => return HEX_KEYS,
+        };
+
+        let mut keypad = grid;
+
+        for (spot, button) in GRID.into_iter().enumerate() {
+            keypad[button] = grid[spot];
+        }
+
+        keypad
+    }
+}
+
+/// The key layout menu (Ctrl+K): Up/Down picks a layout and Return applies it.
+pub struct LayoutMenu {
+    selected: usize, // in KeyLayout::ALL order
+}
+
+impl LayoutMenu {
+    pub fn new(current: Option<KeyLayout>) -> Self {
+        Self {
+            selected: KeyLayout::ALL
+                .iter()
+                .position(|layout| Some(*layout) == current)
+                .unwrap_or(0),
+        }
+    }
+
+    /// Applies a key, returns the layout picked with Return.
+    pub fn key_down(&mut self, key: Keycode) -> Option<KeyLayout> {
+        let count = KeyLayout::ALL.len();
+
+        match key {
+            Keycode::Up => self.selected = (self.selected + count - 1) % count,
+            Keycode::Down => self.selected = (self.selected + 1) % count,
+            Keycode::Return | Keycode::KpEnter => return Some(KeyLayout::ALL[self.selected]),
+            _ => (),
+        }
+
+        None
+    }
+
+    /// A line per layout, the selected one marked with `>` and the current one in brackets;
+    /// keys bound one by one match none of them.
+    pub fn hud_lines(&self, current: Option<KeyLayout>) -> Vec<String> {
+        let mut lines = vec!["KEY LAYOUT".to_string()];
+
+        for (idx, layout) in KeyLayout::ALL.into_iter().enumerate() {
+            let marker = if idx == self.selected { ">" } else { " " };
+            let name = layout.name().to_uppercase();
+
+            lines.push(if Some(layout) == current {
+                format!("{marker} [{name}]")
+            } else {
+                format!("{marker}  {name}")
+            });
+        }
+
+        lines.push("RETURN APPLIES".to_string());
+        lines
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_tools::file_io;
+use sdl2::keyboard::{Keycode, Scancode};
+
+use crate::managers::config::Config;
+use crate::managers::key_layout::KeyLayout;
+
+// inside the config directory
+const KEY_MAP_FILE: &str = "keys";
+
+/// Emulator actions that can be bound to any key. Escape always quits.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Pause,
+    Step,
+    Breakpoint, // toggled at PC while paused
+    QuickSave,
+    QuickLoad,
+    FastForward, // held
+    Rewind,      // held
+    SpeedUp,
+    SlowDown,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Pause,
+        Action::Step,
+        Action::Breakpoint,
+        Action::QuickSave,
+        Action::QuickLoad,
+        Action::FastForward,
+        Action::Rewind,
+        Action::SpeedUp,
+        Action::SlowDown,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Pause => "pause",
+            Action::Step => "step",
+            Action::Breakpoint => "breakpoint",
+            Action::QuickSave => "quick save",
+            Action::QuickLoad => "quick load",
+            Action::FastForward => "fast forward",
+            Action::Rewind => "rewind",
+            Action::SpeedUp => "speed up",
+            Action::SlowDown => "slow down",
+        }
+    }
+}
+
+/// Keyboard bindings for the 16 keypad buttons and the emulator actions, stored in the
+/// config directory as `name=Key` lines, e.g. `A=Z` or `pause=P`. Key names are SDL's.
+///
+/// With `physical=true` the keypad buttons are bound to key positions instead: a binding
+/// names the key at that spot of a US QWERTY keyboard and matches whatever key sits there
+/// on the keyboard actually used, so the default 4x4 block stays under the left hand on
+/// AZERTY or Dvorak layouts. `layout=azerty` binds the whole keypad to one of the
+/// `KeyLayout`s at once, lines after it still change single buttons.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyMap {
+    keypad: [Keycode; 16], // indexed by keypad button
+    actions: [Keycode; 9], // in Action::ALL order
+    physical: bool,        // keypad keys are matched by scancode
+}
+
+impl Default for KeyMap {
+    // the usual QWERTY layout, the keypad's 4x4 grid on the 1-4 to Z-V block
+    fn default() -> Self {
+        Self {
+            keypad: KeyLayout::Qwerty.keypad(),
+            actions: [
+                Keycode::P,
+                Keycode::N,
+                Keycode::B,
+                Keycode::F5,
+                Keycode::F9,
+                Keycode::Tab,
+                Keycode::Backspace,
+                Keycode::Equals,
+                Keycode::Minus,
+            ],
+            physical: false,
+        }
+    }
+}
+
+impl KeyMap {
+    fn path() -> PathBuf {
+        file_io::config_dir().join(KEY_MAP_FILE)
+    }
+
+    /// The saved bindings, or those of the config file, `None` when the user never set them
+    /// up. Unknown lines are ignored and missing bindings keep their default.
+    pub fn load() -> Option<Self> {
+        let Ok(text) = fs::read_to_string(Self::path()) else {
+            return Config::get().key_map.clone();
+        };
+
+        let mut key_map = Self::default();
+
+        for line in text.lines() {
+            let Some((name, key)) = line.split_once('=') else {
+                continue;
+            };
+
+            if !key_map.bind(name.trim(), key.trim()) {
+                println!("Ignoring unknown key in key map: {line}");
+            }
+        }
+
+        Some(key_map)
+    }
+
+    /// Binds the keypad button `name` (in hex) or the action called `name` to `key`, sets
+    /// `physical` to `true` or `false`, or binds the keypad to the `layout` called `key`.
+    /// Returns false when `key` is unknown; unknown names are ignored.
+    pub fn bind(&mut self, name: &str, key: &str) -> bool {
+        if name == "physical" {
+            self.physical = key == "true";
+            return true;
+        }
+
+        if name == "layout" {
+            let Some(layout) = KeyLayout::parse(key) else {
+                return false;
+            };
+
+            self.set_layout(layout);
+            return true;
+        }
+
+        let Some(key) = Keycode::from_name(key) else {
+            return false;
+        };
+
+        if let Some(button) = u8::from_str_radix(name, 16)
+            .ok()
+            .filter(|button| *button < 16)
+        {
+            self.keypad[button as usize] = key;
+        } else if let Some(idx) = Action::ALL.iter().position(|action| action.name() == name) {
+            self.actions[idx] = key;
+        }
+
+        true
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let mut text = String::new();
+
+        for (button, key) in self.keypad.iter().enumerate() {
+            text += &format!("{button:X}={}\n", key.name());
+        }
+
+        for (action, key) in Action::ALL.iter().zip(self.actions) {
+            text += &format!("{}={}\n", action.name(), key.name());
+        }
+
+        text += &format!("physical={}\n", self.physical);
+
+        fs::create_dir_all(file_io::config_dir())?;
+        fs::write(Self::path(), text)
+    }
+
+    pub fn keypad_key(&self, button: usize) -> Keycode {
+        self.keypad[button]
+    }
+
+    pub fn set_keypad_key(&mut self, button: usize, key: Keycode) {
+        self.keypad[button] = key;
+    }
+
+    pub fn action_key(&self, action: Action) -> Keycode {
+        self.actions[action as usize]
+    }
+
+    pub fn set_action_key(&mut self, action: Action, key: Keycode) {
+        self.actions[action as usize] = key;
+    }
+
+    /// Binds the whole keypad to `layout`, matching keys by what they type.
+    pub fn set_layout(&mut self, layout: KeyLayout) {
+        self.keypad = layout.keypad();
+        self.physical = false;
+    }
+
+    /// The layout the keypad is bound to, `None` when its keys were picked one by one.
+    pub fn layout(&self) -> Option<KeyLayout> {
+        KeyLayout::ALL
+            .into_iter()
+            .find(|layout| !self.physical && layout.keypad() == self.keypad)
+    }
+
+    pub fn physical(&self) -> bool {
+        self.physical
+    }
+
+    pub fn set_physical(&mut self, physical: bool) {
+        self.physical = physical;
+    }
+
+    /// The key a keypad binding to the key pressed names: `key` itself, or with physical
+    /// bindings the key at the same position (`scancode`) of a QWERTY keyboard.
+    pub fn keypad_binding(&self, key: Keycode, scancode: Option<Scancode>) -> Keycode {
+        scancode
+            .filter(|_| self.physical)
+            // SDL names scancodes after the US layout
+            .and_then(|scancode| Keycode::from_name(scancode.name()))
+            .unwrap_or(key)
+    }
+
+    /// Keypad button bound to the key pressed, see `keypad_binding`.
+    pub fn button(&self, key: Keycode, scancode: Option<Scancode>) -> Option<usize> {
+        let key = self.keypad_binding(key, scancode);
+
+        self.keypad.iter().position(|bound| *bound == key)
+    }
+
+    /// Action bound to `key`, actions win over keypad buttons bound to the same key.
+    pub fn action(&self, key: Keycode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.action_key(*action) == key)
+    }
+}
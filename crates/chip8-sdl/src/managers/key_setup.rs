@@ -0,0 +1,247 @@
+use chip8_core::machine::Machine;
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use sdl2::Sdl;
+
+use crate::constants::{BLACK_COLOR, WHITE_COLOR};
+use crate::managers::hud;
+use crate::managers::key_map::{Action, KeyMap};
+
+const TITLE_SCALE: u32 = 8;
+const TEXT_SCALE: u32 = 4;
+const MARGIN: u32 = 40;
+
+const DIM_COLOR: Color = Color::RGB(120, 120, 120);
+
+// keypad buttons as they are laid out on the COSMAC VIP, other machines' keypads are
+// mapped onto it
+const KEYPAD_LAYOUT: [usize; 16] = Machine::CosmacVip.keypad_layout();
+
+// steer the wizard itself, so they can't be bound
+const RESERVED_KEYS: [Keycode; 3] = [Keycode::Escape, Keycode::Backspace, Keycode::Return];
+
+/// Guided key binding setup: asks for a key for each keypad button, in keypad order, then for
+/// each action, and finally shows a test screen lighting up the buttons as their keys are
+/// pressed. Shown on the first run, before any key map was saved, and with `--setup-keys`.
+pub struct KeySetup {
+    key_map: KeyMap,
+    step: usize, // keypad buttons first, then actions; past the end is the test screen
+    pressed: [bool; 16],
+}
+
+impl Default for KeySetup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeySetup {
+    pub fn new() -> Self {
+        Self {
+            key_map: KeyMap::default(),
+            step: 0,
+            pressed: [false; 16],
+        }
+    }
+
+    /// Learns keypad keys by position rather than label, see `KeyMap::keypad_binding`.
+    pub fn set_physical(&mut self, physical: bool) {
+        self.key_map.set_physical(physical);
+    }
+
+    fn steps() -> usize {
+        KEYPAD_LAYOUT.len() + Action::ALL.len()
+    }
+
+    /// Runs the setup until the user saves the new bindings or skips it (Escape, closing
+    /// the window), which keeps the default bindings.
+    pub fn run(&mut self, sdl: &Sdl, canvas: &mut WindowCanvas) -> Option<KeyMap> {
+        let mut event_pump = sdl.event_pump().unwrap();
+
+        loop {
+            for evt in event_pump.poll_iter() {
+                match evt {
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => return None,
+
+                    // back one step, or start over from the test screen
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Backspace),
+                        ..
+                    } => {
+                        self.step = if self.step == Self::steps() {
+                            0
+                        } else {
+                            self.step.saturating_sub(1)
+                        };
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return),
+                        ..
+                    } if self.step == Self::steps() => return Some(self.key_map.clone()),
+
+                    Event::KeyDown {
+                        keycode: Some(key),
+                        scancode,
+                        repeat: false,
+                        ..
+                    } => self.key_down(key, scancode),
+
+                    Event::KeyUp {
+                        keycode: Some(key),
+                        scancode,
+                        ..
+                    } => {
+                        if let Some(button) = self.key_map.button(key, scancode) {
+                            self.pressed[button] = false;
+                        }
+                    }
+
+                    _ => (),
+                }
+            }
+
+            self.draw(canvas);
+        }
+    }
+
+    fn key_down(&mut self, key: Keycode, scancode: Option<Scancode>) {
+        if self.step == Self::steps() {
+            if let Some(button) = self.key_map.button(key, scancode) {
+                self.pressed[button] = true;
+            }
+
+            return;
+        }
+
+        if RESERVED_KEYS.contains(&key) {
+            return;
+        }
+
+        // with physical bindings a keypad key is stored by where it sits, not what it types
+        let key = match KEYPAD_LAYOUT.get(self.step) {
+            Some(_) => self.key_map.keypad_binding(key, scancode),
+            None => key,
+        };
+
+        if self.bound_before_step(key) {
+            return;
+        }
+
+        match KEYPAD_LAYOUT.get(self.step) {
+            Some(button) => self.key_map.set_keypad_key(*button, key),
+            None => {
+                let action = Action::ALL[self.step - KEYPAD_LAYOUT.len()];
+                self.key_map.set_action_key(action, key);
+            }
+        }
+
+        self.step += 1;
+    }
+
+    // a key can only do one thing, the steps after the current one still hold defaults
+    fn bound_before_step(&self, key: Keycode) -> bool {
+        (0..self.step).any(|step| self.step_key(step) == key)
+    }
+
+    fn step_key(&self, step: usize) -> Keycode {
+        match KEYPAD_LAYOUT.get(step) {
+            Some(button) => self.key_map.keypad_key(*button),
+            None => self.key_map.action_key(Action::ALL[step - KEYPAD_LAYOUT.len()]),
+        }
+    }
+
+    fn draw(&self, canvas: &mut WindowCanvas) {
+        let (window_width, window_height) = canvas.output_size().unwrap();
+
+        canvas.set_draw_color(BLACK_COLOR);
+        canvas.clear();
+
+        canvas.set_draw_color(WHITE_COLOR);
+        hud::draw_text(canvas, "KEY SETUP", MARGIN as i32, MARGIN as i32, TITLE_SCALE);
+
+        let testing = self.step == Self::steps();
+
+        let (prompt, hint) = if testing {
+            (
+                "PRESS KEYS TO TEST THEM".to_string(),
+                "RETURN: SAVE   BACKSPACE: START OVER",
+            )
+        } else {
+            let target = match KEYPAD_LAYOUT.get(self.step) {
+                Some(button) => format!("KEYPAD {button:X}"),
+                None => Action::ALL[self.step - KEYPAD_LAYOUT.len()].name().to_string(),
+            };
+
+            (
+                format!("PRESS A KEY FOR {target}"),
+                "BACKSPACE: BACK   ESC: KEEP THE DEFAULT KEYS",
+            )
+        };
+
+        let line_height = (hud::GLYPH_HEIGHT + 2) * TEXT_SCALE;
+        let prompt_top = MARGIN * 2 + hud::GLYPH_HEIGHT * TITLE_SCALE;
+
+        hud::draw_text(canvas, &prompt, MARGIN as i32, prompt_top as i32, TEXT_SCALE);
+
+        canvas.set_draw_color(DIM_COLOR);
+        hud::draw_text(
+            canvas,
+            hint,
+            MARGIN as i32,
+            (window_height - MARGIN - hud::GLYPH_HEIGHT * TEXT_SCALE) as i32,
+            TEXT_SCALE,
+        );
+
+        // 4x4 keypad grid with the bound key names, filling the space between the texts
+        let grid_top = prompt_top + line_height * 2;
+        let grid_bottom = window_height.saturating_sub(MARGIN * 2 + line_height);
+        let cell_width = window_width.saturating_sub(MARGIN * 2) / 4;
+        let cell_height = grid_bottom.saturating_sub(grid_top) / 4;
+
+        for (cell, button) in KEYPAD_LAYOUT.iter().enumerate() {
+            let x = MARGIN + (cell % 4) as u32 * cell_width;
+            let y = grid_top + (cell / 4) as u32 * cell_height;
+            let rect = Rect::new(
+                x as i32,
+                y as i32,
+                cell_width.saturating_sub(4).max(1),
+                cell_height.saturating_sub(4).max(1),
+            );
+
+            let current = !testing && cell == self.step;
+            let lit = testing && self.pressed[*button];
+
+            canvas.set_draw_color(if current || lit { WHITE_COLOR } else { DIM_COLOR });
+            canvas.draw_rect(rect).unwrap();
+
+            if lit {
+                canvas.fill_rect(rect).unwrap();
+            }
+
+            let label = if cell < self.step {
+                format!("{button:X} {}", self.key_map.keypad_key(*button).name())
+            } else {
+                format!("{button:X}")
+            };
+
+            canvas.set_draw_color(if lit { BLACK_COLOR } else { WHITE_COLOR });
+            hud::draw_text(
+                canvas,
+                &label,
+                (x + TEXT_SCALE * 2) as i32,
+                (y + TEXT_SCALE * 2) as i32,
+                TEXT_SCALE,
+            );
+        }
+
+        canvas.present();
+    }
+}
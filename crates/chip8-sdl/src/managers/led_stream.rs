@@ -0,0 +1,144 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::{TcpStream, UdpSocket};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chip8_core::core::Chip8;
+use chip8_core::resolution::Resolution;
+
+// starts every frame, so a device that came up mid-stream finds the next one
+const MAGIC: [u8; 2] = *b"C8";
+
+// an unchanged screen is sent again this often, for panels that were reset or plugged in late
+const RESEND_INTERVAL: Duration = Duration::from_secs(1);
+
+// a write taking longer than this means the device is gone
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Mirrors the screen on real hardware, e.g. an LED matrix panel driven by a
+/// microcontroller, over a serial port, a TCP connection or UDP datagrams (`--led`).
+///
+/// Protocol, a message per frame and nothing ever read back:
+/// - the two bytes `C8`
+/// - the big endian u16 width and height of the screen
+/// - the pixels row major, top row first, 8 to a byte with the leftmost in the top bit;
+///   `width * height / 8` bytes, 256 for the usual 64x32 screen
+///
+/// Frames are only sent when the screen changed, or every second when it didn't. A device
+/// slower than the game (a serial port at 115200 baud manages about 40 frames per second of
+/// 64x32) skips frames instead of falling behind.
+pub struct LedStream {
+    sender: SyncSender<Vec<u8>>,
+    screen: Vec<bool>,
+    resolution: Resolution,
+    sent_at: Instant,
+}
+
+impl LedStream {
+    /// Opens `target`: `tcp:HOST:PORT`, `udp:HOST:PORT` or the path of a serial device,
+    /// whose baud rate is set outside of the emulator (e.g. `stty -F /dev/ttyUSB0 115200 raw`).
+    pub fn open(target: &str) -> std::io::Result<Self> {
+        let mut output: Box<dyn Write + Send> = if let Some(addr) = target.strip_prefix("tcp:") {
+            let stream = TcpStream::connect(addr)?;
+
+            stream.set_nodelay(true)?;
+            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+            Box::new(stream)
+        } else if let Some(addr) = target.strip_prefix("udp:") {
+            let socket = UdpSocket::bind("0.0.0.0:0")?;
+
+            socket.connect(addr)?;
+
+            Box::new(UdpWriter(socket))
+        } else {
+            Box::new(OpenOptions::new().write(true).open(target)?)
+        };
+
+        println!("Streaming the screen to {target}");
+
+        // room for a single frame, a newer one is dropped while the device is busy
+        let (sender, receiver) = mpsc::sync_channel(1);
+
+        thread::spawn(move || Self::send(&mut output, receiver));
+
+        Ok(Self {
+            sender,
+            screen: Vec::new(),
+            resolution: Resolution::default(),
+            sent_at: Instant::now(),
+        })
+    }
+
+    /// Sends the screen when it changed. Without `dirty` (nothing drew since the last call)
+    /// it isn't even compared, only resent once in a while.
+    pub fn publish(&mut self, chip8: &Chip8, dirty: bool) {
+        if !dirty && self.sent_at.elapsed() < RESEND_INTERVAL {
+            return;
+        }
+
+        let screen = chip8.get_screen();
+        let resolution = chip8.resolution();
+
+        if screen == self.screen.as_slice()
+            && resolution == self.resolution
+            && self.sent_at.elapsed() < RESEND_INTERVAL
+        {
+            return;
+        }
+
+        // a full channel or a stopped thread both drop the frame, the output is best effort
+        if self
+            .sender
+            .try_send(Self::encode_frame(screen, resolution))
+            .is_ok()
+        {
+            self.screen = screen.to_vec();
+            self.resolution = resolution;
+            self.sent_at = Instant::now();
+        }
+    }
+
+    fn send(output: &mut Box<dyn Write + Send>, receiver: Receiver<Vec<u8>>) {
+        for frame in receiver {
+            if let Err(err) = output.write_all(&frame).and_then(|_| output.flush()) {
+                println!("LED output stopped: {err}");
+                return;
+            }
+        }
+    }
+
+    fn encode_frame(screen: &[bool], resolution: Resolution) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+
+        data.extend_from_slice(&(resolution.width as u16).to_be_bytes());
+        data.extend_from_slice(&(resolution.height as u16).to_be_bytes());
+
+        for pixels in screen.chunks(8) {
+            let byte = pixels
+                .iter()
+                .enumerate()
+                .filter(|(_, on)| **on)
+                .fold(0u8, |byte, (bit, _)| byte | 0x80 >> bit);
+
+            data.push(byte);
+        }
+
+        data
+    }
+}
+
+// a datagram per frame, so a lost one never shifts the next
+struct UdpWriter(UdpSocket);
+
+impl Write for UdpWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+
+use crate::managers::config::Config;
+use crate::managers::rom_library::{Provenance, RomLibrary};
+
+/// `chip8-emulator library sync INDEX [DIR]` downloads the games listed in a ROM index into
+/// `DIR` (the configured ROM directory unless given) and records where each came from in the game library,
+/// with its tags. `INDEX` is a URL or a file, a JSON manifest:
+///
+/// ```json
+/// {
+///     "roms": [
+///         {
+///             "file": "maze.ch8",
+///             "url": "https://example.org/roms/maze.ch8",
+///             "sha1": "3f9a...",
+///             "title": "Maze",
+///             "author": "David Winter",
+///             "license": "public domain",
+///             "tags": ["demo"]
+///         }
+///     ]
+/// }
+/// ```
+///
+/// A `url` may be relative to the index. Every download is checked against its SHA-1
+/// before it is written; a game already in `DIR` is kept and only its record updated, and
+/// a different file of the same name is never overwritten. URLs are fetched with `curl`.
+pub fn sync(index: &str, dir: Option<&str>) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or(&Config::get().rom_dir));
+
+    let manifest = fetch(index)?;
+    let manifest: Value =
+        serde_json::from_slice(&manifest).map_err(|err| format!("{index}: {err}"))?;
+    let entries = manifest["roms"]
+        .as_array()
+        .ok_or(format!("{index} lists no roms"))?;
+
+    fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+
+    let mut library = RomLibrary::load();
+    let (mut added, mut present, mut failed) = (0, 0, 0);
+
+    for entry in entries {
+        let name = entry["file"].as_str().unwrap_or("?");
+
+        match sync_rom(entry, index, dir, &mut library) {
+            Ok(true) => {
+                println!("Added {name}");
+                added += 1;
+            }
+            Ok(false) => present += 1,
+            Err(err) => {
+                println!("Skipping {name}: {err}");
+                failed += 1;
+            }
+        }
+    }
+
+    library.save();
+
+    println!("{added} added, {present} already there, {failed} failed");
+
+    if failed > 0 {
+        return Err(format!(
+            "{failed} of {} roms could not be synced",
+            entries.len()
+        ));
+    }
+
+    Ok(())
+}
+
+// downloads a game unless it is there already and records it, returns whether it was added
+fn sync_rom(
+    entry: &Value,
+    index: &str,
+    dir: &Path,
+    library: &mut RomLibrary,
+) -> Result<bool, String> {
+    let field = |name: &str| entry[name].as_str().unwrap_or_default().to_string();
+
+    let file = field("file");
+
+    // a name from the internet doesn't get to pick the directory
+    if file.is_empty()
+        || file.starts_with('.')
+        || file.contains(['/', '\\'])
+        || Path::new(&file).file_name().is_none()
+    {
+        return Err("no valid file name".to_string());
+    }
+
+    let sha1 = field("sha1").to_ascii_lowercase();
+
+    if sha1.len() != 40 {
+        return Err("no SHA-1 to check the download against".to_string());
+    }
+
+    let url = field("url");
+
+    if url.is_empty() {
+        return Err("no url".to_string());
+    }
+
+    let url = resolve(index, &url);
+    let path = dir.join(&file);
+
+    let (rom, added) = match fs::read(&path) {
+        Ok(rom) if hex_sha1(&rom) == sha1 => (rom, false),
+        Ok(_) => return Err(format!("{} is another game", path.display())),
+        Err(_) => {
+            let rom = fetch(&url)?;
+            let digest = hex_sha1(&rom);
+
+            if digest != sha1 {
+                return Err(format!("SHA-1 {digest} doesn't match the index"));
+            }
+
+            fs::write(&path, &rom).map_err(|err| format!("{}: {err}", path.display()))?;
+
+            (rom, true)
+        }
+    };
+
+    let key = RomLibrary::key(&rom);
+
+    for tag in entry["tags"].as_array().into_iter().flatten() {
+        if let Some(tag) = tag.as_str() {
+            library.add_tag(&key, tag);
+        }
+    }
+
+    library.set_provenance(
+        &key,
+        Provenance {
+            title: field("title"),
+            author: field("author"),
+            license: field("license"),
+            url,
+            index: index.to_string(),
+        },
+    );
+
+    Ok(added)
+}
+
+// `url` of an index entry, made absolute: relative ones are next to the index
+fn resolve(index: &str, url: &str) -> String {
+    if url.contains("://") || Path::new(url).is_absolute() {
+        return url.to_string();
+    }
+
+    match index.rfind('/') {
+        Some(end) => format!("{}/{url}", &index[..end]),
+        None => url.to_string(),
+    }
+}
+
+/// The contents of a URL or a file.
+pub fn fetch(location: &str) -> Result<Vec<u8>, String> {
+    if !location.starts_with("http://") && !location.starts_with("https://") {
+        let path = location.strip_prefix("file://").unwrap_or(location);
+
+        return fs::read(path).map_err(|err| format!("{path}: {err}"));
+    }
+
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", location])
+        .output()
+        .map_err(|err| format!("Could not run curl to download {location}: {err}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{location}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+fn hex_sha1(data: &[u8]) -> String {
+    Sha1::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -0,0 +1,156 @@
+use chip8_core::constants::RAM_SIZE;
+use chip8_core::memory_access::MemoryAccesses;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, WindowCanvas};
+
+use crate::managers::hud;
+
+// 64x64 cells, one per address, each row is 64 bytes
+const COLUMNS: usize = 64;
+const ROWS: usize = RAM_SIZE / COLUMNS;
+
+// share of the heat left after a frame, about a quarter second to halve
+const HEAT_DECAY: f32 = 0.95;
+
+const BACKGROUND_COLOR: Color = Color::RGBA(0, 0, 0, 220);
+const LOADED_COLOR: Color = Color::RGB(40, 40, 40); // non zero memory nobody touched lately
+const LABEL_COLOR: Color = Color::RGB(120, 120, 120);
+
+const READ_COLOR: Color = Color::RGB(0, 255, 0);
+const WRITE_COLOR: Color = Color::RGB(255, 0, 0);
+const EXECUTE_COLOR: Color = Color::RGB(0, 96, 255);
+
+/// Debug view of the 4KB address space (F4): a cell per address, green where it was read
+/// lately, red where it was written and blue where it was executed, fading out over a few
+/// frames, so the code, the data and self-modifying parts of a ROM stand out while it runs.
+pub struct MemoryHeatmap {
+    reads: Vec<f32>,
+    writes: Vec<f32>,
+    executes: Vec<f32>,
+}
+
+impl Default for MemoryHeatmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryHeatmap {
+    pub fn new() -> Self {
+        Self {
+            reads: vec![0.0; RAM_SIZE],
+            writes: vec![0.0; RAM_SIZE],
+            executes: vec![0.0; RAM_SIZE],
+        }
+    }
+
+    /// Cools everything down by a frame and heats up the addresses accessed meanwhile.
+    pub fn update(&mut self, accesses: &MemoryAccesses) {
+        for (heat, counts) in [
+            (&mut self.reads, &accesses.reads),
+            (&mut self.writes, &accesses.writes),
+            (&mut self.executes, &accesses.executes),
+        ] {
+            for (heat, count) in heat.iter_mut().zip(counts) {
+                *heat = if *count > 0 { 1.0 } else { *heat * HEAT_DECAY };
+            }
+        }
+    }
+
+    /// Size of the grid drawn with `cell` pixel cells, its labels included.
+    pub fn size(cell: u32, text_scale: u32) -> (u32, u32) {
+        let label_width = hud::text_width("000 ", text_scale);
+        let legend_height = (hud::GLYPH_HEIGHT + 2) * text_scale;
+
+        (
+            label_width + COLUMNS as u32 * cell,
+            ROWS as u32 * cell + legend_height,
+        )
+    }
+
+    /// The largest cell size at which the grid fits in `width` x `height` pixels.
+    pub fn fit_cell(width: u32, height: u32, text_scale: u32) -> u32 {
+        let (fixed_width, fixed_height) = Self::size(0, text_scale);
+
+        (width.saturating_sub(fixed_width) / COLUMNS as u32)
+            .min(height.saturating_sub(fixed_height) / ROWS as u32)
+            .max(1)
+    }
+
+    /// Draws the grid with its top left corner at (`x`, `y`), see `size`.
+    pub fn draw(
+        &self,
+        canvas: &mut WindowCanvas,
+        x: i32,
+        y: i32,
+        cell: u32,
+        text_scale: u32,
+        memory: &[u8],
+    ) {
+        let (width, height) = Self::size(cell, text_scale);
+        let grid_x = x + hud::text_width("000 ", text_scale) as i32;
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(BACKGROUND_COLOR);
+        canvas.fill_rect(Rect::new(x, y, width, height)).unwrap();
+
+        // XO-CHIP's memory beyond 4KB isn't shown
+        for (addr, value) in memory.iter().take(RAM_SIZE).enumerate() {
+            let heat = |channel: f32| (channel * 255.0) as u8;
+
+            let mut color = Color::RGB(
+                heat(self.writes[addr]),
+                heat(self.reads[addr]),
+                heat(self.executes[addr]),
+            );
+
+            if color == Color::RGB(0, 0, 0) {
+                if *value == 0 {
+                    continue;
+                }
+
+                color = LOADED_COLOR;
+            }
+
+            let col = (addr % COLUMNS) as i32;
+            let row = (addr / COLUMNS) as i32;
+
+            canvas.set_draw_color(color);
+            canvas
+                .fill_rect(Rect::new(
+                    grid_x + col * cell as i32,
+                    y + row * cell as i32,
+                    cell,
+                    cell,
+                ))
+                .unwrap();
+        }
+
+        // the address of every 16th row, 0x400 bytes apart
+        canvas.set_draw_color(LABEL_COLOR);
+
+        for row in (0..ROWS).step_by(16) {
+            hud::draw_text(
+                canvas,
+                &format!("{:03X}", row * COLUMNS),
+                x,
+                y + (row as u32 * cell) as i32,
+                text_scale,
+            );
+        }
+
+        let legend_y = y + (ROWS as u32 * cell + text_scale) as i32;
+        let mut legend_x = grid_x;
+
+        for (text, color) in [
+            ("READ ", READ_COLOR),
+            ("WRITE ", WRITE_COLOR),
+            ("EXEC", EXECUTE_COLOR),
+        ] {
+            canvas.set_draw_color(color);
+            hud::draw_text(canvas, text, legend_x, legend_y, text_scale);
+            legend_x += hud::text_width(text, text_scale) as i32;
+        }
+    }
+}
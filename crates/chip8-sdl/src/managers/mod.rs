@@ -0,0 +1,39 @@
+pub mod bios;
+pub mod comparison;
+pub mod config;
+pub mod debugger;
+pub mod desktop_manager;
+pub mod frame_history;
+pub mod gamepad;
+pub mod headless_manager;
+pub mod hud;
+pub mod input;
+pub mod input_test;
+pub mod key_layout;
+pub mod key_map;
+pub mod key_setup;
+pub mod led_stream;
+pub mod library_sync;
+pub mod memory_heatmap;
+pub mod movie;
+pub mod opcode_quiz;
+pub mod orientation;
+pub mod palette;
+pub mod palette_editor;
+pub mod play_stats;
+pub mod post_effects;
+pub mod replay_check;
+pub mod rewind;
+pub mod rom_browser;
+pub mod rom_editor;
+pub mod rom_library;
+pub mod screenshots;
+pub mod session;
+pub mod skin;
+pub mod sound_events;
+pub mod signature;
+pub mod spectator_server;
+pub mod sprite_editor;
+pub mod update_check;
+pub mod verifier;
+pub mod view_preset;
@@ -0,0 +1,445 @@
+use chip8_core::clock::Clock;
+use chip8_core::constants::NUM_KEYS;
+use chip8_core::core::Chip8;
+use chip8_core::quirks::Quirks;
+use chip8_core::random::RandomSource;
+use chip8_core::savestate::SaveState;
+use chip8_tools::file_io;
+use sha1::{Digest, Sha1};
+
+use crate::managers::rom_library::RomLibrary;
+
+const MAGIC: &[u8; 4] = b"C8MV";
+const VERSION: u8 = 2; // version 1 had no quirks, random sources or ending
+
+const HASH_SIZE: usize = 20;
+
+// full machine state at the start of a frame, playback can start from any of them
+struct Keyframe {
+    frame: u32,
+    state: SaveState,
+    rng_state: u64,
+    instructions_per_frame: f64,
+    clock_budget: f64,
+    quirks: Option<Quirks>, // None in version 1 movies, which use the game library's
+    random_source: Option<RandomSource>,
+}
+
+impl Keyframe {
+    fn capture(frame: u32, chip8: &Chip8, clock: &Clock) -> Self {
+        Self {
+            frame,
+            state: chip8.save_state(),
+            rng_state: chip8.get_rng_state(),
+            instructions_per_frame: clock.instructions_per_frame(),
+            clock_budget: clock.budget(),
+            quirks: Some(chip8.get_quirks()),
+            random_source: Some(chip8.random_source()),
+        }
+    }
+
+    fn restore(&self, chip8: &mut Chip8, clock: &mut Clock) {
+        chip8.load_state(&self.state);
+
+        if let Some(quirks) = self.quirks {
+            chip8.set_quirks(quirks);
+        }
+
+        // before the state, picking a source reseeds it
+        if let Some(random_source) = self.random_source {
+            chip8.set_random_source(random_source);
+        }
+
+        chip8.set_rng_state(self.rng_state);
+
+        // fast forwarding is up to the viewer, not part of the movie
+        let speed = clock.speed();
+        let realtime_timers = clock.realtime_timers();
+
+        *clock = Clock::from_ipf(self.instructions_per_frame);
+        clock.set_budget(self.clock_budget);
+        clock.set_speed(speed);
+        clock.set_realtime_timers(realtime_timers);
+    }
+
+    fn state_hash(&self) -> [u8; HASH_SIZE] {
+        state_hash(&self.state, self.rng_state)
+    }
+}
+
+// what a machine is fully determined by, hashed to compare replays with the recording
+fn state_hash(state: &SaveState, rng_state: u64) -> [u8; HASH_SIZE] {
+    let mut hasher = Sha1::new();
+
+    hasher.update(state.to_bytes());
+    hasher.update(rng_state.to_be_bytes());
+
+    hasher.finalize().into()
+}
+
+fn machine_hash(chip8: &Chip8) -> [u8; HASH_SIZE] {
+    state_hash(&chip8.save_state(), chip8.get_rng_state())
+}
+
+// where the recording stopped, so a replay can be checked to end up there too
+struct Ending {
+    rom_key: String, // see RomLibrary::key
+    state_hash: [u8; HASH_SIZE],
+}
+
+/// A `.c8m` movie: the keypad state of every frame plus keyframes, full machine states taken
+/// every few seconds and whenever the game was changed by something other than the keypad
+/// (loading a state, the ROM editor...). Playback restores keyframes as it reaches them and
+/// seeking replays frames headless from the nearest keyframe before the target, so both are
+/// frame exact. A finished recording also stores the game and a hash of the machine after
+/// its last frame, which `divergence` checks a replay against.
+///
+/// Layout (all numbers big endian):
+///
+/// ```text
+/// "C8MV"              magic
+/// u8                  version (2)
+/// u32                 number of frames
+/// u16 per frame       keypad state, bit n set while key n is down
+/// u32                 number of keyframes
+/// per keyframe:
+///     u32             frame
+///     u64             random number generator state
+///     f64             instructions per frame
+///     f64             unspent instruction budget
+///     u32             save state length
+///     [u8]            save state in the native format, packed like `file_io::pack_state`
+///     u8 + [u8]       quirks, as `Quirks::names` writes them (version 2)
+///     u8 + [u8]       random source, as `RandomSource::name` writes it (version 2)
+/// u8                  1 when the ending follows, 0 otherwise (version 2)
+/// u8 + [u8]           key of the game in the game library
+/// [u8; 20]            SHA-1 of the save state and random number generator state at the end
+/// ```
+pub struct Movie {
+    inputs: Vec<u16>,
+    keyframes: Vec<Keyframe>, // sorted by frame, the first one is at frame 0
+    keyframe_interval: u32,
+    resync: bool, // the next recorded frame needs a keyframe
+    ending: Option<Ending>,
+}
+
+impl Movie {
+    /// An empty movie for recording, with a keyframe every `keyframe_interval` frames.
+    pub fn new(keyframe_interval: u32) -> Self {
+        Self {
+            inputs: Vec::new(),
+            keyframes: Vec::new(),
+            keyframe_interval: keyframe_interval.max(1),
+            resync: true,
+            ending: None,
+        }
+    }
+
+    /// Number of frames.
+    pub fn len(&self) -> u32 {
+        self.inputs.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// The machine changed outside of the keypad, store its state with the next frame.
+    pub fn resync(&mut self) {
+        self.resync = true;
+    }
+
+    /// Records the frame about to run, call before running it.
+    pub fn record_frame(&mut self, chip8: &Chip8, clock: &Clock) {
+        let frame = self.len();
+
+        if self.resync || frame.is_multiple_of(self.keyframe_interval) {
+            self.keyframes.push(Keyframe::capture(frame, chip8, clock));
+            self.resync = false;
+        }
+
+        let keys = chip8
+            .get_keys()
+            .iter()
+            .enumerate()
+            .fold(0u16, |keys, (key, down)| keys | ((*down as u16) << key));
+
+        self.inputs.push(keys);
+    }
+
+    /// Stores where the recording ends: `rom` being played and the machine after the last
+    /// recorded frame. Call once recording stops, before saving.
+    pub fn finish(&mut self, chip8: &Chip8, rom: &[u8]) {
+        self.ending = Some(Ending {
+            rom_key: RomLibrary::key(rom),
+            state_hash: machine_hash(chip8),
+        });
+    }
+
+    /// Whether the movie stores where it ends, see `finish`. Older movies don't.
+    pub fn is_finished(&self) -> bool {
+        self.ending.is_some()
+    }
+
+    /// Whether the recording was finished on `rom`.
+    pub fn recorded_on(&self, rom: &[u8]) -> bool {
+        self.ending
+            .as_ref()
+            .is_some_and(|ending| ending.rom_key == RomLibrary::key(rom))
+    }
+
+    fn keyframe(&self, frame: u32) -> Option<&Keyframe> {
+        self.keyframes
+            .binary_search_by_key(&frame, |keyframe| keyframe.frame)
+            .ok()
+            .map(|idx| &self.keyframes[idx])
+    }
+
+    /// Sets up the machine for playing `frame`: restores the keyframe recorded with it, if
+    /// any, and presses the recorded keys. The caller then runs the frame as usual.
+    /// Returns whether a keyframe was restored.
+    pub fn prepare_frame(&self, frame: u32, chip8: &mut Chip8, clock: &mut Clock) -> bool {
+        let keyframe = self.keyframe(frame);
+
+        if let Some(keyframe) = keyframe {
+            keyframe.restore(chip8, clock);
+        }
+
+        self.press_keys(frame, chip8);
+
+        keyframe.is_some()
+    }
+
+    fn press_keys(&self, frame: u32, chip8: &mut Chip8) {
+        let keys = self.inputs.get(frame as usize).copied().unwrap_or(0);
+
+        for key in 0..NUM_KEYS {
+            chip8.keypress(key, keys & (1 << key) != 0);
+        }
+    }
+
+    /// Replays the movie headless from its start on the recorded input alone and returns
+    /// the first frame at which the machine doesn't match the recording: a keyframe whose
+    /// state it doesn't have, or the frame after the last one when it doesn't end in the
+    /// recorded final state. Keyframes the replay matches only pass on speed changes.
+    pub fn divergence(&self) -> Option<u32> {
+        let mut chip8 = Chip8::new();
+        let mut clock = Clock::default();
+
+        chip8.set_sound_enabled(false);
+
+        for frame in 0..self.len() {
+            match self.keyframe(frame) {
+                Some(keyframe) if frame == 0 || keyframe.state_hash() == machine_hash(&chip8) => {
+                    self.prepare_frame(frame, &mut chip8, &mut clock);
+                }
+                Some(_) => return Some(frame),
+                None => self.press_keys(frame, &mut chip8),
+            }
+
+            // a game that crashed stays stopped on the instruction, like when it was played
+            let _ = chip8.run_frame(clock.ticks_for_frame());
+        }
+
+        match &self.ending {
+            Some(ending) if ending.state_hash != machine_hash(&chip8) => Some(self.len()),
+            _ => None,
+        }
+    }
+
+    /// Puts the machine in the state it had at the start of `frame` (clamped to the end of
+    /// the movie) and returns that frame.
+    pub fn seek(&self, frame: u32, chip8: &mut Chip8, clock: &mut Clock) -> u32 {
+        let frame = frame.min(self.len());
+
+        let start = match self
+            .keyframes
+            .iter()
+            .rev()
+            .find(|keyframe| keyframe.frame <= frame)
+        {
+            Some(keyframe) => keyframe.frame,
+            None => return 0,
+        };
+
+        // no beeping while fast forwarding
+        chip8.set_sound_enabled(false);
+
+        for played in start..frame {
+            self.prepare_frame(played, chip8, clock);
+            let _ = chip8.run_frame(clock.ticks_for_frame());
+        }
+
+        chip8.set_sound_enabled(true);
+
+        // the keyframe at the target itself, or the one the loop above started from
+        self.prepare_frame(frame, chip8, clock);
+
+        frame
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+
+        data.extend_from_slice(&self.len().to_be_bytes());
+        for keys in &self.inputs {
+            data.extend_from_slice(&keys.to_be_bytes());
+        }
+
+        data.extend_from_slice(&(self.keyframes.len() as u32).to_be_bytes());
+        for keyframe in &self.keyframes {
+            let state = file_io::pack_state(keyframe.state.to_bytes());
+
+            data.extend_from_slice(&keyframe.frame.to_be_bytes());
+            data.extend_from_slice(&keyframe.rng_state.to_be_bytes());
+            data.extend_from_slice(&keyframe.instructions_per_frame.to_be_bytes());
+            data.extend_from_slice(&keyframe.clock_budget.to_be_bytes());
+            data.extend_from_slice(&(state.len() as u32).to_be_bytes());
+            data.extend_from_slice(&state);
+
+            let quirks = keyframe.quirks.unwrap_or_default().names();
+            let random_source = keyframe.random_source.unwrap_or_default().name();
+
+            for text in [quirks, random_source] {
+                data.push(text.len() as u8);
+                data.extend_from_slice(text.as_bytes());
+            }
+        }
+
+        match &self.ending {
+            Some(ending) => {
+                data.push(1);
+                data.push(ending.rom_key.len() as u8);
+                data.extend_from_slice(ending.rom_key.as_bytes());
+                data.extend_from_slice(&ending.state_hash);
+            }
+            None => data.push(0),
+        }
+
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if !data.starts_with(MAGIC) {
+            return Err("not a chip8-emulator movie".to_string());
+        }
+
+        let version = *data.get(MAGIC.len()).ok_or("movie is truncated")?;
+
+        if !(1..=VERSION).contains(&version) {
+            return Err(format!("unsupported movie version {version}"));
+        }
+
+        let mut reader = Reader {
+            data,
+            pos: MAGIC.len() + 1,
+        };
+
+        let frames = reader.u32()?;
+        let inputs = (0..frames)
+            .map(|_| reader.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]])))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let count = reader.u32()?;
+        let mut keyframes: Vec<Keyframe> = Vec::new();
+
+        for _ in 0..count {
+            let frame = reader.u32()?;
+            let rng_state = reader.u64()?;
+            let instructions_per_frame = f64::from_bits(reader.u64()?);
+            let clock_budget = f64::from_bits(reader.u64()?);
+            let len = reader.u32()? as usize;
+            let state = file_io::decompress(reader.bytes(len)?.to_vec())
+                .map_err(|err| err.to_string())
+                .and_then(|state| SaveState::from_bytes(&state).map_err(|err| err.to_string()))?;
+
+            let (quirks, random_source) = if version >= 2 {
+                let quirks = reader.text()?;
+                let random_source = reader.text()?;
+
+                (
+                    Some(Quirks::parse(&quirks).ok_or(format!("unknown quirks {quirks}"))?),
+                    Some(
+                        RandomSource::parse(&random_source)
+                            .ok_or(format!("unknown random source {random_source}"))?,
+                    ),
+                )
+            } else {
+                (None, None)
+            };
+
+            if keyframes.last().is_some_and(|last| last.frame >= frame) {
+                return Err("movie keyframes are out of order".to_string());
+            }
+
+            keyframes.push(Keyframe {
+                frame,
+                state,
+                rng_state,
+                instructions_per_frame,
+                clock_budget,
+                quirks,
+                random_source,
+            });
+        }
+
+        let ending = match version {
+            2.. if reader.bytes(1)?[0] == 1 => Some(Ending {
+                rom_key: reader.text()?,
+                state_hash: reader.bytes(HASH_SIZE)?.try_into().unwrap(),
+            }),
+            _ => None,
+        };
+
+        if keyframes.first().map(|keyframe| keyframe.frame) != Some(0) {
+            return Err("movie has no keyframe at its start".to_string());
+        }
+
+        Ok(Self {
+            inputs,
+            keyframes,
+            keyframe_interval: 1,
+            resync: false,
+            ending,
+        })
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or("movie is truncated")?;
+
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.bytes(4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        self.bytes(8)
+            .map(|b| u64::from_be_bytes(b.try_into().unwrap()))
+    }
+
+    // a byte of length and that much UTF-8
+    fn text(&mut self) -> Result<String, String> {
+        let len = self.bytes(1)?[0] as usize;
+
+        String::from_utf8(self.bytes(len)?.to_vec())
+            .map_err(|_| "movie has invalid text".to_string())
+    }
+}
@@ -0,0 +1,169 @@
+use chip8_core::core::Chip8;
+use chip8_core::disassembler;
+use rand::seq::SliceRandom;
+
+const CHOICES: usize = 4;
+
+struct Question {
+    addr: u16,
+    op: u16,
+    choices: Vec<String>,
+    correct: usize,
+    selected: usize,
+    answered: bool,
+}
+
+/// Learning mode (`--learn`): before each instruction runs, shows its raw opcode and asks
+/// what it does, with the right description among ones of other instructions built from
+/// the same operands. Up/Down picks an answer, Return answers and Return again runs it.
+pub struct OpcodeQuiz {
+    question: Option<Question>,
+    asked: u32,
+    right: u32,
+}
+
+impl Default for OpcodeQuiz {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpcodeQuiz {
+    pub fn new() -> Self {
+        Self {
+            question: None,
+            asked: 0,
+            right: 0,
+        }
+    }
+
+    /// Asks about the instruction at PC unless a question is still open.
+    pub fn ask(&mut self, chip8: &Chip8) {
+        if self.question.is_some() {
+            return;
+        }
+
+        let addr = chip8.get_program_counter();
+        let op = disassembler::opcode_at(chip8.get_memory(), addr);
+        let answer = disassembler::describe(op);
+
+        let mut wrong: Vec<String> = Self::similar_opcodes(op)
+            .into_iter()
+            .map(disassembler::describe)
+            .filter(|description| *description != answer)
+            .collect();
+
+        wrong.sort();
+        wrong.dedup();
+
+        let mut rng = rand::thread_rng();
+        wrong.shuffle(&mut rng);
+
+        let mut choices: Vec<String> = wrong.into_iter().take(CHOICES - 1).collect();
+        choices.push(answer.clone());
+        choices.shuffle(&mut rng);
+
+        let correct = choices.iter().position(|choice| *choice == answer).unwrap();
+
+        self.question = Some(Question {
+            addr,
+            op,
+            choices,
+            correct,
+            selected: 0,
+            answered: false,
+        });
+    }
+
+    // one of each instruction, with the operands of `op`
+    fn similar_opcodes(op: u16) -> Vec<u16> {
+        let nnn = op & 0x0FFF;
+        let xy = op & 0x0FF0;
+        let x = op & 0x0F00;
+
+        let mut ops = vec![0x00E0, 0x00EE];
+
+        ops.extend(
+            [
+                0x1000, 0x2000, 0x3000, 0x4000, 0x6000, 0x7000, 0xA000, 0xB000, 0xC000, 0xD000,
+            ]
+            .map(|base| base | nnn),
+        );
+        ops.extend([0x5000, 0x9000].map(|base| base | xy));
+        ops.extend([0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0xE].map(|n| 0x8000 | xy | n));
+        ops.extend(
+            [
+                0xE09E, 0xE0A1, 0xF007, 0xF00A, 0xF015, 0xF018, 0xF01E, 0xF029, 0xF033, 0xF055,
+                0xF065,
+            ]
+            .map(|base| base | x),
+        );
+
+        ops
+    }
+
+    pub fn select(&mut self, step: isize) {
+        if let Some(question) = &mut self.question {
+            if !question.answered {
+                let last = question.choices.len() as isize - 1;
+                question.selected = (question.selected as isize + step).clamp(0, last) as usize;
+            }
+        }
+    }
+
+    /// The first call answers the question, the second one returns true: the instruction
+    /// should run now and the next one gets asked about.
+    pub fn confirm(&mut self) -> bool {
+        let Some(question) = &mut self.question else {
+            return false;
+        };
+
+        if !question.answered {
+            question.answered = true;
+
+            self.asked += 1;
+            self.right += (question.selected == question.correct) as u32;
+
+            return false;
+        }
+
+        self.question = None;
+
+        true
+    }
+
+    pub fn hud_lines(&self) -> Vec<String> {
+        let Some(question) = &self.question else {
+            return Vec::new();
+        };
+
+        let mut lines = vec![format!(
+            "{:03X}: {:04X}   SCORE {}/{}",
+            question.addr, question.op, self.right, self.asked
+        )];
+
+        for (i, choice) in question.choices.iter().enumerate() {
+            let marker = match (
+                question.answered,
+                i == question.selected,
+                i == question.correct,
+            ) {
+                (true, _, true) => "OK ",
+                (true, true, false) => "-- ",
+                (_, true, _) => ">  ",
+                _ => "   ",
+            };
+
+            lines.push(format!("{marker}{choice}"));
+        }
+
+        if question.answered {
+            lines.push(format!(
+                "   {}, RETURN RUNS IT",
+                disassembler::disassemble(question.op)
+            ));
+        }
+
+        lines
+    }
+}
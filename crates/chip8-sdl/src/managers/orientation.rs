@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::resolution::Resolution;
+use chip8_tools::file_io;
+
+// inside the config directory
+const ORIENTATION_FILE: &str = "orientation";
+
+/// How the screen is turned for unusual display mounts, e.g. a monitor on its side for
+/// vertical ("TATE") games: rotated clockwise by a multiple of 90 degrees, after an
+/// optional horizontal mirroring. Stored in the config directory as `rotate=90` and
+/// `mirror=true` lines.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Orientation {
+    rotation: u16, // 0, 90, 180 or 270 degrees
+    mirrored: bool,
+}
+
+impl Orientation {
+    fn path() -> PathBuf {
+        file_io::config_dir().join(ORIENTATION_FILE)
+    }
+
+    /// `rotation` is rounded down to a multiple of 90 degrees.
+    pub fn new(rotation: u16, mirrored: bool) -> Self {
+        Self {
+            rotation: rotation / 90 % 4 * 90,
+            mirrored,
+        }
+    }
+
+    /// The saved orientation, the default one when there is none.
+    pub fn load() -> Self {
+        let Ok(text) = fs::read_to_string(Self::path()) else {
+            return Self::default();
+        };
+
+        let mut orientation = Self::default();
+
+        for line in text.lines() {
+            match line
+                .split_once('=')
+                .map(|(name, value)| (name.trim(), value.trim()))
+            {
+                Some(("rotate", degrees)) => match degrees.parse() {
+                    Ok(degrees) => orientation = Self::new(degrees, orientation.mirrored),
+                    Err(_) => println!("Ignoring invalid rotation: {line}"),
+                },
+                Some(("mirror", mirrored)) => orientation.mirrored = mirrored == "true",
+                _ => (),
+            }
+        }
+
+        orientation
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        fs::create_dir_all(file_io::config_dir())?;
+        fs::write(
+            Self::path(),
+            format!("rotate={}\nmirror={}\n", self.rotation, self.mirrored),
+        )
+    }
+
+    pub fn rotation(&self) -> u16 {
+        self.rotation
+    }
+
+    pub fn mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    /// Turned a further 90 degrees clockwise.
+    pub fn rotated(self) -> Self {
+        Self::new(self.rotation + 90, self.mirrored)
+    }
+
+    pub fn toggled_mirror(self) -> Self {
+        Self::new(self.rotation, !self.mirrored)
+    }
+
+    /// Whether the screen is upright, taller than wide.
+    pub fn is_portrait(&self) -> bool {
+        self.rotation == 90 || self.rotation == 270
+    }
+
+    /// Width and height of a screen of `resolution` once turned, in emulated pixels.
+    pub fn display_size(&self, resolution: Resolution) -> (usize, usize) {
+        let Resolution { width, height } = resolution;
+
+        if self.is_portrait() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Where the emulated pixel (x, y) of a screen of `resolution` ends up once turned.
+    pub fn map(&self, x: usize, y: usize, resolution: Resolution) -> (usize, usize) {
+        let Resolution { width, height } = resolution;
+
+        let x = if self.mirrored { width - 1 - x } else { x };
+
+        match self.rotation {
+            90 => (height - 1 - y, x),
+            180 => (width - 1 - x, height - 1 - y),
+            270 => (y, width - 1 - x),
+            _ => (x, y),
+        }
+    }
+}
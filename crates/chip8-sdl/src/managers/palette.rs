@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chip8_tools::file_io;
+use sdl2::pixels::Color;
+
+use crate::constants::{BLACK_COLOR, GRAY_COLOR, LIGHT_GRAY_COLOR, WHITE_COLOR};
+
+// inside the config directory
+const PALETTE_FILE: &str = "palette";
+
+/// The colors the screen is drawn with, stored as `name=RRGGBB` lines, e.g.
+/// `foreground=33FF66`, in the config directory or a theme file given with `--palette`.
+/// XO-CHIP games draw on two planes: `foreground2` is a pixel set only on the second and
+/// `blend` one set on both.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Palette {
+    pub background: Color,
+    pub foreground: Color,
+    pub foreground2: Color,
+    pub blend: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: BLACK_COLOR,
+            foreground: WHITE_COLOR,
+            foreground2: GRAY_COLOR,
+            blend: LIGHT_GRAY_COLOR,
+        }
+    }
+}
+
+impl Palette {
+    pub const NAMES: [&'static str; 4] = ["background", "foreground", "foreground2", "blend"];
+
+    pub const PRESETS: [&'static str; 4] = ["green", "amber", "lcd", "inverted"];
+
+    /// The built-in palettes by name: `green` (or `green-phosphor`) and `amber` monochrome
+    /// monitors, the `lcd` of early handhelds and the default `inverted`, black on white.
+    pub fn preset(name: &str) -> Option<Self> {
+        let [background, foreground, foreground2, blend] = match name {
+            "green" | "green-phosphor" => ["0A1A0A", "33FF66", "1A8033", "26BF4D"],
+            "amber" => ["1A0F00", "FFB000", "805800", "BF8400"],
+            "lcd" => ["9BBC0F", "0F380F", "6B8C2F", "306230"],
+            "inverted" => {
+                let default = Self::default();
+
+                return Some(Self {
+                    background: default.foreground,
+                    foreground: default.background,
+                    foreground2: default.blend,
+                    blend: default.foreground2,
+                });
+            }
+            _ => return None,
+        }
+        .map(|hex| parse_hex(hex).unwrap());
+
+        Some(Self {
+            background,
+            foreground,
+            foreground2,
+            blend,
+        })
+    }
+
+    pub fn config_path() -> PathBuf {
+        file_io::config_dir().join(PALETTE_FILE)
+    }
+
+    /// The palette saved at `path`, `None` when there is none. Unknown lines are ignored
+    /// and missing colors keep their default.
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+
+        let mut palette = Self::default();
+
+        for line in text.lines() {
+            let Some((name, hex)) = line.split_once('=') else {
+                continue;
+            };
+
+            let Some(color) = parse_hex(hex.trim()) else {
+                println!("Ignoring invalid color in palette: {line}");
+                continue;
+            };
+
+            if let Some(idx) = Self::NAMES.iter().position(|known| *known == name.trim()) {
+                *palette.color_mut(idx) = color;
+            }
+        }
+
+        Some(palette)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut text = String::new();
+
+        for (idx, name) in Self::NAMES.iter().enumerate() {
+            text += &format!("{name}={}\n", to_hex(self.color(idx)));
+        }
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, text)
+    }
+
+    /// Colors by index, in NAMES order. The index of a pixel's color has a bit per plane
+    /// it is set on.
+    pub fn color(&self, idx: usize) -> Color {
+        match idx {
+            0 => self.background,
+            1 => self.foreground,
+            2 => self.foreground2,
+            _ => self.blend,
+        }
+    }
+
+    pub fn color_mut(&mut self, idx: usize) -> &mut Color {
+        match idx {
+            0 => &mut self.background,
+            1 => &mut self.foreground,
+            2 => &mut self.foreground2,
+            _ => &mut self.blend,
+        }
+    }
+}
+
+/// `RRGGBB`, with or without a leading `#`.
+pub fn parse_hex(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+
+    Some(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+pub fn to_hex(color: Color) -> String {
+    format!("{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+}
@@ -0,0 +1,141 @@
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+
+use crate::managers::palette::{self, Palette};
+
+const CHANNELS: [&str; 3] = ["R", "G", "B"];
+
+// one slider step of the arrow keys
+const STEP: u8 = 8;
+const SLIDER_WIDTH: usize = 16;
+
+/// Palette editor (F3): the game keeps running with the palette being edited, so every
+/// change shows on it right away. Up/Down picks a color channel and Left/Right slides it,
+/// or typing six hex digits sets the whole selected color. Return saves the palette.
+pub struct PaletteEditor {
+    row: usize,  // color * 3 + channel
+    hex: String, // digits typed so far for the selected color
+}
+
+impl Default for PaletteEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PaletteEditor {
+    pub fn new() -> Self {
+        Self {
+            row: 0,
+            hex: String::new(),
+        }
+    }
+
+    fn rows() -> usize {
+        Palette::NAMES.len() * CHANNELS.len()
+    }
+
+    fn channel(color: &mut Color, channel: usize) -> &mut u8 {
+        match channel {
+            0 => &mut color.r,
+            1 => &mut color.g,
+            _ => &mut color.b,
+        }
+    }
+
+    // the digit a key types, the number row, the keypad and A-F
+    fn hex_digit(key: Keycode) -> Option<char> {
+        let name = key.name();
+        let name = name.strip_prefix("Keypad ").unwrap_or(&name);
+
+        let mut chars = name.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_hexdigit() => Some(c.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+
+    /// Applies a key to `palette`, returns true when the user asked to save it.
+    pub fn key_down(&mut self, key: Keycode, palette: &mut Palette) -> bool {
+        let color = self.row / CHANNELS.len();
+        let channel = self.row % CHANNELS.len();
+
+        match key {
+            Keycode::Up | Keycode::Down => {
+                self.row = if key == Keycode::Up {
+                    (self.row + Self::rows() - 1) % Self::rows()
+                } else {
+                    (self.row + 1) % Self::rows()
+                };
+
+                // a new color starts a new hex entry
+                if self.row / CHANNELS.len() != color {
+                    self.hex.clear();
+                }
+            }
+
+            Keycode::Left | Keycode::Right => {
+                let value = Self::channel(palette.color_mut(color), channel);
+
+                *value = if key == Keycode::Left {
+                    value.saturating_sub(STEP)
+                } else {
+                    value.saturating_add(STEP)
+                };
+            }
+
+            Keycode::Backspace => {
+                self.hex.pop();
+            }
+
+            Keycode::Return => return true,
+
+            _ => {
+                if let Some(digit) = Self::hex_digit(key) {
+                    self.hex.push(digit);
+
+                    if let Some(parsed) = palette::parse_hex(&self.hex) {
+                        *palette.color_mut(color) = parsed;
+                        self.hex.clear();
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    pub fn hud_lines(&self, palette: &Palette) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for (color_idx, name) in Palette::NAMES.iter().enumerate() {
+            let mut color = palette.color(color_idx);
+
+            let hex = if self.row / CHANNELS.len() == color_idx && !self.hex.is_empty() {
+                format!("{:-<6}", self.hex)
+            } else {
+                palette::to_hex(color)
+            };
+
+            lines.push(format!("{name} {hex}"));
+
+            for (channel, channel_name) in CHANNELS.iter().enumerate() {
+                let row = color_idx * CHANNELS.len() + channel;
+                let value = *Self::channel(&mut color, channel);
+                let filled = value as usize * SLIDER_WIDTH / 255;
+
+                lines.push(format!(
+                    "{}{channel_name} {value:3} [{}{}]",
+                    if row == self.row { "> " } else { "  " },
+                    "I".repeat(filled),
+                    "-".repeat(SLIDER_WIDTH - filled)
+                ));
+            }
+        }
+
+        lines.push("ARROWS ADJUST, HEX DIGITS SET, RETURN SAVES, F3 CLOSES".to_string());
+
+        lines
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chip8_tools::file_io;
+use serde_json::{json, Value};
+
+use crate::managers::rom_library::{self, RomLibrary};
+
+// inside the config directory
+const STATS_FILE: &str = "stats.json";
+
+/// Counts of what was played, for a session or all of them.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Counters {
+    pub roms_played: u64,
+    pub play_time: u64, // seconds
+    pub instructions: u64,
+    pub beeps: u64,
+    pub states_saved: u64,
+}
+
+impl Counters {
+    fn to_json(self) -> Value {
+        json!({
+            "roms_played": self.roms_played,
+            "play_time": self.play_time,
+            "instructions": self.instructions,
+            "beeps": self.beeps,
+            "states_saved": self.states_saved,
+        })
+    }
+}
+
+/// The stats dashboard (Ctrl+I), for this session and all of them. The games played and
+/// the play time come from the game library, the instructions, beeps and saved states are
+/// counted here and kept in `stats.json` in the config directory.
+#[derive(Default)]
+pub struct PlayStats {
+    session: Counters,
+    saved: Counters,       // lifetime counts up to this session, the library's aside
+    library: (u64, u64),   // games played and play time from the library, all sessions
+    roms: HashSet<String>, // library keys of the games played this session
+    sounding: bool,        // the buzzer sounded last frame
+}
+
+impl PlayStats {
+    fn path() -> PathBuf {
+        file_io::config_dir().join(STATS_FILE)
+    }
+
+    /// The saved lifetime counts, none when there are none or they can't be read.
+    pub fn load() -> Self {
+        let saved = fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+            .map(|json| Counters {
+                instructions: json["instructions"].as_u64().unwrap_or(0),
+                beeps: json["beeps"].as_u64().unwrap_or(0),
+                states_saved: json["states_saved"].as_u64().unwrap_or(0),
+                ..Counters::default()
+            })
+            .unwrap_or_default();
+
+        let mut stats = Self {
+            saved,
+            ..Self::default()
+        };
+
+        stats.read_library();
+        stats
+    }
+
+    fn read_library(&mut self) {
+        let library = RomLibrary::load();
+
+        self.library = (library.played_count() as u64, library.total_play_time());
+    }
+
+    /// Adds this session's counts to the saved ones.
+    pub fn save(&self) {
+        let lifetime = self.saved_with_session();
+        let json = json!({
+            "instructions": lifetime.instructions,
+            "beeps": lifetime.beeps,
+            "states_saved": lifetime.states_saved,
+        });
+
+        let saved = fs::create_dir_all(file_io::config_dir())
+            .and_then(|_| fs::write(Self::path(), serde_json::to_string_pretty(&json).unwrap()));
+
+        if let Err(err) = saved {
+            println!("Could not save the play stats: {err}");
+        }
+    }
+
+    fn saved_with_session(&self) -> Counters {
+        Counters {
+            instructions: self.saved.instructions + self.session.instructions,
+            beeps: self.saved.beeps + self.session.beeps,
+            states_saved: self.saved.states_saved + self.session.states_saved,
+            ..Counters::default()
+        }
+    }
+
+    pub fn session(&self) -> Counters {
+        self.session
+    }
+
+    /// All sessions, this one included; play time is counted when a game ends.
+    pub fn lifetime(&self) -> Counters {
+        Counters {
+            roms_played: self.library.0,
+            play_time: self.library.1,
+            ..self.saved_with_session()
+        }
+    }
+
+    /// A game with the library key `key` started.
+    pub fn game_started(&mut self, key: &str) {
+        if self.roms.insert(key.to_string()) {
+            self.session.roms_played += 1;
+        }
+    }
+
+    /// A play session of a game that was just recorded in the library ended.
+    pub fn add_play_time(&mut self, played: Duration) {
+        self.session.play_time += played.as_secs();
+        self.read_library();
+    }
+
+    /// A frame ran `instructions` with the buzzer sounding or not at its end.
+    pub fn frame_ran(&mut self, instructions: u32, sounding: bool) {
+        self.session.instructions += u64::from(instructions);
+
+        if sounding && !self.sounding {
+            self.session.beeps += 1;
+        }
+
+        self.sounding = sounding;
+    }
+
+    pub fn state_saved(&mut self) {
+        self.session.states_saved += 1;
+    }
+
+    /// The dashboard, a line per count with the session's and all sessions'.
+    pub fn hud_lines(&self) -> Vec<String> {
+        let (session, lifetime) = (self.session, self.lifetime());
+
+        let line = |name: &str, session: String, lifetime: String| {
+            format!("{name:<13}{session:>12}{lifetime:>12}")
+        };
+
+        vec![
+            line("", "SESSION".to_string(), "LIFETIME".to_string()),
+            line(
+                "ROMS PLAYED",
+                session.roms_played.to_string(),
+                lifetime.roms_played.to_string(),
+            ),
+            line(
+                "PLAY TIME",
+                rom_library::format_play_time(session.play_time),
+                rom_library::format_play_time(lifetime.play_time),
+            ),
+            line(
+                "INSTRUCTIONS",
+                session.instructions.to_string(),
+                lifetime.instructions.to_string(),
+            ),
+            line(
+                "BEEPS",
+                session.beeps.to_string(),
+                lifetime.beeps.to_string(),
+            ),
+            line(
+                "STATES SAVED",
+                session.states_saved.to_string(),
+                lifetime.states_saved.to_string(),
+            ),
+            "CTRL-E EXPORTS AS JSON".to_string(),
+        ]
+    }
+
+    /// Writes the session's and all sessions' counts as JSON, e.g. for a spreadsheet.
+    pub fn export(&self, path: &str) -> std::io::Result<()> {
+        let json = json!({
+            "session": self.session.to_json(),
+            "lifetime": self.lifetime().to_json(),
+        });
+
+        file_io::write_file(
+            path,
+            serde_json::to_string_pretty(&json).unwrap().as_bytes(),
+        )
+    }
+}
+
+/// `chip8-emulator stats` prints the counts of all sessions as JSON.
+pub fn print() -> Result<(), String> {
+    let lifetime = PlayStats::load().lifetime().to_json();
+
+    println!("{}", serde_json::to_string_pretty(&lifetime).unwrap());
+
+    Ok(())
+}
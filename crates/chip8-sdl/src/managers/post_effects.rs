@@ -0,0 +1,410 @@
+use std::fs;
+use std::path::Path;
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use toml_edit::{Document, Table};
+
+use crate::managers::palette;
+
+// the `scaler` only multiplies the frame up to this, the window does the rest
+const MAX_SCALE_FACTOR: usize = 8;
+
+// brightness a `phosphor` pixel keeps per frame, unless given
+const DEFAULT_DECAY: f32 = 0.6;
+
+/// The screen as an image, a color per pixel, row major, top row first.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, color: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![color; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    /// The pixels as RGB24 bytes, for a streaming texture.
+    pub fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect()
+    }
+}
+
+/// A step of the `Pipeline` the screen goes through before it is shown. Effects get the
+/// frame the previous one produced and may change its size; the window stretches the last
+/// one over the screen area.
+pub trait PostEffect {
+    fn apply(&mut self, frame: Frame) -> Frame;
+}
+
+/// The display effects, applied in order to every frame, given with `--effects effects.toml`:
+///
+/// ```toml
+/// [[effect]]
+/// name = "phosphor"   # lit pixels fade out instead of going dark at once
+/// decay = 0.6         # brightness kept per frame, 0 to 1
+///
+/// [[effect]]
+/// name = "scanlines"  # doubles the rows, every other one darkened
+/// intensity = 0.5     # how much darker, 0 to 1
+///
+/// [[effect]]
+/// name = "scaler"     # multiplies the size before the window stretches it
+/// factor = 4          # 1 to 8
+/// smooth = true       # rounds diagonal edges (Scale2x) instead of repeating pixels
+///
+/// [[effect]]
+/// name = "overlay"    # tints the whole screen, e.g. a green monitor
+/// color = "33FF66"
+/// opacity = 0.3       # 0 to 1
+/// ```
+///
+/// Other effects, e.g. of a program embedding the emulator, are added with `push`.
+#[derive(Default)]
+pub struct Pipeline {
+    effects: Vec<Box<dyn PostEffect>>,
+}
+
+impl Pipeline {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let document: Document = text.parse().map_err(|err| format!("{err}"))?;
+
+        let mut pipeline = Self::default();
+
+        let Some(effects) = document.get("effect") else {
+            return Ok(pipeline);
+        };
+        let effects = effects
+            .as_array_of_tables()
+            .ok_or("effects are written `[[effect]]`")?;
+
+        for table in effects {
+            pipeline.push(Self::effect(table)?);
+        }
+
+        Ok(pipeline)
+    }
+
+    fn effect(table: &Table) -> Result<Box<dyn PostEffect>, String> {
+        let name = table
+            .get("name")
+            .and_then(|name| name.as_str())
+            .ok_or("an effect without a name")?;
+
+        let effect: Box<dyn PostEffect> = match name {
+            "phosphor" => phosphor(fraction(table, "decay", DEFAULT_DECAY)?),
+            "scanlines" => Box::new(Scanlines {
+                intensity: fraction(table, "intensity", 0.5)?,
+            }),
+            "scaler" => {
+                let factor = match table.get("factor") {
+                    Some(factor) => factor
+                        .as_integer()
+                        .and_then(|factor| usize::try_from(factor).ok())
+                        .filter(|factor| (1..=MAX_SCALE_FACTOR).contains(factor))
+                        .ok_or(format!("scaler factor is 1 to {MAX_SCALE_FACTOR}"))?,
+                    None => 2,
+                };
+                let smooth = match table.get("smooth") {
+                    Some(smooth) => smooth.as_bool().ok_or("scaler smooth is true or false")?,
+                    None => false,
+                };
+
+                Box::new(Scaler { factor, smooth })
+            }
+            "overlay" => {
+                let color = table
+                    .get("color")
+                    .and_then(|color| color.as_str())
+                    .and_then(palette::parse_hex)
+                    .ok_or("overlay needs a color, RRGGBB")?;
+
+                Box::new(Overlay {
+                    color,
+                    opacity: fraction(table, "opacity", 0.3)?,
+                })
+            }
+            _ => return Err(format!("unknown effect {name}")),
+        };
+
+        Ok(effect)
+    }
+
+    /// Adds `effect` after the ones already there.
+    pub fn push(&mut self, effect: Box<dyn PostEffect>) {
+        self.effects.push(effect);
+    }
+
+    /// Adds the effects of `other` after these.
+    pub fn append(&mut self, other: Pipeline) {
+        self.effects.extend(other.effects);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    pub fn apply(&mut self, frame: Frame) -> Frame {
+        self.effects
+            .iter_mut()
+            .fold(frame, |frame, effect| effect.apply(frame))
+    }
+}
+
+/// Draws the screen through a `Pipeline` instead of a rectangle per pixel.
+pub struct EffectsScreen {
+    pipeline: Pipeline,
+    texture_creator: &'static TextureCreator<WindowContext>,
+    texture: Option<Texture<'static>>, // sized for the last frame
+}
+
+impl EffectsScreen {
+    pub fn new(pipeline: Pipeline, canvas: &WindowCanvas) -> Self {
+        // the textures live as long as the window, which is the whole run
+        let texture_creator: &'static TextureCreator<WindowContext> =
+            Box::leak(Box::new(canvas.texture_creator()));
+
+        Self {
+            pipeline,
+            texture_creator,
+            texture: None,
+        }
+    }
+
+    /// Runs `frame` through the pipeline and stretches the result over `area`.
+    pub fn draw(&mut self, canvas: &mut WindowCanvas, frame: Frame, area: Rect) {
+        let frame = self.pipeline.apply(frame);
+        let (width, height) = (frame.width as u32, frame.height as u32);
+
+        let texture = match self.texture.take() {
+            Some(texture) if (texture.query().width, texture.query().height) == (width, height) => {
+                texture
+            }
+            _ => self
+                .texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
+                .unwrap(),
+        };
+        let texture = self.texture.insert(texture);
+
+        texture
+            .update(None, &frame.rgb_bytes(), frame.width * 3)
+            .unwrap();
+        canvas.copy(texture, None, area).unwrap();
+    }
+}
+
+// `key` of `table`, a number from 0 to 1
+fn fraction(table: &Table, key: &str, default: f32) -> Result<f32, String> {
+    let Some(value) = table.get(key) else {
+        return Ok(default);
+    };
+
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|value| value as f64))
+        .map(|value| value as f32)
+        .filter(|value| (0.0..=1.0).contains(value))
+        .ok_or(format!("{key} is a number from 0 to 1"))
+}
+
+// `from` moved `amount` of the way to `to`
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let channel =
+        |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+
+    Color::RGB(
+        channel(from.r, to.r),
+        channel(from.g, to.g),
+        channel(from.b, to.b),
+    )
+}
+
+/// Lit pixels fade out over a few frames, keeping `decay` (0 to 1) of their brightness each
+/// frame, like the persistence of a CRT: sprites flickering from XOR drawing stop strobing.
+pub fn phosphor(decay: f32) -> Box<dyn PostEffect> {
+    Box::new(Phosphor {
+        decay,
+        previous: None,
+    })
+}
+
+struct Phosphor {
+    decay: f32,
+    previous: Option<Frame>, // what was shown, faded pixels included
+}
+
+impl PostEffect for Phosphor {
+    fn apply(&mut self, mut frame: Frame) -> Frame {
+        // a resized screen starts over
+        if let Some(previous) = self
+            .previous
+            .as_ref()
+            .filter(|previous| (previous.width, previous.height) == (frame.width, frame.height))
+        {
+            for (pixel, shown) in frame.pixels.iter_mut().zip(&previous.pixels) {
+                let faded = |now: u8, shown: u8| now.max((shown as f32 * self.decay) as u8);
+
+                *pixel = Color::RGB(
+                    faded(pixel.r, shown.r),
+                    faded(pixel.g, shown.g),
+                    faded(pixel.b, shown.b),
+                );
+            }
+        }
+
+        self.previous = Some(frame.clone());
+
+        frame
+    }
+}
+
+struct Scanlines {
+    intensity: f32,
+}
+
+impl PostEffect for Scanlines {
+    fn apply(&mut self, frame: Frame) -> Frame {
+        let mut pixels = Vec::with_capacity(frame.pixels.len() * 2);
+
+        for row in frame.pixels.chunks(frame.width) {
+            pixels.extend_from_slice(row);
+            pixels.extend(
+                row.iter()
+                    .map(|pixel| mix(*pixel, Color::BLACK, self.intensity)),
+            );
+        }
+
+        Frame {
+            width: frame.width,
+            height: frame.height * 2,
+            pixels,
+        }
+    }
+}
+
+struct Scaler {
+    factor: usize,
+    smooth: bool,
+}
+
+impl Scaler {
+    // each pixel repeated `factor` times both ways
+    fn nearest(frame: &Frame, factor: usize) -> Frame {
+        let (width, height) = (frame.width * factor, frame.height * factor);
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(frame.pixel(x / factor, y / factor));
+            }
+        }
+
+        Frame {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // Scale2x: a pixel becomes four, each corner taking the color of the two neighbours
+    // it touches when they agree, which rounds off staircases
+    fn scale2x(frame: &Frame) -> Frame {
+        let (width, height) = (frame.width * 2, frame.height * 2);
+        let mut output = Frame::new(width, height, Color::BLACK);
+
+        // a neighbour off the edge is the pixel itself
+        let at = |x: usize, y: usize, dx: isize, dy: isize| {
+            let (nx, ny) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+
+            if nx < frame.width && ny < frame.height {
+                frame.pixel(nx, ny)
+            } else {
+                frame.pixel(x, y)
+            }
+        };
+
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let p = frame.pixel(x, y);
+                let (up, right) = (at(x, y, 0, -1), at(x, y, 1, 0));
+                let (left, down) = (at(x, y, -1, 0), at(x, y, 0, 1));
+
+                let corner = |side: Color, other: Color, across: Color, other_across: Color| {
+                    if side == other && side != across && other != other_across {
+                        side
+                    } else {
+                        p
+                    }
+                };
+
+                // top left, top right, bottom left, bottom right
+                let corners = [
+                    corner(up, left, right, down),
+                    corner(right, up, down, left),
+                    corner(left, down, up, right),
+                    corner(down, right, left, up),
+                ];
+
+                for (corner, color) in corners.into_iter().enumerate() {
+                    let (dx, dy) = (corner % 2, corner / 2);
+
+                    output.pixels[(y * 2 + dy) * width + x * 2 + dx] = color;
+                }
+            }
+        }
+
+        output
+    }
+}
+
+impl PostEffect for Scaler {
+    fn apply(&mut self, mut frame: Frame) -> Frame {
+        let mut factor = self.factor;
+
+        // Scale2x as often as it fits, whole multiples of the rest
+        if self.smooth {
+            while factor.is_multiple_of(2) {
+                frame = Self::scale2x(&frame);
+                factor /= 2;
+            }
+        }
+
+        if factor > 1 {
+            frame = Self::nearest(&frame, factor);
+        }
+
+        frame
+    }
+}
+
+struct Overlay {
+    color: Color,
+    opacity: f32,
+}
+
+impl PostEffect for Overlay {
+    fn apply(&mut self, mut frame: Frame) -> Frame {
+        for pixel in &mut frame.pixels {
+            *pixel = mix(*pixel, self.color, self.opacity);
+        }
+
+        frame
+    }
+}
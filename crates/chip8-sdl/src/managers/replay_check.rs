@@ -0,0 +1,43 @@
+use chip8_tools::cartridge::Cartridge;
+use chip8_tools::file_io;
+
+use crate::managers::movie::Movie;
+use crate::managers::signature::Signer;
+
+/// `chip8-emulator verify-replay ROM MOVIE` replays a movie headless on its recorded input
+/// and checks it ends in the state it was recorded with, e.g. for a community to check a
+/// submitted high score run. Prints PASS, or FAIL with the first keyframe the replay no
+/// longer matches and fails the command. Signed movies need the signing key, like when playing them.
+pub fn verify(rom_path: &str, movie_path: &str) -> Result<(), String> {
+    let cartridge = Cartridge::open(rom_path)?;
+
+    let data = file_io::read_file(movie_path).map_err(|err| format!("{movie_path}: {err}"))?;
+    let data = Signer::load()
+        .verify(&data)
+        .map_err(|err| format!("{movie_path}: {err}"))?;
+    let movie = Movie::from_bytes(data).map_err(|err| format!("{movie_path}: {err}"))?;
+
+    if !movie.is_finished() {
+        return Err(format!("{movie_path} has no final state to check against"));
+    }
+
+    if !(0..cartridge.len()).any(|idx| movie.recorded_on(cartridge.rom(idx))) {
+        return Err(format!("{movie_path} was recorded with another game"));
+    }
+
+    match movie.divergence() {
+        None => {
+            println!(
+                "PASS: {} frames replay to the recorded final state",
+                movie.len()
+            );
+            Ok(())
+        }
+        Some(frame) if frame == movie.len() => Err(format!(
+            "FAIL: the replay doesn't end in the recorded final state after {frame} frames"
+        )),
+        Some(frame) => Err(format!(
+            "FAIL: the replay no longer matches the recording at frame {frame}"
+        )),
+    }
+}
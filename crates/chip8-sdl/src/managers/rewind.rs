@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+
+use chip8_core::constants::FRAMES_PER_SECOND;
+use chip8_core::core::Chip8;
+use chip8_core::savestate::SaveState;
+use chip8_tools::file_io;
+
+// frames of the game between snapshots, rewinding goes back this many frames per 60th of a second
+const SNAPSHOT_INTERVAL: u32 = 2;
+
+/// The last seconds of the game, a snapshot every few frames, played back while the rewind
+/// key is held. Oldest snapshots are dropped first.
+pub struct Rewind {
+    snapshots: VecDeque<(Vec<u8>, u64)>, // packed states and the random source's, oldest first
+    capacity: usize,
+    frames: u32, // since the last snapshot
+}
+
+impl Rewind {
+    /// Keeps `seconds` of the game, none for 0.
+    pub fn new(seconds: u32) -> Self {
+        let capacity = (seconds * FRAMES_PER_SECOND / SNAPSHOT_INTERVAL) as usize;
+
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+            frames: 0,
+        }
+    }
+
+    /// Called after every frame of the game, takes a snapshot when one is due.
+    pub fn record(&mut self, chip8: &Chip8) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.frames += 1;
+
+        if self.frames < SNAPSHOT_INTERVAL {
+            return;
+        }
+
+        self.frames = 0;
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        let state = file_io::pack_state(chip8.save_state().to_bytes());
+
+        self.snapshots.push_back((state, chip8.get_rng_state()));
+    }
+
+    /// Goes back to the latest snapshot and forgets it, false when there are none left.
+    pub fn step_back(&mut self, chip8: &mut Chip8) -> bool {
+        let Some((state, rng_state)) = self.snapshots.pop_back() else {
+            return false;
+        };
+
+        // written by `record`, they always read back
+        let state = file_io::decompress(state)
+            .ok()
+            .and_then(|data| SaveState::from_bytes(&data).ok())
+            .expect("rewind snapshots are valid states");
+
+        chip8.load_state(&state);
+        chip8.set_rng_state(rng_state);
+        self.frames = 0;
+
+        true
+    }
+
+    /// Forgets every snapshot, e.g. when another game is loaded.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.frames = 0;
+    }
+}
@@ -0,0 +1,871 @@
+use std::fs;
+use std::path::Path;
+
+use chip8_tools::assets;
+use chip8_tools::cartridge::Cartridge;
+use chip8_tools::file_io;
+use sdl2::controller::{Button, GameController};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use sdl2::Sdl;
+use serde_json::Value;
+
+use crate::constants::{BLACK_COLOR, WHITE_COLOR};
+use crate::managers::config::Config;
+use crate::managers::hud;
+use crate::managers::input_test::InputTest;
+use crate::managers::key_map::KeyMap;
+use crate::managers::rom_library::{self, RomLibrary, TAGS};
+use crate::managers::screenshots::Gallery;
+
+const TITLE_SCALE: u32 = 8;
+const ENTRY_SCALE: u32 = 5;
+const MARGIN: u32 = 40;
+
+// entries skipped by PageUp/PageDown and the controller shoulder buttons
+const PAGE_SIZE: usize = 10;
+
+const DIM_COLOR: Color = Color::RGB(120, 120, 120);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Filter {
+    All,
+    Favorites,
+    Tag(&'static str),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Sort {
+    Title,
+    LastPlayed,
+    PlayTime,
+}
+
+// Tab (or the controller's Back button) cycles through these
+const FILTERS: [Filter; 5] = [
+    Filter::All,
+    Filter::Favorites,
+    Filter::Tag(TAGS[0]),
+    Filter::Tag(TAGS[1]),
+    Filter::Tag(TAGS[2]),
+];
+
+// S (or the controller's X button) cycles through these
+const SORTS: [Sort; 3] = [Sort::Title, Sort::LastPlayed, Sort::PlayTime];
+
+/// A game in the browser, cartridges get one entry per game they contain.
+pub struct RomEntry {
+    pub path: String,
+    pub sub_game: usize,
+    pub title: String,
+    pub cover: Option<String>, // image path from the sidecar
+    pub key: String,           // identifies the game in the library
+}
+
+/// Living room friendly launcher drawn in the emulator window, navigable with the keyboard
+/// or any game controller.
+///
+/// A ROM can have a sidecar `<rom>.json` next to it with optional metadata:
+///
+/// ```json
+/// { "title": "Brix", "cover": "covers/brix.png" }
+/// ```
+///
+/// The cover is a PNG, relative to the ROM's directory, shown next to the list.
+///
+/// F (controller Y) marks the selected game as a favorite and 1-3 toggle its tags; they are
+/// kept in the game library together with when and how long each game was played. F1 opens
+/// the input test screen and D the selected game's details, its screenshots and their notes.
+pub struct RomBrowser {
+    entries: Vec<RomEntry>,
+    library: RomLibrary,
+    filter: usize,            // index in FILTERS
+    sort: usize,              // index in SORTS
+    visible: Vec<usize>,      // entries passing the filter, in sort order
+    selected: usize,          // index in visible
+    show_stats: bool,         // play time of every game instead of the list
+    details: Option<Gallery>, // of the selected game, instead of the list
+}
+
+impl RomBrowser {
+    pub fn scan(dir: &str) -> Self {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        paths.sort();
+
+        let mut entries = Vec::new();
+
+        for path in paths {
+            let path_str = path.to_string_lossy().into_owned();
+
+            // sidecars, covers and other non-rom files
+            if !Self::is_rom(&path) {
+                continue;
+            }
+
+            let sidecar = Self::read_sidecar(&path);
+
+            let cover = sidecar["cover"]
+                .as_str()
+                .map(|cover| path.with_file_name(cover).to_string_lossy().into_owned());
+
+            let title = sidecar["title"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| file_io::display_name(&path));
+
+            // unreadable files and sources that don't assemble
+            let Ok(cartridge) = Cartridge::open(&path_str) else {
+                continue;
+            };
+
+            for (sub_game, name) in cartridge.names().enumerate() {
+                let title = if cartridge.len() > 1 {
+                    format!("{title} / {name}")
+                } else {
+                    title.clone()
+                };
+
+                entries.push(RomEntry {
+                    path: path_str.clone(),
+                    sub_game,
+                    title,
+                    cover: cover.clone(),
+                    key: RomLibrary::key(cartridge.rom(sub_game)),
+                });
+            }
+        }
+
+        // portable builds list their bundled games after the ones on disk
+        let bundled = assets::GAMES.and_then(|_| Cartridge::open(assets::GAMES_PATH).ok());
+
+        if let Some(cartridge) = bundled {
+            for (sub_game, name) in cartridge.names().enumerate() {
+                entries.push(RomEntry {
+                    path: assets::GAMES_PATH.to_string(),
+                    sub_game,
+                    title: name.to_string(),
+                    cover: None,
+                    key: RomLibrary::key(cartridge.rom(sub_game)),
+                });
+            }
+        }
+
+        let mut browser = Self {
+            entries,
+            library: RomLibrary::load(),
+            filter: 0,
+            sort: 0,
+            visible: Vec::new(),
+            selected: 0,
+            show_stats: false,
+            details: None,
+        };
+
+        browser.update_visible();
+        browser
+    }
+
+    pub fn entries(&self) -> &[RomEntry] {
+        &self.entries
+    }
+
+    fn key(&self, entry: usize) -> &str {
+        &self.entries[entry].key
+    }
+
+    fn selected_entry(&self) -> Option<usize> {
+        self.visible.get(self.selected).copied()
+    }
+
+    // re-applies the filter and sort, keeping the selected game selected when it is still shown
+    fn update_visible(&mut self) {
+        let previous = self.selected_entry();
+
+        let mut visible: Vec<usize> = (0..self.entries.len())
+            .filter(|idx| {
+                let record = self.library.get(self.key(*idx));
+
+                match FILTERS[self.filter] {
+                    Filter::All => true,
+                    Filter::Favorites => record.favorite,
+                    Filter::Tag(tag) => record.tags.iter().any(|t| t == tag),
+                }
+            })
+            .collect();
+
+        // titles break ties, the sort is stable so they stay in name order
+        visible.sort_by_key(|idx| self.entries[*idx].title.to_uppercase());
+
+        match SORTS[self.sort] {
+            Sort::Title => (),
+            Sort::LastPlayed => visible
+                .sort_by_key(|idx| std::cmp::Reverse(self.library.get(self.key(*idx)).last_played)),
+            Sort::PlayTime => visible
+                .sort_by_key(|idx| std::cmp::Reverse(self.library.get(self.key(*idx)).play_time)),
+        }
+
+        self.selected = previous
+            .and_then(|entry| visible.iter().position(|idx| *idx == entry))
+            .unwrap_or(0);
+
+        self.visible = visible;
+    }
+
+    fn is_rom(path: &Path) -> bool {
+        let ignored = ["json", "png", "state", "txt", "md"];
+
+        path.is_file()
+            && !file_io::inner_extension(path).is_some_and(|ext| ignored.contains(&ext.as_str()))
+    }
+
+    fn read_sidecar(rom_path: &Path) -> Value {
+        let sidecar_path = format!("{}.json", rom_path.to_string_lossy());
+
+        fs::read_to_string(sidecar_path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or(Value::Null)
+    }
+
+    /// Shows the browser until a game is picked (path and game inside the cartridge)
+    /// or the user quits.
+    pub fn run(&mut self, sdl: &Sdl, canvas: &mut WindowCanvas) -> Option<(String, usize)> {
+        if self.entries.is_empty() {
+            println!("No games found");
+            return None;
+        }
+
+        let mut event_pump = sdl.event_pump().unwrap();
+
+        // play statistics may have changed since the scan
+        self.library = RomLibrary::load();
+        self.update_visible();
+
+        // controllers have to stay open to send events
+        let controller_subsystem = sdl.game_controller().ok();
+        let mut controllers: Vec<GameController> = Vec::new();
+
+        let texture_creator = canvas.texture_creator();
+        let mut cover: Option<(usize, Option<Texture>)> = None;
+        let mut screenshot: Option<(String, Option<Texture>)> = None;
+
+        // on while a note is written, SDL sends the typed text meanwhile
+        let text_input = sdl.video().unwrap().text_input();
+        text_input.stop();
+
+        loop {
+            for evt in event_pump.poll_iter() {
+                let mut step: isize = 0;
+
+                if let Some(gallery) = &mut self.details {
+                    if matches!(evt, Event::Quit { .. }) {
+                        return None;
+                    }
+
+                    if !Self::details_event(gallery, &evt) {
+                        self.details = None;
+                    }
+
+                    let writing = self
+                        .details
+                        .as_ref()
+                        .is_some_and(|gallery| gallery.note.is_some());
+
+                    if writing && !text_input.is_active() {
+                        text_input.start();
+                    } else if !writing && text_input.is_active() {
+                        text_input.stop();
+                    }
+
+                    continue;
+                }
+
+                // any key or button closes the stats screen
+                if self.show_stats
+                    && matches!(
+                        evt,
+                        Event::KeyDown { .. } | Event::ControllerButtonDown { .. }
+                    )
+                {
+                    self.show_stats = false;
+                    continue;
+                }
+
+                match evt {
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => return None,
+
+                    Event::ControllerDeviceAdded { which, .. } => {
+                        if let Some(controller) = controller_subsystem
+                            .as_ref()
+                            .and_then(|subsystem| subsystem.open(which).ok())
+                        {
+                            controllers.push(controller);
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return | Keycode::Space),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::A | Button::Start,
+                        ..
+                    } => {
+                        if let Some(entry) = self.selected_entry() {
+                            let entry = &self.entries[entry];
+
+                            return Some((entry.path.clone(), entry.sub_game));
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::Y, ..
+                    } => {
+                        if let Some(entry) = self.selected_entry() {
+                            let key = self.key(entry).to_string();
+                            self.library.toggle_favorite(&key);
+                            self.library.save();
+                            self.update_visible();
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(key @ (Keycode::Num1 | Keycode::Num2 | Keycode::Num3)),
+                        ..
+                    } => {
+                        let tag = match key {
+                            Keycode::Num1 => TAGS[0],
+                            Keycode::Num2 => TAGS[1],
+                            _ => TAGS[2],
+                        };
+
+                        if let Some(entry) = self.selected_entry() {
+                            let key = self.key(entry).to_string();
+                            self.library.toggle_tag(&key, tag);
+                            self.library.save();
+                            self.update_visible();
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::I),
+                        ..
+                    } => self.show_stats = true,
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::D),
+                        ..
+                    } => {
+                        if let Some(entry) = self.selected_entry() {
+                            self.details = Some(Gallery::open(self.key(entry)));
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::F1),
+                        ..
+                    } => InputTest::new(KeyMap::load().unwrap_or_default()).run(sdl, canvas),
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Tab),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::Back,
+                        ..
+                    } => {
+                        self.filter = (self.filter + 1) % FILTERS.len();
+                        self.update_visible();
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::S),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::X, ..
+                    } => {
+                        self.sort = (self.sort + 1) % SORTS.len();
+                        self.update_visible();
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Up),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::DPadUp,
+                        ..
+                    } => step = -1,
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Down),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::DPadDown,
+                        ..
+                    } => step = 1,
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::PageUp),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::LeftShoulder,
+                        ..
+                    } => step = -(PAGE_SIZE as isize),
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::PageDown),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::RightShoulder,
+                        ..
+                    } => step = PAGE_SIZE as isize,
+
+                    _ => (),
+                }
+
+                let last = (self.visible.len() as isize - 1).max(0);
+                self.selected = (self.selected as isize + step).clamp(0, last) as usize;
+            }
+
+            // only decode the cover when the selection changed
+            let selected = self.selected_entry();
+
+            if cover.as_ref().map(|(idx, _)| Some(*idx)) != Some(selected) {
+                let texture = selected
+                    .and_then(|entry| self.entries[entry].cover.as_ref())
+                    .and_then(|path| Self::load_cover(&texture_creator, path));
+
+                cover = selected.map(|entry| (entry, texture));
+            }
+
+            let texture = cover.as_ref().and_then(|(_, texture)| texture.as_ref());
+
+            if let Some(gallery) = &self.details {
+                // only decode the screenshot when another one is shown
+                let path = gallery
+                    .current()
+                    .map(|shot| shot.path.to_string_lossy().into_owned());
+
+                if screenshot.as_ref().map(|(shown, _)| Some(shown)) != Some(path.as_ref()) {
+                    screenshot = path.map(|path| {
+                        let texture = Self::load_cover(&texture_creator, &path);
+
+                        (path, texture)
+                    });
+                }
+
+                let texture = screenshot
+                    .as_ref()
+                    .and_then(|(_, texture)| texture.as_ref());
+
+                self.draw_details(canvas, gallery, texture);
+            } else if self.show_stats {
+                self.draw_stats(canvas);
+            } else {
+                self.draw(canvas, texture);
+            }
+        }
+    }
+
+    fn draw(&self, canvas: &mut WindowCanvas, cover: Option<&Texture>) {
+        let (window_width, window_height) = canvas.output_size().unwrap();
+
+        canvas.set_draw_color(BLACK_COLOR);
+        canvas.clear();
+
+        canvas.set_draw_color(WHITE_COLOR);
+        hud::draw_text(
+            canvas,
+            "CHIP-8 GAMES",
+            MARGIN as i32,
+            MARGIN as i32,
+            TITLE_SCALE,
+        );
+
+        let line_height = (hud::GLYPH_HEIGHT + 2) * ENTRY_SCALE;
+        let status_top = MARGIN * 2 + hud::GLYPH_HEIGHT * TITLE_SCALE;
+        let list_top = status_top + line_height * 2;
+        let visible = ((window_height - list_top - MARGIN) / line_height) as usize;
+
+        canvas.set_draw_color(DIM_COLOR);
+        hud::draw_text(
+            canvas,
+            &self.status_text(),
+            MARGIN as i32,
+            status_top as i32,
+            ENTRY_SCALE,
+        );
+
+        // keep the selection in the middle of the visible part of the list
+        let first = self
+            .selected
+            .saturating_sub(visible / 2)
+            .min(self.visible.len().saturating_sub(visible));
+
+        for (row, entry) in self.visible.iter().enumerate().skip(first).take(visible) {
+            let y = list_top + (row - first) as u32 * line_height;
+
+            let record = self.library.get(self.key(*entry));
+
+            let (marker, color) = if row == self.selected {
+                ("> ", WHITE_COLOR)
+            } else {
+                ("  ", DIM_COLOR)
+            };
+
+            let favorite = if record.favorite { "* " } else { "" };
+
+            let tags = if record.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", record.tags.join(","))
+            };
+
+            canvas.set_draw_color(color);
+            hud::draw_text(
+                canvas,
+                &format!("{marker}{favorite}{}{tags}", self.entries[*entry].title),
+                MARGIN as i32,
+                y as i32,
+                ENTRY_SCALE,
+            );
+        }
+
+        if let Some(cover) = cover {
+            // fit the cover in the right half of the window, keeping its aspect ratio
+            let area_width = window_width / 2 - MARGIN;
+            let area_height = window_height - list_top - MARGIN;
+
+            let query = cover.query();
+            let scale = (area_width as f32 / query.width as f32)
+                .min(area_height as f32 / query.height as f32);
+
+            let width = (query.width as f32 * scale) as u32;
+            let height = (query.height as f32 * scale) as u32;
+
+            let rect = Rect::new((window_width / 2) as i32, list_top as i32, width, height);
+
+            canvas.copy(cover, None, rect).unwrap();
+        }
+
+        canvas.present();
+    }
+
+    // current filter and sort, and how much the selected game was played
+    fn status_text(&self) -> String {
+        let filter = match FILTERS[self.filter] {
+            Filter::All => "ALL".to_string(),
+            Filter::Favorites => "FAVORITES".to_string(),
+            Filter::Tag(tag) => tag.to_string(),
+        };
+
+        let sort = match SORTS[self.sort] {
+            Sort::Title => "TITLE",
+            Sort::LastPlayed => "LAST PLAYED",
+            Sort::PlayTime => "PLAY TIME",
+        };
+
+        let played = match self
+            .selected_entry()
+            .map(|entry| self.library.get(self.key(entry)))
+        {
+            Some(record) if record.last_played > 0 => {
+                let days = rom_library::now().saturating_sub(record.last_played) / (24 * 60 * 60);
+
+                format!(
+                    "   PLAYED {}, {}",
+                    rom_library::format_play_time(record.play_time),
+                    match days {
+                        0 => "TODAY".to_string(),
+                        1 => "YESTERDAY".to_string(),
+                        days => format!("{days} DAYS AGO"),
+                    }
+                )
+            }
+            _ => String::new(),
+        };
+
+        format!(
+            "TAB: {filter}   S: SORT BY {sort}   I: STATS   D: DETAILS   F1: INPUT TEST{played}"
+        )
+    }
+
+    // applies an event to the details view, false when it closes
+    fn details_event(gallery: &mut Gallery, evt: &Event) -> bool {
+        if gallery.note.is_some() {
+            match evt {
+                Event::TextInput { text, .. } => gallery.type_text(text),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => gallery.backspace(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Return | Keycode::KpEnter),
+                    ..
+                } => gallery.save_note(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => gallery.note = None,
+                _ => (),
+            }
+
+            return true;
+        }
+
+        match evt {
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape | Keycode::D),
+                ..
+            }
+            | Event::ControllerButtonDown {
+                button: Button::B, ..
+            } => return false,
+
+            Event::KeyDown {
+                keycode: Some(Keycode::Left),
+                ..
+            }
+            | Event::ControllerButtonDown {
+                button: Button::DPadLeft,
+                ..
+            } => gallery.step(-1),
+
+            Event::KeyDown {
+                keycode: Some(Keycode::Right),
+                ..
+            }
+            | Event::ControllerButtonDown {
+                button: Button::DPadRight,
+                ..
+            } => gallery.step(1),
+
+            Event::KeyDown {
+                keycode: Some(Keycode::N),
+                ..
+            } => gallery.edit_note(),
+
+            _ => (),
+        }
+
+        true
+    }
+
+    // the selected game's screenshots, one at a time with its note
+    fn draw_details(&self, canvas: &mut WindowCanvas, gallery: &Gallery, image: Option<&Texture>) {
+        let (window_width, window_height) = canvas.output_size().unwrap();
+
+        canvas.set_draw_color(BLACK_COLOR);
+        canvas.clear();
+
+        let title = self
+            .selected_entry()
+            .map_or("", |entry| self.entries[entry].title.as_str());
+
+        canvas.set_draw_color(WHITE_COLOR);
+        hud::draw_text(canvas, title, MARGIN as i32, MARGIN as i32, TITLE_SCALE);
+
+        let line_height = (hud::GLYPH_HEIGHT + 2) * ENTRY_SCALE;
+        let status_top = MARGIN * 2 + hud::GLYPH_HEIGHT * TITLE_SCALE;
+        let image_top = status_top + line_height * 2;
+
+        let status = match gallery.current() {
+            None => "NO SCREENSHOTS, CTRL+S TAKES ONE WHILE PLAYING   ESC: BACK".to_string(),
+            Some(_) => format!(
+                "SCREENSHOT {} OF {}   LEFT/RIGHT: BROWSE   N: NOTE   ESC: BACK",
+                gallery.selected + 1,
+                gallery.screenshots.len()
+            ),
+        };
+
+        canvas.set_draw_color(DIM_COLOR);
+        hud::draw_text(
+            canvas,
+            &status,
+            MARGIN as i32,
+            status_top as i32,
+            ENTRY_SCALE,
+        );
+
+        // the note goes under the image
+        let note_top = window_height.saturating_sub(MARGIN + line_height);
+
+        if let Some(image) = image {
+            // fit the screenshot between the status and the note, keeping its aspect ratio
+            let area_width = window_width - MARGIN * 2;
+            let area_height = note_top.saturating_sub(image_top + line_height);
+
+            let query = image.query();
+            let scale = (area_width as f32 / query.width as f32)
+                .min(area_height as f32 / query.height as f32);
+
+            let width = (query.width as f32 * scale) as u32;
+            let height = (query.height as f32 * scale) as u32;
+
+            let rect = Rect::new(MARGIN as i32, image_top as i32, width, height);
+
+            canvas.copy(image, None, rect).unwrap();
+        }
+
+        let note = match (&gallery.note, gallery.current()) {
+            (Some(note), _) => {
+                canvas.set_draw_color(WHITE_COLOR);
+                format!("NOTE: {note}_   RETURN: SAVE")
+            }
+            (None, Some(shot)) => {
+                canvas.set_draw_color(DIM_COLOR);
+                shot.note.clone()
+            }
+            (None, None) => String::new(),
+        };
+
+        hud::draw_text(canvas, &note, MARGIN as i32, note_top as i32, ENTRY_SCALE);
+
+        canvas.present();
+    }
+
+    // games that were played, most played first
+    fn draw_stats(&self, canvas: &mut WindowCanvas) {
+        let (_, window_height) = canvas.output_size().unwrap();
+
+        canvas.set_draw_color(BLACK_COLOR);
+        canvas.clear();
+
+        canvas.set_draw_color(WHITE_COLOR);
+        hud::draw_text(
+            canvas,
+            "PLAY STATS",
+            MARGIN as i32,
+            MARGIN as i32,
+            TITLE_SCALE,
+        );
+
+        let line_height = (hud::GLYPH_HEIGHT + 2) * ENTRY_SCALE;
+        let status_top = MARGIN * 2 + hud::GLYPH_HEIGHT * TITLE_SCALE;
+        let list_top = status_top + line_height * 2;
+        let rows = (window_height.saturating_sub(list_top + MARGIN) / line_height) as usize;
+
+        let total = format!(
+            "TOTAL {}",
+            rom_library::format_play_time(self.library.total_play_time())
+        );
+
+        canvas.set_draw_color(DIM_COLOR);
+        hud::draw_text(
+            canvas,
+            &total,
+            MARGIN as i32,
+            status_top as i32,
+            ENTRY_SCALE,
+        );
+
+        let mut played: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| (entry, self.library.get(&entry.key).play_time))
+            .filter(|(_, play_time)| *play_time > 0)
+            .collect();
+
+        played.sort_by_key(|(_, play_time)| std::cmp::Reverse(*play_time));
+
+        canvas.set_draw_color(WHITE_COLOR);
+
+        for (row, (entry, play_time)) in played.iter().take(rows).enumerate() {
+            let line = format!(
+                "{}  {}",
+                rom_library::format_play_time(*play_time),
+                entry.title
+            );
+            let y = list_top + row as u32 * line_height;
+
+            hud::draw_text(canvas, &line, MARGIN as i32, y as i32, ENTRY_SCALE);
+        }
+
+        canvas.present();
+    }
+
+    fn load_cover<'a>(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        path: &str,
+    ) -> Option<Texture<'a>> {
+        let (width, height, rgba) = match Self::decode_png(path) {
+            Ok(image) => image,
+            Err(err) => {
+                println!("Could not load cover {path}: {err}");
+                return None;
+            }
+        };
+
+        let mut texture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGBA32, width, height)
+            .ok()?;
+
+        texture.update(None, &rgba, width as usize * 4).ok()?;
+
+        Some(texture)
+    }
+
+    // width, height and RGBA pixels
+    pub fn decode_png(path: &str) -> Result<(u32, u32, Vec<u8>), png::DecodingError> {
+        let mut decoder = png::Decoder::new(fs::File::open(path)?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let pixels = &buf[..info.buffer_size()];
+
+        let rgba = match info.color_type {
+            png::ColorType::Rgba => pixels.to_vec(),
+            png::ColorType::Rgb => pixels
+                .chunks(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => pixels
+                .chunks(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            // grayscale, palettes were expanded to RGB by the transformations
+            _ => pixels.iter().flat_map(|p| [*p, *p, *p, 255]).collect(),
+        };
+
+        Ok((info.width, info.height, rgba))
+    }
+}
+
+/// `chip8-emulator --list-games` prints the games of the ROM directory for launchers, a
+/// path and title a line separated by a tab. A cartridge has a line per game, started with
+/// `--game TITLE`.
+pub fn print_games() -> Result<(), String> {
+    for entry in RomBrowser::scan(&Config::get().rom_dir).entries() {
+        println!("{}\t{}", entry.path, entry.title);
+    }
+
+    Ok(())
+}
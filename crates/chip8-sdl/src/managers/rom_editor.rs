@@ -0,0 +1,194 @@
+use std::io::Write;
+
+use chip8_core::core::Chip8;
+use chip8_core::disassembler;
+use chip8_tools::file_io;
+use sdl2::clipboard::ClipboardUtil;
+
+use crate::managers::debugger::Debugger;
+
+// bytes shown per line when viewing memory
+const BYTES_PER_LINE: usize = 16;
+
+/// Patches the loaded ROM in memory with an undo stack and saves it back out as a new ROM.
+/// Driven from a small console prompt while the game is paused:
+///
+/// ```text
+/// view <addr> [len]        hex dump memory, e.g. `view 200 40`
+/// poke <addr> <bytes...>   overwrite bytes, e.g. `poke 2A4 12 34`
+/// undo                     revert the last poke
+/// save <path>              write the patched ROM (`.gz` paths are compressed)
+/// copy <addr> [len]        copy a hex dump to the clipboard, like `view`
+/// copy rom                 copy the disassembly of the ROM
+/// copy regs                copy the registers, timers and stack
+/// resume                   go back to the game
+/// ```
+///
+/// Addresses and bytes are hexadecimal.
+pub struct RomEditor {
+    rom_len: usize,
+    undo_stack: Vec<Vec<(u16, u8)>>, // every poke remembers the previous value of each byte
+}
+
+impl RomEditor {
+    pub fn new(rom_len: usize) -> Self {
+        Self {
+            rom_len,
+            undo_stack: Vec::new(),
+        }
+    }
+
+    /// Runs the editor prompt until the user resumes the game.
+    pub fn run_console(&mut self, chip8: &mut Chip8, clipboard: &ClipboardUtil) {
+        println!("ROM editor (game paused). Commands: view, poke, undo, save, copy, resume");
+
+        loop {
+            print!("editor> ");
+            std::io::stdout().flush().unwrap();
+
+            let mut line = String::new();
+
+            // stdin closed, nothing more to edit
+            if std::io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+
+            let args: Vec<&str> = line.split_whitespace().collect();
+
+            let result = match args.as_slice() {
+                [] => Ok(()),
+                ["view", addr] => self.view(chip8, addr, "10"),
+                ["view", addr, len] => self.view(chip8, addr, len),
+                ["poke", addr, bytes @ ..] if !bytes.is_empty() => self.poke(chip8, addr, bytes),
+                ["undo"] => self.undo(chip8),
+                ["save", path] => self.save(chip8, path),
+                ["copy", "rom"] => Self::copy(clipboard, &self.rom_listing(chip8)),
+                ["copy", "regs"] => Self::copy(clipboard, &Debugger::registers_text(chip8)),
+                ["copy", addr] => Self::dump(chip8, addr, "10")
+                    .and_then(|lines| Self::copy(clipboard, &lines.join("\n"))),
+                ["copy", addr, len] => Self::dump(chip8, addr, len)
+                    .and_then(|lines| Self::copy(clipboard, &lines.join("\n"))),
+                ["resume"] | ["exit"] | ["quit"] => return,
+                _ => Err(format!("Unknown command: {}", line.trim())),
+            };
+
+            if let Err(err) = result {
+                println!("{err}");
+            }
+        }
+    }
+
+    fn view(&self, chip8: &Chip8, addr: &str, len: &str) -> Result<(), String> {
+        for line in Self::dump(chip8, addr, len)? {
+            println!("{line}");
+        }
+
+        Ok(())
+    }
+
+    // `ADDR: BYTES` lines of a hex dump
+    fn dump(chip8: &Chip8, addr: &str, len: &str) -> Result<Vec<String>, String> {
+        let start = Self::parse_addr(addr, chip8)? as usize;
+        let len = usize::from_str_radix(len, 16).map_err(|_| format!("Invalid length: {len}"))?;
+
+        let memory = chip8.get_memory();
+        let end = (start + len).min(memory.len());
+
+        let lines = (start..end)
+            .step_by(BYTES_PER_LINE)
+            .map(|line_start| {
+                let line_end = (line_start + BYTES_PER_LINE).min(end);
+
+                let bytes: Vec<String> = memory[line_start..line_end]
+                    .iter()
+                    .map(|byte| format!("{byte:02X}"))
+                    .collect();
+
+                format!("{:03X}: {}", line_start, bytes.join(" "))
+            })
+            .collect();
+
+        Ok(lines)
+    }
+
+    // the ROM as it is in memory now, patches included
+    fn rom_listing(&self, chip8: &Chip8) -> String {
+        let start = chip8.start_addr() as usize;
+        let end = (start + self.rom_len).min(chip8.memory_size());
+
+        disassembler::listing(&chip8.get_memory()[start..end], start as u16).join("\n")
+    }
+
+    fn copy(clipboard: &ClipboardUtil, text: &str) -> Result<(), String> {
+        clipboard.set_clipboard_text(text)?;
+
+        println!("Copied {} lines", text.lines().count());
+
+        Ok(())
+    }
+
+    fn poke(&mut self, chip8: &mut Chip8, addr: &str, bytes: &[&str]) -> Result<(), String> {
+        let start = Self::parse_addr(addr, chip8)?;
+
+        let values = bytes
+            .iter()
+            .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| format!("Invalid byte: {byte}")))
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        if start as usize + values.len() > chip8.memory_size() {
+            return Err("Patch does not fit in memory".to_string());
+        }
+
+        let mut previous = Vec::with_capacity(values.len());
+
+        for (offset, value) in values.into_iter().enumerate() {
+            let addr = start + offset as u16;
+
+            previous.push((addr, chip8.get_memory()[addr as usize]));
+            chip8.write_memory(addr, value);
+        }
+
+        self.undo_stack.push(previous);
+
+        Ok(())
+    }
+
+    fn undo(&mut self, chip8: &mut Chip8) -> Result<(), String> {
+        let previous = self.undo_stack.pop().ok_or("Nothing to undo")?;
+
+        for (addr, value) in previous {
+            chip8.write_memory(addr, value);
+        }
+
+        Ok(())
+    }
+
+    // the ROM region grows when a patch was written past the original end of the ROM
+    fn save(&self, chip8: &Chip8, path: &str) -> Result<(), String> {
+        let start = chip8.start_addr() as usize;
+
+        let patched_end = self
+            .undo_stack
+            .iter()
+            .flatten()
+            .map(|(addr, _)| *addr as usize + 1)
+            .max()
+            .unwrap_or(0);
+
+        let end = (start + self.rom_len).max(patched_end);
+
+        file_io::write_file(path, &chip8.get_memory()[start..end])
+            .map_err(|err| format!("Could not save {path}: {err}"))?;
+
+        println!("Saved patched ROM to {path}");
+
+        Ok(())
+    }
+
+    fn parse_addr(addr: &str, chip8: &Chip8) -> Result<u16, String> {
+        match u16::from_str_radix(addr.trim_start_matches("0x"), 16) {
+            Ok(addr) if (addr as usize) < chip8.memory_size() => Ok(addr),
+            _ => Err(format!("Invalid address: {addr}")),
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chip8_core::gamepad_map::GamepadMap;
+use chip8_core::machine::Machine;
+use chip8_core::quirks::Quirks;
+use chip8_core::random::RandomSource;
+use chip8_tools::file_io;
+use flate2::Crc;
+use serde_json::{json, Map, Value};
+
+// inside the config directory
+const LIBRARY_FILE: &str = "library.json";
+
+/// Tags offered in the browser.
+pub const TAGS: [&str; 3] = ["puzzle", "action", "demo"];
+
+/// What the user told us or we learned about a game.
+#[derive(Clone, Default, Debug)]
+pub struct RomRecord {
+    pub favorite: bool,
+    pub tags: Vec<String>,
+    pub last_played: u64, // seconds since the Unix epoch, 0 if never played
+    pub play_time: u64,   // seconds
+    pub quirks: Option<Quirks>, // None until detected
+    pub load_addr: Option<u16>, // None for the machine's
+    pub machine: Machine,
+    pub random: RandomSource,
+    pub provenance: Option<Provenance>, // None for games the user brought
+    pub gamepad_map: Option<GamepadMap>, // None for the one every game uses
+}
+
+/// Where a game synced from a ROM index came from, see `library_sync`.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Provenance {
+    pub title: String,
+    pub author: String,
+    pub license: String,
+    pub url: String,   // the ROM was downloaded from
+    pub index: String, // the index listing it
+}
+
+/// Per game favorites, tags and play statistics, stored as JSON in the config directory.
+/// Games are identified by a hash of their ROM, so they keep their statistics when the
+/// file is renamed, moved, compressed or packed into a cartridge.
+#[derive(Default)]
+pub struct RomLibrary {
+    records: HashMap<String, RomRecord>,
+}
+
+impl RomLibrary {
+    fn path() -> PathBuf {
+        file_io::config_dir().join(LIBRARY_FILE)
+    }
+
+    /// CRC-32 and length of the rom, e.g. `1c3d5e7f-246`.
+    pub fn key(rom: &[u8]) -> String {
+        let mut crc = Crc::new();
+        crc.update(rom);
+
+        format!("{:08x}-{}", crc.sum(), rom.len())
+    }
+
+    /// The saved library, empty when there is none or it can't be read.
+    pub fn load() -> Self {
+        let Ok(text) = fs::read_to_string(Self::path()) else {
+            return Self::default();
+        };
+
+        let json: Value = match serde_json::from_str(&text) {
+            Ok(json) => json,
+            Err(err) => {
+                println!("Ignoring unreadable game library: {err}");
+                return Self::default();
+            }
+        };
+
+        let records = json
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(key, record)| {
+                let tags = record["tags"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|tag| tag.as_str().map(str::to_string))
+                    .collect();
+
+                let record = RomRecord {
+                    favorite: record["favorite"].as_bool().unwrap_or(false),
+                    tags,
+                    last_played: record["last_played"].as_u64().unwrap_or(0),
+                    play_time: record["play_time"].as_u64().unwrap_or(0),
+                    quirks: record["quirks"].as_str().and_then(Quirks::parse),
+                    load_addr: record["load_addr"]
+                        .as_str()
+                        .and_then(|addr| u16::from_str_radix(addr, 16).ok()),
+                    machine: record["machine"]
+                        .as_str()
+                        .and_then(Machine::parse)
+                        .unwrap_or_default(),
+                    random: record["random"]
+                        .as_str()
+                        .and_then(RandomSource::parse)
+                        .unwrap_or_default(),
+                    provenance: record["provenance"].as_object().map(|provenance| {
+                        let field = |name: &str| {
+                            provenance
+                                .get(name)
+                                .and_then(Value::as_str)
+                                .unwrap_or_default()
+                                .to_string()
+                        };
+
+                        Provenance {
+                            title: field("title"),
+                            author: field("author"),
+                            license: field("license"),
+                            url: field("url"),
+                            index: field("index"),
+                        }
+                    }),
+                    gamepad_map: record["gamepad_map"]
+                        .as_str()
+                        .and_then(|text| GamepadMap::parse(text).ok()),
+                };
+
+                (key.clone(), record)
+            })
+            .collect();
+
+        Self { records }
+    }
+
+    pub fn save(&self) {
+        let json: Map<String, Value> = self
+            .records
+            .iter()
+            .map(|(key, record)| {
+                let value = json!({
+                    "favorite": record.favorite,
+                    "tags": record.tags,
+                    "last_played": record.last_played,
+                    "play_time": record.play_time,
+                    "quirks": record.quirks.map(|quirks| quirks.names()),
+                    "load_addr": record.load_addr.map(|addr| format!("{addr:03X}")),
+                    "machine": record.machine.name(),
+                    "random": record.random.name(),
+                    "provenance": record.provenance.as_ref().map(|provenance| json!({
+                        "title": provenance.title,
+                        "author": provenance.author,
+                        "license": provenance.license,
+                        "url": provenance.url,
+                        "index": provenance.index,
+                    })),
+                    "gamepad_map": record.gamepad_map.map(|map| map.to_text()),
+                });
+
+                (key.clone(), value)
+            })
+            .collect();
+
+        let saved = fs::create_dir_all(file_io::config_dir())
+            .and_then(|_| fs::write(Self::path(), serde_json::to_string_pretty(&json).unwrap()));
+
+        if let Err(err) = saved {
+            println!("Could not save the game library: {err}");
+        }
+    }
+
+    /// Number of games that were played at all.
+    pub fn played_count(&self) -> usize {
+        self.records
+            .values()
+            .filter(|record| record.play_time > 0)
+            .count()
+    }
+
+    /// Play time of all games together.
+    pub fn total_play_time(&self) -> u64 {
+        self.records.values().map(|record| record.play_time).sum()
+    }
+
+    pub fn get(&self, key: &str) -> RomRecord {
+        self.records.get(key).cloned().unwrap_or_default()
+    }
+
+    fn get_mut(&mut self, key: &str) -> &mut RomRecord {
+        self.records.entry(key.to_string()).or_default()
+    }
+
+    pub fn toggle_favorite(&mut self, key: &str) {
+        let record = self.get_mut(key);
+        record.favorite = !record.favorite;
+    }
+
+    pub fn toggle_tag(&mut self, key: &str, tag: &str) {
+        let tags = &mut self.get_mut(key).tags;
+
+        match tags.iter().position(|t| t == tag) {
+            Some(idx) => {
+                tags.remove(idx);
+            }
+            None => tags.push(tag.to_string()),
+        }
+    }
+
+    /// Adds `tag` unless the game has it already.
+    pub fn add_tag(&mut self, key: &str, tag: &str) {
+        let tags = &mut self.get_mut(key).tags;
+
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    pub fn set_provenance(&mut self, key: &str, provenance: Provenance) {
+        self.get_mut(key).provenance = Some(provenance);
+    }
+
+    pub fn set_quirks(&mut self, key: &str, quirks: Quirks) {
+        self.get_mut(key).quirks = Some(quirks);
+    }
+
+    pub fn set_load_addr(&mut self, key: &str, addr: u16) {
+        self.get_mut(key).load_addr = Some(addr);
+    }
+
+    pub fn set_machine(&mut self, key: &str, machine: Machine) {
+        self.get_mut(key).machine = machine;
+    }
+
+    pub fn set_random(&mut self, key: &str, random: RandomSource) {
+        self.get_mut(key).random = random;
+    }
+
+    pub fn set_gamepad_map(&mut self, key: &str, map: GamepadMap) {
+        self.get_mut(key).gamepad_map = Some(map);
+    }
+
+    /// Adds a play session that just ended.
+    pub fn record_play(&mut self, key: &str, played: Duration) {
+        let record = self.get_mut(key);
+
+        record.last_played = now();
+        record.play_time += played.as_secs();
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+/// Play time as hours and minutes, e.g. `3H 07M`.
+pub fn format_play_time(seconds: u64) -> String {
+    format!("{}H {:02}M", seconds / 3600, seconds / 60 % 60)
+}
@@ -0,0 +1,181 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use chip8_core::core::Chip8;
+use chip8_tools::file_io;
+
+use crate::managers::palette::Palette;
+use crate::managers::rom_library;
+
+// inside the config directory, a directory per game named by its library key
+const SCREENSHOTS_DIR: &str = "screenshots";
+
+// blown up like flipbook frames, single pixels are hard to look at
+const PNG_SCALE: usize = 4;
+
+/// A screenshot of a game, `<unix time>.png` in the game's directory, and the note written
+/// for it, kept next to it as `<unix time>.txt`.
+pub struct Screenshot {
+    pub path: PathBuf,
+    pub note: String,
+}
+
+impl Screenshot {
+    fn note_path(path: &Path) -> PathBuf {
+        path.with_extension("txt")
+    }
+
+    /// Saves the note, an empty one removes it.
+    pub fn set_note(&mut self, note: &str) -> std::io::Result<()> {
+        let note_path = Self::note_path(&self.path);
+
+        if note.is_empty() {
+            if note_path.exists() {
+                fs::remove_file(note_path)?;
+            }
+        } else {
+            fs::write(note_path, note)?;
+        }
+
+        self.note = note.to_string();
+
+        Ok(())
+    }
+
+    /// When it was taken, as seconds since the epoch.
+    pub fn taken(&self) -> u64 {
+        self.path
+            .file_stem()
+            .and_then(|stem| stem.to_str()?.split('-').next()?.parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+/// Where the screenshots of the game with library key `key` are kept.
+pub fn dir(key: &str) -> PathBuf {
+    file_io::config_dir().join(SCREENSHOTS_DIR).join(key)
+}
+
+/// Saves the screen in `palette`'s colors as a new screenshot of the game, returns its path.
+pub fn take(key: &str, chip8: &Chip8, palette: &Palette) -> std::io::Result<PathBuf> {
+    let dir = dir(key);
+
+    fs::create_dir_all(&dir)?;
+
+    // a second one within the same second gets a suffix
+    let now = rom_library::now();
+    let path = (1..)
+        .map(|n| match n {
+            1 => dir.join(format!("{now}.png")),
+            n => dir.join(format!("{now}-{n}.png")),
+        })
+        .find(|path| !path.exists())
+        .unwrap();
+
+    let resolution = chip8.resolution();
+    let (width, height) = (resolution.width * PNG_SCALE, resolution.height * PNG_SCALE);
+    let (plane0, plane1) = (chip8.get_plane(0), chip8.get_plane(1));
+
+    let mut image = Vec::with_capacity(width * height * 3);
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y / PNG_SCALE) * resolution.width + x / PNG_SCALE;
+            let color = palette.color(usize::from(plane0[i]) | usize::from(plane1[i]) << 1);
+
+            image.extend([color.r, color.g, color.b]);
+        }
+    }
+
+    let file = BufWriter::new(fs::File::create(&path)?);
+
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&image))
+        .map_err(std::io::Error::other)?;
+
+    Ok(path)
+}
+
+/// The screenshots of the game with library key `key`, oldest first.
+pub fn list(key: &str) -> Vec<Screenshot> {
+    let mut screenshots: Vec<Screenshot> = fs::read_dir(dir(key))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+                .map(|path| Screenshot {
+                    note: fs::read_to_string(Screenshot::note_path(&path)).unwrap_or_default(),
+                    path,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // `-2` suffixes come after the first of their second, and `-10` after `-9`
+    screenshots.sort_by_key(|shot| (shot.taken(), shot.path.as_os_str().len(), shot.path.clone()));
+
+    screenshots
+}
+
+/// The screenshots of a game in the ROM browser's details view (D): Left/Right go through
+/// them and N writes a note for the one shown, Return saves it.
+pub struct Gallery {
+    pub screenshots: Vec<Screenshot>,
+    pub selected: usize,
+    pub note: Option<String>, // being written, replaces the shown one's when saved
+}
+
+impl Gallery {
+    pub fn open(key: &str) -> Self {
+        Self {
+            screenshots: list(key),
+            selected: 0,
+            note: None,
+        }
+    }
+
+    pub fn current(&self) -> Option<&Screenshot> {
+        self.screenshots.get(self.selected)
+    }
+
+    pub fn step(&mut self, step: isize) {
+        let last = (self.screenshots.len() as isize - 1).max(0);
+
+        self.selected = (self.selected as isize + step).clamp(0, last) as usize;
+    }
+
+    pub fn edit_note(&mut self) {
+        self.note = self.current().map(|shot| shot.note.clone());
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        if let Some(note) = &mut self.note {
+            // a line, as the HUD font draws it
+            note.extend(text.chars().filter(|c| !c.is_control()));
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(note) = &mut self.note {
+            note.pop();
+        }
+    }
+
+    pub fn save_note(&mut self) {
+        let (Some(note), Some(shot)) = (self.note.take(), self.screenshots.get_mut(self.selected))
+        else {
+            return;
+        };
+
+        if let Err(err) = shot.set_note(note.trim()) {
+            println!("Could not save the note of {}: {err}", shot.path.display());
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::core::Chip8;
+use chip8_core::savestate::SaveState;
+use chip8_tools::file_io;
+use serde_json::{json, Value};
+
+use crate::managers::palette::{self, Palette};
+use crate::managers::rom_library::RomLibrary;
+
+// inside the config directory
+const SESSION_FILE: &str = "session.json";
+const SESSION_STATE_FILE: &str = "session.state";
+
+/// Position and size of the window in desktop coordinates.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+}
+
+/// Everything needed to pick up where the last run left off (`--restore-session`): the
+/// window, the game, the speed and the palette, saved as JSON in the config directory,
+/// and the machine state, saved next to it. The state is only resumed when the game still
+/// has the same ROM.
+pub struct Session {
+    pub window: WindowGeometry,
+    pub game_path: String,
+    pub sub_game: usize,
+    pub instructions_per_frame: f64,
+    pub palette: Palette,
+    rom_key: String,
+    rng_state: u64,
+}
+
+impl Session {
+    fn path() -> PathBuf {
+        file_io::config_dir().join(SESSION_FILE)
+    }
+
+    fn state_path() -> PathBuf {
+        file_io::config_dir().join(SESSION_STATE_FILE)
+    }
+
+    pub fn new(
+        window: WindowGeometry,
+        game_path: &str,
+        sub_game: usize,
+        instructions_per_frame: f64,
+        palette: Palette,
+    ) -> Self {
+        // relative paths would break when the next run starts from elsewhere
+        let game_path = fs::canonicalize(game_path)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| game_path.to_string());
+
+        Self {
+            window,
+            game_path,
+            sub_game,
+            instructions_per_frame,
+            palette,
+            rom_key: String::new(),
+            rng_state: 0,
+        }
+    }
+
+    /// The last saved session, `None` when there is none or it can't be read.
+    pub fn load() -> Option<Self> {
+        let text = fs::read_to_string(Self::path()).ok()?;
+
+        let json: Value = match serde_json::from_str(&text) {
+            Ok(json) => json,
+            Err(err) => {
+                println!("Ignoring unreadable session: {err}");
+                return None;
+            }
+        };
+
+        let window = &json["window"];
+        let color = |name: &str| json["palette"][name].as_str().and_then(palette::parse_hex);
+        let default_palette = Palette::default();
+
+        Some(Self {
+            window: WindowGeometry {
+                x: window["x"].as_i64()? as i32,
+                y: window["y"].as_i64()? as i32,
+                width: window["width"].as_u64()? as u32,
+                height: window["height"].as_u64()? as u32,
+                fullscreen: window["fullscreen"].as_bool().unwrap_or(false),
+            },
+            game_path: json["game"].as_str()?.to_string(),
+            sub_game: json["sub_game"].as_u64().unwrap_or(0) as usize,
+            instructions_per_frame: json["instructions_per_frame"].as_f64()?,
+            palette: Palette {
+                background: color("background").unwrap_or(default_palette.background),
+                foreground: color("foreground").unwrap_or(default_palette.foreground),
+                foreground2: color("foreground2").unwrap_or(default_palette.foreground2),
+                blend: color("blend").unwrap_or(default_palette.blend),
+            },
+            rom_key: json["rom"].as_str().unwrap_or_default().to_string(),
+            rng_state: json["rng_state"].as_u64().unwrap_or(1),
+        })
+    }
+
+    /// Saves the session along with the state of `chip8`, which runs `rom`.
+    pub fn save(&mut self, chip8: &Chip8, rom: &[u8]) {
+        self.rom_key = RomLibrary::key(rom);
+        self.rng_state = chip8.get_rng_state();
+
+        let json = json!({
+            "window": {
+                "x": self.window.x,
+                "y": self.window.y,
+                "width": self.window.width,
+                "height": self.window.height,
+                "fullscreen": self.window.fullscreen,
+            },
+            "game": self.game_path,
+            "sub_game": self.sub_game,
+            "rom": self.rom_key,
+            "instructions_per_frame": self.instructions_per_frame,
+            "rng_state": self.rng_state,
+            "palette": {
+                "background": palette::to_hex(self.palette.background),
+                "foreground": palette::to_hex(self.palette.foreground),
+                "foreground2": palette::to_hex(self.palette.foreground2),
+                "blend": palette::to_hex(self.palette.blend),
+            },
+        });
+
+        let saved = fs::create_dir_all(file_io::config_dir())
+            .and_then(|_| fs::write(Self::path(), serde_json::to_string_pretty(&json).unwrap()))
+            .and_then(|_| {
+                let state = file_io::pack_state(chip8.save_state().to_bytes());
+
+                fs::write(Self::state_path(), state)
+            });
+
+        if let Err(err) = saved {
+            println!("Could not save the session: {err}");
+        }
+    }
+
+    /// Puts `chip8`, freshly loaded with `rom`, back in the saved state. Returns whether it
+    /// did, which it doesn't when the game changed since.
+    pub fn resume(&self, chip8: &mut Chip8, rom: &[u8]) -> bool {
+        if self.rom_key != RomLibrary::key(rom) {
+            println!(
+                "{} changed since the last session, starting it over",
+                self.game_path
+            );
+            return false;
+        }
+
+        let state = file_io::read_file(Self::state_path())
+            .map_err(|err| err.to_string())
+            .and_then(|data| SaveState::from_bytes(&data).map_err(|err| err.to_string()));
+
+        match state {
+            Ok(state) => {
+                chip8.load_state(&state);
+                chip8.set_rng_state(self.rng_state);
+
+                true
+            }
+
+            Err(err) => {
+                println!("Could not resume the last session: {err}");
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_tools::file_io;
+use sha1::{Digest, Sha1};
+
+// inside the config directory, the key is the file's content
+const KEY_FILE: &str = "signing.key";
+
+// appended to signed files: "C8SG" followed by the HMAC-SHA1 of everything before it
+const MAGIC: &[u8; 4] = b"C8SG";
+const MAC_SIZE: usize = 20;
+const TRAILER_SIZE: usize = MAGIC.len() + MAC_SIZE;
+
+const HMAC_BLOCK_SIZE: usize = 64;
+
+#[derive(Debug)]
+pub enum SignatureError {
+    Unsigned,
+    Mismatch,
+}
+
+impl Display for SignatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::Unsigned => write!(f, "the file is not signed"),
+            SignatureError::Mismatch => write!(
+                f,
+                "the signature doesn't match, the file was changed or signed with another key"
+            ),
+        }
+    }
+}
+
+/// Optional signing of save states and movies, for tournaments: when the config directory
+/// has a `signing.key`, every state and movie saved gets an HMAC-SHA1 of its content
+/// appended, and loading refuses files that aren't signed with the same key. Organizers
+/// give the key to the machines players use and check the submitted files with it.
+///
+/// Octo `.json` states are left out: a signature would make them unreadable to Octo, so
+/// they are saved unsigned and load unchecked. Runs are checked on their movies, which
+/// record a state being loaded.
+///
+/// Without a key files are saved unsigned, and signed files still load.
+pub struct Signer {
+    key: Option<Vec<u8>>,
+}
+
+impl Signer {
+    fn key_path() -> PathBuf {
+        file_io::config_dir().join(KEY_FILE)
+    }
+
+    pub fn load() -> Self {
+        let key = fs::read_to_string(Self::key_path())
+            .ok()
+            .map(|key| key.trim().as_bytes().to_vec())
+            .filter(|key| !key.is_empty());
+
+        Self { key }
+    }
+
+    /// `data` with its signature appended, unchanged without a key.
+    pub fn sign(&self, mut data: Vec<u8>) -> Vec<u8> {
+        if let Some(key) = &self.key {
+            let mac = hmac(key, &data);
+
+            data.extend_from_slice(MAGIC);
+            data.extend_from_slice(&mac);
+        }
+
+        data
+    }
+
+    /// The content of a file saved by `sign`, once its signature was checked.
+    pub fn verify<'a>(&self, data: &'a [u8]) -> Result<&'a [u8], SignatureError> {
+        let split = data
+            .len()
+            .checked_sub(TRAILER_SIZE)
+            .filter(|split| &data[*split..*split + MAGIC.len()] == MAGIC);
+
+        let Some(key) = &self.key else {
+            return Ok(split.map_or(data, |split| &data[..split]));
+        };
+
+        let split = split.ok_or(SignatureError::Unsigned)?;
+        let (content, trailer) = data.split_at(split);
+
+        // every byte is compared, how long it takes tells nothing about the key
+        let difference = hmac(key, content)
+            .iter()
+            .zip(&trailer[MAGIC.len()..])
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+
+        if difference != 0 {
+            return Err(SignatureError::Mismatch);
+        }
+
+        Ok(content)
+    }
+}
+
+// RFC 2104
+fn hmac(key: &[u8], data: &[u8]) -> [u8; MAC_SIZE] {
+    let mut block = [0; HMAC_BLOCK_SIZE];
+
+    if key.len() > HMAC_BLOCK_SIZE {
+        block[..MAC_SIZE].copy_from_slice(&Sha1::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let pad = |byte: u8| block.map(|key_byte| key_byte ^ byte);
+
+    let inner = Sha1::new()
+        .chain_update(pad(0x36))
+        .chain_update(data)
+        .finalize();
+
+    Sha1::new()
+        .chain_update(pad(0x5C))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use std::path::Path;
+
+use chip8_core::constants::NUM_KEYS;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use serde_json::Value;
+
+use crate::constants::WHITE_COLOR;
+use crate::managers::hud;
+use crate::managers::palette;
+use crate::managers::rom_browser::RomBrowser;
+
+const DEFAULT_LABEL_SCALE: u32 = 4;
+
+/// A frame drawn around the screen, e.g. to brand a streamer's window, given with
+/// `--skin skin.json`. Positions are in pixels of the background image, which the window
+/// keeps the aspect ratio of; images are PNG files next to the skin file:
+///
+/// ```json
+/// {
+///     "background": "frame.png",
+///     "screen": { "x": 80, "y": 60, "width": 640, "height": 320 },
+///     "logo": { "image": "logo.png", "x": 16, "y": 16 },
+///     "keypad_labels": { "scale": 4, "color": "FFCC00", "keys": { "1": [760, 80], "A": [760, 120] } }
+/// }
+/// ```
+///
+/// Keypad labels show the key each keypad button is bound to. Without a background image
+/// the skin needs a `width` and `height` of its own.
+pub struct Skin<'a> {
+    width: u32,
+    height: u32,
+    background: Option<Texture<'a>>,
+    screen: Rect,
+    logo: Option<(Texture<'a>, Rect)>,
+    keypad_labels: Vec<(usize, i32, i32)>, // keypad button and top left corner
+    label_scale: u32,
+    label_color: Color,
+}
+
+impl<'a> Skin<'a> {
+    pub fn load(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        path: &Path,
+    ) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let json: Value = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let image = |name: &Value| -> Result<Option<(Texture<'a>, u32, u32)>, String> {
+            let Some(name) = name.as_str() else {
+                return Ok(None);
+            };
+
+            Self::texture(texture_creator, &dir.join(name)).map(Some)
+        };
+
+        let background = image(&json["background"])?;
+
+        let (width, height) = match &background {
+            Some((_, width, height)) => (*width, *height),
+            None => (
+                Self::number(&json["width"]).ok_or("no background image, width or height")?,
+                Self::number(&json["height"]).ok_or("no background image, width or height")?,
+            ),
+        };
+
+        let screen = &json["screen"];
+        let screen = match (
+            Self::number(&screen["x"]),
+            Self::number(&screen["y"]),
+            Self::number(&screen["width"]),
+            Self::number(&screen["height"]),
+        ) {
+            (Some(x), Some(y), Some(w), Some(h)) if w > 0 && h > 0 => {
+                Rect::new(x as i32, y as i32, w, h)
+            }
+            _ => return Err("screen needs an x, y, width and height".to_string()),
+        };
+
+        let logo = &json["logo"];
+        let logo = image(&logo["image"])?.map(|(texture, w, h)| {
+            let x = Self::number(&logo["x"]).unwrap_or(0);
+            let y = Self::number(&logo["y"]).unwrap_or(0);
+
+            (texture, Rect::new(x as i32, y as i32, w, h))
+        });
+
+        let labels = &json["keypad_labels"];
+        let mut keypad_labels = Vec::new();
+
+        for (key, pos) in labels["keys"].as_object().into_iter().flatten() {
+            let button = usize::from_str_radix(key, 16)
+                .ok()
+                .filter(|button| *button < NUM_KEYS);
+
+            match (button, Self::number(&pos[0]), Self::number(&pos[1])) {
+                (Some(button), Some(x), Some(y)) => {
+                    keypad_labels.push((button, x as i32, y as i32))
+                }
+                _ => println!("Ignoring invalid keypad label in skin: {key}"),
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            background: background.map(|(texture, _, _)| texture),
+            screen,
+            logo,
+            keypad_labels,
+            label_scale: Self::number(&labels["scale"]).unwrap_or(DEFAULT_LABEL_SCALE),
+            label_color: labels["color"]
+                .as_str()
+                .and_then(palette::parse_hex)
+                .unwrap_or(WHITE_COLOR),
+        })
+    }
+
+    fn number(json: &Value) -> Option<u32> {
+        json.as_u64().and_then(|number| number.try_into().ok())
+    }
+
+    // the texture and its width and height
+    fn texture(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        path: &Path,
+    ) -> Result<(Texture<'a>, u32, u32), String> {
+        let (width, height, rgba) = RomBrowser::decode_png(&path.to_string_lossy())
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+
+        let mut texture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGBA32, width, height)
+            .map_err(|err| err.to_string())?;
+
+        texture
+            .update(None, &rgba, width as usize * 4)
+            .map_err(|err| err.to_string())?;
+
+        Ok((texture, width, height))
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // Scale and top left corner of the skin inside a drawable area of `output` pixels,
+    // centered like the screen is without a skin.
+    fn layout(&self, output: (u32, u32)) -> (f32, i32, i32) {
+        let scale = (output.0 as f32 / self.width as f32).min(output.1 as f32 / self.height as f32);
+
+        let left = (output.0 as f32 - self.width as f32 * scale) / 2.0;
+        let top = (output.1 as f32 - self.height as f32 * scale) / 2.0;
+
+        (scale, left as i32, top as i32)
+    }
+
+    // `rect` of the skin in output pixels
+    fn place(&self, output: (u32, u32), rect: Rect) -> Rect {
+        let (scale, left, top) = self.layout(output);
+
+        let x = left + (rect.x() as f32 * scale) as i32;
+        let y = top + (rect.y() as f32 * scale) as i32;
+
+        Rect::new(
+            x,
+            y,
+            ((rect.width() as f32 * scale) as u32).max(1),
+            ((rect.height() as f32 * scale) as u32).max(1),
+        )
+    }
+
+    /// Where the screen goes inside a drawable area of `output` pixels.
+    pub fn screen_area(&self, output: (u32, u32)) -> Rect {
+        self.place(output, self.screen)
+    }
+
+    /// Draws the background, logo and keypad labels, `key_name` gives the label of a
+    /// keypad button. The screen is drawn over them.
+    pub fn draw(&self, canvas: &mut WindowCanvas, key_name: impl Fn(usize) -> String) {
+        let output = canvas.output_size().unwrap();
+
+        if let Some(background) = &self.background {
+            let area = self.place(output, Rect::new(0, 0, self.width, self.height));
+            canvas.copy(background, None, area).unwrap();
+        }
+
+        if let Some((logo, rect)) = &self.logo {
+            canvas.copy(logo, None, self.place(output, *rect)).unwrap();
+        }
+
+        let (scale, _, _) = self.layout(output);
+        let label_scale = ((self.label_scale as f32 * scale).round() as u32).max(1);
+
+        canvas.set_draw_color(self.label_color);
+
+        for (button, x, y) in &self.keypad_labels {
+            let at = self.place(output, Rect::new(*x, *y, 1, 1));
+            let name = key_name(*button).to_uppercase();
+
+            hud::draw_text(canvas, &name, at.x(), at.y(), label_scale);
+        }
+    }
+}
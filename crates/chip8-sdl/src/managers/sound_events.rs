@@ -0,0 +1,134 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::UdpSocket;
+
+const DEFAULT_CHANNEL: u8 = 1;
+const DEFAULT_NOTE: u8 = 60; // middle C
+const VELOCITY: u8 = 100;
+
+// MIDI status bytes, the channel goes in the low nibble
+const NOTE_OFF: u8 = 0x80;
+const NOTE_ON: u8 = 0x90;
+
+const OSC_ADDRESS: &str = "/chip8/sound";
+
+enum Output {
+    Midi(File),
+    Osc(UdpSocket),
+}
+
+/// Tells other software or hardware when the buzzer starts and stops (`--sound-events`),
+/// e.g. to trigger a synthesizer or an installation from a game:
+/// - `midi:DEVICE` writes a note on and a note off to a raw MIDI device, e.g.
+///   `/dev/snd/midiC1D0`, on the channel (1-16) and note (0-127) of `--sound-events-channel`
+///   and `--sound-events-note`
+/// - `osc:HOST:PORT` sends OSC messages over UDP: `/chip8/sound` with the note and 1 when
+///   it starts, the note and 0 when it stops
+pub struct SoundEvents {
+    output: Option<Output>, // None once writing failed
+    channel: u8,            // 0-15, as in the status byte
+    note: u8,
+    playing: bool,
+}
+
+impl SoundEvents {
+    pub fn open(target: &str, channel: Option<&str>, note: Option<&str>) -> Result<Self, String> {
+        let channel = match channel {
+            Some(channel) => channel
+                .parse::<u8>()
+                .ok()
+                .filter(|channel| (1..=16).contains(channel))
+                .ok_or_else(|| format!("{channel} is not a MIDI channel, 1 to 16"))?,
+            None => DEFAULT_CHANNEL,
+        };
+
+        let note = match note {
+            Some(note) => note
+                .parse::<u8>()
+                .ok()
+                .filter(|note| *note < 128)
+                .ok_or_else(|| format!("{note} is not a MIDI note, 0 to 127"))?,
+            None => DEFAULT_NOTE,
+        };
+
+        let output = if let Some(device) = target.strip_prefix("midi:") {
+            let file = OpenOptions::new()
+                .write(true)
+                .open(device)
+                .map_err(|err| format!("{device}: {err}"))?;
+
+            Output::Midi(file)
+        } else if let Some(addr) = target.strip_prefix("osc:") {
+            let socket = UdpSocket::bind("0.0.0.0:0")
+                .and_then(|socket| socket.connect(addr).map(|_| socket))
+                .map_err(|err| format!("{addr}: {err}"))?;
+
+            Output::Osc(socket)
+        } else {
+            return Err(format!("{target} is neither midi:DEVICE nor osc:HOST:PORT"));
+        };
+
+        Ok(Self {
+            output: Some(output),
+            channel: channel - 1,
+            note,
+            playing: false,
+        })
+    }
+
+    /// Called every frame with whether the buzzer sounds, sends an event when that changed.
+    pub fn update(&mut self, playing: bool) {
+        if playing == self.playing {
+            return;
+        }
+
+        self.playing = playing;
+
+        let Some(output) = &mut self.output else {
+            return;
+        };
+
+        let sent = match output {
+            Output::Midi(file) => {
+                let message = if playing {
+                    [NOTE_ON | self.channel, self.note, VELOCITY]
+                } else {
+                    [NOTE_OFF | self.channel, self.note, 0]
+                };
+
+                file.write_all(&message).and_then(|_| file.flush())
+            }
+
+            Output::Osc(socket) => socket
+                .send(&Self::osc_message(self.note, playing))
+                .map(|_| ()),
+        };
+
+        if let Err(err) = sent {
+            println!("Sound events stopped: {err}");
+            self.output = None;
+        }
+    }
+
+    // an OSC message with two int32 arguments; strings are zero terminated and padded to 4 bytes
+    fn osc_message(note: u8, playing: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        for text in [OSC_ADDRESS, ",ii"] {
+            data.extend_from_slice(text.as_bytes());
+            data.resize((data.len() / 4 + 1) * 4, 0);
+        }
+
+        data.extend_from_slice(&(note as i32).to_be_bytes());
+        data.extend_from_slice(&(playing as i32).to_be_bytes());
+
+        data
+    }
+}
+
+// a note left on would sound forever on the receiving end
+impl Drop for SoundEvents {
+    fn drop(&mut self) {
+        self.update(false);
+    }
+}
@@ -0,0 +1,242 @@
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use chip8_core::core::Chip8;
+use chip8_core::resolution::Resolution;
+use tungstenite::{Message, WebSocket};
+
+// Spectators never talk back, a slow one must not stall the others for long
+const SPECTATOR_TIMEOUT: Duration = Duration::from_secs(1);
+
+// how long the server thread waits for a frame before checking for new spectators
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+// everything a spectator sees for a single frame
+struct SpectatorFrame {
+    screen: Vec<bool>,
+    resolution: Resolution,
+    state: String,
+}
+
+struct Spectator {
+    socket: WebSocket<TcpStream>,
+    synced: bool, // false until the spectator received a keyframe and the register panel
+}
+
+/// Streams the live framebuffer, the register panel and the OSD text to any number of
+/// WebSocket clients (see `web/spectator.html`).
+///
+/// Protocol:
+/// - binary message: frame. The first byte is 1 for a keyframe (the client clears its screen
+///   first) and 0 for a diff. Keyframes go on with the big endian u16 width and height of the
+///   screen, and are sent again when the game changes resolution. Then come big endian u16 run
+///   lengths over the pixels, alternating between unchanged and flipped runs and starting
+///   with an unchanged one.
+/// - text message: JSON register panel and OSD text, only sent when something changed.
+pub struct SpectatorServer {
+    sender: Sender<SpectatorFrame>,
+}
+
+impl SpectatorServer {
+    pub fn start(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+
+        // accept is polled from the server thread in between frames
+        listener.set_nonblocking(true)?;
+
+        println!("Spectators can connect to ws://{}", listener.local_addr()?);
+
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || Self::serve(listener, receiver));
+
+        Ok(Self { sender })
+    }
+
+    pub fn publish(&self, chip8: &Chip8, osd: &str) {
+        let frame = SpectatorFrame {
+            screen: chip8.get_screen().to_vec(),
+            resolution: chip8.resolution(),
+            state: Self::encode_state(chip8, osd),
+        };
+
+        // the server thread only stops if it panicked, spectating is best effort
+        let _ = self.sender.send(frame);
+    }
+
+    fn serve(listener: TcpListener, receiver: Receiver<SpectatorFrame>) {
+        let mut spectators: Vec<Spectator> = Vec::new();
+
+        let mut resolution = Resolution::default();
+        let mut screen = vec![false; resolution.pixels()];
+        let mut state = String::new();
+
+        loop {
+            let frame = match receiver.recv_timeout(ACCEPT_INTERVAL) {
+                Ok(frame) => Some(frame),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+
+            Self::accept_spectators(&listener, &mut spectators);
+
+            let (diff, state_changed) = match frame {
+                Some(frame) if frame.resolution != resolution => {
+                    // everyone starts over with a keyframe of the new size
+                    for spectator in &mut spectators {
+                        spectator.synced = false;
+                    }
+
+                    resolution = frame.resolution;
+                    screen = frame.screen;
+                    state = frame.state;
+
+                    (None, false)
+                }
+                Some(frame) => {
+                    let diff = Self::encode_frame(&screen, &frame.screen, None);
+                    let state_changed = frame.state != state;
+
+                    screen = frame.screen;
+                    state = frame.state;
+
+                    (diff, state_changed)
+                }
+
+                None => (None, false),
+            };
+
+            if spectators.is_empty() {
+                continue;
+            }
+
+            let blank = vec![false; screen.len()];
+
+            spectators.retain_mut(|spectator| {
+                let mut messages = Vec::new();
+
+                if !spectator.synced {
+                    let keyframe =
+                        Self::encode_frame(&blank, &screen, Some(resolution)).unwrap_or_default();
+
+                    messages.push(Message::Binary(keyframe));
+                    messages.push(Message::Text(state.clone()));
+
+                    spectator.synced = true;
+                } else {
+                    if let Some(diff) = &diff {
+                        messages.push(Message::Binary(diff.clone()));
+                    }
+
+                    if state_changed {
+                        messages.push(Message::Text(state.clone()));
+                    }
+                }
+
+                // drop spectators that disconnected or can not keep up
+                messages
+                    .into_iter()
+                    .all(|message| spectator.socket.send(message).is_ok())
+            });
+        }
+    }
+
+    fn accept_spectators(listener: &TcpListener, spectators: &mut Vec<Spectator>) {
+        while let Ok((stream, addr)) = listener.accept() {
+            let configured = stream
+                .set_nonblocking(false)
+                .and_then(|_| stream.set_read_timeout(Some(SPECTATOR_TIMEOUT)))
+                .and_then(|_| stream.set_write_timeout(Some(SPECTATOR_TIMEOUT)));
+
+            if configured.is_err() {
+                continue;
+            }
+
+            match tungstenite::accept(stream) {
+                Ok(socket) => {
+                    println!("Spectator connected from {addr}");
+
+                    spectators.push(Spectator {
+                        socket,
+                        synced: false,
+                    });
+                }
+
+                Err(err) => println!("Spectator handshake with {addr} failed: {err}"),
+            }
+        }
+    }
+
+    // a keyframe when the resolution is given, None when nothing changed and it is not one
+    fn encode_frame(
+        previous: &[bool],
+        current: &[bool],
+        keyframe: Option<Resolution>,
+    ) -> Option<Vec<u8>> {
+        let mut data = vec![keyframe.is_some() as u8];
+
+        if let Some(resolution) = keyframe {
+            data.extend_from_slice(&(resolution.width as u16).to_be_bytes());
+            data.extend_from_slice(&(resolution.height as u16).to_be_bytes());
+        }
+
+        let mut flipped = false;
+        let mut run: u16 = 0;
+        let mut changed = false;
+
+        for (old, new) in previous.iter().zip(current) {
+            if (old != new) != flipped {
+                data.extend_from_slice(&run.to_be_bytes());
+
+                flipped = !flipped;
+                run = 0;
+                changed = true;
+            }
+
+            run += 1;
+        }
+
+        data.extend_from_slice(&run.to_be_bytes());
+
+        if changed || keyframe.is_some() {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
+    fn encode_state(chip8: &Chip8, osd: &str) -> String {
+        let join = |values: Vec<String>| values.join(",");
+
+        let registers = join(chip8.get_registers().iter().map(|v| v.to_string()).collect());
+        let stack = join(chip8.get_stack().iter().map(|v| v.to_string()).collect());
+
+        format!(
+            "{{\"pc\":{},\"i\":{},\"v\":[{}],\"stack\":[{}],\"dt\":{},\"st\":{},\"osd\":\"{}\"}}",
+            chip8.get_program_counter(),
+            chip8.get_i_register(),
+            registers,
+            stack,
+            chip8.get_delay_timer(),
+            chip8.get_sound_timer(),
+            Self::escape_json(osd),
+        )
+    }
+
+    fn escape_json(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+
+        for c in text.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+
+        escaped
+    }
+}
@@ -0,0 +1,211 @@
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, WindowCanvas};
+
+use crate::managers::hud;
+
+// sprites are a byte wide and DXYN draws up to 15 rows
+const WIDTH: usize = 8;
+pub const MAX_HEIGHT: usize = 15;
+const DEFAULT_HEIGHT: usize = 8;
+
+const BACKGROUND_COLOR: Color = Color::RGBA(0, 0, 0, 220);
+const GRID_COLOR: Color = Color::RGB(40, 40, 40);
+const PIXEL_COLOR: Color = Color::RGB(255, 255, 255);
+const CURSOR_COLOR: Color = Color::RGB(255, 200, 0);
+const LABEL_COLOR: Color = Color::RGB(120, 120, 120);
+
+/// What the desktop does for the sprite editor, which has no access to the machine.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpriteAction {
+    /// Read the sprite from memory at I, see `load`.
+    Load,
+    /// Write the sprite into memory at I.
+    Write,
+    /// Put `db_line` on the clipboard.
+    Copy,
+}
+
+/// Sprite editor (F12): a grid of 8 pixels by up to 15 rows drawn over the left of the
+/// screen, to try out sprites on a running game. The arrow keys move the cursor and Space
+/// flips the pixel under it, or the mouse draws with the left button and erases with the
+/// right; `+`/`-` change the height. L loads the sprite at I, Return writes it there and C
+/// copies its bytes as an assembler `DB` line.
+pub struct SpriteEditor {
+    rows: Vec<u8>, // top first, the leftmost pixel in the top bit
+    cursor: (usize, usize),
+}
+
+impl Default for SpriteEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpriteEditor {
+    pub fn new() -> Self {
+        Self {
+            rows: vec![0; DEFAULT_HEIGHT],
+            cursor: (0, 0),
+        }
+    }
+
+    /// The sprite's bytes, a row each.
+    pub fn bytes(&self) -> &[u8] {
+        &self.rows
+    }
+
+    /// Replaces the rows with `bytes`, as many as the sprite is high.
+    pub fn load(&mut self, bytes: &[u8]) {
+        for (row, byte) in self.rows.iter_mut().zip(bytes) {
+            *row = *byte;
+        }
+    }
+
+    /// The sprite as an assembler line, e.g. `DB 0x60, 0xF0, 0x60`.
+    pub fn db_line(&self) -> String {
+        let bytes: Vec<String> = self.rows.iter().map(|row| format!("0x{row:02X}")).collect();
+
+        format!("DB {}", bytes.join(", "))
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        let mask = 0x80 >> x;
+
+        if on {
+            self.rows[y] |= mask;
+        } else {
+            self.rows[y] &= !mask;
+        }
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        self.rows[y] & (0x80 >> x) != 0
+    }
+
+    /// Applies a key, returns what the desktop has to do for it.
+    pub fn key_down(&mut self, key: Keycode) -> Option<SpriteAction> {
+        let (x, y) = self.cursor;
+        let height = self.rows.len();
+
+        match key {
+            Keycode::Left => self.cursor.0 = (x + WIDTH - 1) % WIDTH,
+            Keycode::Right => self.cursor.0 = (x + 1) % WIDTH,
+            Keycode::Up => self.cursor.1 = (y + height - 1) % height,
+            Keycode::Down => self.cursor.1 = (y + 1) % height,
+
+            Keycode::Space => self.set_pixel(x, y, !self.pixel(x, y)),
+
+            Keycode::Plus | Keycode::KpPlus | Keycode::Equals if height < MAX_HEIGHT => {
+                self.rows.push(0);
+            }
+
+            Keycode::Minus | Keycode::KpMinus if height > 1 => {
+                self.rows.pop();
+                self.cursor.1 = y.min(height - 2);
+            }
+
+            Keycode::L => return Some(SpriteAction::Load),
+            Keycode::Return => return Some(SpriteAction::Write),
+            Keycode::C => return Some(SpriteAction::Copy),
+
+            _ => (),
+        }
+
+        None
+    }
+
+    /// Size of the panel drawn with `cell` pixel cells, its labels included.
+    pub fn size(&self, cell: u32, text_scale: u32) -> (u32, u32) {
+        let label_width = hud::text_width(" 00", text_scale);
+        let legend_height = (hud::GLYPH_HEIGHT + 2) * text_scale;
+
+        (
+            WIDTH as u32 * cell + label_width,
+            self.rows.len() as u32 * cell + legend_height,
+        )
+    }
+
+    /// The pixel at (`x`, `y`) of a panel drawn at (`left`, `top`), see `draw`.
+    pub fn pixel_at(
+        &self,
+        x: i32,
+        y: i32,
+        left: i32,
+        top: i32,
+        cell: u32,
+    ) -> Option<(usize, usize)> {
+        let column = (x - left).div_euclid(cell as i32);
+        let row = (y - top).div_euclid(cell as i32);
+
+        let column = usize::try_from(column)
+            .ok()
+            .filter(|column| *column < WIDTH)?;
+        let row = usize::try_from(row)
+            .ok()
+            .filter(|row| *row < self.rows.len())?;
+
+        Some((column, row))
+    }
+
+    /// Draws the panel with its top left corner at (`x`, `y`): the grid, the byte of each
+    /// row on its right and the height below.
+    pub fn draw(&self, canvas: &mut WindowCanvas, x: i32, y: i32, cell: u32, text_scale: u32) {
+        let (width, height) = self.size(cell, text_scale);
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(BACKGROUND_COLOR);
+        canvas.fill_rect(Rect::new(x, y, width, height)).unwrap();
+
+        let label_x = x + (WIDTH as u32 * cell) as i32;
+        let inset = cell / 8; // shows the grid between pixels
+
+        for (row, byte) in self.rows.iter().enumerate() {
+            let top = y + (row as u32 * cell) as i32;
+
+            for column in 0..WIDTH {
+                let left = x + (column as u32 * cell) as i32;
+                let color = if self.pixel(column, row) {
+                    PIXEL_COLOR
+                } else {
+                    GRID_COLOR
+                };
+
+                canvas.set_draw_color(color);
+                canvas
+                    .fill_rect(Rect::new(
+                        left + inset as i32,
+                        top + inset as i32,
+                        (cell - 2 * inset).max(1),
+                        (cell - 2 * inset).max(1),
+                    ))
+                    .unwrap();
+            }
+
+            canvas.set_draw_color(LABEL_COLOR);
+            hud::draw_text(canvas, &format!(" {byte:02X}"), label_x, top, text_scale);
+        }
+
+        let (column, row) = self.cursor;
+
+        canvas.set_draw_color(CURSOR_COLOR);
+        canvas
+            .draw_rect(Rect::new(
+                x + (column as u32 * cell) as i32,
+                y + (row as u32 * cell) as i32,
+                cell,
+                cell,
+            ))
+            .unwrap();
+
+        canvas.set_draw_color(LABEL_COLOR);
+        hud::draw_text(
+            canvas,
+            &format!("8X{}", self.rows.len()),
+            x,
+            y + (self.rows.len() as u32 * cell + text_scale) as i32,
+            text_scale,
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use serde_json::Value;
+
+use crate::managers::library_sync;
+
+// the newest published release, drafts and pre-releases left out
+const RELEASE_FEED: &str =
+    "https://api.github.com/repos/Yassen-Higazi/chip8-emulator/releases/latest";
+
+/// A published build of the emulator.
+pub struct Release {
+    pub version: String, // without the tag's `v`
+    pub notes: String,
+    pub url: String, // of the release page
+}
+
+/// Asks the release feed for the newest build in the background, so a slow or missing
+/// network never holds up the emulator. Off unless `check_updates = true` is in the config
+/// file or `--check-updates` is given.
+pub struct UpdateCheck {
+    receiver: Receiver<Release>,
+}
+
+impl UpdateCheck {
+    /// Starts looking for a release newer than `current`, the version of the running binary.
+    pub fn start(current: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let current = current.to_string();
+
+        thread::spawn(move || match latest_release() {
+            Ok(release) if is_newer(&release.version, &current) => {
+                let _ = sender.send(release);
+            }
+            Ok(_) => {}
+            Err(err) => println!("Could not check for updates: {err}"),
+        });
+
+        Self { receiver }
+    }
+
+    /// The newer release once the check found one, at most once.
+    pub fn newer_release(&self) -> Option<Release> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+fn latest_release() -> Result<Release, String> {
+    let feed = library_sync::fetch(RELEASE_FEED)?;
+    let feed: Value =
+        serde_json::from_slice(&feed).map_err(|err| format!("{RELEASE_FEED}: {err}"))?;
+
+    let tag = feed["tag_name"]
+        .as_str()
+        .ok_or(format!("{RELEASE_FEED} names no release"))?;
+
+    Ok(Release {
+        version: tag.trim_start_matches('v').to_string(),
+        notes: feed["body"].as_str().unwrap_or_default().trim().to_string(),
+        url: feed["html_url"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Whether version `latest` comes after `current`, comparing their dotted numbers, e.g.
+/// 0.10.0 after 0.9.2. Anything after a `-` (0.2.0-beta) is left out.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> {
+        let release = version.split('-').next().unwrap_or_default();
+
+        release.split('.').map(|n| n.parse().unwrap_or(0)).collect()
+    };
+
+    let (mut latest, mut current) = (numbers(latest), numbers(current));
+    let len = latest.len().max(current.len());
+
+    // 1.2 is 1.2.0
+    latest.resize(len, 0);
+    current.resize(len, 0);
+
+    latest > current
+}
@@ -0,0 +1,102 @@
+use chip8_core::core::Chip8;
+use chip8_core::savestate::SaveState;
+use flate2::Crc;
+
+/// Determinism check (`--verify`): a second machine started from the same state runs in
+/// lockstep with the one being played, fed the same keys and instruction budget, and their
+/// state hashes must match after every frame. A mismatch means the core depends on something
+/// outside its state (uninitialized memory, a leaking random generator...), which would also
+/// break movies, so it stops the emulator with a description of what differs.
+pub struct Verifier {
+    chip8: Chip8,
+    frame: u64,
+}
+
+impl Verifier {
+    pub fn new(chip8: &Chip8) -> Self {
+        let mut verifier = Self {
+            chip8: Chip8::new(),
+            frame: 0,
+        };
+
+        verifier.chip8.set_sound_enabled(false);
+        verifier.sync(chip8);
+
+        verifier
+    }
+
+    /// Copies the state of `chip8`, after it was changed by something other than running
+    /// frames (loading a state, the ROM editor, stepping...).
+    pub fn sync(&mut self, chip8: &Chip8) {
+        self.chip8.set_start_addr(chip8.start_addr());
+        self.chip8.load_state(&chip8.save_state());
+        self.chip8.set_random_source(chip8.random_source());
+        self.chip8.set_rng_state(chip8.get_rng_state());
+        self.chip8.set_quirks(chip8.get_quirks());
+    }
+
+    /// Runs the same frame `chip8` just ran and compares the results.
+    pub fn run_frame(&mut self, ticks: u32, chip8: &Chip8) {
+        for (key, down) in chip8.get_keys().iter().enumerate() {
+            self.chip8.keypress(key, *down);
+        }
+
+        // the played machine stopped at the instruction that crashed it, so does this one
+        let _ = self.chip8.run_frame(ticks);
+        self.frame += 1;
+
+        if state_hash(&self.chip8) != state_hash(chip8) {
+            panic!(
+                "Determinism check failed at frame {}: {}",
+                self.frame,
+                describe_difference(&chip8.save_state(), &self.chip8.save_state())
+            );
+        }
+    }
+}
+
+fn state_hash(chip8: &Chip8) -> u32 {
+    let mut crc = Crc::new();
+
+    crc.update(&chip8.save_state().to_bytes());
+    crc.update(&chip8.get_rng_state().to_be_bytes());
+
+    crc.sum()
+}
+
+// the first part of the machine that differs
+fn describe_difference(a: &SaveState, b: &SaveState) -> String {
+    if a.pc != b.pc {
+        return format!("PC is {:03X} and {:03X}", a.pc, b.pc);
+    }
+
+    if let Some(reg) = (0..a.v_reg.len()).find(|reg| a.v_reg[*reg] != b.v_reg[*reg]) {
+        return format!("V{reg:X} is {:02X} and {:02X}", a.v_reg[reg], b.v_reg[reg]);
+    }
+
+    if a.i_reg != b.i_reg {
+        return format!("I is {:03X} and {:03X}", a.i_reg, b.i_reg);
+    }
+
+    if a.delay_timer_reg != b.delay_timer_reg || a.sound_timer_reg != b.sound_timer_reg {
+        return "the timers differ".to_string();
+    }
+
+    if a.stack != b.stack {
+        return format!("the stacks are {:03X?} and {:03X?}", a.stack, b.stack);
+    }
+
+    if let Some(addr) = (0..a.memory.len()).find(|addr| a.memory[*addr] != b.memory[*addr]) {
+        return format!("memory at {addr:03X} is {:02X} and {:02X}", a.memory[addr], b.memory[addr]);
+    }
+
+    if a.rpl_flags != b.rpl_flags {
+        return format!("the RPL flags are {:02X?} and {:02X?}", a.rpl_flags, b.rpl_flags);
+    }
+
+    if a.screen != b.screen || a.second_plane != b.second_plane {
+        return "the screens differ".to_string();
+    }
+
+    "the random number generators differ".to_string()
+}
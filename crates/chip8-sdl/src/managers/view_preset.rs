@@ -0,0 +1,139 @@
+use chip8_core::resolution::Resolution;
+use sdl2::keyboard::Keycode;
+
+// the largest of the 1x to 8x presets
+const MAX_SCALE: u32 = 8;
+
+/// How large the screen is drawn in the window, picked with Ctrl+1 to Ctrl+8,
+/// Ctrl+Plus/Minus or the view menu (Ctrl+V).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ViewPreset {
+    /// N times the size of the 64x32 screen whatever the game's resolution, so a high
+    /// resolution game's pixels are half as large. Smaller when the window is.
+    Scale(u32),
+    /// Every pixel the same whole number of device pixels, as large as fits.
+    #[default]
+    PixelPerfect,
+    /// As large as fits, keeping the aspect ratio; pixels may differ by a device pixel.
+    Fit,
+    /// The whole window, stretched.
+    Fill,
+}
+
+impl ViewPreset {
+    /// In menu order, from the smallest.
+    pub fn all() -> Vec<ViewPreset> {
+        (1..=MAX_SCALE)
+            .map(ViewPreset::Scale)
+            .chain([ViewPreset::PixelPerfect, ViewPreset::Fit, ViewPreset::Fill])
+            .collect()
+    }
+
+    /// `4x`, `pixel-perfect`, `fit` or `fill`.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            ViewPreset::Scale(factor) => format!("{factor}x"),
+            ViewPreset::PixelPerfect => "pixel-perfect".to_string(),
+            ViewPreset::Fit => "fit".to_string(),
+            ViewPreset::Fill => "fill".to_string(),
+        }
+    }
+
+    /// The next or previous preset in menu order, staying at the ends.
+    pub fn step(self, forward: bool) -> Self {
+        let all = Self::all();
+        let idx = all.iter().position(|preset| *preset == self).unwrap_or(0);
+
+        if forward {
+            all[(idx + 1).min(all.len() - 1)]
+        } else {
+            all[idx.saturating_sub(1)]
+        }
+    }
+
+    /// Device pixels per game pixel across and down for a screen of `size` pixels, as
+    /// displayed, with the game at `resolution`, in an `area` of device pixels with
+    /// `pixel_ratio` of them per window coordinate.
+    pub fn scale(
+        self,
+        size: (usize, usize),
+        resolution: Resolution,
+        area: (u32, u32),
+        pixel_ratio: u32,
+    ) -> (f32, f32) {
+        let fit_x = area.0 as f32 / size.0 as f32;
+        let fit_y = area.1 as f32 / size.1 as f32;
+        let fit = fit_x.min(fit_y);
+
+        match self {
+            ViewPreset::Scale(factor) => {
+                let lores_width = Resolution::LORES.width as f32;
+                let scale =
+                    factor as f32 * pixel_ratio as f32 * lores_width / resolution.width as f32;
+
+                (scale.min(fit), scale.min(fit))
+            }
+            ViewPreset::PixelPerfect => {
+                let scale = fit.floor().max(1.0);
+                (scale, scale)
+            }
+            ViewPreset::Fit => (fit, fit),
+            ViewPreset::Fill => (fit_x, fit_y),
+        }
+    }
+}
+
+/// The view menu (Ctrl+V): Up/Down picks a preset and Return applies it.
+pub struct ViewMenu {
+    selected: usize, // in ViewPreset::all() order
+}
+
+impl ViewMenu {
+    pub fn new(current: ViewPreset) -> Self {
+        Self {
+            selected: ViewPreset::all()
+                .iter()
+                .position(|preset| *preset == current)
+                .unwrap_or(0),
+        }
+    }
+
+    /// Applies a key, returns the preset picked with Return.
+    pub fn key_down(&mut self, key: Keycode) -> Option<ViewPreset> {
+        let count = ViewPreset::all().len();
+
+        match key {
+            Keycode::Up => self.selected = (self.selected + count - 1) % count,
+            Keycode::Down => self.selected = (self.selected + 1) % count,
+            Keycode::Return | Keycode::KpEnter => return Some(ViewPreset::all()[self.selected]),
+            _ => (),
+        }
+
+        None
+    }
+
+    /// A line per preset, the selected one marked with `>` and the current one in brackets.
+    pub fn hud_lines(&self, current: ViewPreset) -> Vec<String> {
+        let mut lines = vec!["VIEW".to_string()];
+
+        for (idx, preset) in ViewPreset::all().into_iter().enumerate() {
+            let marker = if idx == self.selected { ">" } else { " " };
+            let name = preset.name().to_uppercase();
+
+            lines.push(if preset == current {
+                format!("{marker} [{name}]")
+            } else {
+                format!("{marker}  {name}")
+            });
+        }
+
+        lines.push("RETURN APPLIES".to_string());
+        lines
+    }
+}
@@ -0,0 +1,12 @@
+// Files portable builds (`--features portable`, made by `chip8-emulator package`) carry
+// inside the executable. The fontsets and the beep tone are always built in.
+
+/// Games bundled by `chip8-emulator package`, as a cartridge.
+#[cfg(feature = "portable")]
+pub const GAMES: Option<&[u8]> = Some(include_bytes!("../../../portable/games.c8pak"));
+
+#[cfg(not(feature = "portable"))]
+pub const GAMES: Option<&[u8]> = None;
+
+/// Where the bundled games appear to come from, for the browser and save state names.
+pub const GAMES_PATH: &str = "bundled.c8pak";
@@ -0,0 +1,139 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+use chip8_core::clock::Clock;
+use chip8_core::core::Chip8;
+use chip8_core::quirks::Quirks;
+use chip8_core::reference::Reference;
+
+use crate::cartridge::Cartridge;
+use crate::quirk_detector;
+
+const DEFAULT_SECONDS: f64 = 3.0;
+
+// frames, or instructions for the reference, run between looks at the clock
+const BATCH_FRAMES: u32 = 1_000;
+const BATCH_INSTRUCTIONS: u32 = 10_000;
+
+/// The ways of running instructions there are to compare: the interpreter's dispatch and
+/// the reference interpreter, which is written to be obviously right rather than fast.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Strategy {
+    Match,
+    Reference,
+}
+
+impl Strategy {
+    const ALL: [Strategy; 2] = [Strategy::Match, Strategy::Reference];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::Match => "match",
+            Strategy::Reference => "reference",
+        }
+    }
+}
+
+/// `chip8-emulator bench ROM [SECONDS]` runs the first game of a rom or cartridge flat out
+/// under each way of running instructions for a while (3 seconds unless given) and prints
+/// their throughput, relative to the interpreter's. Every run starts from the same loaded
+/// machine with no keys held; a crashed run counts up to the crash.
+pub fn report(path: &str, seconds: Option<&str>) -> Result<(), String> {
+    let seconds = match seconds {
+        Some(seconds) => seconds
+            .parse::<f64>()
+            .ok()
+            .filter(|seconds| *seconds > 0.0)
+            .ok_or_else(|| format!("Bad number of seconds {seconds}"))?,
+        None => DEFAULT_SECONDS,
+    };
+
+    let cartridge = Cartridge::open(path)?;
+    let rom = cartridge.rom(0);
+
+    let instructions_per_frame = Clock::default().instructions_per_frame();
+    let quirks = quirk_detector::detect(rom, instructions_per_frame)[0].quirks;
+
+    let duration = Duration::from_secs_f64(seconds);
+    let mut baseline = None;
+
+    for strategy in Strategy::ALL {
+        let (instructions, elapsed, crashed) = run(strategy, machine(rom, quirks)?, duration);
+        let per_second = instructions as f64 / elapsed.as_secs_f64();
+        let baseline = *baseline.get_or_insert(per_second);
+
+        println!(
+            "{:<10} {:>12.0} instructions/s  {:>5.2}x{}",
+            strategy.name(),
+            per_second,
+            per_second / baseline,
+            if crashed { "  (crashed)" } else { "" }
+        );
+    }
+
+    Ok(())
+}
+
+fn machine(rom: &[u8], quirks: Quirks) -> Result<Chip8, String> {
+    let mut chip8 = Chip8::with_quirks(quirks);
+
+    chip8.load(rom).map_err(|err| err.to_string())?;
+    chip8.set_sound_enabled(false);
+    chip8.set_rng_state(1);
+
+    Ok(chip8)
+}
+
+// instructions run, the time they took and whether the game crashed
+fn run(strategy: Strategy, mut chip8: Chip8, duration: Duration) -> (u64, Duration, bool) {
+    let start = Instant::now();
+    let mut instructions = 0;
+
+    let crashed = match strategy {
+        Strategy::Match => {
+            let mut clock = Clock::default();
+
+            'run: loop {
+                if start.elapsed() >= duration {
+                    break false;
+                }
+
+                for _ in 0..BATCH_FRAMES {
+                    let ticks = clock.ticks_for_frame();
+
+                    // close enough, a crash ends the run anyway
+                    instructions += ticks as u64;
+
+                    if chip8.run_frame(ticks).is_err() {
+                        break 'run true;
+                    }
+                }
+            }
+        }
+
+        Strategy::Reference => {
+            let mut reference = Reference::of(&chip8);
+            let keys = chip8.get_keys().to_vec();
+            let quirks = chip8.get_quirks();
+
+            // it panics where the interpreter reports an error, there's nothing to print
+            let panic_hook = panic::take_hook();
+            panic::set_hook(Box::new(|_| {}));
+
+            let ran = panic::catch_unwind(AssertUnwindSafe(|| {
+                while start.elapsed() < duration {
+                    for _ in 0..BATCH_INSTRUCTIONS {
+                        reference.step(&keys, quirks);
+                        instructions += 1;
+                    }
+                }
+            }));
+
+            panic::set_hook(panic_hook);
+
+            ran.is_err()
+        }
+    };
+
+    (instructions, start.elapsed(), crashed)
+}
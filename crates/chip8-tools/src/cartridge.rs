@@ -0,0 +1,177 @@
+use chip8_core::constants::{START_ADDR, XO_RAM_SIZE};
+
+use crate::assets;
+use crate::file_io;
+use crate::project;
+
+const MAGIC: &[u8; 4] = b"C8PK";
+const VERSION: u8 = 1;
+
+// the biggest rom that fits in memory after the interpreter area, XO-CHIP's 64KB of it
+const MAX_ROM_SIZE: usize = XO_RAM_SIZE - START_ADDR as usize;
+
+/// A "virtual console" multi-game cartridge: several named ROMs in a single file.
+///
+/// Layout (all numbers big endian):
+///
+/// ```text
+/// "C8PK"              magic
+/// u8                  version (1)
+/// u8                  number of games
+/// index table, per game:
+///     u8              name length
+///     [u8]            name (UTF-8)
+///     u32             offset of the rom from the start of the file
+///     u16             rom length
+/// rom data
+/// ```
+///
+/// Plain ROMs are treated as a cartridge with a single game, so the rest of the
+/// emulator doesn't have to tell them apart.
+pub struct Cartridge {
+    games: Vec<(String, Vec<u8>)>,
+}
+
+impl Cartridge {
+    pub fn is_cartridge(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    /// Reads a cartridge or plain ROM file. Assembly sources are built on the fly and
+    /// `.ch8.gz` roms are decompressed. Portable builds open their bundled games at
+    /// GAMES_PATH.
+    pub fn open(path: &str) -> Result<Self, String> {
+        if let Some(games) = assets::GAMES.filter(|_| path == assets::GAMES_PATH) {
+            return Self::load(path, games.to_vec());
+        }
+
+        if project::is_source_file(path) {
+            let rom = project::assemble_file(path)?;
+
+            return Self::load(path, rom);
+        }
+
+        let data = file_io::read_file(path).map_err(|err| format!("{path}: {err}"))?;
+
+        Self::load(path, data)
+    }
+
+    /// `path` names the game when `data` is a plain ROM.
+    pub fn load(path: &str, data: Vec<u8>) -> Result<Self, String> {
+        if !Self::is_cartridge(&data) {
+            if data.len() > MAX_ROM_SIZE {
+                return Err(format!("{path}: does not fit in memory"));
+            }
+
+            return Ok(Self {
+                games: vec![(file_io::display_name(path), data)],
+            });
+        }
+
+        let truncated = || format!("{path}: cartridge is truncated");
+
+        let version = *data.get(MAGIC.len()).ok_or_else(truncated)?;
+
+        if version != VERSION {
+            return Err(format!("{path}: unsupported cartridge version {version}"));
+        }
+
+        let count = *data.get(MAGIC.len() + 1).ok_or_else(truncated)? as usize;
+
+        let mut pos = MAGIC.len() + 2;
+        let mut games = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            let name_len = *data.get(pos).ok_or_else(truncated)? as usize;
+            pos += 1;
+
+            let name = data.get(pos..pos + name_len).ok_or_else(truncated)?;
+            let name = String::from_utf8_lossy(name).into_owned();
+            pos += name_len;
+
+            let header = data.get(pos..pos + 6).ok_or_else(truncated)?;
+            let offset = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+            pos += 6;
+
+            if len > MAX_ROM_SIZE {
+                return Err(format!("{path}: {name} does not fit in memory"));
+            }
+
+            let rom = data.get(offset..offset + len).ok_or_else(truncated)?;
+
+            games.push((name, rom.to_vec()));
+        }
+
+        if games.is_empty() {
+            return Err(format!("{path}: cartridge has no games"));
+        }
+
+        Ok(Self { games })
+    }
+
+    /// Builds a cartridge out of `(name, rom)` pairs, see `to_bytes` to write it out.
+    pub fn new(games: Vec<(String, Vec<u8>)>) -> Result<Self, String> {
+        if games.is_empty() || games.len() > u8::MAX as usize {
+            return Err(format!("a cartridge holds 1 to {} games", u8::MAX));
+        }
+
+        for (name, rom) in &games {
+            if name.len() > u8::MAX as usize {
+                return Err(format!("{name}: name is longer than {} bytes", u8::MAX));
+            }
+
+            if rom.len() > MAX_ROM_SIZE {
+                return Err(format!("{name}: does not fit in memory"));
+            }
+        }
+
+        Ok(Self { games })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let index_len: usize = self.games.iter().map(|(name, _)| 1 + name.len() + 6).sum();
+
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+        data.push(self.games.len() as u8);
+
+        let mut offset = data.len() + index_len;
+
+        for (name, rom) in &self.games {
+            data.push(name.len() as u8);
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(&(offset as u32).to_be_bytes());
+            data.extend_from_slice(&(rom.len() as u16).to_be_bytes());
+
+            offset += rom.len();
+        }
+
+        for (_, rom) in &self.games {
+            data.extend_from_slice(rom);
+        }
+
+        data
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    pub fn name(&self, idx: usize) -> &str {
+        &self.games[idx].0
+    }
+
+    pub fn rom(&self, idx: usize) -> &[u8] {
+        &self.games[idx].1
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.games.iter().map(|(name, _)| name.as_str())
+    }
+}
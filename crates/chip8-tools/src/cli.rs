@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+
+/// `--help`: the usage and every option, a line each as `--name VALUE` (no value for a
+/// switch) and its description, two spaces apart at least. The parser reads the options
+/// from here too, so the two never disagree.
+pub const HELP: &str = "\
+Usage: chip8-emulator [OPTIONS] [ROM]
+       chip8-emulator COMMAND ...       see the README for the commands
+
+Options:
+  --help, -h                      print this help
+  --list-games                    list the games of the ROM directory, a path and title a line
+  --list-audio-devices            list the audio output devices
+  --frontend NAME                 sdl (a window, the default), tui (the terminal) or headless
+  --game NAME                     the game of a cartridge to start with
+  --pack FILE                     bundle the ROMs given into a cartridge
+  --browser                       pick the game in the ROM browser instead of the boot menu
+  --restore-session               pick up where the last run left off
+  --no-resume                     start the game over instead of resuming it
+  --speed N                       instructions per frame, like --ipf
+  --ipf N                         instructions per frame, possibly fractional
+  --hz N                          instructions per second
+  --quirks LIST                   quirks or a preset, e.g. schip or vy-shift,clip
+  --machine NAME                  run the game as on another machine, e.g. eti-660
+  --load-addr ADDR                where the ROM is loaded, in hex
+  --random KIND                   where RND gets its numbers: vip, os or seed:N
+  --resolution WxH                the screen size, e.g. 128x64
+  --scale N                       preferred window scale
+  --view NAME                     1x to 8x, pixel-perfect, fit or fill
+  --fractional-scale              the fit view
+  --rotate DEGREES                turn the screen by 90, 180 or 270 degrees
+  --mirror                        mirror the screen
+  --palette NAME                  a built-in palette (green, amber, lcd or inverted) or a theme file
+  --skin FILE                     draw the screen inside a skin
+  --effects FILE                  put the screen through a display effects pipeline
+  --phosphor DECAY                fade pixels out, keeping DECAY (0 to 1) of them a frame
+  --mute                          no sound
+  --volume N                      the beep's volume, 0 to 1
+  --audio-device NAME             the audio output device, remembered
+  --audio-buffer FRAMES           the audio buffer size, remembered
+  --beep-frequency HZ             the beep's pitch
+  --beep-sound FILE               play the start of a sound file as the beep
+  --visual-sound                  flash the window border while the buzzer sounds
+  --fast-forward-speed N          how much faster Tab runs the game
+  --fast-forward-audio MODE       mute or pitch, remembered
+  --realtime-timers               keep the timers and the sound at 60 Hz while fast forwarding
+  --rewind-seconds N              how far back the rewind key goes, 0 to turn it off
+  --grab-keyboard                 capture the keyboard while the game runs
+  --check-updates                 look for a newer release at startup
+  --setup-keys                    set up the key bindings
+  --key-mapping MODE              physical (key positions) or layout (key labels)
+  --key-layout NAME               qwerty, azerty, dvorak or hex, the keypad's keys, remembered
+  --gamepad-map LIST              the game's controller buttons, e.g. 12=1,13=4, remembered
+  --max-keys N                    keypad buttons held at once at most
+  --key-priority WHICH            newest or oldest, the buttons kept past --max-keys
+  --break ADDRS                   pause at these addresses, in hex
+  --dev                           interpret the debug instructions
+  --reference-check               cross-check every instruction with the reference interpreter
+  --learn                         ask what each instruction does before running it
+  --verify                        run a second machine in lockstep, checking they never diverge
+  --watch                         reload the ROM when it changes
+  --hot-patch                     with --watch, patch the running game instead of restarting it
+  --compare-rom FILE              run a second ROM in lockstep and compare the screens
+  --flipbook-frames N             frames in an exported flipbook
+  --record FILE                   record a movie
+  --play FILE                     play a movie
+  --seek FRAME                    start the movie at a frame
+  --keyframe-seconds N            seconds between a movie's keyframes
+  --export-state FILE             save the state there on exit
+  --import-state FILE             resume from a saved state
+  --spectate ADDR                 stream the screen to spectators
+  --led TARGET                    mirror the screen on an LED matrix
+  --sound-events TARGET           send the buzzer as MIDI or OSC
+  --sound-events-channel N        the MIDI channel
+  --sound-events-note N           the MIDI note
+
+Terminal and headless frontends:
+  --frames N                      quit after N frames
+  --until WHEN                    headless: stop at halt or an address
+  --press LIST                    headless: press keypad buttons at frames, e.g. 1@30,A@90
+  --screen FILE                   headless: write the final screen
+  --state FILE                    headless: write the final state
+";
+
+/// The command line of the emulator, `chip8-emulator [OPTIONS] [ROM]`, read by every
+/// frontend. Options are checked against HELP, so a typo stops the emulator with a message
+/// instead of being silently ignored, and take their value after a space or `=`.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Cli {
+    pub positional: Vec<String>, // the ROM, or the ROMs of `--pack`
+    values: HashMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Cli {
+    /// Parses the arguments, without the program's name.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut cli = Self::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') || arg == "-" {
+                cli.positional.push(arg.clone());
+                continue;
+            }
+
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+
+            let value = option(name).ok_or(format!("Unknown option {name}, see --help"))?;
+
+            match value {
+                Some(value) => {
+                    let given = inline
+                        .or_else(|| args.next().cloned())
+                        .ok_or(format!("{name} needs a value: {name} {value}"))?;
+
+                    cli.values.insert(name.to_string(), given);
+                }
+                None if inline.is_some() => return Err(format!("{name} takes no value")),
+                None => cli.switches.push(name.to_string()),
+            }
+        }
+
+        Ok(cli)
+    }
+
+    /// The process's command line, parsed once. `main` has already reported a bad one, which
+    /// reads as empty here.
+    pub fn get() -> &'static Cli {
+        static CLI: OnceLock<Cli> = OnceLock::new();
+
+        CLI.get_or_init(|| {
+            let args: Vec<String> = env::args().skip(1).collect();
+
+            Self::parse(&args).unwrap_or_default()
+        })
+    }
+
+    /// The value of an option, e.g. `16` for `--scale 16`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|switch| switch == name)
+    }
+
+    /// `--help` or `-h`.
+    pub fn wants_help(&self) -> bool {
+        self.switch("--help") || self.switch("-h")
+    }
+}
+
+// the placeholder of an option's value in HELP, `Some(None)` for a switch, `None` for an
+// unknown option
+fn option(name: &str) -> Option<Option<&'static str>> {
+    HELP.lines()
+        .filter_map(|line| line.strip_prefix("  ").filter(|line| line.starts_with('-')))
+        .find_map(|line| {
+            let (usage, _) = line.split_once("  ")?;
+
+            // `--scale N`, `--mute` or `--help, -h`
+            let (names, value) = match usage.rsplit_once(' ') {
+                Some((names, value)) if !value.starts_with('-') => (names, Some(value)),
+                _ => (usage, None),
+            };
+
+            names
+                .split(", ")
+                .any(|known| known == name)
+                .then_some(value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+        Cli::parse(&args)
+    }
+
+    #[test]
+    fn options_take_their_value_after_a_space_or_equals() {
+        let cli = parse(&["--scale", "16", "--palette=amber", "--mute", "game.ch8"]).unwrap();
+
+        assert_eq!(cli.value("--scale"), Some("16"));
+        assert_eq!(cli.value("--palette"), Some("amber"));
+        assert!(cli.switch("--mute"));
+        assert!(!cli.switch("--mirror"));
+        assert_eq!(cli.positional, ["game.ch8"]);
+    }
+
+    #[test]
+    fn values_may_look_like_options() {
+        let cli = parse(&["--rotate", "-90", "--quirks=a=b"]).unwrap();
+
+        assert_eq!(cli.value("--rotate"), Some("-90"));
+        assert_eq!(cli.value("--quirks"), Some("a=b"));
+    }
+
+    #[test]
+    fn a_lone_dash_is_positional() {
+        assert_eq!(parse(&["-"]).unwrap().positional, ["-"]);
+    }
+
+    #[test]
+    fn both_help_names_want_help() {
+        assert!(parse(&["--help"]).unwrap().wants_help());
+        assert!(parse(&["-h"]).unwrap().wants_help());
+        assert!(!parse(&[]).unwrap().wants_help());
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        assert_eq!(
+            parse(&["--scael", "16"]),
+            Err("Unknown option --scael, see --help".to_string())
+        );
+        assert_eq!(
+            parse(&["--scael=16"]),
+            Err("Unknown option --scael, see --help".to_string())
+        );
+    }
+
+    #[test]
+    fn a_missing_value_is_rejected() {
+        assert_eq!(
+            parse(&["game.ch8", "--scale"]),
+            Err("--scale needs a value: --scale N".to_string())
+        );
+    }
+
+    #[test]
+    fn a_switch_given_a_value_is_rejected() {
+        assert_eq!(
+            parse(&["--mute=yes"]),
+            Err("--mute takes no value".to_string())
+        );
+    }
+}
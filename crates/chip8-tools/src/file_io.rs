@@ -0,0 +1,106 @@
+use std::{env, fs};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+// every gzip stream starts with these two bytes
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Where settings are remembered between runs: `$XDG_CONFIG_HOME/chip8-emulator`,
+/// `~/.config/chip8-emulator` or `./.chip8-emulator` when neither is set.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        return PathBuf::from(dir).join("chip8-emulator");
+    }
+
+    match env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+        Some(home) => PathBuf::from(home).join(".config").join("chip8-emulator"),
+        None => PathBuf::from(".chip8-emulator"),
+    }
+}
+
+/// Reads a whole file, transparently decompressing it when it is gzipped
+/// (e.g. `BRIX.ch8.gz`), whatever its extension.
+pub fn read_file<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<u8>> {
+    decompress(fs::read(path)?)
+}
+
+/// `data` gunzipped when it is gzipped, as it is otherwise.
+pub fn decompress(data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(data);
+    }
+
+    let mut decompressed = Vec::new();
+
+    GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+
+    Ok(decompressed)
+}
+
+/// A save state's bytes as they are kept, on disk or in memory: gzipped with the
+/// `compressed-states` feature, a few hundred bytes instead of the 4KB of RAM and the screen.
+/// The gzip header tells them apart, so `decompress` (and `read_file`) take either.
+pub fn pack_state(data: Vec<u8>) -> Vec<u8> {
+    if !cfg!(feature = "compressed-states") {
+        return data;
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+
+    // writing to memory can't fail
+    encoder.write_all(&data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Writes a whole file, gzipping it when the path ends in `.gz`.
+/// Large artifacts like trace logs should be given a `.gz` path by their writers.
+pub fn write_file<P: AsRef<Path>>(path: P, data: &[u8]) -> std::io::Result<()> {
+    if !is_gzip_path(&path) {
+        return fs::write(path, data);
+    }
+
+    let mut encoder = GzEncoder::new(fs::File::create(path)?, Compression::default());
+
+    encoder.write_all(data)?;
+    encoder.finish()?;
+
+    Ok(())
+}
+
+pub fn is_gzip_path<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// The file's extension ignoring a trailing `.gz`, so `state.json.gz` gives `json`.
+pub fn inner_extension<P: AsRef<Path>>(path: P) -> Option<String> {
+    let path = path.as_ref();
+
+    let path = if is_gzip_path(path) {
+        Path::new(path.file_stem()?)
+    } else {
+        path
+    };
+
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// The name a ROM is shown with, without the directory and the `.gz` suffix.
+pub fn display_name<P: AsRef<Path>>(path: P) -> String {
+    let path = path.as_ref();
+
+    let name = if is_gzip_path(path) {
+        path.file_stem()
+    } else {
+        path.file_name()
+    };
+
+    name.map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
@@ -0,0 +1,48 @@
+use chip8_core::core::Chip8;
+
+/// Swaps the game loaded into `chip8` from `old` to `new` while it runs, e.g. a translation
+/// with reworded text: only the bytes that differ are written, so the registers, the stack,
+/// the screen and the rest of memory stay as they are and the game goes on from where it
+/// is. Returns how many bytes were patched.
+///
+/// Refused when the game wrote over one of the differing bytes since it was loaded, as the
+/// patch would clobber its data (e.g. a longer version reaching into memory the game uses),
+/// or when the new version doesn't fit in memory. Code that moved is not caught, keeping the
+/// registers and stack is best effort.
+pub fn apply(chip8: &mut Chip8, old: &[u8], new: &[u8]) -> Result<usize, String> {
+    let start = chip8.start_addr() as usize;
+    let memory = chip8.get_memory();
+
+    if start + new.len() > memory.len() {
+        return Err(format!(
+            "the new version doesn't fit in {} bytes of memory",
+            memory.len()
+        ));
+    }
+
+    let mut patches = Vec::new();
+
+    for offset in 0..old.len().max(new.len()) {
+        // past its end a version leaves memory empty
+        let before = old.get(offset).copied().unwrap_or(0);
+        let after = new.get(offset).copied().unwrap_or(0);
+
+        if before == after {
+            continue;
+        }
+
+        let addr = start + offset;
+
+        if memory.get(addr) != Some(&before) {
+            return Err(format!("the game changed {addr:03X} since it was loaded"));
+        }
+
+        patches.push((addr as u16, after));
+    }
+
+    for (addr, value) in &patches {
+        chip8.write_memory(*addr, *value);
+    }
+
+    Ok(patches.len())
+}
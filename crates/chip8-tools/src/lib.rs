@@ -0,0 +1,12 @@
+pub mod assets;
+pub mod bench;
+pub mod cartridge;
+pub mod cli;
+pub mod file_io;
+pub mod hot_patch;
+pub mod listing;
+pub mod package;
+pub mod profile;
+pub mod project;
+pub mod quirk_detector;
+pub mod quirks;
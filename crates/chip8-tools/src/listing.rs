@@ -0,0 +1,29 @@
+use chip8_core::constants::START_ADDR;
+use chip8_core::disassembler;
+
+use crate::cartridge::Cartridge;
+
+/// `chip8-emulator disassemble ROM [ADDR]` prints every instruction of a rom or of each
+/// game of a cartridge, with addresses counted from ADDR (hex, 200 unless given) so they
+/// match the jumps of games loaded elsewhere, e.g. `disassemble GAME.ch8 600` for ETI-660.
+pub fn print(path: &str, load_addr: Option<&str>) -> Result<(), String> {
+    let base = match load_addr {
+        Some(addr) => u16::from_str_radix(addr.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("Bad load address {addr}"))?,
+        None => START_ADDR,
+    };
+
+    let cartridge = Cartridge::open(path)?;
+
+    for (idx, name) in cartridge.names().enumerate() {
+        if cartridge.len() > 1 {
+            println!("{name}:");
+        }
+
+        for line in disassembler::listing(cartridge.rom(idx), base) {
+            println!("{line}");
+        }
+    }
+
+    Ok(())
+}
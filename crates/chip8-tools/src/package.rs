@@ -0,0 +1,61 @@
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::cartridge::Cartridge;
+use crate::file_io;
+
+// read by the `portable` feature at build time, see assets.rs
+const BUNDLE_PATH: &str = "portable/games.c8pak";
+
+const DIST_DIR: &str = "dist";
+
+/// `chip8-emulator package ROM...` builds a self-contained executable for people who just
+/// want to play: the given roms, cartridges or sources are packed into the bundle the
+/// `portable` feature embeds, then a release build is made
+/// and copied to `dist/`. Runs from the emulator's source directory and needs cargo.
+pub fn package(roms: &[&str]) -> Result<(), String> {
+    if !Path::new("Cargo.toml").exists() {
+        return Err("Run package from the chip8-emulator source directory".to_string());
+    }
+
+    let mut games = Vec::new();
+
+    for path in roms {
+        let cartridge = Cartridge::open(path)?;
+
+        for (idx, name) in cartridge.names().enumerate() {
+            games.push((name.to_string(), cartridge.rom(idx).to_vec()));
+        }
+    }
+
+    let bundle = Cartridge::new(games)?;
+
+    fs::create_dir_all("portable").map_err(|err| format!("Could not create portable/: {err}"))?;
+    file_io::write_file(BUNDLE_PATH, &bundle.to_bytes())
+        .map_err(|err| format!("Could not write {BUNDLE_PATH}: {err}"))?;
+
+    println!("Bundled {} games into {BUNDLE_PATH}", bundle.len());
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+
+    let status = Command::new(cargo)
+        .args(["build", "--release", "--features", "portable"])
+        .status()
+        .map_err(|err| format!("Could not run cargo: {err}"))?;
+
+    if !status.success() {
+        return Err("The portable build failed".to_string());
+    }
+
+    let built = format!("target/release/chip8-emulator{EXE_SUFFIX}");
+    let packaged = format!("{DIST_DIR}/chip8-emulator-portable{EXE_SUFFIX}");
+
+    fs::create_dir_all(DIST_DIR).map_err(|err| format!("Could not create {DIST_DIR}/: {err}"))?;
+    fs::copy(&built, &packaged).map_err(|err| format!("Could not copy {built}: {err}"))?;
+
+    println!("Packaged {packaged}");
+
+    Ok(())
+}
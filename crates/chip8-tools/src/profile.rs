@@ -0,0 +1,108 @@
+use chip8_core::clock::Clock;
+use chip8_core::constants::{FRAMES_PER_SECOND, NUM_KEYS};
+use chip8_core::core::Chip8;
+use chip8_core::disassembler;
+use chip8_core::draw_stats::DrawStats;
+
+use crate::cartridge::Cartridge;
+use crate::quirk_detector;
+
+const DEFAULT_SECONDS: u32 = 10;
+
+// draw instructions listed per game, busiest first
+const LISTED_DRAWS: usize = 8;
+
+/// `chip8-emulator profile ROM [SECONDS]` plays every game of a rom or cartridge headless
+/// for a while (10 seconds unless given) with the quirk detection's scripted input and
+/// prints where the time goes, for homebrew authors looking for rendering hotspots:
+/// sprite draws per frame, their average height, overdraw and the busiest DXYN.
+pub fn report(path: &str, seconds: Option<&str>) -> Result<(), String> {
+    let seconds = match seconds {
+        Some(seconds) => seconds
+            .parse::<u32>()
+            .map_err(|_| format!("Bad number of seconds {seconds}"))?,
+        None => DEFAULT_SECONDS,
+    };
+
+    let cartridge = Cartridge::open(path)?;
+    let instructions_per_frame = Clock::default().instructions_per_frame();
+
+    for (idx, name) in cartridge.names().enumerate() {
+        let rom = cartridge.rom(idx);
+        let quirks = quirk_detector::detect(rom, instructions_per_frame)[0].quirks;
+
+        let mut chip8 = Chip8::new();
+
+        chip8.load(rom).map_err(|err| format!("{name}: {err}"))?;
+        chip8.set_quirks(quirks);
+        chip8.set_sound_enabled(false);
+        chip8.set_rng_state(1);
+        chip8.set_draw_tracking(true);
+
+        let mut clock = Clock::from_ipf(instructions_per_frame);
+
+        let crashed_at = (0..seconds * FRAMES_PER_SECOND).find(|frame| {
+            let key = quirk_detector::scripted_key(*frame);
+
+            for idx in 0..NUM_KEYS {
+                chip8.keypress(idx, key == Some(idx));
+            }
+
+            let ticks = clock.ticks_for_frame();
+
+            chip8.run_frame(ticks).is_err()
+        });
+
+        println!("{name}: quirks {}", quirks.names());
+
+        if let Some(frame) = crashed_at {
+            println!("  crashed at frame {frame}, counted up to there");
+        }
+
+        if let Some(stats) = chip8.draw_stats() {
+            for line in describe(stats, chip8.get_memory()) {
+                println!("  {line}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn describe(stats: &DrawStats, memory: &[u8]) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "{} frames, {:.1} sprite draws per frame, {} in the busiest one",
+            stats.frames,
+            stats.draws_per_frame(),
+            stats.busiest_frame
+        ),
+        format!("average sprite height {:.1} rows", stats.average_height()),
+        format!(
+            "overdraw {:.1}%: {} of {} pixel flips hit a pixel already flipped that frame",
+            stats.overdraw() * 100.0,
+            stats.overdrawn,
+            stats.pixels
+        ),
+    ];
+
+    let mut draws: Vec<(u16, u64)> = stats.draws_by_addr.iter().map(|(a, n)| (*a, *n)).collect();
+    draws.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    if !draws.is_empty() {
+        lines.push("busiest draws:".to_string());
+    }
+
+    for (addr, count) in draws.into_iter().take(LISTED_DRAWS) {
+        let op = disassembler::opcode_at(memory, addr);
+
+        lines.push(format!(
+            "  {addr:03X}: {:>8} ({:>4.1}%)  {}",
+            count,
+            count as f64 * 100.0 / stats.draws as f64,
+            disassembler::disassemble(op)
+        ));
+    }
+
+    lines
+}
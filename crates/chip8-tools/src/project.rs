@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+
+use chip8_core::assembler;
+
+use crate::file_io;
+
+const SOURCE_FILE: &str = "main.c8s";
+
+const SOURCE_TEMPLATE: &str = r#"; {name} - a CHIP-8 program
+;
+; Mnemonics follow Cowgod's Chip-8 Technical Reference, see crates/chip8-core/src/assembler.rs
+; in chip8-emulator for the full syntax.
+
+start:
+    CLS
+    LD V1, 28          ; x
+    LD V2, 12          ; y
+    LD V3, 0x0A        ; first digit to show
+    LD F, V3
+    DRW V1, V2, 5
+
+loop:
+    LD V3, K           ; wait for a key
+    DRW V1, V2, 5      ; erase the previous digit, I still points at it
+    LD F, V3
+    DRW V1, V2, 5      ; draw the pressed key
+    JP loop
+"#;
+
+const MAKEFILE_TEMPLATE: &str = "# build: assemble the rom, watch: play it and reload on every save
+build:
+\tchip8-emulator build main.c8s -o {name}.ch8
+
+watch:
+\tchip8-emulator main.c8s --watch
+
+.PHONY: build watch
+";
+
+/// `chip8-emulator new <name>` creates `<name>/` with a starter program and a Makefile
+/// with build and watch (live reload) tasks.
+pub fn new_project(name: &str) -> Result<(), String> {
+    let dir = Path::new(name);
+
+    if dir.exists() {
+        return Err(format!("{name} already exists"));
+    }
+
+    let project_name = file_io::display_name(dir);
+
+    let write = |file: &str, template: &str| {
+        fs::write(dir.join(file), template.replace("{name}", &project_name))
+            .map_err(|err| format!("Could not write {file}: {err}"))
+    };
+
+    fs::create_dir_all(dir).map_err(|err| format!("Could not create {name}: {err}"))?;
+
+    write(SOURCE_FILE, SOURCE_TEMPLATE)?;
+    write("Makefile", MAKEFILE_TEMPLATE)?;
+
+    println!("Created {name}/{SOURCE_FILE}");
+    println!("Run `make watch` inside {name} to play it, it reloads whenever the source is saved");
+
+    Ok(())
+}
+
+/// `chip8-emulator build <source> [-o <rom>]` assembles a source file into a rom,
+/// next to the source by default.
+pub fn build(source_path: &str, rom_path: Option<&str>) -> Result<(), String> {
+    let rom = assemble_file(source_path)?;
+
+    let rom_path = match rom_path {
+        Some(path) => path.to_string(),
+        None => Path::new(source_path)
+            .with_extension("ch8")
+            .to_string_lossy()
+            .into_owned(),
+    };
+
+    file_io::write_file(&rom_path, &rom).map_err(|err| format!("Could not write {rom_path}: {err}"))?;
+
+    println!("Assembled {source_path} into {rom_path} ({} bytes)", rom.len());
+
+    Ok(())
+}
+
+pub fn is_source_file(path: &str) -> bool {
+    file_io::inner_extension(path).is_some_and(|ext| ext == "c8s")
+}
+
+pub fn assemble_file(source_path: &str) -> Result<Vec<u8>, String> {
+    let source = file_io::read_file(source_path)
+        .map_err(|err| format!("Could not read {source_path}: {err}"))?;
+
+    assembler::assemble(&String::from_utf8_lossy(&source))
+        .map_err(|err| format!("{source_path}: {err}"))
+}
@@ -0,0 +1,173 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use chip8_core::clock::Clock;
+use chip8_core::constants::{NUM_KEYS, START_ADDR};
+use chip8_core::core::{Chip8, HookAction};
+use chip8_core::quirks::Quirks;
+use flate2::Crc;
+
+// ten seconds of play per combination
+const DETECTION_FRAMES: u32 = 600;
+
+// the scripted input presses the next key for this many frames out of every KEY_PERIOD
+const KEY_PERIOD: u32 = 30;
+const KEY_HELD_FRAMES: u32 = 10;
+
+// a run that crashes or derails loses this much, minus how long it lasted
+const CRASH_PENALTY: i64 = 10_000;
+
+// per instruction overwriting code that already ran
+const CODE_OVERWRITE_PENALTY: i64 = 5;
+const MAX_CODE_OVERWRITE_PENALTY: i64 = 500;
+
+/// How a game behaved under one combination of quirks.
+#[derive(Clone, Debug)]
+pub struct QuirkScore {
+    pub quirks: Quirks,
+    pub score: i64,
+    pub crashed_at: Option<u32>, // frame it crashed or ran off its code
+    pub screens: usize,          // distinct screens drawn
+    pub code_overwrites: u32,    // stores into instructions that already ran
+}
+
+// filled by the pre-execution hook while a combination runs
+#[derive(Default)]
+struct Probe {
+    executed: Vec<bool>, // addresses instructions ran from
+    code_overwrites: u32,
+    derailed: bool,
+}
+
+/// Guesses which quirks a game was written for: runs it headless for a few seconds under
+/// every combination with a scripted input, and scores each run by whether it crashed or
+/// ran into empty memory, how many different screens it drew and whether it stored data
+/// over its own instructions, which usually means I ended up in the wrong place.
+///
+/// Returns the scores best first; a tie keeps the default behavior on top.
+pub fn detect(rom: &[u8], instructions_per_frame: f64) -> Vec<QuirkScore> {
+    let mut scores: Vec<QuirkScore> = Quirks::combinations()
+        .into_iter()
+        .map(|quirks| run(rom, quirks, instructions_per_frame))
+        .collect();
+
+    // stable, so the default combination stays first among equals
+    scores.sort_by_key(|score| std::cmp::Reverse(score.score));
+
+    scores
+}
+
+/// The key the scripted input holds in `frame`: every key in turn, pressed for a moment
+/// and released, so games get past their title screens and move around.
+pub fn scripted_key(frame: u32) -> Option<usize> {
+    let pressed = frame % KEY_PERIOD < KEY_HELD_FRAMES;
+
+    pressed.then_some((frame / KEY_PERIOD) as usize % NUM_KEYS)
+}
+
+fn run(rom: &[u8], quirks: Quirks, instructions_per_frame: f64) -> QuirkScore {
+    let mut chip8 = Chip8::new();
+
+    // a game too large for the memory crashes right away
+    let mut crashed_at = chip8.load(rom).is_err().then_some(0);
+
+    chip8.set_quirks(quirks);
+    chip8.set_sound_enabled(false);
+    chip8.set_rng_state(1);
+
+    let probe = Rc::new(RefCell::new(Probe {
+        executed: vec![false; chip8.memory_size()],
+        ..Probe::default()
+    }));
+
+    let hook_probe = probe.clone();
+
+    chip8.set_pre_exec_hook(Box::new(move |chip8, op| {
+        let mut probe = hook_probe.borrow_mut();
+        let pc = chip8.get_program_counter();
+
+        // 0000 is empty memory, the font is no code either
+        if op == 0 || pc < START_ADDR {
+            probe.derailed = true;
+            return HookAction::Break;
+        }
+
+        let len = probe.executed.len();
+        probe.executed[pc as usize % len] = true;
+
+        // FX33 and FX55 store at I
+        let stored = match op & 0xF0FF {
+            0xF033 => 3,
+            0xF055 => ((op >> 8) & 0xF) as usize + 1,
+            _ => 0,
+        };
+
+        let i = chip8.get_i_register() as usize;
+
+        if (i..i + stored).any(|addr| probe.executed[addr % len]) {
+            probe.code_overwrites += 1;
+        }
+
+        HookAction::Continue
+    }));
+
+    let mut clock = Clock::from_ipf(instructions_per_frame);
+    let mut screens = HashSet::new();
+    let frames = crashed_at.map_or(DETECTION_FRAMES, |_| 0);
+
+    for frame in 0..frames {
+        let key = scripted_key(frame);
+
+        for idx in 0..NUM_KEYS {
+            chip8.keypress(idx, key == Some(idx));
+        }
+
+        let ticks = clock.ticks_for_frame();
+
+        let mut crashed = false;
+
+        for _ in 0..ticks {
+            crashed = chip8.tick().is_err();
+
+            if crashed || chip8.take_break_request() {
+                break;
+            }
+        }
+
+        chip8.tick_timers();
+
+        if crashed || probe.borrow().derailed {
+            crashed_at = Some(frame);
+            break;
+        }
+
+        let mut crc = Crc::new();
+        let pixels: Vec<u8> = chip8
+            .get_screen()
+            .iter()
+            .map(|pixel| *pixel as u8)
+            .collect();
+
+        crc.update(&pixels);
+        screens.insert(crc.sum());
+    }
+
+    let code_overwrites = probe.borrow().code_overwrites;
+
+    let mut score = screens.len() as i64;
+
+    score -= (code_overwrites as i64 * CODE_OVERWRITE_PENALTY).min(MAX_CODE_OVERWRITE_PENALTY);
+
+    if let Some(frame) = crashed_at {
+        score -= CRASH_PENALTY - frame as i64;
+    }
+
+    QuirkScore {
+        quirks,
+        score,
+        crashed_at,
+        screens: screens.len(),
+        code_overwrites,
+    }
+}
@@ -0,0 +1,40 @@
+use chip8_core::clock::Clock;
+
+use crate::cartridge::Cartridge;
+use crate::quirk_detector::{self, QuirkScore};
+
+// best combinations listed per game
+const LISTED_SCORES: usize = 5;
+
+/// `chip8-emulator quirks ROM` runs the quirk detection on every game of a rom or
+/// cartridge and lists the best scoring combinations, without touching the game library.
+pub fn report(path: &str) -> Result<(), String> {
+    let cartridge = Cartridge::open(path)?;
+    let instructions_per_frame = Clock::default().instructions_per_frame();
+
+    for (idx, name) in cartridge.names().enumerate() {
+        let scores = quirk_detector::detect(cartridge.rom(idx), instructions_per_frame);
+
+        println!("{name}: suggested quirks {}", scores[0].quirks.names());
+
+        for score in scores.iter().take(LISTED_SCORES) {
+            println!("  {}", describe(score));
+        }
+    }
+
+    Ok(())
+}
+
+fn describe(score: &QuirkScore) -> String {
+    let crashed = score
+        .crashed_at
+        .map_or(String::new(), |frame| format!(", crashed at frame {frame}"));
+
+    format!(
+        "{:<40} score {:>6}: {} screens, {} code overwrites{crashed}",
+        score.quirks.names(),
+        score.score,
+        score.screens,
+        score.code_overwrites
+    )
+}
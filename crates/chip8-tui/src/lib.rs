@@ -0,0 +1,2 @@
+pub mod raw_mode;
+pub mod terminal_manager;
@@ -0,0 +1,55 @@
+/// The terminal without line buffering, echo or signal keys, so every key reaches the
+/// game as it is pressed and Ctrl+C can be handled like any key. Reads from stdin return
+/// at once, empty when nothing was typed. The previous settings are restored on drop.
+pub struct RawMode {
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+impl RawMode {
+    /// Switches stdin to raw mode, `None` when it isn't a terminal, e.g. in CI.
+    #[cfg(unix)]
+    pub fn enable() -> Option<Self> {
+        // SAFETY: termios is plain data filled in by tcgetattr before it is read
+        unsafe {
+            if libc::isatty(libc::STDIN_FILENO) == 0 {
+                return None;
+            }
+
+            let mut termios: libc::termios = std::mem::zeroed();
+
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return None;
+            }
+
+            let original = termios;
+
+            termios.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            termios.c_iflag &= !(libc::IXON | libc::ICRNL);
+            termios.c_cc[libc::VMIN] = 0;
+            termios.c_cc[libc::VTIME] = 0;
+
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+                return None;
+            }
+
+            Some(Self { original })
+        }
+    }
+
+    // only Unix terminals are supported
+    #[cfg(not(unix))]
+    pub fn enable() -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read in `enable`
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
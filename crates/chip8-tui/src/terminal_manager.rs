@@ -0,0 +1,233 @@
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use chip8_core::clock::Clock;
+use chip8_core::constants::NUM_KEYS;
+use chip8_core::core::{Buzzer, Chip8};
+use chip8_core::disassembler;
+use chip8_core::frontend::{self, AudioSink, Input, InputSource, Renderer};
+use chip8_core::resolution::Resolution;
+use chip8_tools::cartridge::Cartridge;
+use chip8_tools::cli::Cli;
+use chip8_tools::quirk_detector;
+
+use crate::raw_mode::RawMode;
+
+// terminals only report presses, so a key counts as held for this many frames after it was
+// pressed or repeated
+const KEY_HOLD_FRAMES: u32 = 10;
+
+// the keys of keypad buttons 0 to F, the desktop's default QWERTY layout
+const KEYPAD: [u8; NUM_KEYS] = *b"x123qweasdzc4rfv";
+
+const ESCAPE: u8 = 0x1B;
+const CTRL_C: u8 = 0x03;
+
+/// Plays a game in the terminal (`--frontend tui`), e.g. on a server over SSH or as a smoke
+/// test where there is no display: the screen is drawn with half block characters, two
+/// pixels to a character, and the keypad read from the keyboard with the desktop's default
+/// layout. Escape or Ctrl+C quits; the buzzer rings the terminal bell.
+pub struct TerminalGameManager {
+    screen: TerminalScreen,
+    keys: TerminalKeys,
+}
+
+impl TerminalGameManager {
+    /// `chip8-emulator --frontend tui ROM` plays the first game of a rom or cartridge with
+    /// its detected quirks, `--frames N` quits after N frames.
+    pub fn play() -> Result<(), String> {
+        let path = Cli::get()
+            .positional
+            .first()
+            .ok_or("No ROM given: chip8-emulator --frontend tui ROM")?;
+
+        let frames = match Cli::get().value("--frames") {
+            Some(frames) => Some(
+                frames
+                    .parse::<u32>()
+                    .map_err(|_| format!("Bad number of frames {frames}"))?,
+            ),
+            None => None,
+        };
+
+        let cartridge = Cartridge::open(path)?;
+        let rom = cartridge.rom(0);
+
+        let mut clock = Clock::default();
+        let quirks = quirk_detector::detect(rom, clock.instructions_per_frame())[0].quirks;
+
+        let mut chip8 = Chip8::with_quirks(quirks);
+
+        chip8.load(rom).map_err(|err| format!("{path}: {err}"))?;
+
+        let mut manager = Self {
+            screen: TerminalScreen::new(path),
+            keys: TerminalKeys::new(frames),
+        };
+
+        let result = frontend::run(
+            &mut chip8,
+            &mut clock,
+            &mut manager.screen,
+            &mut manager.keys,
+            Some(Rc::new(Bell::default())),
+        );
+
+        // the terminal is back to normal before anything is printed
+        drop(manager);
+
+        result.map_err(|err| format!("The game crashed: {err}\n{}", disassembler::at_pc(&chip8)))
+    }
+}
+
+struct TerminalScreen {
+    title: String,
+    shown: Vec<bool>, // what the terminal shows, redrawn only when the screen changes
+    resolution: Resolution,
+}
+
+impl TerminalScreen {
+    fn new(title: &str) -> Self {
+        // the alternate screen keeps the shell's scrollback, the cursor is hidden
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+
+        Self {
+            title: title.to_string(),
+            shown: Vec::new(),
+            resolution: Resolution::default(),
+        }
+    }
+}
+
+impl Renderer for TerminalScreen {
+    fn draw(&mut self, chip8: &Chip8) {
+        let screen = chip8.get_screen();
+        let resolution = chip8.resolution();
+
+        if screen == self.shown.as_slice() && resolution == self.resolution {
+            return;
+        }
+
+        let mut text = if resolution == self.resolution {
+            String::from("\x1b[H")
+        } else {
+            String::from("\x1b[2J\x1b[H")
+        };
+
+        let width = resolution.width;
+
+        // a line of characters per two rows of pixels
+        for rows in screen.chunks(width * 2) {
+            let (top, bottom) = rows.split_at(width.min(rows.len()));
+
+            for (x, top) in top.iter().enumerate() {
+                text.push(match (*top, bottom.get(x).copied().unwrap_or(false)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+
+            text.push_str("\r\n");
+        }
+
+        text.push_str(&format!("{}  (Esc quits)", self.title));
+
+        // a terminal that went away ends the game through the input
+        let mut stdout = io::stdout().lock();
+        let _ = stdout
+            .write_all(text.as_bytes())
+            .and_then(|_| stdout.flush());
+
+        self.shown = screen.to_vec();
+        self.resolution = resolution;
+    }
+}
+
+impl Drop for TerminalScreen {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+    }
+}
+
+struct TerminalKeys {
+    raw_mode: Option<RawMode>, // None when stdin isn't a terminal, nothing is read then
+    held: [u32; NUM_KEYS],     // frames each button stays down
+    frames_left: Option<u32>,  // `--frames`
+}
+
+impl TerminalKeys {
+    fn new(frames: Option<u32>) -> Self {
+        Self {
+            raw_mode: RawMode::enable(),
+            held: [0; NUM_KEYS],
+            frames_left: frames,
+        }
+    }
+}
+
+impl InputSource for TerminalKeys {
+    fn poll(&mut self) -> Input {
+        if let Some(frames) = &mut self.frames_left {
+            if *frames == 0 {
+                return Input::Quit;
+            }
+
+            *frames -= 1;
+        }
+
+        for held in &mut self.held {
+            *held = held.saturating_sub(1);
+        }
+
+        if self.raw_mode.is_some() {
+            let mut buffer = [0; 64];
+            let count = io::stdin().read(&mut buffer).unwrap_or(0);
+            let typed = &buffer[..count];
+
+            // Escape alone, not the start of an arrow key's sequence
+            if typed.contains(&CTRL_C) || typed == [ESCAPE] {
+                return Input::Quit;
+            }
+
+            for byte in typed {
+                if let Some(button) = KEYPAD
+                    .iter()
+                    .position(|key| *key == byte.to_ascii_lowercase())
+                {
+                    self.held[button] = KEY_HOLD_FRAMES;
+                }
+            }
+        }
+
+        Input::Keys(self.held.map(|frames| frames > 0))
+    }
+}
+
+// the terminal bell, once per sound whatever its length: the sound timer beeps every frame
+// it runs, which would ring the bell as often
+#[derive(Default)]
+struct Bell {
+    until: Cell<Option<Instant>>, // end of the sound the bell rang for
+}
+
+impl Buzzer for Bell {
+    fn beep(&self, duration: Duration) {
+        let now = Instant::now();
+        let ringing = self.until.get().is_some_and(|until| now < until);
+
+        self.until.set(Some(now + duration));
+
+        if !ringing {
+            print!("\x07");
+            let _ = io::stdout().flush();
+        }
+    }
+}
+
+// the bell rings the same at any speed
+impl AudioSink for Bell {}
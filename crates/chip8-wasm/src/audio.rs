@@ -0,0 +1,105 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use chip8_core::core::Buzzer;
+use wasm_bindgen::prelude::*;
+use web_sys::{AudioContext, Document, GainNode, OscillatorType};
+
+// a square wave, the classic buzzer sound, played quietly
+const BEEP_FREQUENCY: f32 = 440.0;
+const BEEP_VOLUME: f32 = 0.15;
+
+// user gestures that allow a page to start playing sound
+const GESTURES: [&str; 3] = ["pointerdown", "keydown", "touchstart"];
+
+/// The buzzer, with Web Audio: a square wave oscillator that runs all the time behind a
+/// gain node, which is opened for as long as the sound timer runs.
+///
+/// Browsers only let a page play sound once the user interacted with it, so the audio
+/// context is created on the first click, key or touch; beeps before that are silent.
+#[derive(Default)]
+pub struct WebBeeper {
+    output: RefCell<Option<(AudioContext, GainNode)>>,
+    muted: Cell<bool>,
+}
+
+impl WebBeeper {
+    /// A beeper waiting for the first user gesture on `document` to start.
+    pub fn new(document: &Document) -> Result<Rc<Self>, JsValue> {
+        let beeper = Rc::new(Self::default());
+
+        for gesture in GESTURES {
+            let beeper = beeper.clone();
+            let listener = Closure::<dyn FnMut()>::new(move || {
+                if let Err(err) = beeper.start() {
+                    web_sys::console::error_1(&err);
+                }
+            });
+
+            document
+                .add_event_listener_with_callback(gesture, listener.as_ref().unchecked_ref())?;
+
+            // lives as long as the page, later gestures find the output started
+            listener.forget();
+        }
+
+        Ok(beeper)
+    }
+
+    pub fn muted(&self) -> bool {
+        self.muted.get()
+    }
+
+    /// Silences the beeps, or lets them be heard again. A beep playing stops at once.
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.set(muted);
+
+        if let Some((context, gain)) = self.output.borrow().as_ref().filter(|_| muted) {
+            let _ = gain.gain().cancel_scheduled_values(0.0);
+            let _ = gain.gain().set_value_at_time(0.0, context.current_time());
+        }
+    }
+
+    fn start(&self) -> Result<(), JsValue> {
+        if self.output.borrow().is_some() {
+            return Ok(());
+        }
+
+        let context = AudioContext::new()?;
+
+        let oscillator = context.create_oscillator()?;
+        oscillator.set_type(OscillatorType::Square);
+        oscillator.frequency().set_value(BEEP_FREQUENCY);
+
+        let gain = context.create_gain()?;
+        gain.gain().set_value(0.0);
+
+        oscillator.connect_with_audio_node(&gain)?;
+        gain.connect_with_audio_node(&context.destination())?;
+        oscillator.start()?;
+
+        *self.output.borrow_mut() = Some((context, gain));
+
+        Ok(())
+    }
+}
+
+impl Buzzer for WebBeeper {
+    // the gain is opened now and scheduled to close when the timer runs out, so a beep
+    // still playing is extended and the sound stops on time even if frames come late
+    fn beep(&self, duration: Duration) {
+        if self.muted.get() {
+            return;
+        }
+
+        if let Some((context, gain)) = self.output.borrow().as_ref() {
+            let now = context.current_time();
+            let gain = gain.gain();
+
+            let _ = gain.cancel_scheduled_values(now);
+            let _ = gain.set_value_at_time(BEEP_VOLUME, now);
+            let _ = gain.set_value_at_time(0.0, now + duration.as_secs_f64());
+        }
+    }
+}
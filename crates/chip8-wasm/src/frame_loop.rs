@@ -0,0 +1,16 @@
+use js_sys::Promise;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+/// Resolves with the timestamp of the next animation frame, in ms. Browsers don't run
+/// animation frames in hidden tabs, so this waits until the tab is shown again.
+pub async fn next_animation_frame(window: &web_sys::Window) -> Result<f64, JsValue> {
+    let mut requested = Ok(0);
+    let promise = Promise::new(&mut |resolve, _reject| {
+        requested = window.request_animation_frame(&resolve);
+    });
+
+    requested?;
+
+    Ok(JsFuture::from(promise).await?.as_f64().unwrap_or_default())
+}
@@ -0,0 +1,57 @@
+use chip8_core::constants::NUM_KEYS;
+use chip8_core::gamepad_map::GamepadMap;
+use wasm_bindgen::prelude::*;
+use web_sys::{Gamepad, GamepadButton, GamepadEvent, Navigator, Window};
+
+/// The keypad buttons held on any connected gamepad, through `map`.
+///
+/// The Gamepad API is polled: `getGamepads()` lists whatever is plugged in right now, so
+/// a controller plugged in mid-game works from the next frame on.
+pub fn held_keys(navigator: &Navigator, map: &GamepadMap) -> [bool; NUM_KEYS] {
+    let mut keys = [false; NUM_KEYS];
+
+    let Ok(gamepads) = navigator.get_gamepads() else {
+        return keys;
+    };
+
+    // unplugged slots are null
+    for gamepad in gamepads
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+    {
+        for (button, state) in gamepad.buttons().iter().enumerate() {
+            let pressed = state
+                .dyn_into::<GamepadButton>()
+                .is_ok_and(|state| state.pressed());
+
+            if let Some(key) = map.key(button).filter(|_| pressed) {
+                keys[key] = true;
+            }
+        }
+    }
+
+    keys
+}
+
+/// Logs the gamepads plugged in and out to the console. Browsers only announce a gamepad
+/// once one of its buttons was pressed while the page had focus.
+pub fn log_hot_plug(window: &Window) -> Result<(), JsValue> {
+    for (event, action) in [
+        ("gamepadconnected", "connected"),
+        ("gamepaddisconnected", "disconnected"),
+    ] {
+        let listener = Closure::<dyn FnMut(GamepadEvent)>::new(move |event: GamepadEvent| {
+            if let Some(gamepad) = event.gamepad() {
+                let message = format!("Gamepad {} {action}: {}", gamepad.index(), gamepad.id());
+                web_sys::console::log_1(&message.into());
+            }
+        });
+
+        window.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())?;
+
+        // lives as long as the page
+        listener.forget();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,6 @@
+pub mod audio;
+pub mod frame_loop;
+pub mod gamepad;
+pub mod share;
+pub mod storage;
+pub mod wasm_manager;
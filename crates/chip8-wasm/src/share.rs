@@ -0,0 +1,241 @@
+use chip8_core::clock::Clock;
+use chip8_core::quirks::Quirks;
+use wasm_bindgen::prelude::*;
+
+// larger games don't make links anyone wants to paste, they are shared by URL instead
+pub const MAX_LINKED_ROM_SIZE: usize = 2048;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A game and how to play it, in the hash of a page link so that opening the link boots
+/// straight into it:
+///
+/// ```text
+/// #name=PONG&rom=ahbgAWAA...&quirks=vf-reset,clip&ipf=10&palette=1A0F00-FFB000
+/// #name=BRIX&url=https%3A%2F%2Fexample.com%2FBRIX.ch8
+/// ```
+///
+/// The ROM itself is embedded (unpadded base64url) up to MAX_LINKED_ROM_SIZE bytes, larger
+/// ones are fetched from `url`. Every other field is optional.
+#[wasm_bindgen]
+#[derive(Clone, Default, Debug)]
+pub struct SharedGame {
+    name: String,
+    rom: Option<Vec<u8>>,
+    url: Option<String>,
+    quirks: Option<Quirks>,
+    ipf: Option<f64>,
+    palette: Option<String>, // background-foreground, 6 hex digits each
+}
+
+#[wasm_bindgen]
+impl SharedGame {
+    /// Reads the hash of a link, with or without its leading `#`.
+    pub fn parse(hash: &str) -> Result<SharedGame, String> {
+        let mut shared = SharedGame::default();
+
+        for field in hash
+            .trim_start_matches('#')
+            .split('&')
+            .filter(|field| !field.is_empty())
+        {
+            let (name, value) = field.split_once('=').unwrap_or((field, ""));
+            let value = String::from(
+                js_sys::decode_uri_component(value).map_err(|_| format!("Bad {name} in link"))?,
+            );
+
+            match name {
+                "name" => shared.name = value,
+                "rom" => {
+                    let rom = decode_base64(&value).ok_or("Bad rom in link")?;
+
+                    if rom.len() > MAX_LINKED_ROM_SIZE {
+                        return Err(format!(
+                            "The rom in the link is over {MAX_LINKED_ROM_SIZE} bytes"
+                        ));
+                    }
+
+                    shared.rom = Some(rom);
+                }
+                "url" => shared.url = Some(value),
+                "quirks" => {
+                    shared.quirks = Some(
+                        Quirks::parse(&value).ok_or_else(|| format!("Unknown quirks {value}"))?,
+                    )
+                }
+                "ipf" => {
+                    let ipf = value
+                        .parse()
+                        .ok()
+                        .filter(|ipf| Clock::is_valid_ipf(*ipf))
+                        .ok_or_else(|| format!("Bad speed {value}"))?;
+
+                    shared.ipf = Some(ipf);
+                }
+                "palette" if parse_palette(&value).is_some() => shared.palette = Some(value),
+                "palette" => return Err(format!("Bad palette {value}")),
+                _ => return Err(format!("Unknown field {name} in link")),
+            }
+        }
+
+        if shared.rom.is_none() && shared.url.is_none() {
+            return Err("The link has no game".to_string());
+        }
+
+        Ok(shared)
+    }
+
+    /// A link to `rom` called `name`, when it is small enough to be embedded.
+    pub fn embed(name: &str, rom: &[u8]) -> Result<SharedGame, String> {
+        if rom.len() > MAX_LINKED_ROM_SIZE {
+            return Err(format!(
+                "{name} is over {MAX_LINKED_ROM_SIZE} bytes, share a link to the ROM's URL instead"
+            ));
+        }
+
+        Ok(SharedGame {
+            name: name.to_string(),
+            rom: Some(rom.to_vec()),
+            ..SharedGame::default()
+        })
+    }
+
+    /// A link to the ROM at `url`, called `name`.
+    pub fn from_url(name: &str, url: &str) -> SharedGame {
+        SharedGame {
+            name: name.to_string(),
+            url: Some(url.to_string()),
+            ..SharedGame::default()
+        }
+    }
+
+    /// The hash read by `parse`, with its leading `#`.
+    pub fn to_hash(&self) -> String {
+        let encode = |value: &str| String::from(js_sys::encode_uri_component(value));
+
+        let mut fields = vec![format!("name={}", encode(&self.name))];
+
+        if let Some(rom) = &self.rom {
+            fields.push(format!("rom={}", encode_base64(rom)));
+        }
+
+        if let Some(url) = &self.url {
+            fields.push(format!("url={}", encode(url)));
+        }
+
+        if let Some(quirks) = self.quirks {
+            fields.push(format!("quirks={}", quirks.names()));
+        }
+
+        if let Some(ipf) = self.ipf {
+            fields.push(format!("ipf={ipf}"));
+        }
+
+        if let Some(palette) = &self.palette {
+            fields.push(format!("palette={palette}"));
+        }
+
+        format!("#{}", fields.join("&"))
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// The embedded ROM, `url` has it otherwise.
+    pub fn rom(&self) -> Option<Vec<u8>> {
+        self.rom.clone()
+    }
+
+    pub fn url(&self) -> Option<String> {
+        self.url.clone()
+    }
+
+    /// Quirks as for `--quirks`, e.g. `vf-reset,clip`.
+    pub fn quirks(&self) -> Option<String> {
+        self.quirks.map(|quirks| quirks.names())
+    }
+
+    pub fn set_quirks(&mut self, quirks: &str) -> Result<(), String> {
+        self.quirks =
+            Some(Quirks::parse(quirks).ok_or_else(|| format!("Unknown quirks {quirks}"))?);
+
+        Ok(())
+    }
+
+    /// Instructions per 60Hz frame.
+    pub fn ipf(&self) -> Option<f64> {
+        self.ipf
+    }
+
+    pub fn set_ipf(&mut self, ipf: f64) {
+        self.ipf = Some(ipf);
+    }
+
+    /// Background and foreground colors as `1A0F00-FFB000`.
+    pub fn palette(&self) -> Option<String> {
+        self.palette.clone()
+    }
+
+    pub fn set_palette(&mut self, palette: &str) -> Result<(), String> {
+        parse_palette(palette).ok_or_else(|| format!("Bad palette {palette}"))?;
+        self.palette = Some(palette.to_string());
+
+        Ok(())
+    }
+}
+
+/// The background and foreground colors of `background-foreground`, as RGB.
+pub fn parse_palette(text: &str) -> Option<([u8; 3], [u8; 3])> {
+    let color = |hex: &str| -> Option<[u8; 3]> {
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        let [_, r, g, b] = value.to_be_bytes();
+
+        Some([r, g, b])
+    };
+
+    let (background, foreground) = text.split_once('-')?;
+
+    Some((color(background)?, color(foreground)?))
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, byte)| {
+            bits | (*byte as u32) << (16 - idx * 8)
+        });
+
+        // a chunk of n bytes needs n + 1 characters, unpadded
+        for n in 0..=chunk.len() {
+            text.push(BASE64_ALPHABET[(bits >> (18 - n * 6)) as usize & 0x3F] as char);
+        }
+    }
+
+    text
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut bits = 0u32;
+
+        for (idx, c) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET.iter().position(|known| known == c)? as u32;
+            bits |= value << (18 - idx * 6);
+        }
+
+        data.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+
+    Some(data)
+}
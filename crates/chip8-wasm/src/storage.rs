@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use chip8_core::storage::Storage;
+use js_sys::{Array, Promise, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+const DATABASE: &str = "chip8";
+const DATABASE_VERSION: u32 = 1;
+const STORE: &str = "entries";
+
+/// `Storage` in an IndexedDB object store, so settings and saves survive page reloads.
+///
+/// IndexedDB is asynchronous: `open` reads every entry into memory, reads are served from
+/// there and writes update it at once, then reach the database in the background.
+pub struct IndexedDbStorage {
+    database: IdbDatabase,
+    entries: HashMap<String, Vec<u8>>,
+}
+
+impl IndexedDbStorage {
+    pub async fn open() -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or("no window")?;
+        let factory = window.indexed_db()?.ok_or("IndexedDB is not available")?;
+        let request = factory.open_with_u32(DATABASE, DATABASE_VERSION)?;
+
+        // first visit, or a newer layout
+        let upgrade = Closure::<dyn FnMut()>::new({
+            let request = request.clone();
+
+            move || {
+                if let Ok(database) = request.result() {
+                    let _ = database
+                        .unchecked_into::<IdbDatabase>()
+                        .create_object_store(STORE);
+                }
+            }
+        });
+
+        request.set_onupgradeneeded(Some(upgrade.as_ref().unchecked_ref()));
+
+        let database: IdbDatabase = done(&request).await?.unchecked_into();
+
+        let store = Self::store(&database, IdbTransactionMode::Readonly)?;
+        let keys: Array = done(&store.get_all_keys()?).await?.unchecked_into();
+        let values: Array = done(&store.get_all()?).await?.unchecked_into();
+
+        let entries = keys
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(key, value)| {
+                let value = value.dyn_into::<Uint8Array>().ok()?;
+                Some((key.as_string()?, value.to_vec()))
+            })
+            .collect();
+
+        Ok(Self { database, entries })
+    }
+
+    fn store(database: &IdbDatabase, mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue> {
+        database
+            .transaction_with_str_and_mode(STORE, mode)?
+            .object_store(STORE)
+    }
+}
+
+impl Storage for IndexedDbStorage {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &[u8]) -> Result<(), String> {
+        self.entries.insert(key.to_string(), value.to_vec());
+
+        Self::store(&self.database, IdbTransactionMode::Readwrite)
+            .and_then(|store| store.put_with_key(&Uint8Array::from(value), &key.into()))
+            .map(|_| ())
+            .map_err(|err| format!("Could not store {key}: {err:?}"))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), String> {
+        self.entries.remove(key);
+
+        Self::store(&self.database, IdbTransactionMode::Readwrite)
+            .and_then(|store| store.delete(&key.into()))
+            .map(|_| ())
+            .map_err(|err| format!("Could not remove {key}: {err:?}"))
+    }
+}
+
+// the result of `request` once it succeeded
+async fn done(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+
+    JsFuture::from(promise).await?;
+
+    request.result()
+}
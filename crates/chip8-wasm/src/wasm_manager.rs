@@ -0,0 +1,460 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use chip8_core::clock::Clock;
+use chip8_core::constants::NUM_KEYS;
+use chip8_core::core::Chip8;
+use chip8_core::frame_loop::FrameLoop;
+use chip8_core::gamepad_map::GamepadMap;
+use chip8_core::quirks::Quirks;
+use chip8_core::resolution::Resolution;
+use chip8_core::savestate::SaveState;
+use chip8_core::storage::Storage;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{
+    CanvasRenderingContext2d, Document, HtmlCanvasElement, ImageData, KeyboardEvent, Window,
+};
+
+use crate::audio::WebBeeper;
+use crate::frame_loop;
+use crate::gamepad;
+use crate::share::{self, SharedGame};
+use crate::storage::IndexedDbStorage;
+
+// keypad buttons 0-F, by the physical keys of a QWERTY keyboard like the desktop defaults
+const KEY_CODES: [&str; NUM_KEYS] = [
+    "KeyX", "Digit1", "Digit2", "Digit3", "KeyQ", "KeyW", "KeyE", "KeyA", "KeyS", "KeyD", "KeyZ",
+    "KeyC", "Digit4", "KeyR", "KeyF", "KeyV",
+];
+
+// storage keys, see `Storage`; games and their states are stored by name
+const GAMEPAD_MAP_KEY: &str = "config/gamepad_map";
+const MUTED_KEY: &str = "config/muted";
+const RECENT_KEY: &str = "recent";
+const ROM_PREFIX: &str = "rom/";
+const STATE_PREFIX: &str = "state/";
+
+const MAX_RECENT_ROMS: usize = 10;
+
+// black and white until the game's palette is set
+const DEFAULT_COLORS: ([u8; 3], [u8; 3]) = ([0, 0, 0], [255, 255, 255]);
+
+/// Runs a game in a web page, drawing on a canvas the size of the machine's screen (64x32
+/// for most games) scaled by CSS.
+///
+/// The game loop is an async function awaiting animation frames rather than a blocking
+/// loop, so the page stays responsive; the machine still runs at 60Hz whatever the display
+/// rate, see `FrameLoop`, and pauses while the tab is hidden. Hiding the tab, which
+/// includes leaving or reloading the page, saves the game to be resumed next time.
+pub struct WasmGameManager {
+    window: Window,
+    context: CanvasRenderingContext2d,
+    game: Game, // the machine and its settings, shared with the page
+    keys: Rc<RefCell<[bool; NUM_KEYS]>>, // written by the keyboard listeners
+    gamepad_map: Rc<RefCell<GamepadMap>>, // changed by the page's mapping UI, see `Library`
+    shown: Rc<Cell<bool>>, // the tab was shown again since the last frame
+    pixels: Vec<u8>, // RGBA, reused every frame
+    resolution: Resolution, // the canvas is sized for
+}
+
+impl WasmGameManager {
+    pub fn new(
+        canvas: &HtmlCanvasElement,
+        game: &Game,
+        gamepad_map: Rc<RefCell<GamepadMap>>,
+    ) -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or("no window")?;
+        let document = window.document().ok_or("no document")?;
+
+        let resolution = game.chip8.borrow().resolution();
+
+        canvas.set_width(resolution.width as u32);
+        canvas.set_height(resolution.height as u32);
+
+        let context = canvas
+            .get_context("2d")?
+            .ok_or("no 2d context")?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+
+        let keys = Rc::new(RefCell::new([false; NUM_KEYS]));
+        Self::listen_keys(&document, &keys)?;
+
+        let shown = Rc::new(Cell::new(false));
+        Self::listen_visibility(&document, &shown, game.clone())?;
+
+        gamepad::log_hot_plug(&window)?;
+
+        Ok(Self {
+            window,
+            context,
+            game: game.clone(),
+            keys,
+            gamepad_map,
+            shown,
+            pixels: vec![0; resolution.pixels() * 4],
+            resolution,
+        })
+    }
+
+    /// Plays until the page goes away, one machine frame per 60th of a second.
+    pub async fn run(mut self) -> Result<(), JsValue> {
+        let mut frame_loop = FrameLoop::default();
+
+        loop {
+            let now = frame_loop::next_animation_frame(&self.window).await?;
+
+            // the hidden time is not made up for
+            if self.shown.replace(false) {
+                frame_loop.reset();
+            }
+
+            let frames = frame_loop.frames_due(now);
+
+            for _ in 0..frames {
+                self.run_frame();
+            }
+
+            if frames > 0 {
+                self.draw()?;
+            }
+        }
+    }
+
+    fn run_frame(&mut self) {
+        let held = gamepad::held_keys(&self.window.navigator(), &self.gamepad_map.borrow());
+        let mut chip8 = self.game.chip8.borrow_mut();
+
+        for (idx, pressed) in self.keys.borrow().iter().enumerate() {
+            chip8.keypress(idx, *pressed || held[idx]);
+        }
+
+        let ticks = self.game.clock.borrow_mut().ticks_for_frame();
+
+        // the game stays stopped on the instruction, reported once per frame
+        if let Err(err) = chip8.run_frame(ticks) {
+            web_sys::console::error_1(&err.to_string().into());
+        }
+    }
+
+    fn draw(&mut self) -> Result<(), JsValue> {
+        let (background, foreground) = self.game.colors.get();
+        let resolution = self.game.chip8.borrow().resolution();
+
+        // the game or a loaded state changed the screen size
+        if resolution != self.resolution {
+            if let Some(canvas) = self.context.canvas() {
+                canvas.set_width(resolution.width as u32);
+                canvas.set_height(resolution.height as u32);
+            }
+
+            self.pixels = vec![0; resolution.pixels() * 4];
+            self.resolution = resolution;
+        }
+
+        for (pixel, lit) in self
+            .pixels
+            .chunks_mut(4)
+            .zip(self.game.chip8.borrow().get_screen())
+        {
+            let [r, g, b] = if *lit { foreground } else { background };
+            pixel.copy_from_slice(&[r, g, b, 255]);
+        }
+
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.pixels),
+            resolution.width as u32,
+            resolution.height as u32,
+        )?;
+
+        self.context.put_image_data(&image, 0.0, 0.0)
+    }
+
+    // the listeners live as long as the page, their closures are leaked on purpose
+    fn listen_keys(
+        document: &Document,
+        keys: &Rc<RefCell<[bool; NUM_KEYS]>>,
+    ) -> Result<(), JsValue> {
+        for (event, pressed) in [("keydown", true), ("keyup", false)] {
+            let keys = keys.clone();
+            let listener = Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                if let Some(idx) = KEY_CODES.iter().position(|code| *code == event.code()) {
+                    keys.borrow_mut()[idx] = pressed;
+                    event.prevent_default();
+                }
+            });
+
+            document.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())?;
+            listener.forget();
+        }
+
+        Ok(())
+    }
+
+    fn listen_visibility(
+        document: &Document,
+        shown: &Rc<Cell<bool>>,
+        game: Game,
+    ) -> Result<(), JsValue> {
+        let target = document.clone();
+        let shown = shown.clone();
+        let listener = Closure::<dyn FnMut()>::new(move || {
+            if !target.hidden() {
+                shown.set(true);
+            } else if let Err(err) = game.save_state() {
+                web_sys::console::error_1(&err);
+            }
+        });
+
+        document.add_event_listener_with_callback(
+            "visibilitychange",
+            listener.as_ref().unchecked_ref(),
+        )?;
+        listener.forget();
+
+        Ok(())
+    }
+}
+
+/// The games and settings kept in the browser, see `IndexedDbStorage`. The page's entry
+/// point: `await Library.open()`, then `start` a game.
+#[wasm_bindgen]
+pub struct Library {
+    storage: Rc<RefCell<IndexedDbStorage>>,
+    gamepad_map: Rc<RefCell<GamepadMap>>,
+    beeper: Rc<WebBeeper>,
+}
+
+#[wasm_bindgen]
+impl Library {
+    pub async fn open() -> Result<Library, JsValue> {
+        let storage = IndexedDbStorage::open().await?;
+
+        let gamepad_map = storage
+            .get(GAMEPAD_MAP_KEY)
+            .and_then(|text| GamepadMap::parse(&String::from_utf8_lossy(&text)).ok())
+            .unwrap_or_default();
+
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("no document")?;
+
+        let beeper = WebBeeper::new(&document)?;
+        beeper.set_muted(storage.get(MUTED_KEY).is_some_and(|muted| muted == b"true"));
+
+        Ok(Library {
+            storage: Rc::new(RefCell::new(storage)),
+            gamepad_map: Rc::new(RefCell::new(gamepad_map)),
+            beeper,
+        })
+    }
+
+    pub fn muted(&self) -> bool {
+        self.beeper.muted()
+    }
+
+    /// Silences the sound, or turns it back on, and remembers the choice.
+    pub fn set_muted(&self, muted: bool) -> Result<(), JsValue> {
+        self.beeper.set_muted(muted);
+
+        Ok(self
+            .storage
+            .borrow_mut()
+            .set(MUTED_KEY, muted.to_string().as_bytes())?)
+    }
+
+    /// The gamepad mapping, in the `GamepadMap` text form.
+    pub fn gamepad_map(&self) -> String {
+        self.gamepad_map.borrow().to_text()
+    }
+
+    /// Uses and remembers a gamepad mapping in the `GamepadMap` text form, the running game
+    /// picks it up at once.
+    pub fn set_gamepad_map(&self, text: &str) -> Result<(), JsValue> {
+        let map = GamepadMap::parse(text)?;
+
+        *self.gamepad_map.borrow_mut() = map;
+
+        Ok(self
+            .storage
+            .borrow_mut()
+            .set(GAMEPAD_MAP_KEY, map.to_text().as_bytes())?)
+    }
+
+    /// Names of the games played lately, the latest first, see `start_recent`.
+    pub fn recent_roms(&self) -> Vec<String> {
+        self.storage
+            .borrow()
+            .get(RECENT_KEY)
+            .map(|names| {
+                String::from_utf8_lossy(&names)
+                    .lines()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Plays `rom` on `canvas` until the page goes away. The game is remembered as `name`,
+    /// and resumed where it was left when it was played before.
+    pub fn start(
+        &self,
+        canvas: &HtmlCanvasElement,
+        name: &str,
+        rom: &[u8],
+    ) -> Result<Game, JsValue> {
+        self.remember(name, rom)?;
+
+        let mut chip8 = Chip8::new();
+        chip8.load(rom).map_err(|err| format!("{name}: {err}"))?;
+        chip8.set_buzzer(Some(Box::new(self.beeper.clone())));
+
+        let game = Game {
+            chip8: Rc::new(RefCell::new(chip8)),
+            clock: Rc::new(RefCell::new(Clock::default())),
+            colors: Rc::new(Cell::new(DEFAULT_COLORS)),
+            name: name.to_string(),
+            rom: Rc::new(rom.to_vec()),
+            storage: self.storage.clone(),
+            state_key: format!("{STATE_PREFIX}{name}"),
+        };
+
+        // a fresh start when there is nothing to resume
+        let _ = game.load_state();
+
+        let manager = WasmGameManager::new(canvas, &game, self.gamepad_map.clone())?;
+
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = manager.run().await {
+                web_sys::console::error_1(&err);
+            }
+        });
+
+        Ok(game)
+    }
+
+    /// Plays one of the `recent_roms` again.
+    pub fn start_recent(&self, canvas: &HtmlCanvasElement, name: &str) -> Result<Game, JsValue> {
+        let rom = self
+            .storage
+            .borrow()
+            .get(&format!("{ROM_PREFIX}{name}"))
+            .ok_or_else(|| format!("{name} is not stored"))?;
+
+        self.start(canvas, name, &rom)
+    }
+
+    // stores the rom and moves it to the top of the recent games, the oldest is dropped
+    fn remember(&self, name: &str, rom: &[u8]) -> Result<(), String> {
+        let mut recent = self.recent_roms();
+        recent.retain(|recent| recent != name);
+        recent.insert(0, name.to_string());
+
+        let mut storage = self.storage.borrow_mut();
+
+        for dropped in recent.drain(recent.len().min(MAX_RECENT_ROMS)..) {
+            storage.remove(&format!("{ROM_PREFIX}{dropped}"))?;
+            storage.remove(&format!("{STATE_PREFIX}{dropped}"))?;
+        }
+
+        storage.set(&format!("{ROM_PREFIX}{name}"), rom)?;
+        storage.set(RECENT_KEY, recent.join("\n").as_bytes())
+    }
+}
+
+/// A game started by `Library::start`, for the page to control while it runs.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Game {
+    chip8: Rc<RefCell<Chip8>>,
+    clock: Rc<RefCell<Clock>>,
+    colors: Rc<Cell<([u8; 3], [u8; 3])>>, // background and foreground
+    name: String,
+    rom: Rc<Vec<u8>>,
+    storage: Rc<RefCell<IndexedDbStorage>>,
+    state_key: String,
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// Saves the machine, the game is resumed from there next time. Done on its own when
+    /// the page is hidden or left.
+    pub fn save_state(&self) -> Result<(), JsValue> {
+        let state = self.chip8.borrow().save_state().to_bytes();
+
+        Ok(self.storage.borrow_mut().set(&self.state_key, &state)?)
+    }
+
+    /// Goes back to the last saved state.
+    pub fn load_state(&self) -> Result<(), JsValue> {
+        let data = self
+            .storage
+            .borrow()
+            .get(&self.state_key)
+            .ok_or("No saved state")?;
+
+        let state = SaveState::from_bytes(&data).map_err(|err| err.to_string())?;
+        self.chip8.borrow_mut().load_state(&state);
+
+        Ok(())
+    }
+
+    /// Plays with the quirks, speed and palette of a shared link, those it has.
+    pub fn apply(&self, shared: &SharedGame) -> Result<(), JsValue> {
+        if let Some(quirks) = shared.quirks() {
+            self.set_quirks(&quirks)?;
+        }
+
+        if let Some(ipf) = shared.ipf() {
+            self.set_ipf(ipf);
+        }
+
+        if let Some(palette) = shared.palette() {
+            self.set_palette(&palette)?;
+        }
+
+        Ok(())
+    }
+
+    /// Quirks as for `--quirks`, e.g. `vf-reset,clip`.
+    pub fn set_quirks(&self, quirks: &str) -> Result<(), JsValue> {
+        let quirks = Quirks::parse(quirks).ok_or_else(|| format!("Unknown quirks {quirks}"))?;
+        self.chip8.borrow_mut().set_quirks(quirks);
+
+        Ok(())
+    }
+
+    /// Instructions per 60Hz frame.
+    pub fn set_ipf(&self, ipf: f64) {
+        *self.clock.borrow_mut() = Clock::from_ipf(ipf);
+    }
+
+    /// Background and foreground colors as `1A0F00-FFB000`.
+    pub fn set_palette(&self, palette: &str) -> Result<(), JsValue> {
+        let colors =
+            share::parse_palette(palette).ok_or_else(|| format!("Bad palette {palette}"))?;
+        self.colors.set(colors);
+
+        Ok(())
+    }
+
+    /// A link playing this game as it is set up now, when the ROM is small enough to be
+    /// embedded in it.
+    pub fn share(&self) -> Result<SharedGame, JsValue> {
+        let mut shared = SharedGame::embed(&self.name, &self.rom)?;
+        let (background, foreground) = self.colors.get();
+        let hex = |[r, g, b]: [u8; 3]| format!("{r:02X}{g:02X}{b:02X}");
+
+        shared.set_quirks(&self.chip8.borrow().get_quirks().names())?;
+        shared.set_ipf(self.clock.borrow().instructions_per_frame());
+        shared.set_palette(&format!("{}-{}", hex(background), hex(foreground)))?;
+
+        Ok(shared)
+    }
+}
+
+/// The mapping used until the page sets one, in the `GamepadMap` text form.
+#[wasm_bindgen]
+pub fn default_gamepad_map() -> String {
+    GamepadMap::default().to_text()
+}
@@ -0,0 +1,8 @@
+//! The emulator as a library, for embedding the interpreter in another frontend. `chip8`
+//! is the machine alone (`chip8::Chip8`), which is all most frontends need; depending on
+//! the `chip8-core` crate directly leaves out the desktop's SDL dependencies.
+
+pub use chip8_core as chip8;
+pub use chip8_sdl::managers;
+pub use chip8_tools as tools;
+pub use chip8_tui as tui;
@@ -1,16 +1,85 @@
-use managers::desktop_manager::DesktopGameManager;
+use std::env;
+use std::process::ExitCode;
 
-use crate::chip8::core::Chip8;
+use chip8_emulator::chip8::Chip8;
+use chip8_emulator::managers::desktop_manager::DesktopGameManager;
+use chip8_emulator::managers::headless_manager::HeadlessGameManager;
+use chip8_emulator::tools::cli::{self, Cli};
+use chip8_emulator::tui::terminal_manager::TerminalGameManager;
+use chip8_emulator::{managers, tools};
 
-pub mod chip8;
-pub mod managers;
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
 
-fn main() {
-    // TODO: make webAssembly manager
+    // subcommands that don't need a window
+    let result = match args.iter().skip(1).map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["new", name] => Some(tools::project::new_project(name)),
+        ["build", source] => Some(tools::project::build(source, None)),
+        ["build", source, "-o", rom] => Some(tools::project::build(source, Some(rom))),
+        ["package", roms @ ..] if !roms.is_empty() => Some(tools::package::package(roms)),
+        ["quirks", rom] => Some(tools::quirks::report(rom)),
+        ["profile", rom] => Some(tools::profile::report(rom, None)),
+        ["profile", rom, seconds] => Some(tools::profile::report(rom, Some(seconds))),
+        ["bench", rom] => Some(tools::bench::report(rom, None)),
+        ["bench", rom, seconds] => Some(tools::bench::report(rom, Some(seconds))),
+        ["disassemble" | "--disassemble", rom] => Some(tools::listing::print(rom, None)),
+        ["disassemble" | "--disassemble", rom, addr] => {
+            Some(tools::listing::print(rom, Some(addr)))
+        }
+        ["verify-replay", rom, movie] => Some(managers::replay_check::verify(rom, movie)),
+        ["stats"] => Some(managers::play_stats::print()),
+        ["library", "sync", index] => Some(managers::library_sync::sync(index, None)),
+        ["library", "sync", index, dir] => Some(managers::library_sync::sync(index, Some(dir))),
+        _ => None,
+    };
+
+    // otherwise the options, checked before anything runs
+    let result = result.or_else(|| match Cli::parse(&args[1..]) {
+        Err(err) => Some(Err(err)),
+        Ok(cli) if cli.wants_help() => {
+            print!("{}", cli::HELP);
+            Some(Ok(()))
+        }
+        Ok(cli) if cli.switch("--list-games") => Some(managers::rom_browser::print_games()),
+        Ok(cli) if cli.switch("--list-audio-devices") => {
+            for name in chip8_sdl::audio::output_device_names() {
+                println!("{name}");
+            }
+
+            Some(Ok(()))
+        }
+        Ok(_) => None,
+    });
+
+    // `--frontend tui` plays in the terminal instead of a window, `--frontend headless` without
+    // any output but the final screen and state
+    let result = match Cli::get().value("--frontend") {
+        Some("tui") if result.is_none() => Some(TerminalGameManager::play()),
+        Some("headless") if result.is_none() => Some(HeadlessGameManager::play()),
+        Some("tui" | "headless" | "sdl") | None => result,
+        Some(other) => Some(Err(format!(
+            "Unknown frontend {other}, the choices are sdl, tui and headless"
+        ))),
+    };
+
+    if let Some(result) = result {
+        return match result {
+            Ok(_) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
 
     let mut game_manager = DesktopGameManager::new();
 
+    // the release feed is compared with this binary's version, not the library crate's
+    game_manager.check_for_updates(env!("CARGO_PKG_VERSION"));
+
     let chip8 = Chip8::new();
 
     game_manager.start_game_loop(chip8);
+
+    ExitCode::SUCCESS
 }
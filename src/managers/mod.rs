@@ -1 +0,0 @@
-pub mod desktop_manager;
@@ -0,0 +1,47 @@
+// Caches the page, the emulator and its assets so the installed app starts offline. ROMs
+// and save states are in IndexedDB already, so the recent games play offline too.
+
+// bump to drop the old cache once the files below change
+const CACHE = "chip8-v1";
+
+const FILES = [
+    "play.html",
+    "manifest.webmanifest",
+    "icon.svg",
+    "../crates/chip8-wasm/pkg/chip8_wasm.js",
+    "../crates/chip8-wasm/pkg/chip8_wasm_bg.wasm",
+];
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(FILES)));
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches.keys().then((names) => Promise.all(names.filter((name) => name !== CACHE).map((name) => caches.delete(name))))
+    );
+});
+
+// the network first, so a rebuilt emulator is picked up, and the cache when offline
+self.addEventListener("fetch", (event) => {
+    const url = new URL(event.request.url);
+
+    if (event.request.method !== "GET" || url.origin !== location.origin) {
+        return;
+    }
+
+    event.respondWith(
+        fetch(event.request)
+            .then((response) => {
+                const copy = response.clone();
+
+                if (response.ok) {
+                    caches.open(CACHE).then((cache) => cache.put(event.request, copy));
+                }
+
+                return response;
+            })
+            // a shared link's hash is not part of the request, play.html is found as is
+            .catch(() => caches.match(event.request, { ignoreSearch: true }))
+    );
+});